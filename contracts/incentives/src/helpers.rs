@@ -5,6 +5,7 @@ use cosmwasm_std::{
     Uint128,
 };
 use mars_red_bank_types::{incentives::AssetIncentive, red_bank};
+use serde::Serialize;
 
 use crate::state::{ASSET_INCENTIVES, USER_ASSET_INDICES, USER_UNCLAIMED_REWARDS};
 
@@ -102,14 +103,9 @@ pub fn compute_user_unclaimed_rewards(
     let mut user_asset_incentive_statuses_to_update: Vec<UserAssetIncentiveStatus> = vec![];
 
     for (denom, mut asset_incentive) in result_asset_incentives? {
-        // Get asset user balances and total supply
-        let collateral: red_bank::UserCollateralResponse = deps.querier.query_wasm_smart(
-            red_bank_addr,
-            &red_bank::QueryMsg::UserCollateral {
-                user: user_addr.to_string(),
-                denom: denom.clone(),
-            },
-        )?;
+        // Get asset user balances, across all of the user's accounts, and total supply
+        let collateral_amount_scaled =
+            query_user_total_collateral_scaled(deps, red_bank_addr, user_addr, &denom)?;
         let market: red_bank::Market = deps.querier.query_wasm_smart(
             red_bank_addr,
             &red_bank::QueryMsg::Market {
@@ -120,7 +116,7 @@ pub fn compute_user_unclaimed_rewards(
         // If user's balance is 0 there should be no rewards to accrue, so we don't care about
         // updating indexes. If the user's balance changes, the indexes will be updated correctly at
         // that point in time.
-        if collateral.amount_scaled.is_zero() {
+        if collateral_amount_scaled.is_zero() {
             continue;
         }
 
@@ -137,7 +133,7 @@ pub fn compute_user_unclaimed_rewards(
         if user_asset_index != asset_incentive.index {
             // Compute user accrued rewards and update user index
             let asset_accrued_rewards = compute_user_accrued_rewards(
-                collateral.amount_scaled,
+                collateral_amount_scaled,
                 user_asset_index,
                 asset_incentive.index,
             )?;
@@ -153,3 +149,46 @@ pub fn compute_user_unclaimed_rewards(
 
     Ok((total_unclaimed_rewards, user_asset_incentive_statuses_to_update))
 }
+
+/// The subset of the red bank 1.0.1 query API the incentives contract falls back to.
+///
+/// Red bank 1.0.1 rejects unknown fields, so its `UserCollateral` query can't be sent through
+/// the current `QueryMsg`, which carries an `account_id`.
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+enum V1RedBankQueryMsg {
+    UserCollateral {
+        user: String,
+        denom: String,
+    },
+}
+
+/// Query the user's scaled collateral in `denom`, summed across all of their accounts.
+///
+/// Red bank 1.0.1 doesn't have `UserTotalCollateralScaled`; until it's upgraded to 2.0.0 we fall
+/// back to its `UserCollateral` query, which is correct there since it has no sub-accounts.
+pub fn query_user_total_collateral_scaled(
+    deps: Deps,
+    red_bank_addr: &Addr,
+    user_addr: &Addr,
+    denom: &str,
+) -> StdResult<Uint128> {
+    deps.querier
+        .query_wasm_smart(
+            red_bank_addr,
+            &red_bank::QueryMsg::UserTotalCollateralScaled {
+                user: user_addr.to_string(),
+                denom: denom.to_string(),
+            },
+        )
+        .or_else(|_| {
+            let collateral: red_bank::UserCollateralResponse = deps.querier.query_wasm_smart(
+                red_bank_addr,
+                &V1RedBankQueryMsg::UserCollateral {
+                    user: user_addr.to_string(),
+                    denom: denom.to_string(),
+                },
+            )?;
+            Ok(collateral.amount_scaled)
+        })
+}
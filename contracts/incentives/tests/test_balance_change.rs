@@ -272,6 +272,54 @@ fn with_zero_previous_balance_and_asset_with_zero_index_accumulates_rewards() {
     }
 }
 
+#[test]
+fn unclaimed_rewards_fall_back_to_red_bank_v1_queries() {
+    let mut deps = th_setup();
+    let denom = "uosmo";
+    let user_addr = Addr::unchecked("user");
+    let emission_per_second = Uint128::new(100);
+    let time_last_updated = 500_000_u64;
+
+    ASSET_INCENTIVES
+        .save(
+            deps.as_mut().storage,
+            denom,
+            &AssetIncentive {
+                emission_per_second,
+                start_time: time_last_updated,
+                duration: 8640000,
+                index: Decimal::zero(),
+                last_updated: time_last_updated,
+            },
+        )
+        .unwrap();
+
+    // red bank 1.0.1 doesn't have the `UserTotalCollateralScaled` query
+    deps.querier.set_red_bank_v1_queries_only(true);
+    deps.querier.set_redbank_market(Market {
+        denom: denom.to_string(),
+        collateral_total_scaled: Uint128::new(100_000),
+        ..Default::default()
+    });
+    deps.querier.set_red_bank_user_collateral(
+        &user_addr,
+        UserCollateralResponse {
+            denom: denom.to_string(),
+            amount_scaled: Uint128::new(100_000),
+            amount: Uint128::zero(), // doesn't matter for this test
+            enabled: true,
+        },
+    );
+
+    let env = mars_testing::mock_env(MockEnvParams {
+        block_time: Timestamp::from_seconds(time_last_updated + 1000),
+        ..Default::default()
+    });
+    let rewards_query =
+        query_user_unclaimed_rewards(deps.as_ref(), env, String::from("user")).unwrap();
+    assert_eq!(Uint128::new(1000).checked_mul(emission_per_second).unwrap(), rewards_query);
+}
+
 #[test]
 fn set_new_asset_incentive_user_non_zero_balance() {
     let env = mock_env();
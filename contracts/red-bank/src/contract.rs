@@ -1,7 +1,12 @@
-use cosmwasm_std::{entry_point, to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response};
-use mars_red_bank_types::red_bank::{ExecuteMsg, InstantiateMsg, QueryMsg};
+use cosmwasm_std::{
+    entry_point, to_binary, Binary, Deps, DepsMut, Empty, Env, MessageInfo, Response,
+};
+use mars_red_bank_types::{
+    error::MarsError,
+    red_bank::{CallbackMsg, ExecuteMsg, InstantiateMsg, QueryMsg},
+};
 
-use crate::{error::ContractError, execute, query};
+use crate::{error::ContractError, execute, migrations, query};
 
 #[entry_point]
 pub fn instantiate(
@@ -20,6 +25,15 @@ pub fn execute(
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
+    // Positions of version 1.0.1 are moved to the main accounts of their owners when first used
+    if migrations::v1_0_1::is_migrating(deps.storage)? {
+        migrations::v1_0_1::migrate_account(deps.storage, &info.sender)?;
+        for user in migrations::v1_0_1::users_in(&msg) {
+            let user_addr = deps.api.addr_validate(user)?;
+            migrations::v1_0_1::migrate_account(deps.storage, &user_addr)?;
+        }
+    }
+
     match msg {
         ExecuteMsg::UpdateOwner(update) => execute::update_owner(deps, info, update),
         ExecuteMsg::UpdateEmergencyOwner(update) => {
@@ -27,7 +41,7 @@ pub fn execute(
         }
         ExecuteMsg::UpdateConfig {
             config,
-        } => execute::update_config(deps, info, config),
+        } => execute::update_config(deps, env, info, config),
         ExecuteMsg::InitAsset {
             denom,
             params,
@@ -36,6 +50,22 @@ pub fn execute(
             denom,
             params,
         } => execute::update_asset(deps, env, info, denom, params),
+        ExecuteMsg::DelistAsset {
+            denom,
+            ramp_duration,
+        } => execute::delist_asset(deps, env, info, denom, ramp_duration),
+        ExecuteMsg::SunsetAsset {
+            denom,
+        } => execute::sunset_asset(deps, env, info, denom),
+        ExecuteMsg::RemoveAsset {
+            denom,
+        } => execute::remove_asset(deps, info, denom),
+        ExecuteMsg::ExecutePendingChange {
+            id,
+        } => execute::execute_pending_change(deps, env, info, id),
+        ExecuteMsg::CancelPendingChange {
+            id,
+        } => execute::cancel_pending_change(deps, info, id),
         ExecuteMsg::UpdateUncollateralizedLoanLimit {
             user,
             denom,
@@ -46,28 +76,96 @@ pub fn execute(
         }
         ExecuteMsg::Deposit {
             on_behalf_of,
+            account_id,
         } => {
             let sent_coin = cw_utils::one_coin(&info)?;
-            execute::deposit(deps, env, info, on_behalf_of, sent_coin.denom, sent_coin.amount)
+            execute::deposit(
+                deps,
+                env,
+                info,
+                on_behalf_of,
+                account_id.unwrap_or_default(),
+                sent_coin.denom,
+                sent_coin.amount,
+            )
         }
         ExecuteMsg::Withdraw {
             denom,
             amount,
             recipient,
-        } => execute::withdraw(deps, env, info, denom, amount, recipient),
+            on_behalf_of,
+            account_id,
+        } => execute::withdraw(
+            deps,
+            env,
+            info,
+            denom,
+            amount,
+            recipient,
+            on_behalf_of,
+            account_id.unwrap_or_default(),
+        ),
         ExecuteMsg::Borrow {
             denom,
             amount,
             recipient,
-        } => execute::borrow(deps, env, info, denom, amount, recipient),
+            on_behalf_of,
+            account_id,
+        } => execute::borrow(
+            deps,
+            env,
+            info,
+            denom,
+            amount,
+            recipient,
+            on_behalf_of,
+            account_id.unwrap_or_default(),
+        ),
+        ExecuteMsg::ApproveDelegation {
+            delegatee,
+            denom,
+            amount,
+            account_id,
+        } => execute::approve_delegation(
+            deps,
+            info,
+            delegatee,
+            denom,
+            amount,
+            account_id.unwrap_or_default(),
+        ),
+        ExecuteMsg::SetOperator {
+            operator,
+            permissions,
+            expires,
+            account_id,
+        } => execute::set_operator(
+            deps,
+            env,
+            info,
+            operator,
+            permissions,
+            expires,
+            account_id.unwrap_or_default(),
+        ),
         ExecuteMsg::Repay {
             on_behalf_of,
+            account_id,
         } => {
             let sent_coin = cw_utils::one_coin(&info)?;
-            execute::repay(deps, env, info, on_behalf_of, sent_coin.denom, sent_coin.amount)
+            execute::repay(
+                deps,
+                env,
+                info,
+                on_behalf_of,
+                account_id.unwrap_or_default(),
+                sent_coin.denom,
+                sent_coin.amount,
+            )
         }
         ExecuteMsg::Liquidate {
             user,
+            account_id,
             collateral_denom,
             recipient,
         } => {
@@ -80,21 +178,116 @@ pub fn execute(
                 collateral_denom,
                 sent_coin.denom,
                 user_addr,
+                account_id.unwrap_or_default(),
                 sent_coin.amount,
                 recipient,
             )
         }
+        ExecuteMsg::LiquidateMany {
+            liquidations,
+            recipient,
+        } => execute::liquidate_many(deps, env, info, liquidations, recipient),
         ExecuteMsg::UpdateAssetCollateralStatus {
             denom,
             enable,
-        } => execute::update_asset_collateral_status(deps, env, info, denom, enable),
+            on_behalf_of,
+            account_id,
+        } => execute::update_asset_collateral_status(
+            deps,
+            env,
+            info,
+            denom,
+            enable,
+            on_behalf_of,
+            account_id.unwrap_or_default(),
+        ),
+        ExecuteMsg::Deleverage {
+            denom,
+            amount,
+            on_behalf_of,
+            account_id,
+        } => {
+            cw_utils::nonpayable(&info)?;
+            execute::deleverage(
+                deps,
+                env,
+                info,
+                denom,
+                amount,
+                on_behalf_of,
+                account_id.unwrap_or_default(),
+            )
+        }
+        ExecuteMsg::FlashLoan {
+            coins,
+            callback,
+        } => execute::flash_loan(deps, env, info, coins, callback),
+        ExecuteMsg::RepayFlashLoan {} => execute::repay_flash_loan(deps, info),
+        ExecuteMsg::SetEModeCategory {
+            category,
+        } => execute::set_emode_category(deps, env, info, category),
+        ExecuteMsg::SetUserEMode {
+            category_id,
+            account_id,
+        } => execute::set_user_emode(deps, env, info, category_id, account_id.unwrap_or_default()),
+        ExecuteMsg::SetPauseState {
+            denom,
+            actions,
+            paused,
+        } => execute::set_pause_state(deps, info, denom, actions, paused),
+        ExecuteMsg::CoverDeficit {
+            denom,
+        } => execute::cover_deficit(deps, env, info, denom),
+        ExecuteMsg::Batch {
+            actions,
+            account_id,
+        } => execute::batch(deps, env, info, actions, account_id.unwrap_or_default()),
+        ExecuteMsg::MigrateV1Positions {
+            limit,
+        } => migrations::v1_0_1::migrate_positions(deps, info, limit),
+        ExecuteMsg::Callback(callback) => {
+            if info.sender != env.contract.address {
+                return Err(MarsError::Unauthorized {}.into());
+            }
+            match callback {
+                CallbackMsg::FinalizeFlashLoan {} => execute::finalize_flash_loan(deps, env),
+            }
+        }
     }
 }
 
 #[entry_point]
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
+    // Positions of version 1.0.1 not moved yet are read as if they had been
+    if let Some(user) = migrations::v1_0_1::queried_user(&msg) {
+        if migrations::v1_0_1::is_migrating(deps.storage)? {
+            let user_addr = deps.api.addr_validate(user)?;
+            let storage = migrations::v1_0_1::MigratedStorage::new(deps.storage, &user_addr)?;
+            let deps = Deps {
+                storage: &storage,
+                api: deps.api,
+                querier: deps.querier,
+            };
+            return query_inner(deps, env, msg);
+        }
+    }
+
+    query_inner(deps, env, msg)
+}
+
+fn query_inner(deps: Deps, env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
     let res = match msg {
         QueryMsg::Config {} => to_binary(&query::query_config(deps)?),
+        QueryMsg::PauseState {
+            denom,
+        } => to_binary(&query::query_pause_state(deps, denom)?),
+        QueryMsg::PendingChange {
+            id,
+        } => to_binary(&query::query_pending_change(deps, id)?),
+        QueryMsg::PendingChanges {
+            start_after,
+            limit,
+        } => to_binary(&query::query_pending_changes(deps, start_after, limit)?),
         QueryMsg::Market {
             denom,
         } => to_binary(&query::query_market(deps, denom)?),
@@ -102,6 +295,106 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> Result<Binary, ContractErro
             start_after,
             limit,
         } => to_binary(&query::query_markets(deps, start_after, limit)?),
+        QueryMsg::MarketLive {
+            denom,
+        } => to_binary(&query::query_market_live(deps, env, denom)?),
+        QueryMsg::MarketsLive {
+            start_after,
+            limit,
+        } => to_binary(&query::query_markets_live(deps, env, start_after, limit)?),
+        QueryMsg::LiquidationBonus {
+            denom,
+            liquidation_health_factor,
+        } => to_binary(&query::query_liquidation_bonus(deps, denom, liquidation_health_factor)?),
+        QueryMsg::LiquidationPreview {
+            user,
+            account_id,
+            collateral_denom,
+            debt_denom,
+            amount,
+        } => {
+            let user_addr = deps.api.addr_validate(&user)?;
+            to_binary(&query::query_liquidation_preview(
+                deps,
+                env,
+                user_addr,
+                account_id.unwrap_or_default(),
+                collateral_denom,
+                debt_denom,
+                amount,
+            )?)
+        }
+        QueryMsg::EModeCategory {
+            id,
+        } => to_binary(&query::query_emode_category(deps, id)?),
+        QueryMsg::EModeCategories {
+            start_after,
+            limit,
+        } => to_binary(&query::query_emode_categories(deps, start_after, limit)?),
+        QueryMsg::UserEMode {
+            user,
+            account_id,
+        } => {
+            let user_addr = deps.api.addr_validate(&user)?;
+            to_binary(&query::query_user_emode(deps, user_addr, account_id.unwrap_or_default())?)
+        }
+        QueryMsg::Deficit {
+            denom,
+        } => to_binary(&query::query_deficit(deps, denom)?),
+        QueryMsg::Deficits {
+            start_after,
+            limit,
+        } => to_binary(&query::query_deficits(deps, start_after, limit)?),
+        QueryMsg::Borrowers {
+            start_after,
+            limit,
+            max_health_factor,
+        } => to_binary(&query::query_borrowers(deps, env, start_after, limit, max_health_factor)?),
+        QueryMsg::BorrowAllowance {
+            delegator,
+            account_id,
+            delegatee,
+            denom,
+        } => {
+            let delegator_addr = deps.api.addr_validate(&delegator)?;
+            let delegatee_addr = deps.api.addr_validate(&delegatee)?;
+            to_binary(&query::query_borrow_allowance(
+                deps,
+                delegator_addr,
+                account_id.unwrap_or_default(),
+                delegatee_addr,
+                denom,
+            )?)
+        }
+        QueryMsg::Operator {
+            user,
+            account_id,
+            operator,
+        } => {
+            let user_addr = deps.api.addr_validate(&user)?;
+            let operator_addr = deps.api.addr_validate(&operator)?;
+            to_binary(&query::query_operator(
+                deps,
+                user_addr,
+                account_id.unwrap_or_default(),
+                operator_addr,
+            )?)
+        }
+        QueryMsg::Operators {
+            user,
+            account_id,
+            start_after,
+            limit,
+        } => {
+            let user_addr = deps.api.addr_validate(&user)?;
+            to_binary(&query::query_operators(
+                deps,
+                user_addr,
+                account_id.unwrap_or_default(),
+                start_after,
+                limit,
+            )?)
+        }
         QueryMsg::UncollateralizedLoanLimit {
             user,
             denom,
@@ -125,27 +418,57 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> Result<Binary, ContractErro
         QueryMsg::UserDebt {
             user,
             denom,
+            account_id,
         } => {
             let user_addr = deps.api.addr_validate(&user)?;
-            to_binary(&query::query_user_debt(deps, &env.block, user_addr, denom)?)
+            to_binary(&query::query_user_debt(
+                deps,
+                &env.block,
+                user_addr,
+                account_id.unwrap_or_default(),
+                denom,
+            )?)
         }
         QueryMsg::UserDebts {
             user,
+            account_id,
             start_after,
             limit,
         } => {
             let user_addr = deps.api.addr_validate(&user)?;
-            to_binary(&query::query_user_debts(deps, &env.block, user_addr, start_after, limit)?)
+            to_binary(&query::query_user_debts(
+                deps,
+                &env.block,
+                user_addr,
+                account_id.unwrap_or_default(),
+                start_after,
+                limit,
+            )?)
         }
         QueryMsg::UserCollateral {
             user,
             denom,
+            account_id,
         } => {
             let user_addr = deps.api.addr_validate(&user)?;
-            to_binary(&query::query_user_collateral(deps, &env.block, user_addr, denom)?)
+            to_binary(&query::query_user_collateral(
+                deps,
+                &env.block,
+                user_addr,
+                account_id.unwrap_or_default(),
+                denom,
+            )?)
+        }
+        QueryMsg::UserTotalCollateralScaled {
+            user,
+            denom,
+        } => {
+            let user_addr = deps.api.addr_validate(&user)?;
+            to_binary(&query::query_user_total_collateral_scaled(deps, user_addr, denom)?)
         }
         QueryMsg::UserCollaterals {
             user,
+            account_id,
             start_after,
             limit,
         } => {
@@ -154,15 +477,50 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> Result<Binary, ContractErro
                 deps,
                 &env.block,
                 user_addr,
+                account_id.unwrap_or_default(),
                 start_after,
                 limit,
             )?)
         }
         QueryMsg::UserPosition {
             user,
+            account_id,
         } => {
             let user_addr = deps.api.addr_validate(&user)?;
-            to_binary(&query::query_user_position(deps, env, user_addr)?)
+            to_binary(&query::query_user_position(
+                deps,
+                env,
+                user_addr,
+                account_id.unwrap_or_default(),
+            )?)
+        }
+        QueryMsg::MaxBorrow {
+            user,
+            denom,
+            account_id,
+        } => {
+            let user_addr = deps.api.addr_validate(&user)?;
+            to_binary(&query::query_max_borrow(
+                deps,
+                env,
+                user_addr,
+                account_id.unwrap_or_default(),
+                denom,
+            )?)
+        }
+        QueryMsg::MaxWithdraw {
+            user,
+            denom,
+            account_id,
+        } => {
+            let user_addr = deps.api.addr_validate(&user)?;
+            to_binary(&query::query_max_withdraw(
+                deps,
+                env,
+                user_addr,
+                account_id.unwrap_or_default(),
+                denom,
+            )?)
         }
         QueryMsg::ScaledLiquidityAmount {
             denom,
@@ -183,3 +541,8 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> Result<Binary, ContractErro
     };
     res.map_err(Into::into)
 }
+
+#[entry_point]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: Empty) -> Result<Response, ContractError> {
+    migrations::v1_0_1::migrate(deps)
+}
@@ -1,4 +1,4 @@
-use cosmwasm_std::{OverflowError, StdError};
+use cosmwasm_std::{CheckedMultiplyRatioError, OverflowError, StdError, Uint128};
 use cw_utils::PaymentError;
 use mars_health::error::HealthError;
 use mars_owner::OwnerError;
@@ -29,6 +29,15 @@ pub enum ContractError {
     #[error("{0}")]
     Health(#[from] HealthError),
 
+    #[error("{0}")]
+    CheckedMultiplyRatio(#[from] CheckedMultiplyRatioError),
+
+    #[error("{0}")]
+    Version(#[from] cw2::VersionError),
+
+    #[error("No positions of version 1.0.1 are left to migrate")]
+    NoMigrationInProgress {},
+
     #[error("Price not found for asset: {denom:?}")]
     PriceNotFound {
         denom: String,
@@ -61,6 +70,50 @@ pub enum ContractError {
         denom: String,
     },
 
+    #[error("Borrow Cap exceeded for {denom:?}")]
+    BorrowCapExceeded {
+        denom: String,
+    },
+
+    #[error("Debt in {denom:?} must be repaid in full or be at least {min_debt}")]
+    DebtBelowMinimum {
+        denom: String,
+        min_debt: Uint128,
+    },
+
+    #[error("Debt ceiling exceeded for isolated collateral {denom:?}")]
+    DebtCeilingExceeded {
+        denom: String,
+    },
+
+    #[error("Borrowing {denom:?} is not allowed in isolation mode")]
+    BorrowNotAllowedInIsolationMode {
+        denom: String,
+    },
+
+    #[error("Cannot enable {denom:?} as collateral together with isolated collateral")]
+    IsolatedCollateralConflict {
+        denom: String,
+    },
+
+    #[error("Cannot enable isolated collateral {denom:?} while having debt")]
+    IsolatedCollateralWithDebt {
+        denom: String,
+    },
+
+    #[error("Borrowing {denom:?} is not allowed outside of the user's e-mode category")]
+    BorrowOutsideEModeCategory {
+        denom: String,
+    },
+
+    #[error("User has debt in {denom:?}, which is not in the e-mode category")]
+    DebtOutsideEModeCategory {
+        denom: String,
+    },
+
+    #[error("User's health factor can't be less than 1 after changing e-mode")]
+    InvalidHealthFactorAfterSettingEMode {},
+
     #[error("Cannot have 0 as liquidity index")]
     InvalidLiquidityIndex {},
 
@@ -84,6 +137,18 @@ pub enum ContractError {
     #[error("User has a positive uncollateralized loan limit and thus cannot be liquidated")]
     CannotLiquidateWhenPositiveUncollateralizedLoanLimit {},
 
+    #[error("Batch must contain at least one action")]
+    EmptyBatch {},
+
+    #[error("Coins sent must add up to the amounts of the deposits and repayments in each denom")]
+    InvalidBatchFunds {},
+
+    #[error("User's health factor can't be less than 1 after executing the batch")]
+    InvalidHealthFactorAfterBatch {},
+
+    #[error("Coins sent must add up to the amounts of the liquidations in each denom")]
+    InvalidLiquidationFunds {},
+
     #[error("User can't be liquidated for asset {denom:?} not being used as collateral")]
     CannotLiquidateWhenCollateralUnset {
         denom: String,
@@ -140,4 +205,113 @@ pub enum ContractError {
 
     #[error("Cannot repay uncollateralized loan on behalf of another user")]
     CannotRepayUncollateralizedLoanOnBehalfOf {},
+
+    #[error("Cannot borrow uncollateralized loan on behalf of another user")]
+    CannotBorrowUncollateralizedLoanOnBehalfOf {},
+
+    #[error("Borrow amount exceeds the allowance of {denom:?} approved by the delegator")]
+    BorrowAmountExceedsAllowance {
+        denom: String,
+    },
+
+    #[error("Sender is not approved by the user as operator with {permission:?} permission")]
+    OperatorNotApproved {
+        permission: String,
+    },
+
+    #[error("Operator approval must expire in the future")]
+    InvalidOperatorExpiration {},
+
+    #[error("Operator cannot send the withdrawn funds to another recipient than the user")]
+    OperatorCannotSetRecipient {},
+
+    #[error("Deleverage amount must be greater than 0 and not exceed the collateral nor debt of {denom:?}")]
+    InvalidDeleverageAmount {
+        denom: String,
+    },
+
+    #[error("Deleveraging can't lower the position's health factor")]
+    InvalidHealthFactorAfterDeleverage {},
+
+    #[error("Market {denom:?} is delisted")]
+    MarketDelisted {
+        denom: String,
+    },
+
+    #[error("Market {denom:?} must be delisted first")]
+    MarketNotDelisted {
+        denom: String,
+    },
+
+    #[error("Market {denom:?} must be sunset first")]
+    MarketNotSunset {
+        denom: String,
+    },
+
+    #[error("Market {denom:?} still has debt")]
+    MarketHasDebt {
+        denom: String,
+    },
+
+    #[error("Market {denom:?} still has collateral")]
+    MarketHasCollateral {
+        denom: String,
+    },
+
+    #[error("Market {denom:?} still has a deficit to cover")]
+    MarketHasDeficit {
+        denom: String,
+    },
+
+    #[error("Pending change {id} not found")]
+    PendingChangeNotFound {
+        id: u64,
+    },
+
+    #[error("Pending change {id} can't be applied before {executable_at}")]
+    PendingChangeNotReady {
+        id: u64,
+        executable_at: u64,
+    },
+
+    #[error("Pending change {id} expired at {expires_at}")]
+    PendingChangeExpired {
+        id: u64,
+        expires_at: u64,
+    },
+
+    #[error("{action} is paused for {denom:?}")]
+    ActionPaused {
+        action: String,
+        denom: String,
+    },
+
+    #[error("Flash loan coins must be non-zero and have unique denoms")]
+    InvalidFlashLoanCoins {},
+
+    #[error("A flash loan is already in progress")]
+    FlashLoanInProgress {},
+
+    #[error("No flash loan in progress")]
+    NoFlashLoanInProgress {},
+
+    #[error("Flash loan in progress does not include {denom:?}")]
+    UnexpectedFlashLoanRepayment {
+        denom: String,
+    },
+
+    #[error("Flash loan of {denom:?} plus fee has not been repaid")]
+    FlashLoanNotRepaid {
+        denom: String,
+    },
+
+    #[error("No deficit to cover for {denom:?}")]
+    NoDeficit {
+        denom: String,
+    },
+
+    #[error("Deficit for {denom:?} can't be covered as there are no reserves or deposits left")]
+    DeficitExceedsDeposits {
+        denom: String,
+    },
 }
@@ -1,17 +1,28 @@
-use std::{cmp::min, str};
+use std::{
+    cmp::{max, min},
+    collections::BTreeMap,
+    str,
+};
 
 use cosmwasm_std::{
-    Addr, Decimal, DepsMut, Env, MessageInfo, Response, StdError, StdResult, Uint128,
+    to_binary, Addr, Api, BankMsg, Binary, Coin, CosmosMsg, Decimal, DepsMut, Env, Event, Fraction,
+    MessageInfo, Order, QuerierWrapper, Response, StdError, StdResult, Storage, Uint128, WasmMsg,
 };
+use cw_utils::PaymentError;
 use mars_owner::{OwnerError, OwnerInit::SetInitialOwner, OwnerUpdate};
 use mars_red_bank_types::{
     address_provider::{self, MarsAddressType},
     error::MarsError,
+    oracle,
     red_bank::{
-        Config, CreateOrUpdateConfig, Debt, InitOrUpdateAssetParams, InstantiateMsg, Market,
+        Action, CallbackMsg, Config, CreateOrUpdateConfig, Debt, EModeCategory, ExecuteMsg,
+        FlashLoan, InitOrUpdateAssetParams, InstantiateMsg, Liquidation, LiquidationBonus, Market,
+        MarketStatus, Operator, OperatorPermission, ParamsChange, PausableAction, PauseState,
+        PendingChange, RiskParamsRamp,
     },
 };
 use mars_utils::{
+    error::ValidationError,
     helpers::{build_send_asset_msg, option_string_to_addr, validate_native_denom, zero_address},
     math,
 };
@@ -20,21 +31,27 @@ use crate::{
     error::ContractError,
     health::{
         assert_below_liq_threshold_after_withdraw, assert_below_max_ltv_after_borrow,
-        assert_liquidatable,
+        assert_liquidatable, compute_position_health, get_user_positions_map,
     },
     interest_rates::{
-        apply_accumulated_interests, get_scaled_debt_amount, get_scaled_liquidity_amount,
-        get_underlying_debt_amount, get_underlying_liquidity_amount, update_interest_rates,
+        apply_accumulated_interests, compute_scaled_amount, compute_underlying_amount,
+        get_scaled_debt_amount, get_scaled_liquidity_amount, get_underlying_debt_amount,
+        get_underlying_liquidity_amount, update_interest_rates, ScalingOperation,
     },
     state::{
-        COLLATERALS, CONFIG, DEBTS, EMERGENCY_OWNER, MARKETS, OWNER, UNCOLLATERALIZED_LOAN_LIMITS,
+        BORROW_ALLOWANCES, COLLATERALS, CONFIG, DEBTS, DEFICITS, EMERGENCY_OWNER, EMODE_CATEGORIES,
+        FLASH_LOANS, GLOBAL_PAUSE_STATE, ISOLATED_DEBTS, MARKETS, NEXT_PENDING_CHANGE_ID,
+        OPERATORS, OWNER, PAUSE_STATES, PENDING_CHANGES, UNCOLLATERALIZED_LOAN_LIMITS, USER_EMODES,
     },
-    user::User,
+    user::{User, MAIN_ACCOUNT_ID},
 };
 
 pub const CONTRACT_NAME: &str = "crates.io:mars-red-bank";
 pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// Time (in seconds) a queued change can still be applied for once its timelock has passed
+pub const PENDING_CHANGE_GRACE_PERIOD: u64 = 7 * 24 * 60 * 60;
+
 pub fn instantiate(deps: DepsMut, msg: InstantiateMsg) -> Result<Response, ContractError> {
     cw2::set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
@@ -43,6 +60,7 @@ pub fn instantiate(deps: DepsMut, msg: InstantiateMsg) -> Result<Response, Contr
     let CreateOrUpdateConfig {
         address_provider,
         close_factor,
+        timelock,
     } = msg.config;
 
     // All fields should be available
@@ -55,6 +73,8 @@ pub fn instantiate(deps: DepsMut, msg: InstantiateMsg) -> Result<Response, Contr
     let config = Config {
         address_provider: option_string_to_addr(deps.api, address_provider, zero_address())?,
         close_factor: close_factor.unwrap(),
+        // if not specified, changes are applied immediately
+        timelock: timelock.unwrap_or_default(),
     };
 
     config.validate()?;
@@ -96,34 +116,59 @@ pub fn update_emergency_owner(
     Ok(EMERGENCY_OWNER.update(deps, info, update)?)
 }
 
-/// Update config
+/// Update config, or queue the update if a timelock is set
 pub fn update_config(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     new_config: CreateOrUpdateConfig,
 ) -> Result<Response, ContractError> {
     OWNER.assert_owner(deps.storage, &info.sender)?;
 
-    let mut config = CONFIG.load(deps.storage)?;
+    let config = CONFIG.load(deps.storage)?;
+    let timelock = config.timelock;
+
+    let updated_config = update_config_params(deps.api, config, new_config.clone())?;
+
+    if timelock > 0 {
+        return queue_change(
+            deps.storage,
+            &env,
+            timelock,
+            ParamsChange::UpdateConfig {
+                config: new_config,
+            },
+        );
+    }
+
+    CONFIG.save(deps.storage, &updated_config)?;
+
+    Ok(Response::new().add_attribute("action", "update_config"))
+}
 
+fn update_config_params(
+    api: &dyn Api,
+    mut config: Config<Addr>,
+    new_config: CreateOrUpdateConfig,
+) -> Result<Config<Addr>, ContractError> {
     // Destructuring a struct’s fields into separate variables in order to force
     // compile error if we add more params
     let CreateOrUpdateConfig {
         address_provider,
         close_factor,
+        timelock,
     } = new_config;
 
     // Update config
     config.address_provider =
-        option_string_to_addr(deps.api, address_provider, config.address_provider)?;
+        option_string_to_addr(api, address_provider, config.address_provider)?;
     config.close_factor = close_factor.unwrap_or(config.close_factor);
+    config.timelock = timelock.unwrap_or(config.timelock);
 
     // Validate config
     config.validate()?;
 
-    CONFIG.save(deps.storage, &config)?;
-
-    Ok(Response::new().add_attribute("action", "update_config"))
+    Ok(config)
 }
 
 /// Initialize asset if not exist.
@@ -166,6 +211,15 @@ pub fn create_market(
         deposit_enabled,
         borrow_enabled,
         deposit_cap,
+        borrow_cap,
+        min_debt,
+        isolated,
+        debt_ceiling,
+        borrowable_in_isolation,
+        flash_loan_fee,
+        interest_accrual,
+        protocol_liquidation_fee,
+        ramp_duration: _,
     } = params;
 
     // All fields should be available
@@ -188,17 +242,33 @@ pub fn create_market(
         borrow_rate: Decimal::zero(),
         liquidity_rate: Decimal::zero(),
         max_loan_to_value: max_loan_to_value.unwrap(),
+        risk_params_ramp: None,
         reserve_factor: reserve_factor.unwrap(),
+        // if not specified, flash loans are free of charge
+        flash_loan_fee: flash_loan_fee.unwrap_or_else(Decimal::zero),
         indexes_last_updated: block_time,
         collateral_total_scaled: Uint128::zero(),
         debt_total_scaled: Uint128::zero(),
         liquidation_threshold: liquidation_threshold.unwrap(),
         liquidation_bonus: liquidation_bonus.unwrap(),
+        // if not specified, the whole liquidation bonus goes to the liquidator
+        protocol_liquidation_fee: protocol_liquidation_fee.unwrap_or_else(Decimal::zero),
         interest_rate_model: interest_rate_model.unwrap(),
+        // if not specified, interest is accrued linearly
+        interest_accrual: interest_accrual.unwrap_or_default(),
         deposit_enabled: deposit_enabled.unwrap(),
         borrow_enabled: borrow_enabled.unwrap(),
         // if not specified, deposit cap is set to unlimited
         deposit_cap: deposit_cap.unwrap_or(Uint128::MAX),
+        // if not specified, borrow cap is set to unlimited
+        borrow_cap: borrow_cap.unwrap_or(Uint128::MAX),
+        // if not specified, positions can be left with any debt
+        min_debt: min_debt.unwrap_or_else(Uint128::zero),
+        // if not specified, the asset is not isolated and can't be borrowed in isolation mode
+        isolated: isolated.unwrap_or(false),
+        debt_ceiling: debt_ceiling.unwrap_or_else(Uint128::zero),
+        borrowable_in_isolation: borrowable_in_isolation.unwrap_or(false),
+        status: MarketStatus::Listed,
     };
 
     new_market.validate()?;
@@ -206,7 +276,7 @@ pub fn create_market(
     Ok(new_market)
 }
 
-/// Update asset with new params.
+/// Update asset with new params, or queue the update if a timelock is set.
 pub fn update_asset(
     deps: DepsMut,
     env: Env,
@@ -215,7 +285,30 @@ pub fn update_asset(
     params: InitOrUpdateAssetParams,
 ) -> Result<Response, ContractError> {
     if OWNER.is_owner(deps.storage, &info.sender)? {
-        update_asset_by_owner(deps, &env, &denom, params)
+        let timelock = CONFIG.load(deps.storage)?.timelock;
+        if timelock == 0 {
+            return update_asset_by_owner(deps, &env, &denom, params);
+        }
+
+        // Validate the change before queuing it
+        let market =
+            MARKETS.may_load(deps.storage, &denom)?.ok_or(ContractError::AssetNotInitialized {})?;
+        if market.status != MarketStatus::Listed {
+            return Err(ContractError::MarketDelisted {
+                denom,
+            });
+        }
+        update_market_params(market, params.clone(), env.block.time.seconds()).validate()?;
+
+        queue_change(
+            deps.storage,
+            &env,
+            timelock,
+            ParamsChange::UpdateAsset {
+                denom,
+                params,
+            },
+        )
     } else if EMERGENCY_OWNER.is_owner(deps.storage, &info.sender)? {
         update_asset_by_emergency_owner(deps, &denom, params)
     } else {
@@ -232,27 +325,21 @@ fn update_asset_by_owner(
     let market_option = MARKETS.may_load(deps.storage, denom)?;
     match market_option {
         None => Err(ContractError::AssetNotInitialized {}),
+        Some(market) if market.status != MarketStatus::Listed => {
+            Err(ContractError::MarketDelisted {
+                denom: denom.to_string(),
+            })
+        }
         Some(mut market) => {
-            // Destructuring a struct’s fields into separate variables in order to force
-            // compile error if we add more params
-            let InitOrUpdateAssetParams {
-                max_loan_to_value,
-                reserve_factor,
-                liquidation_threshold,
-                liquidation_bonus,
-                interest_rate_model,
-                deposit_enabled,
-                borrow_enabled,
-                deposit_cap,
-            } = params;
-
-            // If reserve factor or interest rates are updated we update indexes with
-            // current values before applying the change to prevent applying this
+            // If reserve factor, interest rates or interest accrual are updated we update
+            // indexes with current values before applying the change to prevent applying this
             // new params to a period where they were not valid yet. Interests rates are
             // recalculated after changes are applied.
-            let should_update_interest_rates = (reserve_factor.is_some()
-                && reserve_factor.unwrap() != market.reserve_factor)
-                || interest_rate_model.is_some();
+            let should_update_interest_rates = (params.reserve_factor.is_some()
+                && params.reserve_factor.unwrap() != market.reserve_factor)
+                || params.interest_rate_model.is_some()
+                || (params.interest_accrual.is_some()
+                    && params.interest_accrual.unwrap() != market.interest_accrual);
 
             let mut response = Response::new();
 
@@ -276,18 +363,7 @@ fn update_asset_by_owner(
                 )?;
             }
 
-            let mut updated_market = Market {
-                max_loan_to_value: max_loan_to_value.unwrap_or(market.max_loan_to_value),
-                reserve_factor: reserve_factor.unwrap_or(market.reserve_factor),
-                liquidation_threshold: liquidation_threshold
-                    .unwrap_or(market.liquidation_threshold),
-                liquidation_bonus: liquidation_bonus.unwrap_or(market.liquidation_bonus),
-                interest_rate_model: interest_rate_model.unwrap_or(market.interest_rate_model),
-                deposit_enabled: deposit_enabled.unwrap_or(market.deposit_enabled),
-                borrow_enabled: borrow_enabled.unwrap_or(market.borrow_enabled),
-                deposit_cap: deposit_cap.unwrap_or(market.deposit_cap),
-                ..market
-            };
+            let mut updated_market = update_market_params(market, params, env.block.time.seconds());
 
             updated_market.validate()?;
 
@@ -308,111 +384,359 @@ fn update_asset_by_owner(
     }
 }
 
-/// Emergency owner can only DISABLE BORROWING.
-fn update_asset_by_emergency_owner(
-    deps: DepsMut,
-    denom: &str,
+fn update_market_params(
+    market: Market,
     params: InitOrUpdateAssetParams,
+    block_time: u64,
+) -> Market {
+    // Destructuring a struct’s fields into separate variables in order to force
+    // compile error if we add more params
+    let InitOrUpdateAssetParams {
+        max_loan_to_value,
+        reserve_factor,
+        liquidation_threshold,
+        liquidation_bonus,
+        interest_rate_model,
+        deposit_enabled,
+        borrow_enabled,
+        deposit_cap,
+        borrow_cap,
+        min_debt,
+        isolated,
+        debt_ceiling,
+        borrowable_in_isolation,
+        flash_loan_fee,
+        interest_accrual,
+        protocol_liquidation_fee,
+        ramp_duration,
+    } = params;
+
+    let risk_params_ramp = if max_loan_to_value.is_some() || liquidation_threshold.is_some() {
+        start_risk_params_ramp(&market, block_time, ramp_duration)
+    } else {
+        market.risk_params_ramp
+    };
+
+    Market {
+        max_loan_to_value: max_loan_to_value.unwrap_or(market.max_loan_to_value),
+        reserve_factor: reserve_factor.unwrap_or(market.reserve_factor),
+        flash_loan_fee: flash_loan_fee.unwrap_or(market.flash_loan_fee),
+        liquidation_threshold: liquidation_threshold.unwrap_or(market.liquidation_threshold),
+        risk_params_ramp,
+        liquidation_bonus: liquidation_bonus.unwrap_or(market.liquidation_bonus),
+        protocol_liquidation_fee: protocol_liquidation_fee
+            .unwrap_or(market.protocol_liquidation_fee),
+        interest_rate_model: interest_rate_model.unwrap_or(market.interest_rate_model),
+        interest_accrual: interest_accrual.unwrap_or(market.interest_accrual),
+        deposit_enabled: deposit_enabled.unwrap_or(market.deposit_enabled),
+        borrow_enabled: borrow_enabled.unwrap_or(market.borrow_enabled),
+        deposit_cap: deposit_cap.unwrap_or(market.deposit_cap),
+        borrow_cap: borrow_cap.unwrap_or(market.borrow_cap),
+        min_debt: min_debt.unwrap_or(market.min_debt),
+        isolated: isolated.unwrap_or(market.isolated),
+        debt_ceiling: debt_ceiling.unwrap_or(market.debt_ceiling),
+        borrowable_in_isolation: borrowable_in_isolation.unwrap_or(market.borrowable_in_isolation),
+        ..market
+    }
+}
+
+/// Ramp to new LTV and liquidation threshold from the market's current values, which may be in the
+/// middle of another ramp. Without a ramp duration, the new values apply immediately.
+fn start_risk_params_ramp(
+    market: &Market,
+    block_time: u64,
+    ramp_duration: Option<u64>,
+) -> Option<RiskParamsRamp> {
+    match ramp_duration {
+        Some(ramp_duration) if ramp_duration > 0 => Some(RiskParamsRamp {
+            initial_max_loan_to_value: market.max_loan_to_value_at(block_time),
+            initial_liquidation_threshold: market.liquidation_threshold_at(block_time),
+            start_time: block_time,
+            end_time: block_time + ramp_duration,
+        }),
+        _ => None,
+    }
+}
+
+/// Whether updating the market with the params only lowers the risk taken by the protocol: lower
+/// LTV, caps or debt ceiling, and disabled deposits or borrowing. Any other param must be left
+/// unchanged. A lower liquidation threshold doesn't count, as it can make positions liquidatable
+/// without giving their owners the timelock to react.
+fn only_lowers_risk(market: &Market, params: &InitOrUpdateAssetParams) -> bool {
+    fn unchanged<T: PartialEq>(new: &Option<T>, current: &T) -> bool {
+        new.as_ref().map(|new| new == current).unwrap_or(true)
+    }
+
+    fn not_higher<T: PartialOrd>(new: &Option<T>, current: &T) -> bool {
+        new.as_ref().map(|new| new <= current).unwrap_or(true)
+    }
+
+    let InitOrUpdateAssetParams {
+        max_loan_to_value,
+        reserve_factor,
+        liquidation_threshold,
+        liquidation_bonus,
+        interest_rate_model,
+        deposit_enabled,
+        borrow_enabled,
+        deposit_cap,
+        borrow_cap,
+        min_debt,
+        isolated,
+        debt_ceiling,
+        borrowable_in_isolation,
+        flash_loan_fee,
+        interest_accrual,
+        protocol_liquidation_fee,
+        ramp_duration: _,
+    } = params;
+
+    not_higher(max_loan_to_value, &market.max_loan_to_value)
+        && not_higher(deposit_enabled, &market.deposit_enabled)
+        && not_higher(borrow_enabled, &market.borrow_enabled)
+        && not_higher(deposit_cap, &market.deposit_cap)
+        && not_higher(borrow_cap, &market.borrow_cap)
+        && not_higher(debt_ceiling, &market.debt_ceiling)
+        && not_higher(borrowable_in_isolation, &market.borrowable_in_isolation)
+        && unchanged(liquidation_threshold, &market.liquidation_threshold)
+        && unchanged(reserve_factor, &market.reserve_factor)
+        && unchanged(liquidation_bonus, &market.liquidation_bonus)
+        && unchanged(interest_rate_model, &market.interest_rate_model)
+        && unchanged(min_debt, &market.min_debt)
+        && unchanged(isolated, &market.isolated)
+        && unchanged(flash_loan_fee, &market.flash_loan_fee)
+        && unchanged(interest_accrual, &market.interest_accrual)
+        && unchanged(protocol_liquidation_fee, &market.protocol_liquidation_fee)
+}
+
+fn queue_change(
+    store: &mut dyn Storage,
+    env: &Env,
+    timelock: u64,
+    change: ParamsChange,
 ) -> Result<Response, ContractError> {
-    if let Some(mut market) = MARKETS.may_load(deps.storage, denom)? {
-        match params.borrow_enabled {
-            Some(borrow_enabled) if !borrow_enabled => {
-                market.borrow_enabled = borrow_enabled;
-                MARKETS.save(deps.storage, denom, &market)?;
+    let id = NEXT_PENDING_CHANGE_ID.may_load(store)?.unwrap_or_default();
+    NEXT_PENDING_CHANGE_ID.save(store, &(id + 1))?;
+
+    let executable_at = env.block.time.seconds() + timelock;
+    let expires_at = executable_at + PENDING_CHANGE_GRACE_PERIOD;
+    let mut response = Response::new().add_attribute("action", "queue_change");
+    response = match &change {
+        ParamsChange::UpdateConfig {
+            ..
+        } => response.add_attribute("change", "update_config"),
+        ParamsChange::UpdateAsset {
+            denom,
+            ..
+        } => response.add_attribute("change", "update_asset").add_attribute("denom", denom),
+        ParamsChange::SetEModeCategory {
+            category,
+        } => response
+            .add_attribute("change", "set_emode_category")
+            .add_attribute("category_id", category.id.to_string()),
+    };
 
-                Ok(Response::new()
-                    .add_attribute("action", "emergency_update_asset")
-                    .add_attribute("denom", denom))
-            }
-            _ => Err(MarsError::Unauthorized {}.into()),
+    PENDING_CHANGES.save(
+        store,
+        id,
+        &PendingChange {
+            id,
+            change,
+            executable_at,
+            expires_at,
+        },
+    )?;
+
+    Ok(response
+        .add_attribute("id", id.to_string())
+        .add_attribute("executable_at", executable_at.to_string())
+        .add_attribute("expires_at", expires_at.to_string()))
+}
+
+/// Apply a queued change. Anyone can apply it once the timelock has passed and until it expires, the
+/// owner and emergency owner can apply it earlier if it only lowers the risk taken by the protocol.
+pub fn execute_pending_change(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: u64,
+) -> Result<Response, ContractError> {
+    let pending_change = PENDING_CHANGES.may_load(deps.storage, id)?.ok_or(
+        ContractError::PendingChangeNotFound {
+            id,
+        },
+    )?;
+
+    // A change left pending for too long may not match the state of the markets anymore
+    if env.block.time.seconds() >= pending_change.expires_at {
+        return Err(ContractError::PendingChangeExpired {
+            id,
+            expires_at: pending_change.expires_at,
+        });
+    }
+
+    if env.block.time.seconds() < pending_change.executable_at {
+        let is_owner = OWNER.is_owner(deps.storage, &info.sender)?
+            || EMERGENCY_OWNER.is_owner(deps.storage, &info.sender)?;
+        // Config changes, such as a higher close factor, are never fast-tracked
+        let lowers_risk = match &pending_change.change {
+            ParamsChange::UpdateConfig {
+                ..
+            } => false,
+            ParamsChange::UpdateAsset {
+                denom,
+                params,
+            } => MARKETS
+                .may_load(deps.storage, denom)?
+                .map(|market| only_lowers_risk(&market, params))
+                .unwrap_or(false),
+            ParamsChange::SetEModeCategory {
+                category,
+            } => EMODE_CATEGORIES
+                .may_load(deps.storage, category.id)?
+                .map(|current| emode_change_only_lowers_risk(&current, category))
+                .unwrap_or(false),
+        };
+        if !is_owner || !lowers_risk {
+            return Err(ContractError::PendingChangeNotReady {
+                id,
+                executable_at: pending_change.executable_at,
+            });
         }
-    } else {
-        Err(ContractError::AssetNotInitialized {})
     }
+
+    PENDING_CHANGES.remove(deps.storage, id);
+
+    let response = match pending_change.change {
+        ParamsChange::UpdateConfig {
+            config: new_config,
+        } => {
+            let config = CONFIG.load(deps.storage)?;
+            let updated_config = update_config_params(deps.api, config, new_config)?;
+            CONFIG.save(deps.storage, &updated_config)?;
+            Response::new().add_attribute("action", "update_config")
+        }
+        ParamsChange::UpdateAsset {
+            denom,
+            params,
+        } => update_asset_by_owner(deps, &env, &denom, params)?,
+        ParamsChange::SetEModeCategory {
+            category,
+        } => update_emode_category(deps.storage, category)?,
+    };
+
+    Ok(response.add_attribute("pending_change_id", id.to_string()))
 }
 
-/// Update uncollateralized loan limit by a given amount in base asset
-pub fn update_uncollateralized_loan_limit(
+/// Drop a queued change
+pub fn cancel_pending_change(
     deps: DepsMut,
     info: MessageInfo,
-    user_addr: Addr,
+    id: u64,
+) -> Result<Response, ContractError> {
+    if !OWNER.is_owner(deps.storage, &info.sender)?
+        && !EMERGENCY_OWNER.is_owner(deps.storage, &info.sender)?
+    {
+        return Err(OwnerError::NotOwner {}.into());
+    }
+
+    if !PENDING_CHANGES.has(deps.storage, id) {
+        return Err(ContractError::PendingChangeNotFound {
+            id,
+        });
+    }
+    PENDING_CHANGES.remove(deps.storage, id);
+
+    Ok(Response::new()
+        .add_attribute("action", "cancel_pending_change")
+        .add_attribute("id", id.to_string()))
+}
+
+/// Disable deposits and borrows of a market for good, and ramp its max LTV and liquidation threshold
+/// to zero. The ramp lasts at least as long as the timelock, so that users get the same notice as
+/// for any other change lowering the collateral weight.
+pub fn delist_asset(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
     denom: String,
-    new_limit: Uint128,
+    ramp_duration: u64,
 ) -> Result<Response, ContractError> {
     OWNER.assert_owner(deps.storage, &info.sender)?;
 
-    // Check that the user has no collateralized debt
-    let current_limit = UNCOLLATERALIZED_LOAN_LIMITS
-        .may_load(deps.storage, (&user_addr, &denom))?
-        .unwrap_or_else(Uint128::zero);
-    let current_debt = DEBTS
-        .may_load(deps.storage, (&user_addr, &denom))?
-        .map(|debt| debt.amount_scaled)
-        .unwrap_or_else(Uint128::zero);
-    if current_limit.is_zero() && !current_debt.is_zero() {
-        return Err(ContractError::UserHasCollateralizedDebt {});
+    let mut market =
+        MARKETS.may_load(deps.storage, &denom)?.ok_or(ContractError::AssetNotInitialized {})?;
+    if market.status != MarketStatus::Listed {
+        return Err(ContractError::MarketDelisted {
+            denom,
+        });
     }
-    if !current_limit.is_zero() && new_limit.is_zero() && !current_debt.is_zero() {
-        return Err(ContractError::UserHasUncollateralizedDebt {});
+
+    let min_ramp_duration = max(CONFIG.load(deps.storage)?.timelock, 1);
+    if ramp_duration < min_ramp_duration {
+        return Err(ValidationError::InvalidParam {
+            param_name: "ramp_duration".to_string(),
+            invalid_value: ramp_duration.to_string(),
+            predicate: format!(">= {min_ramp_duration}"),
+        }
+        .into());
     }
 
-    UNCOLLATERALIZED_LOAN_LIMITS.save(deps.storage, (&user_addr, &denom), &new_limit)?;
+    market.risk_params_ramp =
+        start_risk_params_ramp(&market, env.block.time.seconds(), Some(ramp_duration));
+    market.max_loan_to_value = Decimal::zero();
+    market.liquidation_threshold = Decimal::zero();
+    market.deposit_enabled = false;
+    market.borrow_enabled = false;
+    market.status = MarketStatus::Delisted;
 
-    DEBTS.update(deps.storage, (&user_addr, &denom), |debt_opt: Option<Debt>| -> StdResult<_> {
-        let mut debt = debt_opt.unwrap_or(Debt {
-            amount_scaled: Uint128::zero(),
-            uncollateralized: false,
-        });
-        // if limit == 0 then uncollateralized = false, otherwise uncollateralized = true
-        debt.uncollateralized = !new_limit.is_zero();
-        Ok(debt)
-    })?;
+    MARKETS.save(deps.storage, &denom, &market)?;
+
+    // The params of an e-mode category would otherwise keep applying to the asset
+    remove_from_emode_categories(deps.storage, &denom)?;
 
     Ok(Response::new()
-        .add_attribute("action", "update_uncollateralized_loan_limit")
-        .add_attribute("user", user_addr)
+        .add_attribute("action", "delist_asset")
         .add_attribute("denom", denom)
-        .add_attribute("new_allowance", new_limit))
+        .add_attribute("ramp_duration", ramp_duration.to_string()))
 }
 
-/// Execute deposits
-pub fn deposit(
+fn remove_from_emode_categories(store: &mut dyn Storage, denom: &str) -> StdResult<()> {
+    let categories = EMODE_CATEGORIES
+        .range(store, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (id, mut category) in categories {
+        if category.denoms.iter().any(|category_denom| category_denom == denom) {
+            category.denoms.retain(|category_denom| category_denom != denom);
+            EMODE_CATEGORIES.save(store, id, &category)?;
+        }
+    }
+    Ok(())
+}
+
+/// Stop accruing interest on a delisted market with no debt left
+pub fn sunset_asset(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    on_behalf_of: Option<String>,
     denom: String,
-    deposit_amount: Uint128,
 ) -> Result<Response, ContractError> {
-    let user_addr: Addr;
-    let user = if let Some(address) = on_behalf_of {
-        user_addr = deps.api.addr_validate(&address)?;
-        User(&user_addr)
-    } else {
-        User(&info.sender)
-    };
+    OWNER.assert_owner(deps.storage, &info.sender)?;
 
-    let mut market = MARKETS.load(deps.storage, &denom)?;
-    if !market.deposit_enabled {
-        return Err(ContractError::DepositNotEnabled {
+    let mut market =
+        MARKETS.may_load(deps.storage, &denom)?.ok_or(ContractError::AssetNotInitialized {})?;
+    if market.status != MarketStatus::Delisted {
+        return Err(ContractError::MarketNotDelisted {
             denom,
         });
     }
-
-    let total_scaled_deposits = market.collateral_total_scaled;
-    let total_deposits =
-        get_underlying_liquidity_amount(total_scaled_deposits, &market, env.block.time.seconds())?;
-    if total_deposits.checked_add(deposit_amount)? > market.deposit_cap {
-        return Err(ContractError::DepositCapExceeded {
+    if !market.debt_total_scaled.is_zero() {
+        return Err(ContractError::MarketHasDebt {
             denom,
         });
     }
 
-    let mut response = Response::new();
-
     let config = CONFIG.load(deps.storage)?;
-
-    // update indexes and interest rates
     let addresses = address_provider::helpers::query_contract_addrs(
         deps.as_ref(),
         &config.address_provider,
@@ -421,107 +745,492 @@ pub fn deposit(
     let rewards_collector_addr = &addresses[&MarsAddressType::RewardsCollector];
     let incentives_addr = &addresses[&MarsAddressType::Incentives];
 
-    response = apply_accumulated_interests(
+    // Accrue interest up to now one last time, then zero the rates
+    let mut response = apply_accumulated_interests(
         deps.storage,
         &env,
         &mut market,
         rewards_collector_addr,
         incentives_addr,
-        response,
+        Response::new(),
     )?;
 
+    market.status = MarketStatus::Sunset;
     response = update_interest_rates(&deps, &env, &mut market, Uint128::zero(), &denom, response)?;
 
-    if market.liquidity_index.is_zero() {
-        return Err(ContractError::InvalidLiquidityIndex {});
-    }
-    let deposit_amount_scaled =
-        get_scaled_liquidity_amount(deposit_amount, &market, env.block.time.seconds())?;
-
-    response = user.increase_collateral(
-        deps.storage,
-        &market,
-        deposit_amount_scaled,
-        incentives_addr,
-        response,
-    )?;
-
-    market.increase_collateral(deposit_amount_scaled)?;
     MARKETS.save(deps.storage, &denom, &market)?;
 
-    Ok(response
-        .add_attribute("action", "deposit")
-        .add_attribute("sender", &info.sender)
-        .add_attribute("on_behalf_of", user)
-        .add_attribute("denom", denom)
-        .add_attribute("amount", deposit_amount)
-        .add_attribute("amount_scaled", deposit_amount_scaled))
+    Ok(response.add_attribute("action", "sunset_asset").add_attribute("denom", denom))
 }
 
-/// Burns sent maAsset in exchange of underlying asset
-pub fn withdraw(
+/// Remove a sunset market and its state once all collateral has been withdrawn and its deficit
+/// covered
+pub fn remove_asset(
     deps: DepsMut,
-    env: Env,
     info: MessageInfo,
     denom: String,
-    amount: Option<Uint128>,
-    recipient: Option<String>,
 ) -> Result<Response, ContractError> {
-    let withdrawer = User(&info.sender);
-
-    let mut market = MARKETS.load(deps.storage, &denom)?;
-
-    let collateral = withdrawer.collateral(deps.storage, &denom)?;
-    let withdrawer_balance_scaled_before = collateral.amount_scaled;
+    OWNER.assert_owner(deps.storage, &info.sender)?;
 
-    if withdrawer_balance_scaled_before.is_zero() {
-        return Err(ContractError::UserNoCollateralBalance {
-            user: withdrawer.into(),
+    let market =
+        MARKETS.may_load(deps.storage, &denom)?.ok_or(ContractError::AssetNotInitialized {})?;
+    if market.status != MarketStatus::Sunset {
+        return Err(ContractError::MarketNotSunset {
+            denom,
+        });
+    }
+    if !market.collateral_total_scaled.is_zero() {
+        return Err(ContractError::MarketHasCollateral {
+            denom,
+        });
+    }
+    if !DEFICITS.may_load(deps.storage, &denom)?.unwrap_or_default().is_zero() {
+        return Err(ContractError::MarketHasDeficit {
             denom,
         });
     }
 
-    let withdrawer_balance_before = get_underlying_liquidity_amount(
-        withdrawer_balance_scaled_before,
-        &market,
-        env.block.time.seconds(),
-    )?;
+    MARKETS.remove(deps.storage, &denom);
+    PAUSE_STATES.remove(deps.storage, &denom);
+    ISOLATED_DEBTS.remove(deps.storage, &denom);
+    DEFICITS.remove(deps.storage, &denom);
 
-    let withdraw_amount = match amount {
-        // Check user has sufficient balance to send back
-        Some(amount) if amount.is_zero() || amount > withdrawer_balance_before => {
-            return Err(ContractError::InvalidWithdrawAmount {
-                denom,
-            });
+    // Drop the rest of the state referring to the market, so that none of it applies to a market
+    // initialized again with the same denom
+    remove_from_emode_categories(deps.storage, &denom)?;
+
+    let mut limit_users = vec![];
+    for key in UNCOLLATERALIZED_LOAN_LIMITS.keys(deps.storage, None, None, Order::Ascending) {
+        let (user_addr, limit_denom) = key?;
+        if limit_denom == denom {
+            limit_users.push(user_addr);
         }
-        Some(amount) => amount,
-        // If no amount is specified, the full balance is withdrawn
-        None => withdrawer_balance_before,
-    };
+    }
+    for user_addr in limit_users {
+        UNCOLLATERALIZED_LOAN_LIMITS.remove(deps.storage, (&user_addr, &denom));
+    }
 
-    let config = CONFIG.load(deps.storage)?;
+    let mut allowances = vec![];
+    for key in BORROW_ALLOWANCES.keys_raw(deps.storage, None, None, Order::Ascending) {
+        let (delegator_addr, account_id, delegatee_addr, allowance_denom) =
+            parse_borrow_allowance_key(&key)?;
+        if allowance_denom == denom {
+            allowances.push((delegator_addr, account_id, delegatee_addr));
+        }
+    }
+    for (delegator_addr, account_id, delegatee_addr) in allowances {
+        BORROW_ALLOWANCES
+            .remove(deps.storage, ((&delegator_addr, account_id), &delegatee_addr, &denom));
+    }
 
-    let addresses = address_provider::helpers::query_contract_addrs(
-        deps.as_ref(),
-        &config.address_provider,
-        vec![
-            MarsAddressType::Oracle,
-            MarsAddressType::Incentives,
-            MarsAddressType::RewardsCollector,
-        ],
-    )?;
-    let rewards_collector_addr = &addresses[&MarsAddressType::RewardsCollector];
-    let incentives_addr = &addresses[&MarsAddressType::Incentives];
-    let oracle_addr = &addresses[&MarsAddressType::Oracle];
+    let mut pending_change_ids = vec![];
+    for item in PENDING_CHANGES.range(deps.storage, None, None, Order::Ascending) {
+        let (id, pending_change) = item?;
+        let refers_to_market = match pending_change.change {
+            ParamsChange::UpdateConfig {
+                ..
+            } => false,
+            ParamsChange::UpdateAsset {
+                denom: change_denom,
+                ..
+            } => change_denom == denom,
+            ParamsChange::SetEModeCategory {
+                category,
+            } => category.denoms.contains(&denom),
+        };
+        if refers_to_market {
+            pending_change_ids.push(id);
+        }
+    }
+    for id in pending_change_ids {
+        PENDING_CHANGES.remove(deps.storage, id);
+    }
 
-    // if asset is used as collateral and user is borrowing we need to validate health factor after withdraw,
-    // otherwise no reasons to block the withdraw
-    if collateral.enabled
-        && withdrawer.is_borrowing(deps.storage)
+    Ok(Response::new().add_attribute("action", "remove_asset").add_attribute("denom", denom))
+}
+
+/// Split the raw key of a borrow allowance into the delegator's address and account id, the
+/// delegatee's address and the denom. Keys nesting a tuple can't be deserialized by the storage
+/// library, so the length-prefixed segments are split here.
+fn parse_borrow_allowance_key(key: &[u8]) -> StdResult<(Addr, u32, Addr, String)> {
+    let invalid_key = || StdError::generic_err("Invalid borrow allowance key");
+
+    let mut segments = vec![];
+    let mut rest = key;
+    for _ in 0..3 {
+        if rest.len() < 2 {
+            return Err(invalid_key());
+        }
+        let len = u16::from_be_bytes([rest[0], rest[1]]) as usize;
+        let segment = rest.get(2..2 + len).ok_or_else(invalid_key)?;
+        segments.push(segment);
+        rest = &rest[2 + len..];
+    }
+
+    let account_id = u32::from_be_bytes(segments[1].try_into().map_err(|_| invalid_key())?);
+    Ok((
+        Addr::unchecked(String::from_utf8(segments[0].to_vec())?),
+        account_id,
+        Addr::unchecked(String::from_utf8(segments[2].to_vec())?),
+        String::from_utf8(rest.to_vec())?,
+    ))
+}
+
+/// Emergency owner can only DISABLE BORROWING and LOWER THE BORROW CAP.
+fn update_asset_by_emergency_owner(
+    deps: DepsMut,
+    denom: &str,
+    params: InitOrUpdateAssetParams,
+) -> Result<Response, ContractError> {
+    if let Some(mut market) = MARKETS.may_load(deps.storage, denom)? {
+        let mut updated = false;
+
+        match params.borrow_enabled {
+            Some(borrow_enabled) if !borrow_enabled => {
+                market.borrow_enabled = borrow_enabled;
+                updated = true;
+            }
+            Some(_) => return Err(MarsError::Unauthorized {}.into()),
+            None => {}
+        }
+
+        if let Some(borrow_cap) = params.borrow_cap {
+            if borrow_cap > market.borrow_cap {
+                return Err(MarsError::Unauthorized {}.into());
+            }
+            market.borrow_cap = borrow_cap;
+            updated = true;
+        }
+
+        if !updated {
+            return Err(MarsError::Unauthorized {}.into());
+        }
+
+        MARKETS.save(deps.storage, denom, &market)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "emergency_update_asset")
+            .add_attribute("denom", denom))
+    } else {
+        Err(ContractError::AssetNotInitialized {})
+    }
+}
+
+/// Pause or unpause actions in a market, or in all markets if no denom is specified.
+/// Emergency owner can only PAUSE.
+pub fn set_pause_state(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: Option<String>,
+    actions: Vec<PausableAction>,
+    paused: bool,
+) -> Result<Response, ContractError> {
+    if !OWNER.is_owner(deps.storage, &info.sender)? {
+        if !EMERGENCY_OWNER.is_owner(deps.storage, &info.sender)? {
+            return Err(OwnerError::NotOwner {}.into());
+        }
+        if !paused {
+            return Err(MarsError::Unauthorized {}.into());
+        }
+    }
+
+    if actions.is_empty() {
+        return Err(ValidationError::InvalidParam {
+            param_name: "actions".to_string(),
+            invalid_value: "[]".to_string(),
+            predicate: "not empty".to_string(),
+        }
+        .into());
+    }
+
+    let mut pause_state = match &denom {
+        Some(denom) => {
+            if !MARKETS.has(deps.storage, denom) {
+                return Err(ContractError::AssetNotInitialized {});
+            }
+            PAUSE_STATES.may_load(deps.storage, denom)?.unwrap_or_default()
+        }
+        None => GLOBAL_PAUSE_STATE.may_load(deps.storage)?.unwrap_or_default(),
+    };
+
+    for action in &actions {
+        pause_state.set_paused(*action, paused);
+    }
+
+    match &denom {
+        Some(denom) if pause_state == PauseState::default() => {
+            PAUSE_STATES.remove(deps.storage, denom)
+        }
+        Some(denom) => PAUSE_STATES.save(deps.storage, denom, &pause_state)?,
+        None => GLOBAL_PAUSE_STATE.save(deps.storage, &pause_state)?,
+    }
+
+    let mut response = Response::new().add_attribute("action", "set_pause_state");
+    if let Some(denom) = denom {
+        response = response.add_attribute("denom", denom);
+    }
+    Ok(response
+        .add_attribute(
+            "actions",
+            actions.iter().map(ToString::to_string).collect::<Vec<_>>().join(","),
+        )
+        .add_attribute("paused", paused.to_string()))
+}
+
+/// Fail if the action is paused in the market, either in the market itself or globally
+pub fn assert_not_paused(
+    store: &dyn Storage,
+    denom: &str,
+    action: PausableAction,
+) -> Result<(), ContractError> {
+    let paused_globally =
+        GLOBAL_PAUSE_STATE.may_load(store)?.map(|state| state.is_paused(action)).unwrap_or(false);
+    let paused_in_market =
+        PAUSE_STATES.may_load(store, denom)?.map(|state| state.is_paused(action)).unwrap_or(false);
+    if paused_globally || paused_in_market {
+        return Err(ContractError::ActionPaused {
+            action: action.to_string(),
+            denom: denom.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Update uncollateralized loan limit by a given amount in base asset
+pub fn update_uncollateralized_loan_limit(
+    deps: DepsMut,
+    info: MessageInfo,
+    user_addr: Addr,
+    denom: String,
+    new_limit: Uint128,
+) -> Result<Response, ContractError> {
+    OWNER.assert_owner(deps.storage, &info.sender)?;
+
+    // Check that the user has no collateralized debt
+    let current_limit = UNCOLLATERALIZED_LOAN_LIMITS
+        .may_load(deps.storage, (&user_addr, &denom))?
+        .unwrap_or_else(Uint128::zero);
+    let current_debt = DEBTS
+        .may_load(deps.storage, (&user_addr, MAIN_ACCOUNT_ID, &denom))?
+        .map(|debt| debt.amount_scaled)
+        .unwrap_or_else(Uint128::zero);
+    if current_limit.is_zero() && !current_debt.is_zero() {
+        return Err(ContractError::UserHasCollateralizedDebt {});
+    }
+    if !current_limit.is_zero() && new_limit.is_zero() && !current_debt.is_zero() {
+        return Err(ContractError::UserHasUncollateralizedDebt {});
+    }
+
+    UNCOLLATERALIZED_LOAN_LIMITS.save(deps.storage, (&user_addr, &denom), &new_limit)?;
+
+    DEBTS.update(
+        deps.storage,
+        (&user_addr, MAIN_ACCOUNT_ID, &denom),
+        |debt_opt: Option<Debt>| -> StdResult<_> {
+            let mut debt = debt_opt.unwrap_or(Debt {
+                amount_scaled: Uint128::zero(),
+                uncollateralized: false,
+            });
+            // if limit == 0 then uncollateralized = false, otherwise uncollateralized = true
+            debt.uncollateralized = !new_limit.is_zero();
+            Ok(debt)
+        },
+    )?;
+    User(&user_addr, MAIN_ACCOUNT_ID).update_borrower_index(deps.storage)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_uncollateralized_loan_limit")
+        .add_attribute("user", user_addr)
+        .add_attribute("denom", denom)
+        .add_attribute("new_allowance", new_limit))
+}
+
+/// Execute deposits
+pub fn deposit(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    on_behalf_of: Option<String>,
+    account_id: u32,
+    denom: String,
+    deposit_amount: Uint128,
+) -> Result<Response, ContractError> {
+    let user_addr: Addr;
+    let user = if let Some(address) = on_behalf_of {
+        user_addr = deps.api.addr_validate(&address)?;
+        User(&user_addr, account_id)
+    } else {
+        User(&info.sender, account_id)
+    };
+
+    assert_not_paused(deps.storage, &denom, PausableAction::Deposit)?;
+
+    let mut market = MARKETS.load(deps.storage, &denom)?;
+    if !market.deposit_enabled {
+        return Err(ContractError::DepositNotEnabled {
+            denom,
+        });
+    }
+
+    let total_scaled_deposits = market.collateral_total_scaled;
+    let total_deposits =
+        get_underlying_liquidity_amount(total_scaled_deposits, &market, env.block.time.seconds())?;
+    if total_deposits.checked_add(deposit_amount)? > market.deposit_cap {
+        return Err(ContractError::DepositCapExceeded {
+            denom,
+        });
+    }
+
+    let mut response = Response::new();
+
+    let config = CONFIG.load(deps.storage)?;
+
+    // update indexes and interest rates
+    let addresses = address_provider::helpers::query_contract_addrs(
+        deps.as_ref(),
+        &config.address_provider,
+        vec![MarsAddressType::Incentives, MarsAddressType::RewardsCollector],
+    )?;
+    let rewards_collector_addr = &addresses[&MarsAddressType::RewardsCollector];
+    let incentives_addr = &addresses[&MarsAddressType::Incentives];
+
+    response = apply_accumulated_interests(
+        deps.storage,
+        &env,
+        &mut market,
+        rewards_collector_addr,
+        incentives_addr,
+        response,
+    )?;
+
+    response = update_interest_rates(&deps, &env, &mut market, Uint128::zero(), &denom, response)?;
+
+    if market.liquidity_index.is_zero() {
+        return Err(ContractError::InvalidLiquidityIndex {});
+    }
+    let deposit_amount_scaled =
+        get_scaled_liquidity_amount(deposit_amount, &market, env.block.time.seconds())?;
+
+    response = user.increase_collateral(
+        deps.storage,
+        &market,
+        deposit_amount_scaled,
+        incentives_addr,
+        response,
+    )?;
+
+    market.increase_collateral(deposit_amount_scaled)?;
+    MARKETS.save(deps.storage, &denom, &market)?;
+
+    Ok(response
+        .add_attribute("action", "deposit")
+        .add_attribute("sender", &info.sender)
+        .add_attribute("on_behalf_of", user)
+        .add_attribute("account_id", account_id.to_string())
+        .add_attribute("denom", denom)
+        .add_attribute("amount", deposit_amount)
+        .add_attribute("amount_scaled", deposit_amount_scaled))
+}
+
+/// Burns sent maAsset in exchange of underlying asset
+#[allow(clippy::too_many_arguments)]
+pub fn withdraw(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    denom: String,
+    amount: Option<Uint128>,
+    recipient: Option<String>,
+    on_behalf_of: Option<String>,
+    account_id: u32,
+) -> Result<Response, ContractError> {
+    withdraw_inner(deps, env, info, denom, amount, recipient, on_behalf_of, account_id, true)
+}
+
+/// Withdraw, optionally skipping the health check when it is done by the caller (e.g. at the end of
+/// a batch)
+#[allow(clippy::too_many_arguments)]
+fn withdraw_inner(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    denom: String,
+    amount: Option<Uint128>,
+    recipient: Option<String>,
+    on_behalf_of: Option<String>,
+    account_id: u32,
+    check_health: bool,
+) -> Result<Response, ContractError> {
+    let user_addr: Addr;
+    let withdrawer = if let Some(address) = on_behalf_of {
+        // An operator can only withdraw the funds to the position's owner
+        if recipient.is_some() {
+            return Err(ContractError::OperatorCannotSetRecipient {});
+        }
+        user_addr = deps.api.addr_validate(&address)?;
+        let user = User(&user_addr, account_id);
+        assert_operator(deps.storage, &env, user, &info.sender, OperatorPermission::Withdraw)?;
+        user
+    } else {
+        User(&info.sender, account_id)
+    };
+
+    assert_not_paused(deps.storage, &denom, PausableAction::Withdraw)?;
+
+    let mut market = MARKETS.load(deps.storage, &denom)?;
+
+    let collateral = withdrawer.collateral(deps.storage, &denom)?;
+    let withdrawer_balance_scaled_before = collateral.amount_scaled;
+
+    if withdrawer_balance_scaled_before.is_zero() {
+        return Err(ContractError::UserNoCollateralBalance {
+            user: withdrawer.into(),
+            denom,
+        });
+    }
+
+    let withdrawer_balance_before = get_underlying_liquidity_amount(
+        withdrawer_balance_scaled_before,
+        &market,
+        env.block.time.seconds(),
+    )?;
+
+    let withdraw_amount = match amount {
+        // Check user has sufficient balance to send back
+        Some(amount) if amount.is_zero() || amount > withdrawer_balance_before => {
+            return Err(ContractError::InvalidWithdrawAmount {
+                denom,
+            });
+        }
+        Some(amount) => amount,
+        // If no amount is specified, the full balance is withdrawn
+        None => withdrawer_balance_before,
+    };
+
+    let config = CONFIG.load(deps.storage)?;
+
+    let addresses = address_provider::helpers::query_contract_addrs(
+        deps.as_ref(),
+        &config.address_provider,
+        vec![
+            MarsAddressType::Oracle,
+            MarsAddressType::Incentives,
+            MarsAddressType::RewardsCollector,
+        ],
+    )?;
+    let rewards_collector_addr = &addresses[&MarsAddressType::RewardsCollector];
+    let incentives_addr = &addresses[&MarsAddressType::Incentives];
+    let oracle_addr = &addresses[&MarsAddressType::Oracle];
+
+    // if asset is used as collateral and user is borrowing we need to validate health factor after withdraw,
+    // otherwise no reasons to block the withdraw
+    if check_health
+        && collateral.enabled
+        && withdrawer.is_borrowing(deps.storage)
         && !assert_below_liq_threshold_after_withdraw(
             &deps.as_ref(),
             &env,
-            withdrawer.address(),
+            withdrawer,
             oracle_addr,
             &denom,
             withdraw_amount,
@@ -573,7 +1282,9 @@ pub fn withdraw(
     Ok(response
         .add_message(build_send_asset_msg(&recipient_addr, &denom, withdraw_amount))
         .add_attribute("action", "withdraw")
-        .add_attribute("sender", withdrawer)
+        .add_attribute("sender", &info.sender)
+        .add_attribute("on_behalf_of", withdrawer)
+        .add_attribute("account_id", account_id.to_string())
         .add_attribute("recipient", recipient_addr)
         .add_attribute("denom", denom)
         .add_attribute("amount", withdraw_amount)
@@ -581,6 +1292,7 @@ pub fn withdraw(
 }
 
 /// Add debt for the borrower and send the borrowed funds
+#[allow(clippy::too_many_arguments)]
 pub fn borrow(
     deps: DepsMut,
     env: Env,
@@ -588,8 +1300,33 @@ pub fn borrow(
     denom: String,
     borrow_amount: Uint128,
     recipient: Option<String>,
+    on_behalf_of: Option<String>,
+    account_id: u32,
 ) -> Result<Response, ContractError> {
-    let borrower = User(&info.sender);
+    borrow_inner(deps, env, info, denom, borrow_amount, recipient, on_behalf_of, account_id, true)
+}
+
+/// Borrow, optionally skipping the health check when it is done by the caller (e.g. at the end of
+/// a batch)
+#[allow(clippy::too_many_arguments)]
+fn borrow_inner(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    denom: String,
+    borrow_amount: Uint128,
+    recipient: Option<String>,
+    on_behalf_of: Option<String>,
+    account_id: u32,
+    check_health: bool,
+) -> Result<Response, ContractError> {
+    let delegator_addr: Addr;
+    let borrower = if let Some(address) = on_behalf_of {
+        delegator_addr = deps.api.addr_validate(&address)?;
+        User(&delegator_addr, account_id)
+    } else {
+        User(&info.sender, account_id)
+    };
 
     // Cannot borrow zero amount
     if borrow_amount.is_zero() {
@@ -598,6 +1335,8 @@ pub fn borrow(
         });
     }
 
+    assert_not_paused(deps.storage, &denom, PausableAction::Borrow)?;
+
     // Load market and user state
     let mut borrow_market = MARKETS.load(deps.storage, &denom)?;
 
@@ -607,8 +1346,49 @@ pub fn borrow(
         });
     }
 
+    let total_debt = get_underlying_debt_amount(
+        borrow_market.debt_total_scaled,
+        &borrow_market,
+        env.block.time.seconds(),
+    )?;
+    if total_debt.checked_add(borrow_amount)? > borrow_market.borrow_cap {
+        return Err(ContractError::BorrowCapExceeded {
+            denom,
+        });
+    }
+
+    // Users in e-mode can only borrow assets in their category
+    if let Some(category) = borrower.emode_category(deps.storage)? {
+        if !category.denoms.contains(&denom) {
+            return Err(ContractError::BorrowOutsideEModeCategory {
+                denom,
+            });
+        }
+    }
+
     let uncollateralized_loan_limit = borrower.uncollateralized_loan_limit(deps.storage, &denom)?;
 
+    // Delegatees borrow against the delegator's collateral, within the allowance approved by the
+    // delegator. Uncollateralized loan limits are granted to the delegator only.
+    if borrower.address() != &info.sender {
+        if !uncollateralized_loan_limit.is_zero() {
+            return Err(ContractError::CannotBorrowUncollateralizedLoanOnBehalfOf {});
+        }
+
+        let key = ((borrower.address(), account_id), &info.sender, denom.as_str());
+        let allowance = BORROW_ALLOWANCES.may_load(deps.storage, key)?.unwrap_or_default();
+        let allowance_left = allowance.checked_sub(borrow_amount).map_err(|_| {
+            ContractError::BorrowAmountExceedsAllowance {
+                denom: denom.clone(),
+            }
+        })?;
+        if allowance_left.is_zero() {
+            BORROW_ALLOWANCES.remove(deps.storage, key);
+        } else {
+            BORROW_ALLOWANCES.save(deps.storage, key, &allowance_left)?;
+        }
+    }
+
     let config = CONFIG.load(deps.storage)?;
 
     let addresses = address_provider::helpers::query_contract_addrs(
@@ -627,16 +1407,43 @@ pub fn borrow(
     // Check if user can borrow specified amount
     let mut uncollateralized_debt = false;
     if uncollateralized_loan_limit.is_zero() {
-        if !assert_below_max_ltv_after_borrow(
-            &deps.as_ref(),
-            &env,
-            borrower.address(),
-            oracle_addr,
-            &denom,
-            borrow_amount,
-        )? {
+        // In isolation mode, only assets borrowable in isolation can be borrowed
+        let isolated_market = borrower.isolated_collateral_market(deps.storage)?;
+        if isolated_market.is_some() && !borrow_market.borrowable_in_isolation {
+            return Err(ContractError::BorrowNotAllowedInIsolationMode {
+                denom,
+            });
+        }
+
+        if check_health
+            && !assert_below_max_ltv_after_borrow(
+                &deps.as_ref(),
+                &env,
+                borrower,
+                oracle_addr,
+                &denom,
+                borrow_amount,
+            )?
+        {
             return Err(ContractError::BorrowAmountExceedsGivenCollateral {});
         }
+
+        // The total debt against the isolated collateral can't exceed its debt ceiling
+        if let Some(isolated_market) = isolated_market {
+            let price = oracle::helpers::query_price(&deps.querier, oracle_addr, &denom)?;
+            let borrow_value =
+                borrow_amount.checked_multiply_ratio(price.numerator(), price.denominator())?;
+            let isolated_debt = ISOLATED_DEBTS
+                .may_load(deps.storage, &isolated_market.denom)?
+                .unwrap_or_default()
+                .checked_add(borrow_value)?;
+            if isolated_debt > isolated_market.debt_ceiling {
+                return Err(ContractError::DebtCeilingExceeded {
+                    denom: isolated_market.denom,
+                });
+            }
+            ISOLATED_DEBTS.save(deps.storage, &isolated_market.denom, &isolated_debt)?;
+        }
     } else {
         // Uncollateralized loan: check borrow amount plus debt does not exceed uncollateralized loan limit
         uncollateralized_debt = true;
@@ -667,6 +1474,13 @@ pub fn borrow(
         response,
     )?;
 
+    let debt_amount = get_underlying_debt_amount(
+        borrower.debt_amount_scaled(deps.storage, &denom)?,
+        &borrow_market,
+        env.block.time.seconds(),
+    )?;
+    assert_min_debt(&borrow_market, debt_amount.checked_add(borrow_amount)?)?;
+
     // Set new debt
     let borrow_amount_scaled =
         get_scaled_debt_amount(borrow_amount, &borrow_market, env.block.time.seconds())?;
@@ -682,57 +1496,319 @@ pub fn borrow(
     let recipient_addr = if let Some(recipient) = recipient {
         deps.api.addr_validate(&recipient)?
     } else {
-        borrower.address().clone()
+        info.sender.clone()
     };
 
     Ok(response
-        .add_message(build_send_asset_msg(&recipient_addr, &denom, borrow_amount))
-        .add_attribute("action", "borrow")
-        .add_attribute("sender", borrower)
-        .add_attribute("recipient", recipient_addr)
+        .add_message(build_send_asset_msg(&recipient_addr, &denom, borrow_amount))
+        .add_attribute("action", "borrow")
+        .add_attribute("sender", &info.sender)
+        .add_attribute("on_behalf_of", borrower)
+        .add_attribute("account_id", account_id.to_string())
+        .add_attribute("recipient", recipient_addr)
+        .add_attribute("denom", denom)
+        .add_attribute("amount", borrow_amount)
+        .add_attribute("amount_scaled", borrow_amount_scaled))
+}
+
+/// Set the amount of an asset the delegatee can borrow against the sender's position
+pub fn approve_delegation(
+    deps: DepsMut,
+    info: MessageInfo,
+    delegatee: String,
+    denom: String,
+    amount: Uint128,
+    account_id: u32,
+) -> Result<Response, ContractError> {
+    let delegatee_addr = deps.api.addr_validate(&delegatee)?;
+
+    if !MARKETS.has(deps.storage, &denom) {
+        return Err(ContractError::AssetNotInitialized {});
+    }
+
+    let key = ((&info.sender, account_id), &delegatee_addr, denom.as_str());
+    if amount.is_zero() {
+        BORROW_ALLOWANCES.remove(deps.storage, key);
+    } else {
+        BORROW_ALLOWANCES.save(deps.storage, key, &amount)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "approve_delegation")
+        .add_attribute("delegator", &info.sender)
+        .add_attribute("account_id", account_id.to_string())
+        .add_attribute("delegatee", delegatee_addr)
+        .add_attribute("denom", denom)
+        .add_attribute("amount", amount))
+}
+
+/// Approve an operator to act on the sender's position with the given permissions, or revoke the
+/// operator if no permissions are given
+pub fn set_operator(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    operator: String,
+    mut permissions: Vec<OperatorPermission>,
+    expires: Option<u64>,
+    account_id: u32,
+) -> Result<Response, ContractError> {
+    let operator_addr = deps.api.addr_validate(&operator)?;
+
+    let key = ((&info.sender, account_id), &operator_addr);
+    if permissions.is_empty() {
+        OPERATORS.remove(deps.storage, key);
+    } else {
+        if let Some(expires) = expires {
+            if expires <= env.block.time.seconds() {
+                return Err(ContractError::InvalidOperatorExpiration {});
+            }
+        }
+
+        permissions.sort();
+        permissions.dedup();
+        OPERATORS.save(
+            deps.storage,
+            key,
+            &Operator {
+                permissions: permissions.clone(),
+                expires,
+            },
+        )?;
+    }
+
+    let permissions_str =
+        permissions.iter().map(|permission| permission.to_string()).collect::<Vec<_>>().join(",");
+    let expires_str =
+        expires.map(|expires| expires.to_string()).unwrap_or_else(|| "none".to_string());
+
+    Ok(Response::new()
+        .add_attribute("action", "set_operator")
+        .add_attribute("user", &info.sender)
+        .add_attribute("account_id", account_id.to_string())
+        .add_attribute("operator", operator_addr)
+        .add_attribute("permissions", permissions_str)
+        .add_attribute("expires", expires_str))
+}
+
+/// Check that the sender is approved by the user as operator with the given permission
+fn assert_operator(
+    store: &dyn Storage,
+    env: &Env,
+    user: User,
+    sender: &Addr,
+    permission: OperatorPermission,
+) -> Result<(), ContractError> {
+    if !user.is_operator(store, sender, permission, env.block.time.seconds())? {
+        return Err(ContractError::OperatorNotApproved {
+            permission: permission.to_string(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Handle the repay of native tokens. Refund extra funds if they exist
+pub fn repay(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    on_behalf_of: Option<String>,
+    account_id: u32,
+    denom: String,
+    repay_amount: Uint128,
+) -> Result<Response, ContractError> {
+    let user_addr: Addr;
+    let user = if let Some(address) = on_behalf_of {
+        user_addr = deps.api.addr_validate(&address)?;
+        let user = User(&user_addr, account_id);
+        // Uncollateralized loans should not have 'on behalf of' because it creates accounting complexity for them,
+        // unless repaid by an operator of the user
+        if !user.uncollateralized_loan_limit(deps.storage, &denom)?.is_zero()
+            && !user.is_operator(
+                deps.storage,
+                &info.sender,
+                OperatorPermission::Repay,
+                env.block.time.seconds(),
+            )?
+        {
+            return Err(ContractError::CannotRepayUncollateralizedLoanOnBehalfOf {});
+        }
+        user
+    } else {
+        User(&info.sender, account_id)
+    };
+
+    assert_not_paused(deps.storage, &denom, PausableAction::Repay)?;
+
+    // Check new debt
+    let debt = DEBTS
+        .may_load(deps.storage, (user.address(), account_id, &denom))?
+        .ok_or(ContractError::CannotRepayZeroDebt {})?;
+
+    let config = CONFIG.load(deps.storage)?;
+
+    let addresses = address_provider::helpers::query_contract_addrs(
+        deps.as_ref(),
+        &config.address_provider,
+        vec![
+            MarsAddressType::Oracle,
+            MarsAddressType::Incentives,
+            MarsAddressType::RewardsCollector,
+        ],
+    )?;
+    let rewards_collector_addr = &addresses[&MarsAddressType::RewardsCollector];
+    let incentives_addr = &addresses[&MarsAddressType::Incentives];
+    let oracle_addr = &addresses[&MarsAddressType::Oracle];
+
+    let mut market = MARKETS.load(deps.storage, &denom)?;
+
+    let mut response = Response::new();
+
+    response = apply_accumulated_interests(
+        deps.storage,
+        &env,
+        &mut market,
+        rewards_collector_addr,
+        incentives_addr,
+        response,
+    )?;
+
+    let debt_amount_scaled_before = debt.amount_scaled;
+    let debt_amount_before =
+        get_underlying_debt_amount(debt.amount_scaled, &market, env.block.time.seconds())?;
+
+    // If repay amount exceeds debt, refund any excess amounts
+    let mut refund_amount = Uint128::zero();
+    let mut debt_amount_after = Uint128::zero();
+    if repay_amount > debt_amount_before {
+        refund_amount = repay_amount - debt_amount_before;
+        let refund_msg = build_send_asset_msg(&info.sender, &denom, refund_amount);
+        response = response.add_message(refund_msg);
+    } else {
+        debt_amount_after = debt_amount_before - repay_amount;
+    }
+    assert_min_debt(&market, debt_amount_after)?;
+
+    let debt_amount_scaled_after =
+        get_scaled_debt_amount(debt_amount_after, &market, env.block.time.seconds())?;
+
+    let debt_amount_scaled_delta =
+        debt_amount_scaled_before.checked_sub(debt_amount_scaled_after)?;
+
+    market.decrease_debt(debt_amount_scaled_delta)?;
+    user.decrease_debt(deps.storage, &denom, debt_amount_scaled_delta)?;
+
+    decrease_isolated_debt(
+        deps.storage,
+        &deps.querier,
+        user,
+        oracle_addr,
+        &denom,
+        debt_amount_before.checked_sub(debt_amount_after)?,
+    )?;
+
+    response = update_interest_rates(&deps, &env, &mut market, refund_amount, &denom, response)?;
+    MARKETS.save(deps.storage, &denom, &market)?;
+
+    Ok(response
+        .add_attribute("action", "repay")
+        .add_attribute("sender", &info.sender)
+        .add_attribute("on_behalf_of", user)
+        .add_attribute("account_id", account_id.to_string())
         .add_attribute("denom", denom)
-        .add_attribute("amount", borrow_amount)
-        .add_attribute("amount_scaled", borrow_amount_scaled))
+        .add_attribute("amount", repay_amount.checked_sub(refund_amount)?)
+        .add_attribute("amount_scaled", debt_amount_scaled_delta))
 }
 
-/// Handle the repay of native tokens. Refund extra funds if they exist
-pub fn repay(
+/// Repay the user's debt with their collateral in the same asset. The position's liquidation
+/// health factor can't decrease as a result.
+pub fn deleverage(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    on_behalf_of: Option<String>,
     denom: String,
-    repay_amount: Uint128,
+    amount: Option<Uint128>,
+    on_behalf_of: Option<String>,
+    account_id: u32,
 ) -> Result<Response, ContractError> {
     let user_addr: Addr;
     let user = if let Some(address) = on_behalf_of {
         user_addr = deps.api.addr_validate(&address)?;
-        let user = User(&user_addr);
-        // Uncollateralized loans should not have 'on behalf of' because it creates accounting complexity for them
-        if !user.uncollateralized_loan_limit(deps.storage, &denom)?.is_zero() {
-            return Err(ContractError::CannotRepayUncollateralizedLoanOnBehalfOf {});
-        }
+        let user = User(&user_addr, account_id);
+        assert_operator(deps.storage, &env, user, &info.sender, OperatorPermission::Deleverage)?;
         user
     } else {
-        User(&info.sender)
+        User(&info.sender, account_id)
     };
 
-    // Check new debt
-    let debt = DEBTS
-        .may_load(deps.storage, (user.address(), &denom))?
-        .ok_or(ContractError::CannotRepayZeroDebt {})?;
+    // Deleveraging withdraws collateral to repay debt, so it is paused if either of them is
+    assert_not_paused(deps.storage, &denom, PausableAction::Withdraw)?;
+    assert_not_paused(deps.storage, &denom, PausableAction::Repay)?;
+
+    let mut market = MARKETS.load(deps.storage, &denom)?;
+    let block_time = env.block.time.seconds();
+
+    let collateral = COLLATERALS
+        .may_load(deps.storage, (user.address(), account_id, &denom))?
+        .unwrap_or_default();
+    let debt_amount_scaled_before = user.debt_amount_scaled(deps.storage, &denom)?;
+
+    let collateral_amount_before =
+        get_underlying_liquidity_amount(collateral.amount_scaled, &market, block_time)?;
+    let debt_amount_before =
+        get_underlying_debt_amount(debt_amount_scaled_before, &market, block_time)?;
+
+    let max_amount = min(collateral_amount_before, debt_amount_before);
+    let deleverage_amount = match amount {
+        Some(amount) if amount.is_zero() || amount > max_amount => {
+            return Err(ContractError::InvalidDeleverageAmount {
+                denom,
+            });
+        }
+        Some(amount) => amount,
+        None if max_amount.is_zero() => {
+            return Err(ContractError::InvalidDeleverageAmount {
+                denom,
+            });
+        }
+        None => max_amount,
+    };
+    assert_min_debt(&market, debt_amount_before.checked_sub(deleverage_amount)?)?;
 
     let config = CONFIG.load(deps.storage)?;
 
     let addresses = address_provider::helpers::query_contract_addrs(
         deps.as_ref(),
         &config.address_provider,
-        vec![MarsAddressType::Incentives, MarsAddressType::RewardsCollector],
+        vec![
+            MarsAddressType::Oracle,
+            MarsAddressType::Incentives,
+            MarsAddressType::RewardsCollector,
+        ],
     )?;
     let rewards_collector_addr = &addresses[&MarsAddressType::RewardsCollector];
     let incentives_addr = &addresses[&MarsAddressType::Incentives];
+    let oracle_addr = &addresses[&MarsAddressType::Oracle];
 
-    let mut market = MARKETS.load(deps.storage, &denom)?;
+    // Repaying debt with collateral of the same value improves the health factor, unless it is
+    // already below the asset's liquidation threshold
+    let mut positions = get_user_positions_map(&deps.as_ref(), &env, user, oracle_addr)?;
+    let health_before = compute_position_health(&positions)?;
+    if let Some(p) = positions.get_mut(&denom) {
+        if collateral.enabled {
+            p.collateral_amount = p.collateral_amount.checked_sub(deleverage_amount)?;
+        }
+        p.debt_amount = p.debt_amount.checked_sub(deleverage_amount)?;
+    }
+    let health_after = compute_position_health(&positions)?;
+    if let (Some(hf_before), Some(hf_after)) =
+        (health_before.liquidation_health_factor, health_after.liquidation_health_factor)
+    {
+        if hf_after < hf_before {
+            return Err(ContractError::InvalidHealthFactorAfterDeleverage {});
+        }
+    }
 
     let mut response = Response::new();
 
@@ -745,58 +1821,420 @@ pub fn repay(
         response,
     )?;
 
-    let debt_amount_scaled_before = debt.amount_scaled;
-    let debt_amount_before =
-        get_underlying_debt_amount(debt.amount_scaled, &market, env.block.time.seconds())?;
+    // The repaid debt no longer counts towards the debt ceiling of the user's isolated collateral.
+    // This is done before decreasing the collateral, which may be the isolated collateral itself.
+    decrease_isolated_debt(
+        deps.storage,
+        &deps.querier,
+        user,
+        oracle_addr,
+        &denom,
+        deleverage_amount,
+    )?;
 
-    // If repay amount exceeds debt, refund any excess amounts
-    let mut refund_amount = Uint128::zero();
-    let mut debt_amount_after = Uint128::zero();
-    if repay_amount > debt_amount_before {
-        refund_amount = repay_amount - debt_amount_before;
-        let refund_msg = build_send_asset_msg(&info.sender, &denom, refund_amount);
-        response = response.add_message(refund_msg);
-    } else {
-        debt_amount_after = debt_amount_before - repay_amount;
-    }
+    // reduce the user's scaled collateral amount
+    let collateral_amount_scaled_after = get_scaled_liquidity_amount(
+        collateral_amount_before.checked_sub(deleverage_amount)?,
+        &market,
+        block_time,
+    )?;
+    let collateral_amount_scaled_delta =
+        collateral.amount_scaled.checked_sub(collateral_amount_scaled_after)?;
 
-    let debt_amount_scaled_after =
-        get_scaled_debt_amount(debt_amount_after, &market, env.block.time.seconds())?;
+    response = user.decrease_collateral(
+        deps.storage,
+        &market,
+        collateral_amount_scaled_delta,
+        incentives_addr,
+        response,
+    )?;
+    market.decrease_collateral(collateral_amount_scaled_delta)?;
 
+    // reduce the user's scaled debt amount
+    let debt_amount_scaled_after = get_scaled_debt_amount(
+        debt_amount_before.checked_sub(deleverage_amount)?,
+        &market,
+        block_time,
+    )?;
     let debt_amount_scaled_delta =
         debt_amount_scaled_before.checked_sub(debt_amount_scaled_after)?;
 
-    market.decrease_debt(debt_amount_scaled_delta)?;
     user.decrease_debt(deps.storage, &denom, debt_amount_scaled_delta)?;
+    market.decrease_debt(debt_amount_scaled_delta)?;
 
-    response = update_interest_rates(&deps, &env, &mut market, refund_amount, &denom, response)?;
+    // No coins leave the contract, the collateral withdrawn is used to repay the debt
+    response = update_interest_rates(&deps, &env, &mut market, Uint128::zero(), &denom, response)?;
     MARKETS.save(deps.storage, &denom, &market)?;
 
     Ok(response
-        .add_attribute("action", "repay")
+        .add_attribute("action", "deleverage")
         .add_attribute("sender", &info.sender)
         .add_attribute("on_behalf_of", user)
+        .add_attribute("account_id", account_id.to_string())
         .add_attribute("denom", denom)
-        .add_attribute("amount", repay_amount.checked_sub(refund_amount)?)
-        .add_attribute("amount_scaled", debt_amount_scaled_delta))
+        .add_attribute("amount", deleverage_amount)
+        .add_attribute("collateral_amount_scaled", collateral_amount_scaled_delta)
+        .add_attribute("debt_amount_scaled", debt_amount_scaled_delta))
+}
+
+/// Execute several actions for the sender, checking the health of the position at the end
+pub fn batch(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    actions: Vec<Action>,
+    account_id: u32,
+) -> Result<Response, ContractError> {
+    if actions.is_empty() {
+        return Err(ContractError::EmptyBatch {});
+    }
+
+    // Coins sent must match the amounts deposited and repaid in each denom
+    let mut amounts_to_pay: BTreeMap<String, Uint128> = BTreeMap::new();
+    for action in &actions {
+        if let Action::Deposit {
+            denom,
+            amount,
+        }
+        | Action::Repay {
+            denom,
+            amount,
+        } = action
+        {
+            let amount_to_pay = amounts_to_pay.entry(denom.clone()).or_default();
+            *amount_to_pay = amount_to_pay.checked_add(*amount)?;
+        }
+    }
+    let sent_amounts: BTreeMap<String, Uint128> =
+        info.funds.iter().map(|coin| (coin.denom.clone(), coin.amount)).collect();
+    if amounts_to_pay != sent_amounts {
+        return Err(ContractError::InvalidBatchFunds {});
+    }
+
+    let mut response = Response::new();
+    let mut denoms = vec![];
+    let mut check_health = false;
+    let mut borrowed = false;
+
+    for action in actions {
+        let action_response = match action {
+            Action::Deposit {
+                denom,
+                amount,
+            } => {
+                denoms.push(denom.clone());
+                deposit(deps.branch(), env.clone(), info.clone(), None, account_id, denom, amount)?
+            }
+            Action::Withdraw {
+                denom,
+                amount,
+                recipient,
+            } => {
+                denoms.push(denom.clone());
+                check_health = true;
+                withdraw_inner(
+                    deps.branch(),
+                    env.clone(),
+                    info.clone(),
+                    denom,
+                    amount,
+                    recipient,
+                    None,
+                    account_id,
+                    false,
+                )?
+            }
+            Action::Borrow {
+                denom,
+                amount,
+                recipient,
+            } => {
+                denoms.push(denom.clone());
+                check_health = true;
+                borrowed = true;
+                borrow_inner(
+                    deps.branch(),
+                    env.clone(),
+                    info.clone(),
+                    denom,
+                    amount,
+                    recipient,
+                    None,
+                    account_id,
+                    false,
+                )?
+            }
+            Action::Repay {
+                denom,
+                amount,
+            } => {
+                denoms.push(denom.clone());
+                repay(deps.branch(), env.clone(), info.clone(), None, account_id, denom, amount)?
+            }
+            Action::UpdateAssetCollateralStatus {
+                denom,
+                enable,
+            } => {
+                check_health |= !enable;
+                update_asset_collateral_status_inner(
+                    deps.branch(),
+                    env.clone(),
+                    info.clone(),
+                    denom,
+                    enable,
+                    None,
+                    account_id,
+                    false,
+                )?
+            }
+        };
+
+        response = response
+            .add_submessages(action_response.messages)
+            .add_events(action_response.events)
+            .add_event(Event::new("batch_action").add_attributes(action_response.attributes));
+    }
+
+    // 1. Check the health of the position once, against the max LTV if anything was borrowed
+    let user = User(&info.sender, account_id);
+    if check_health {
+        let config = CONFIG.load(deps.storage)?;
+        let oracle_addr = address_provider::helpers::query_contract_addr(
+            deps.as_ref(),
+            &config.address_provider,
+            MarsAddressType::Oracle,
+        )?;
+
+        let mut positions = get_user_positions_map(&deps.as_ref(), &env, user, &oracle_addr)?;
+
+        // Users in isolation mode can only borrow against their isolated collateral
+        if borrowed {
+            if let Some(isolated_market) = user.isolated_collateral_market(deps.storage)? {
+                positions
+                    .values_mut()
+                    .filter(|p| p.denom != isolated_market.denom)
+                    .for_each(|p| p.collateral_amount = Uint128::zero());
+            }
+        }
+
+        let health = compute_position_health(&positions)?;
+        if (borrowed && health.is_above_max_ltv()) || health.is_liquidatable() {
+            return Err(ContractError::InvalidHealthFactorAfterBatch {});
+        }
+    }
+
+    // 2. Each action updated the interest rates with only its own coins sent out, so update them
+    // again with all the coins the batch sends out
+    denoms.sort();
+    denoms.dedup();
+    for denom in denoms {
+        let liquidity_taken = response
+            .messages
+            .iter()
+            .filter_map(|sub_msg| match &sub_msg.msg {
+                CosmosMsg::Bank(BankMsg::Send {
+                    amount,
+                    ..
+                }) => Some(amount),
+                _ => None,
+            })
+            .flatten()
+            .filter(|coin| coin.denom == denom)
+            .try_fold(Uint128::zero(), |total, coin| total.checked_add(coin.amount))?;
+
+        let mut market = MARKETS.load(deps.storage, &denom)?;
+        response =
+            update_interest_rates(&deps, &env, &mut market, liquidity_taken, &denom, response)?;
+        MARKETS.save(deps.storage, &denom, &market)?;
+    }
+
+    Ok(response
+        .add_attribute("action", "batch")
+        .add_attribute("user", user)
+        .add_attribute("account_id", account_id.to_string()))
 }
 
 /// Execute loan liquidations on under-collateralized loans
 pub fn liquidate(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
     collateral_denom: String,
     debt_denom: String,
     user_addr: Addr,
+    account_id: u32,
     sent_debt_amount: Uint128,
     recipient: Option<String>,
 ) -> Result<Response, ContractError> {
-    let block_time = env.block.time.seconds();
-    let user = User(&user_addr);
     // The recipient address for receiving underlying collateral
     let recipient_addr = option_string_to_addr(deps.api, recipient, info.sender.clone())?;
-    let recipient = User(&recipient_addr);
+
+    let (liquidation_response, refund_amount) = liquidate_position(
+        &mut deps,
+        &env,
+        &info.sender,
+        &recipient_addr,
+        User(&user_addr, account_id),
+        collateral_denom,
+        debt_denom.clone(),
+        sent_debt_amount,
+    )?;
+
+    let mut response = Response::new()
+        .add_submessages(liquidation_response.messages)
+        .add_events(liquidation_response.events);
+
+    // refund sent amount in excess of actual debt amount to liquidate
+    if !refund_amount.is_zero() {
+        response =
+            response.add_message(build_send_asset_msg(&info.sender, &debt_denom, refund_amount));
+    }
+
+    Ok(response
+        .add_attribute("action", "liquidate")
+        .add_attributes(liquidation_response.attributes))
+}
+
+/// Execute several loan liquidations, skipping the ones which can't be processed
+pub fn liquidate_many(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    liquidations: Vec<Liquidation>,
+    recipient: Option<String>,
+) -> Result<Response, ContractError> {
+    // The recipient address for receiving underlying collateral
+    let recipient_addr = option_string_to_addr(deps.api, recipient, info.sender.clone())?;
+
+    // Coins sent must match the amounts to repay in each denom
+    let mut amounts_to_repay: BTreeMap<String, Uint128> = BTreeMap::new();
+    for liquidation in &liquidations {
+        let amount = amounts_to_repay.entry(liquidation.debt_denom.clone()).or_default();
+        *amount = amount.checked_add(liquidation.amount)?;
+    }
+    let sent_amounts: BTreeMap<String, Uint128> =
+        info.funds.iter().map(|coin| (coin.denom.clone(), coin.amount)).collect();
+    if liquidations.is_empty() || amounts_to_repay != sent_amounts {
+        return Err(ContractError::InvalidLiquidationFunds {});
+    }
+
+    let mut response = Response::new();
+    let mut refunds: BTreeMap<String, Uint128> = BTreeMap::new();
+    let mut liquidated_denoms: Vec<String> = vec![];
+    let mut liquidated_count = 0u32;
+
+    for liquidation in liquidations {
+        let Liquidation {
+            user,
+            account_id,
+            collateral_denom,
+            debt_denom,
+            amount,
+        } = liquidation;
+        let user_addr = deps.api.addr_validate(&user)?;
+
+        let refund_amount = match liquidate_position(
+            &mut deps,
+            &env,
+            &info.sender,
+            &recipient_addr,
+            User(&user_addr, account_id.unwrap_or_default()),
+            collateral_denom,
+            debt_denom.clone(),
+            amount,
+        ) {
+            Ok((liquidation_response, refund_amount)) => {
+                liquidated_count += 1;
+                liquidated_denoms.push(debt_denom.clone());
+                response = response
+                    .add_submessages(liquidation_response.messages)
+                    .add_events(liquidation_response.events)
+                    .add_event(
+                        Event::new("liquidation").add_attributes(liquidation_response.attributes),
+                    );
+                refund_amount
+            }
+            // These are checked before the position is changed, so there's nothing to revert
+            Err(
+                err @ (ContractError::CannotLiquidateWhenPositiveUncollateralizedLoanLimit {}
+                | ContractError::CannotLiquidateWhenNoCollateralBalance {}
+                | ContractError::CannotLiquidateWhenCollateralUnset {
+                    ..
+                }
+                | ContractError::CannotLiquidateWhenNoDebtBalance {}
+                | ContractError::CannotLiquidateHealthyPosition {}),
+            ) => {
+                response = response.add_event(
+                    Event::new("liquidation_skipped")
+                        .add_attribute("user", user_addr)
+                        .add_attribute("account_id", account_id.unwrap_or_default().to_string())
+                        .add_attribute("debt_denom", debt_denom.clone())
+                        .add_attribute("reason", err.to_string()),
+                );
+                amount
+            }
+            Err(err) => return Err(err),
+        };
+
+        let refund = refunds.entry(debt_denom).or_default();
+        *refund = refund.checked_add(refund_amount)?;
+    }
+
+    // Each liquidation updated the interest rates of its debt market with only its own refund, so
+    // update them again with all the coins refunded at the end
+    liquidated_denoms.sort();
+    liquidated_denoms.dedup();
+    for denom in liquidated_denoms {
+        let liquidity_taken = refunds.get(&denom).copied().unwrap_or_default();
+        let mut market = MARKETS.load(deps.storage, &denom)?;
+        response =
+            update_interest_rates(&deps, &env, &mut market, liquidity_taken, &denom, response)?;
+        MARKETS.save(deps.storage, &denom, &market)?;
+    }
+
+    // Refund the coins which weren't used, grouped by denom
+    let refund_coins: Vec<Coin> = refunds
+        .into_iter()
+        .filter(|(_, amount)| !amount.is_zero())
+        .map(|(denom, amount)| Coin {
+            denom,
+            amount,
+        })
+        .collect();
+    if !refund_coins.is_empty() {
+        response = response.add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: refund_coins,
+        });
+    }
+
+    Ok(response
+        .add_attribute("action", "liquidate_many")
+        .add_attribute("liquidator", info.sender.to_string())
+        .add_attribute("recipient", recipient_addr)
+        .add_attribute("liquidated", liquidated_count.to_string()))
+}
+
+/// Liquidate the position of a user, with the checks and side effects described in `liquidate`.
+/// Returns the messages, events and attributes of the liquidation, and the amount of debt coins
+/// to refund to the liquidator.
+#[allow(clippy::too_many_arguments)]
+fn liquidate_position(
+    deps: &mut DepsMut,
+    env: &Env,
+    liquidator_addr: &Addr,
+    recipient_addr: &Addr,
+    user: User,
+    collateral_denom: String,
+    debt_denom: String,
+    sent_debt_amount: Uint128,
+) -> Result<(Response, Uint128), ContractError> {
+    let block_time = env.block.time.seconds();
+    // Liquidators receive the collateral in their main account
+    let recipient = User(recipient_addr, MAIN_ACCOUNT_ID);
+
+    assert_not_paused(deps.storage, &collateral_denom, PausableAction::Liquidate)?;
+    assert_not_paused(deps.storage, &debt_denom, PausableAction::Liquidate)?;
 
     // 1. Validate liquidation
     // If user (contract) has a positive uncollateralized limit then the user
@@ -807,7 +2245,7 @@ pub fn liquidate(
 
     // check if the user has enabled the collateral asset as collateral
     let user_collateral = COLLATERALS
-        .may_load(deps.storage, (&user_addr, &collateral_denom))?
+        .may_load(deps.storage, (user.address(), user.account_id(), &collateral_denom))?
         .ok_or(ContractError::CannotLiquidateWhenNoCollateralBalance {})?;
     if !user_collateral.enabled {
         return Err(ContractError::CannotLiquidateWhenCollateralUnset {
@@ -820,7 +2258,7 @@ pub fn liquidate(
 
     // check if user has outstanding debt in the deposited asset that needs to be repayed
     let user_debt = DEBTS
-        .may_load(deps.storage, (&user_addr, &debt_denom))?
+        .may_load(deps.storage, (user.address(), user.account_id(), &debt_denom))?
         .ok_or(ContractError::CannotLiquidateWhenNoDebtBalance {})?;
 
     // 2. Compute health factor
@@ -839,13 +2277,16 @@ pub fn liquidate(
     let incentives_addr = &addresses[&MarsAddressType::Incentives];
     let oracle_addr = &addresses[&MarsAddressType::Oracle];
 
-    let (liquidatable, assets_positions) =
-        assert_liquidatable(&deps.as_ref(), &env, &user_addr, oracle_addr)?;
+    let assets_positions = get_user_positions_map(&deps.as_ref(), env, user, oracle_addr)?;
+    let health = compute_position_health(&assets_positions)?;
 
-    if !liquidatable {
+    if !health.is_liquidatable() {
         return Err(ContractError::CannotLiquidateHealthyPosition {});
     }
 
+    // A liquidatable position has debt, so its health factor is always defined
+    let liquidation_health_factor = health.liquidation_health_factor.unwrap_or_default();
+
     let collateral_and_debt_are_the_same_asset = debt_denom == collateral_denom;
 
     let debt_market = if !collateral_and_debt_are_the_same_asset {
@@ -869,6 +2310,15 @@ pub fn liquidate(
     let user_debt_amount =
         get_underlying_debt_amount(user_debt.amount_scaled, &debt_market, block_time)?;
 
+    // Users in e-mode are liquidated with the liquidation bonus of their category
+    let mut liquidation_market = collateral_market.clone();
+    if let Some(category) = user.emode_category(deps.storage)? {
+        if category.denoms.contains(&collateral_denom) {
+            liquidation_market.liquidation_bonus =
+                LiquidationBonus::fixed(category.liquidation_bonus);
+        }
+    }
+
     let (
         debt_amount_to_repay,
         collateral_amount_to_liquidate,
@@ -878,14 +2328,41 @@ pub fn liquidate(
         user_collateral.amount_scaled,
         user_debt_amount,
         sent_debt_amount,
-        &collateral_market,
+        &liquidation_market,
         collateral_price,
         debt_price,
         block_time,
-        config.close_factor,
+        liquidation_close_factor(config.close_factor, user_debt_amount, &debt_market),
+        liquidation_health_factor,
+    )?;
+
+    // The isolated collateral is looked up before it is seized, as the user may be left without
+    // any collateral enabled
+    let isolated_market = user.isolated_collateral_market(deps.storage)?;
+    release_isolated_debt(
+        deps.storage,
+        &deps.querier,
+        isolated_market.as_ref(),
+        oracle_addr,
+        &debt_denom,
+        debt_amount_to_repay,
     )?;
 
-    // 4. Transfer collateral shares from the user to the liquidator
+    // 4. Transfer collateral shares from the user to the liquidator, keeping the protocol's
+    // portion of the liquidation bonus for the rewards collector
+    let liquidation_bonus =
+        liquidation_market.liquidation_bonus.get_liquidation_bonus(liquidation_health_factor)?;
+    let bonus_amount =
+        collateral_amount_to_liquidate.checked_sub(math::divide_uint128_by_decimal(
+            collateral_amount_to_liquidate,
+            Decimal::one() + liquidation_bonus,
+        )?)?;
+    let protocol_fee_amount = bonus_amount * collateral_market.protocol_liquidation_fee;
+    let protocol_fee_amount_scaled =
+        get_scaled_liquidity_amount(protocol_fee_amount, &collateral_market, block_time)?;
+    let recipient_amount_scaled =
+        collateral_amount_to_liquidate_scaled.checked_sub(protocol_fee_amount_scaled)?;
+
     response = user.decrease_collateral(
         deps.storage,
         &collateral_market,
@@ -896,10 +2373,19 @@ pub fn liquidate(
     response = recipient.increase_collateral(
         deps.storage,
         &collateral_market,
-        collateral_amount_to_liquidate_scaled,
+        recipient_amount_scaled,
         incentives_addr,
         response,
     )?;
+    if !protocol_fee_amount_scaled.is_zero() {
+        response = User(rewards_collector_addr, MAIN_ACCOUNT_ID).increase_collateral(
+            deps.storage,
+            &collateral_market,
+            protocol_fee_amount_scaled,
+            incentives_addr,
+            response,
+        )?;
+    }
 
     // 5. Reduce the user's debt shares
     let user_debt_amount_after = user_debt_amount.checked_sub(debt_amount_to_repay)?;
@@ -926,7 +2412,7 @@ pub fn liquidate(
 
         response = apply_accumulated_interests(
             deps.storage,
-            &env,
+            env,
             &mut asset_market_after,
             rewards_collector_addr,
             incentives_addr,
@@ -936,8 +2422,8 @@ pub fn liquidate(
         asset_market_after.debt_total_scaled = debt_market_debt_total_scaled_after;
 
         response = update_interest_rates(
-            &deps,
-            &env,
+            deps,
+            env,
             &mut asset_market_after,
             refund_amount,
             denom,
@@ -950,7 +2436,7 @@ pub fn liquidate(
 
         response = apply_accumulated_interests(
             deps.storage,
-            &env,
+            env,
             &mut debt_market_after,
             rewards_collector_addr,
             incentives_addr,
@@ -960,40 +2446,199 @@ pub fn liquidate(
         debt_market_after.debt_total_scaled = debt_market_debt_total_scaled_after;
 
         response = update_interest_rates(
-            &deps,
-            &env,
+            deps,
+            env,
             &mut debt_market_after,
             refund_amount,
             &debt_denom,
             response,
         )?;
 
-        MARKETS.save(deps.storage, &debt_denom, &debt_market_after)?;
+        MARKETS.save(deps.storage, &debt_denom, &debt_market_after)?;
+    }
+
+    // 7. If the user has no collateral enabled anymore, the remaining debt can't be liquidated and
+    // is written off as bad debt. Disabled collateral never backed the debt, so the user keeps it.
+    if !user.has_enabled_collateral(deps.storage)? {
+        response = write_off_bad_debt(
+            deps,
+            env,
+            user,
+            isolated_market.as_ref(),
+            oracle_addr,
+            rewards_collector_addr,
+            incentives_addr,
+            response,
+        )?;
+    }
+
+    Ok((
+        response
+            .add_attribute("user", user)
+            .add_attribute("account_id", user.account_id().to_string())
+            .add_attribute("liquidator", liquidator_addr.to_string())
+            .add_attribute("recipient", recipient)
+            .add_attribute("collateral_denom", collateral_denom)
+            .add_attribute("collateral_amount", collateral_amount_to_liquidate)
+            .add_attribute("collateral_amount_scaled", collateral_amount_to_liquidate_scaled)
+            .add_attribute("protocol_fee_amount", protocol_fee_amount)
+            .add_attribute("protocol_fee_amount_scaled", protocol_fee_amount_scaled)
+            .add_attribute("debt_denom", debt_denom)
+            .add_attribute("debt_amount", debt_amount_to_repay)
+            .add_attribute("debt_amount_scaled", debt_amount_scaled_delta),
+        refund_amount,
+    ))
+}
+
+/// Write off the debts of a user with no collateral left against the deficit of their markets,
+/// releasing them from the debt ceiling of the isolated market the user was borrowing against, if
+/// any. Uncollateralized debts are backed by the user's credit line rather than collateral, so they
+/// are kept.
+#[allow(clippy::too_many_arguments)]
+fn write_off_bad_debt(
+    deps: &mut DepsMut,
+    env: &Env,
+    user: User,
+    isolated_market: Option<&Market>,
+    oracle_addr: &Addr,
+    rewards_collector_addr: &Addr,
+    incentives_addr: &Addr,
+    mut response: Response,
+) -> Result<Response, ContractError> {
+    let debts = DEBTS
+        .prefix((user.address(), user.account_id()))
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    for (denom, debt) in debts {
+        if debt.uncollateralized {
+            continue;
+        }
+
+        let mut market = MARKETS.load(deps.storage, &denom)?;
+
+        response = apply_accumulated_interests(
+            deps.storage,
+            env,
+            &mut market,
+            rewards_collector_addr,
+            incentives_addr,
+            response,
+        )?;
+
+        let debt_amount =
+            get_underlying_debt_amount(debt.amount_scaled, &market, env.block.time.seconds())?;
+
+        user.decrease_debt(deps.storage, &denom, debt.amount_scaled)?;
+        market.decrease_debt(debt.amount_scaled)?;
+
+        DEFICITS.update(deps.storage, &denom, |deficit| -> StdResult<_> {
+            Ok(deficit.unwrap_or_default().checked_add(debt_amount)?)
+        })?;
+
+        release_isolated_debt(
+            deps.storage,
+            &deps.querier,
+            isolated_market,
+            oracle_addr,
+            &denom,
+            debt_amount,
+        )?;
+
+        response =
+            update_interest_rates(deps, env, &mut market, Uint128::zero(), &denom, response)?;
+
+        MARKETS.save(deps.storage, &denom, &market)?;
+
+        response = response.add_event(
+            Event::new("bad_debt_written_off")
+                .add_attribute("user", user)
+                .add_attribute("denom", denom)
+                .add_attribute("amount", debt_amount),
+        );
+    }
+
+    Ok(response)
+}
+
+/// Release the value of a debt repayment from the debt ceiling of the user's isolated collateral,
+/// if the user is in isolation mode
+fn decrease_isolated_debt(
+    store: &mut dyn Storage,
+    querier: &QuerierWrapper,
+    user: User,
+    oracle_addr: &Addr,
+    denom: &str,
+    repaid_amount: Uint128,
+) -> Result<(), ContractError> {
+    let isolated_market = user.isolated_collateral_market(store)?;
+    release_isolated_debt(
+        store,
+        querier,
+        isolated_market.as_ref(),
+        oracle_addr,
+        denom,
+        repaid_amount,
+    )
+}
+
+/// Release the value of a debt repayment from the debt ceiling of an isolated market, if the debt
+/// was borrowed against one
+fn release_isolated_debt(
+    store: &mut dyn Storage,
+    querier: &QuerierWrapper,
+    isolated_market: Option<&Market>,
+    oracle_addr: &Addr,
+    denom: &str,
+    repaid_amount: Uint128,
+) -> Result<(), ContractError> {
+    if let Some(isolated_market) = isolated_market {
+        let price = oracle::helpers::query_price(querier, oracle_addr, denom)?;
+        let repaid_value =
+            repaid_amount.checked_multiply_ratio(price.numerator(), price.denominator())?;
+
+        // Debt is valued at different prices when borrowed and repaid, so it can be repaid in excess
+        ISOLATED_DEBTS.update(store, &isolated_market.denom, |debt| -> StdResult<_> {
+            Ok(debt.unwrap_or_default().saturating_sub(repaid_value))
+        })?;
     }
 
-    // 7. Build response
-    // refund sent amount in excess of actual debt amount to liquidate
-    if !refund_amount.is_zero() {
-        response =
-            response.add_message(build_send_asset_msg(&info.sender, &debt_denom, refund_amount));
+    Ok(())
+}
+
+/// Fail if a debt in the market is neither fully repaid nor at least the market's min debt
+fn assert_min_debt(market: &Market, debt_amount: Uint128) -> Result<(), ContractError> {
+    if !debt_amount.is_zero() && debt_amount < market.min_debt {
+        return Err(ContractError::DebtBelowMinimum {
+            denom: market.denom.clone(),
+            min_debt: market.min_debt,
+        });
     }
 
-    Ok(response
-        .add_attribute("action", "liquidate")
-        .add_attribute("user", user)
-        .add_attribute("liquidator", info.sender.to_string())
-        .add_attribute("recipient", recipient)
-        .add_attribute("collateral_denom", collateral_denom)
-        .add_attribute("collateral_amount", collateral_amount_to_liquidate)
-        .add_attribute("collateral_amount_scaled", collateral_amount_to_liquidate_scaled)
-        .add_attribute("debt_denom", debt_denom)
-        .add_attribute("debt_amount", debt_amount_to_repay)
-        .add_attribute("debt_amount_scaled", debt_amount_scaled_delta))
+    Ok(())
+}
+
+/// Share of the user's debt that can be repaid in a liquidation. The whole debt can be repaid if
+/// repaying the close factor would leave less than the debt market's min debt, so that no dust is
+/// left that isn't worth liquidating.
+pub fn liquidation_close_factor(
+    close_factor: Decimal,
+    user_debt_amount: Uint128,
+    debt_market: &Market,
+) -> Decimal {
+    let debt_amount_left = user_debt_amount - close_factor * user_debt_amount;
+    if debt_amount_left < debt_market.min_debt {
+        Decimal::one()
+    } else {
+        close_factor
+    }
 }
 
 /// Computes debt to repay (in debt asset),
-/// collateral to liquidate (in collateral asset) and
+/// collateral to liquidate (in collateral asset, including the liquidation bonus for the
+/// health factor of the position) and
 /// amount to refund the liquidator (in debt asset)
+#[allow(clippy::too_many_arguments)]
 pub fn liquidation_compute_amounts(
     user_collateral_amount_scaled: Uint128,
     user_debt_amount: Uint128,
@@ -1003,14 +2648,19 @@ pub fn liquidation_compute_amounts(
     debt_price: Decimal,
     block_time: u64,
     close_factor: Decimal,
+    liquidation_health_factor: Decimal,
 ) -> StdResult<(Uint128, Uint128, Uint128, Uint128)> {
     // Debt: Only up to a fraction of the total debt (determined by the close factor) can be
     // repayed.
     let mut debt_amount_to_repay = min(sent_debt_amount, close_factor * user_debt_amount);
 
+    // Bonus: the lower the health factor of the position, the higher the bonus
+    let liquidation_bonus =
+        collateral_market.liquidation_bonus.get_liquidation_bonus(liquidation_health_factor)?;
+
     // Collateral: debt to repay in base asset times the liquidation bonus
     let mut collateral_amount_to_liquidate = math::divide_uint128_by_decimal(
-        debt_amount_to_repay * debt_price * (Decimal::one() + collateral_market.liquidation_bonus),
+        debt_amount_to_repay * debt_price * (Decimal::one() + liquidation_bonus),
         collateral_price,
     )?;
     let mut collateral_amount_to_liquidate_scaled =
@@ -1030,7 +2680,7 @@ pub fn liquidation_compute_amounts(
                 collateral_amount_to_liquidate * collateral_price,
                 debt_price,
             )?,
-            Decimal::one() + collateral_market.liquidation_bonus,
+            Decimal::one() + liquidation_bonus,
         )?;
     }
 
@@ -1064,25 +2714,83 @@ pub fn update_asset_collateral_status(
     info: MessageInfo,
     denom: String,
     enable: bool,
+    on_behalf_of: Option<String>,
+    account_id: u32,
 ) -> Result<Response, ContractError> {
-    let user = User(&info.sender);
+    update_asset_collateral_status_inner(
+        deps,
+        env,
+        info,
+        denom,
+        enable,
+        on_behalf_of,
+        account_id,
+        true,
+    )
+}
 
-    let mut collateral =
-        COLLATERALS.may_load(deps.storage, (user.address(), &denom))?.ok_or_else(|| {
-            ContractError::UserNoCollateralBalance {
-                user: user.into(),
-                denom: denom.clone(),
-            }
+/// Update the collateral status, optionally skipping the health check when it is done by the
+/// caller (e.g. at the end of a batch)
+#[allow(clippy::too_many_arguments)]
+fn update_asset_collateral_status_inner(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    denom: String,
+    enable: bool,
+    on_behalf_of: Option<String>,
+    account_id: u32,
+    check_health: bool,
+) -> Result<Response, ContractError> {
+    let user_addr: Addr;
+    let user = if let Some(address) = on_behalf_of {
+        user_addr = deps.api.addr_validate(&address)?;
+        let user = User(&user_addr, account_id);
+        assert_operator(
+            deps.storage,
+            &env,
+            user,
+            &info.sender,
+            OperatorPermission::UpdateAssetCollateralStatus,
+        )?;
+        user
+    } else {
+        User(&info.sender, account_id)
+    };
+
+    assert_not_paused(deps.storage, &denom, PausableAction::UpdateAssetCollateralStatus)?;
+
+    let mut collateral = COLLATERALS
+        .may_load(deps.storage, (user.address(), account_id, &denom))?
+        .ok_or_else(|| ContractError::UserNoCollateralBalance {
+            user: user.into(),
+            denom: denom.clone(),
         })?;
 
     let previously_enabled = collateral.enabled;
 
+    // Isolated collateral can't be enabled together with any other collateral, nor once the user
+    // has debts
+    if !previously_enabled && enable {
+        let market = MARKETS.load(deps.storage, &denom)?;
+        if !user.can_enable_collateral(deps.storage, &market)? {
+            if market.isolated && user.is_borrowing(deps.storage) {
+                return Err(ContractError::IsolatedCollateralWithDebt {
+                    denom,
+                });
+            }
+            return Err(ContractError::IsolatedCollateralConflict {
+                denom,
+            });
+        }
+    }
+
     collateral.enabled = enable;
-    COLLATERALS.save(deps.storage, (user.address(), &denom), &collateral)?;
+    COLLATERALS.save(deps.storage, (user.address(), account_id, &denom), &collateral)?;
 
     // if the collateral was previously enabled, but is not disabled, it is necessary to ensure the
     // user is not liquidatable after disabling
-    if previously_enabled && !enable {
+    if check_health && previously_enabled && !enable {
         let config = CONFIG.load(deps.storage)?;
         let oracle_addr = address_provider::helpers::query_contract_addr(
             deps.as_ref(),
@@ -1090,8 +2798,7 @@ pub fn update_asset_collateral_status(
             MarsAddressType::Oracle,
         )?;
 
-        let (liquidatable, _) =
-            assert_liquidatable(&deps.as_ref(), &env, user.address(), &oracle_addr)?;
+        let (liquidatable, _) = assert_liquidatable(&deps.as_ref(), &env, user, &oracle_addr)?;
 
         if liquidatable {
             return Err(ContractError::InvalidHealthFactorAfterDisablingCollateral {});
@@ -1100,7 +2807,452 @@ pub fn update_asset_collateral_status(
 
     Ok(Response::new()
         .add_attribute("action", "update_asset_collateral_status")
+        .add_attribute("sender", &info.sender)
         .add_attribute("user", user)
+        .add_attribute("account_id", account_id.to_string())
         .add_attribute("denom", denom)
         .add_attribute("enable", enable.to_string()))
 }
+
+/// Create or update an e-mode category, or queue the change if a timelock is set
+pub fn set_emode_category(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    category: EModeCategory,
+) -> Result<Response, ContractError> {
+    OWNER.assert_owner(deps.storage, &info.sender)?;
+
+    let timelock = CONFIG.load(deps.storage)?.timelock;
+    if timelock == 0 {
+        return update_emode_category(deps.storage, category);
+    }
+
+    // Validate the change before queuing it
+    validate_emode_category(deps.storage, &category)?;
+
+    queue_change(
+        deps.storage,
+        &env,
+        timelock,
+        ParamsChange::SetEModeCategory {
+            category,
+        },
+    )
+}
+
+fn update_emode_category(
+    store: &mut dyn Storage,
+    category: EModeCategory,
+) -> Result<Response, ContractError> {
+    validate_emode_category(store, &category)?;
+
+    EMODE_CATEGORIES.save(store, category.id, &category)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_emode_category")
+        .add_attribute("id", category.id.to_string())
+        .add_attribute("denoms", category.denoms.join(",")))
+}
+
+fn validate_emode_category(
+    store: &dyn Storage,
+    category: &EModeCategory,
+) -> Result<(), ContractError> {
+    category.validate()?;
+
+    for denom in &category.denoms {
+        let market =
+            MARKETS.may_load(store, denom)?.ok_or(ContractError::AssetNotInitialized {})?;
+        if market.status != MarketStatus::Listed {
+            return Err(ContractError::MarketDelisted {
+                denom: denom.clone(),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether the category update only lowers the risk taken by the protocol: the category covers the
+/// same assets with a lower or equal LTV, the other params being unchanged
+fn emode_change_only_lowers_risk(current: &EModeCategory, category: &EModeCategory) -> bool {
+    current.denoms.len() == category.denoms.len()
+        && current.denoms.iter().all(|denom| category.denoms.contains(denom))
+        && category.max_loan_to_value <= current.max_loan_to_value
+        && category.liquidation_threshold == current.liquidation_threshold
+        && category.liquidation_bonus == current.liquidation_bonus
+}
+
+/// Opt the user into an e-mode category, or out of e-mode if no category is specified
+pub fn set_user_emode(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    category_id: Option<u8>,
+    account_id: u32,
+) -> Result<Response, ContractError> {
+    let user = User(&info.sender, account_id);
+
+    if let Some(id) = category_id {
+        let category = EMODE_CATEGORIES.load(deps.storage, id)?;
+
+        // all of the user's debt must be in assets of the category
+        for denom in DEBTS.prefix((user.address(), account_id)).keys(
+            deps.storage,
+            None,
+            None,
+            Order::Ascending,
+        ) {
+            let denom = denom?;
+            if !category.denoms.contains(&denom) {
+                return Err(ContractError::DebtOutsideEModeCategory {
+                    denom,
+                });
+            }
+        }
+
+        USER_EMODES.save(deps.storage, (user.address(), account_id), &id)?;
+    } else {
+        USER_EMODES.remove(deps.storage, (user.address(), account_id));
+    }
+
+    // the risk parameters change, so it is necessary to ensure the user is not liquidatable after
+    // the change
+    if user.is_borrowing(deps.storage) {
+        let config = CONFIG.load(deps.storage)?;
+        let oracle_addr = address_provider::helpers::query_contract_addr(
+            deps.as_ref(),
+            &config.address_provider,
+            MarsAddressType::Oracle,
+        )?;
+
+        let (liquidatable, _) = assert_liquidatable(&deps.as_ref(), &env, user, &oracle_addr)?;
+
+        if liquidatable {
+            return Err(ContractError::InvalidHealthFactorAfterSettingEMode {});
+        }
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "set_user_emode")
+        .add_attribute("user", user)
+        .add_attribute("account_id", account_id.to_string())
+        .add_attribute(
+            "category_id",
+            category_id.map(|id| id.to_string()).unwrap_or_else(|| "none".to_string()),
+        ))
+}
+
+/// Send the requested coins to the caller and invoke the caller with the given callback message.
+/// A finalize callback is scheduled last to verify that the principal plus fee has been returned.
+pub fn flash_loan(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    coins: Vec<Coin>,
+    callback: Binary,
+) -> Result<Response, ContractError> {
+    cw_utils::nonpayable(&info)?;
+
+    // Flash loans cannot be nested, otherwise the repayments can't be attributed to a loan
+    if FLASH_LOANS.may_load(deps.storage)?.is_some() {
+        return Err(ContractError::FlashLoanInProgress {});
+    }
+
+    if coins.is_empty() {
+        return Err(ContractError::InvalidFlashLoanCoins {});
+    }
+
+    let mut flash_loans: Vec<FlashLoan> = vec![];
+    for coin in &coins {
+        if coin.amount.is_zero() || flash_loans.iter().any(|loan| loan.denom == coin.denom) {
+            return Err(ContractError::InvalidFlashLoanCoins {});
+        }
+
+        // A flash loan takes out the market's liquidity like a borrow does
+        assert_not_paused(deps.storage, &coin.denom, PausableAction::Borrow)?;
+
+        let market = MARKETS.load(deps.storage, &coin.denom)?;
+        if !market.borrow_enabled {
+            return Err(ContractError::BorrowNotEnabled {
+                denom: coin.denom.clone(),
+            });
+        }
+
+        let available_liquidity =
+            deps.querier.query_balance(&env.contract.address, &coin.denom)?.amount;
+        if available_liquidity < coin.amount {
+            return Err(ContractError::OperationExceedsAvailableLiquidity {});
+        }
+
+        flash_loans.push(FlashLoan {
+            denom: coin.denom.clone(),
+            amount: coin.amount,
+            fee: math::multiply_uint128_by_decimal_and_ceil(coin.amount, market.flash_loan_fee)?,
+            repaid: Uint128::zero(),
+        });
+    }
+
+    FLASH_LOANS.save(deps.storage, &flash_loans)?;
+
+    let callback_msg = WasmMsg::Execute {
+        contract_addr: info.sender.to_string(),
+        msg: callback,
+        funds: coins.clone(),
+    };
+    let finalize_msg = WasmMsg::Execute {
+        contract_addr: env.contract.address.to_string(),
+        msg: to_binary(&ExecuteMsg::Callback(CallbackMsg::FinalizeFlashLoan {}))?,
+        funds: vec![],
+    };
+
+    Ok(Response::new()
+        .add_message(callback_msg)
+        .add_message(finalize_msg)
+        .add_attribute("action", "flash_loan")
+        .add_attribute("sender", &info.sender)
+        .add_attribute("coins", coins_to_string(&coins))
+        .add_attribute("fees", flash_loan_fees_to_string(&flash_loans)))
+}
+
+/// Return (part of) the coins of the flash loan in progress. Funds sent in excess of what is
+/// still owed are refunded.
+pub fn repay_flash_loan(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    if info.funds.is_empty() {
+        return Err(PaymentError::NoFunds {}.into());
+    }
+
+    let mut flash_loans =
+        FLASH_LOANS.may_load(deps.storage)?.ok_or(ContractError::NoFlashLoanInProgress {})?;
+
+    let mut repaid_coins = vec![];
+    let mut refund_coins = vec![];
+    for coin in info.funds {
+        let flash_loan =
+            flash_loans.iter_mut().find(|loan| loan.denom == coin.denom).ok_or_else(|| {
+                ContractError::UnexpectedFlashLoanRepayment {
+                    denom: coin.denom.clone(),
+                }
+            })?;
+
+        let amount_owed =
+            flash_loan.amount.checked_add(flash_loan.fee)?.checked_sub(flash_loan.repaid)?;
+        let repay_amount = min(coin.amount, amount_owed);
+        flash_loan.repaid = flash_loan.repaid.checked_add(repay_amount)?;
+
+        if !repay_amount.is_zero() {
+            repaid_coins.push(Coin::new(repay_amount.u128(), &coin.denom));
+        }
+        if coin.amount > repay_amount {
+            refund_coins.push(Coin::new((coin.amount - repay_amount).u128(), &coin.denom));
+        }
+    }
+
+    FLASH_LOANS.save(deps.storage, &flash_loans)?;
+
+    let mut response = Response::new();
+    if !refund_coins.is_empty() {
+        response = response.add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: refund_coins.clone(),
+        });
+    }
+
+    Ok(response
+        .add_attribute("action", "repay_flash_loan")
+        .add_attribute("sender", &info.sender)
+        .add_attribute("coins", coins_to_string(&repaid_coins))
+        .add_attribute("refund", coins_to_string(&refund_coins)))
+}
+
+/// Check that every flash loan of the transaction has been repaid in full, then mint the fees
+/// as collateral shares to the rewards collector.
+pub fn finalize_flash_loan(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let flash_loans =
+        FLASH_LOANS.may_load(deps.storage)?.ok_or(ContractError::NoFlashLoanInProgress {})?;
+
+    for flash_loan in &flash_loans {
+        if flash_loan.repaid < flash_loan.amount.checked_add(flash_loan.fee)? {
+            return Err(ContractError::FlashLoanNotRepaid {
+                denom: flash_loan.denom.clone(),
+            });
+        }
+    }
+
+    FLASH_LOANS.remove(deps.storage);
+
+    let config = CONFIG.load(deps.storage)?;
+
+    let addresses = address_provider::helpers::query_contract_addrs(
+        deps.as_ref(),
+        &config.address_provider,
+        vec![MarsAddressType::Incentives, MarsAddressType::RewardsCollector],
+    )?;
+    let rewards_collector_addr = &addresses[&MarsAddressType::RewardsCollector];
+    let incentives_addr = &addresses[&MarsAddressType::Incentives];
+
+    let mut response = Response::new();
+
+    for flash_loan in &flash_loans {
+        let mut market = MARKETS.load(deps.storage, &flash_loan.denom)?;
+
+        response = apply_accumulated_interests(
+            deps.storage,
+            &env,
+            &mut market,
+            rewards_collector_addr,
+            incentives_addr,
+            response,
+        )?;
+
+        // The fee has been paid in the underlying asset, so it backs newly minted collateral
+        // shares the same way accrued protocol rewards do
+        let fee_scaled =
+            get_scaled_liquidity_amount(flash_loan.fee, &market, env.block.time.seconds())?;
+        if !fee_scaled.is_zero() {
+            response = User(rewards_collector_addr, MAIN_ACCOUNT_ID).increase_collateral(
+                deps.storage,
+                &market,
+                fee_scaled,
+                incentives_addr,
+                response,
+            )?;
+            market.increase_collateral(fee_scaled)?;
+        }
+
+        // Interest rates may have been updated while the liquidity was lent out
+        response = update_interest_rates(
+            &deps,
+            &env,
+            &mut market,
+            Uint128::zero(),
+            &flash_loan.denom,
+            response,
+        )?;
+        MARKETS.save(deps.storage, &flash_loan.denom, &market)?;
+    }
+
+    Ok(response
+        .add_attribute("action", "finalize_flash_loan")
+        .add_attribute("fees", flash_loan_fees_to_string(&flash_loans)))
+}
+
+fn coins_to_string(coins: &[Coin]) -> String {
+    coins.iter().map(|coin| coin.to_string()).collect::<Vec<_>>().join(",")
+}
+
+fn flash_loan_fees_to_string(flash_loans: &[FlashLoan]) -> String {
+    flash_loans
+        .iter()
+        .map(|loan| format!("{}{}", loan.fee, loan.denom))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Cover the deficit of a market from the protocol reserves, then by spreading what remains across
+/// depositors
+pub fn cover_deficit(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    denom: String,
+) -> Result<Response, ContractError> {
+    OWNER.assert_owner(deps.storage, &info.sender)?;
+
+    let deficit = DEFICITS.may_load(deps.storage, &denom)?.unwrap_or_default();
+    if deficit.is_zero() {
+        return Err(ContractError::NoDeficit {
+            denom,
+        });
+    }
+
+    let mut market = MARKETS.load(deps.storage, &denom)?;
+
+    let config = CONFIG.load(deps.storage)?;
+    let addresses = address_provider::helpers::query_contract_addrs(
+        deps.as_ref(),
+        &config.address_provider,
+        vec![MarsAddressType::Incentives, MarsAddressType::RewardsCollector],
+    )?;
+    let rewards_collector_addr = &addresses[&MarsAddressType::RewardsCollector];
+    let incentives_addr = &addresses[&MarsAddressType::Incentives];
+
+    let mut response = apply_accumulated_interests(
+        deps.storage,
+        &env,
+        &mut market,
+        rewards_collector_addr,
+        incentives_addr,
+        Response::new(),
+    )?;
+
+    // 1. Burn the rewards collector's collateral shares, up to the deficit
+    let rewards_collector = User(rewards_collector_addr, MAIN_ACCOUNT_ID);
+    let reserves_scaled = COLLATERALS
+        .may_load(deps.storage, (rewards_collector_addr, MAIN_ACCOUNT_ID, &denom))?
+        .map(|collateral| collateral.amount_scaled)
+        .unwrap_or_default();
+    let reserves = compute_underlying_amount(
+        reserves_scaled,
+        market.liquidity_index,
+        ScalingOperation::Truncate,
+    )?;
+    let covered_by_reserves = min(deficit, reserves);
+    let burned_scaled = min(
+        compute_scaled_amount(covered_by_reserves, market.liquidity_index, ScalingOperation::Ceil)?,
+        reserves_scaled,
+    );
+
+    if !burned_scaled.is_zero() {
+        response = rewards_collector.decrease_collateral(
+            deps.storage,
+            &market,
+            burned_scaled,
+            incentives_addr,
+            response,
+        )?;
+        market.decrease_collateral(burned_scaled)?;
+    }
+
+    // 2. Spread the rest across depositors, by lowering the liquidity index in proportion to the
+    // share of the deposits that was lost. Part of the deposits is left so that the index doesn't
+    // drop to zero, any deficit beyond that is kept to be covered later.
+    let uncovered = deficit.checked_sub(covered_by_reserves)?;
+    let total_deposits = compute_underlying_amount(
+        market.collateral_total_scaled,
+        market.liquidity_index,
+        ScalingOperation::Truncate,
+    )?;
+    let socialized = min(uncovered, total_deposits.saturating_sub(Uint128::new(1)));
+    let remaining = uncovered.checked_sub(socialized)?;
+
+    if covered_by_reserves.is_zero() && socialized.is_zero() {
+        return Err(ContractError::DeficitExceedsDeposits {
+            denom,
+        });
+    }
+
+    if !socialized.is_zero() {
+        market.liquidity_index = market
+            .liquidity_index
+            .checked_mul(Decimal::from_ratio(total_deposits - socialized, total_deposits))?;
+    }
+
+    if remaining.is_zero() {
+        DEFICITS.remove(deps.storage, &denom);
+    } else {
+        DEFICITS.save(deps.storage, &denom, &remaining)?;
+    }
+
+    response = update_interest_rates(&deps, &env, &mut market, Uint128::zero(), &denom, response)?;
+
+    MARKETS.save(deps.storage, &denom, &market)?;
+
+    Ok(response
+        .add_attribute("action", "cover_deficit")
+        .add_attribute("denom", denom)
+        .add_attribute("covered_by_reserves", covered_by_reserves)
+        .add_attribute("socialized", socialized)
+        .add_attribute("remaining", remaining))
+}
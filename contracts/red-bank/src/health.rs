@@ -8,16 +8,17 @@ use crate::{
     error::ContractError,
     interest_rates::{get_underlying_debt_amount, get_underlying_liquidity_amount},
     state::{COLLATERALS, DEBTS, MARKETS},
+    user::User,
 };
 
 /// Check the Health Factor for a given user
 pub fn assert_liquidatable(
     deps: &Deps,
     env: &Env,
-    user_addr: &Addr,
+    user: User,
     oracle_addr: &Addr,
 ) -> Result<(bool, HashMap<String, Position>), ContractError> {
-    let positions = get_user_positions_map(deps, env, user_addr, oracle_addr)?;
+    let positions = get_user_positions_map(deps, env, user, oracle_addr)?;
     let health = compute_position_health(&positions)?;
 
     Ok((health.is_liquidatable(), positions))
@@ -27,12 +28,12 @@ pub fn assert_liquidatable(
 pub fn assert_below_liq_threshold_after_withdraw(
     deps: &Deps,
     env: &Env,
-    user_addr: &Addr,
+    user: User,
     oracle_addr: &Addr,
     denom: &str,
     withdraw_amount: Uint128,
 ) -> Result<bool, ContractError> {
-    let mut positions = get_user_positions_map(deps, env, user_addr, oracle_addr)?;
+    let mut positions = get_user_positions_map(deps, env, user, oracle_addr)?;
 
     // Update position to compute health factor after withdraw
     match positions.get_mut(denom) {
@@ -55,12 +56,12 @@ pub fn assert_below_liq_threshold_after_withdraw(
 pub fn assert_below_max_ltv_after_borrow(
     deps: &Deps,
     env: &Env,
-    user_addr: &Addr,
+    user: User,
     oracle_addr: &Addr,
     denom: &str,
     borrow_amount: Uint128,
 ) -> Result<bool, ContractError> {
-    let mut positions = get_user_positions_map(deps, env, user_addr, oracle_addr)?;
+    let mut positions = get_user_positions_map(deps, env, user, oracle_addr)?;
 
     // Update position to compute health factor after borrow
     positions
@@ -73,6 +74,14 @@ pub fn assert_below_max_ltv_after_borrow(
         })
         .debt_amount += borrow_amount;
 
+    // Users in isolation mode can only borrow against their isolated collateral
+    if let Some(isolated_market) = user.isolated_collateral_market(deps.storage)? {
+        positions
+            .values_mut()
+            .filter(|p| p.denom != isolated_market.denom)
+            .for_each(|p| p.collateral_amount = Uint128::zero());
+    }
+
     let health = compute_position_health(&positions)?;
     Ok(!health.is_above_max_ltv())
 }
@@ -110,18 +119,18 @@ pub fn compute_position_health(
 pub fn get_user_positions_map(
     deps: &Deps,
     env: &Env,
-    user_addr: &Addr,
+    user: User,
     oracle_addr: &Addr,
 ) -> StdResult<HashMap<String, Position>> {
     let block_time = env.block.time.seconds();
 
     // Find all denoms that the user has a collateral or debt position in
     let collateral_denoms = COLLATERALS
-        .prefix(user_addr)
+        .prefix((user.address(), user.account_id()))
         .keys(deps.storage, None, None, Order::Ascending)
         .collect::<StdResult<Vec<_>>>()?;
     let debt_denoms = DEBTS
-        .prefix(user_addr)
+        .prefix((user.address(), user.account_id()))
         .keys(deps.storage, None, None, Order::Ascending)
         .collect::<StdResult<Vec<_>>>()?;
 
@@ -130,6 +139,9 @@ pub fn get_user_positions_map(
     denoms.extend(collateral_denoms);
     denoms.extend(debt_denoms);
 
+    // Users in e-mode use the risk parameters of their category for the assets in the category
+    let emode_category = user.emode_category(deps.storage)?;
+
     // Enumerate the denoms, compute underlying debt and collateral amount, and query the prices.
     // Finally, collect the results into a hashmap indexed by the denoms.
     denoms
@@ -137,7 +149,9 @@ pub fn get_user_positions_map(
         .map(|denom| {
             let market = MARKETS.load(deps.storage, &denom)?;
 
-            let collateral_amount = match COLLATERALS.may_load(deps.storage, (user_addr, &denom))? {
+            let collateral_amount = match COLLATERALS
+                .may_load(deps.storage, (user.address(), user.account_id(), &denom))?
+            {
                 Some(collateral) if collateral.enabled => {
                     let amount_scaled = collateral.amount_scaled;
                     get_underlying_liquidity_amount(amount_scaled, &market, block_time)?
@@ -146,7 +160,7 @@ pub fn get_user_positions_map(
             };
 
             let (debt_amount, uncollateralized_debt) =
-                match DEBTS.may_load(deps.storage, (user_addr, &denom))? {
+                match DEBTS.may_load(deps.storage, (user.address(), user.account_id(), &denom))? {
                     Some(debt) => {
                         let debt_amount =
                             get_underlying_debt_amount(debt.amount_scaled, &market, block_time)?;
@@ -157,13 +171,23 @@ pub fn get_user_positions_map(
 
             let asset_price = oracle::helpers::query_price(&deps.querier, oracle_addr, &denom)?;
 
+            let (max_ltv, liquidation_threshold) = match &emode_category {
+                Some(category) if category.denoms.contains(&denom) => {
+                    (category.max_loan_to_value, category.liquidation_threshold)
+                }
+                _ => (
+                    market.max_loan_to_value_at(block_time),
+                    market.liquidation_threshold_at(block_time),
+                ),
+            };
+
             let position = Position {
                 denom: denom.clone(),
                 collateral_amount,
                 debt_amount,
                 uncollateralized_debt,
-                max_ltv: market.max_loan_to_value,
-                liquidation_threshold: market.liquidation_threshold,
+                max_ltv,
+                liquidation_threshold,
                 asset_price,
             };
 
@@ -3,10 +3,13 @@ use std::str;
 use cosmwasm_std::{
     Addr, Decimal, DepsMut, Env, Event, Response, StdError, StdResult, Storage, Uint128,
 };
-use mars_red_bank_types::red_bank::Market;
+use mars_red_bank_types::red_bank::{InterestAccrual, Market};
 use mars_utils::math;
 
-use crate::{error::ContractError, user::User};
+use crate::{
+    error::ContractError,
+    user::{User, MAIN_ACCOUNT_ID},
+};
 
 /// Scaling factor used to keep more precision during division / multiplication by index.
 pub const SCALING_FACTOR: Uint128 = Uint128::new(1_000_000);
@@ -39,19 +42,22 @@ pub fn apply_accumulated_interests(
         let time_elapsed = current_timestamp - market.indexes_last_updated;
 
         if !market.borrow_rate.is_zero() {
-            market.borrow_index = calculate_applied_linear_interest_rate(
+            market.borrow_index = calculate_applied_interest_rate(
+                market.interest_accrual,
                 market.borrow_index,
                 market.borrow_rate,
                 time_elapsed,
             )?;
         }
         if !market.liquidity_rate.is_zero() {
-            market.liquidity_index = calculate_applied_linear_interest_rate(
+            market.liquidity_index = calculate_applied_interest_rate(
+                market.interest_accrual,
                 market.liquidity_index,
                 market.liquidity_rate,
                 time_elapsed,
             )?;
         }
+        market.interest_rate_model.apply_drift(time_elapsed)?;
         market.indexes_last_updated = current_timestamp;
     }
 
@@ -84,7 +90,7 @@ pub fn apply_accumulated_interests(
             market.liquidity_index,
             ScalingOperation::Truncate,
         )?;
-        response = User(rewards_collector_addr).increase_collateral(
+        response = User(rewards_collector_addr, MAIN_ACCOUNT_ID).increase_collateral(
             store,
             market,
             reward_amount_scaled,
@@ -97,6 +103,23 @@ pub fn apply_accumulated_interests(
     Ok(response)
 }
 
+/// Applies the rate to the index for the time elapsed, following the accrual mode of the market
+pub fn calculate_applied_interest_rate(
+    interest_accrual: InterestAccrual,
+    index: Decimal,
+    rate: Decimal,
+    time_elapsed: u64,
+) -> StdResult<Decimal> {
+    match interest_accrual {
+        InterestAccrual::Linear => {
+            calculate_applied_linear_interest_rate(index, rate, time_elapsed)
+        }
+        InterestAccrual::Compounding => {
+            calculate_applied_compound_interest_rate(index, rate, time_elapsed)
+        }
+    }
+}
+
 pub fn calculate_applied_linear_interest_rate(
     index: Decimal,
     rate: Decimal,
@@ -109,6 +132,43 @@ pub fn calculate_applied_linear_interest_rate(
     index.checked_mul(Decimal::one() + rate_factor).map_err(StdError::from)
 }
 
+/// Compounds the rate every second for the time elapsed. (1 + rate / SECONDS_PER_YEAR)^time_elapsed
+/// is approximated with the first four terms of its binomial expansion:
+/// 1 + n*x + n*(n-1)/2 * x^2 + n*(n-1)*(n-2)/6 * x^3, with x = rate / SECONDS_PER_YEAR and
+/// n = time_elapsed.
+/// The terms are computed from the linear rate factor (n*x) to keep precision, as the per second
+/// rate raised to a power quickly gets below the precision of a Decimal. The approximation
+/// slightly undercharges interest for very high rates or long periods between index updates.
+pub fn calculate_applied_compound_interest_rate(
+    index: Decimal,
+    rate: Decimal,
+    time_elapsed: u64,
+) -> StdResult<Decimal> {
+    if time_elapsed == 0 {
+        return Ok(index);
+    }
+
+    let n = Uint128::from(time_elapsed);
+    let rate_factor = rate.checked_mul(Decimal::from_ratio(n, Uint128::from(SECONDS_PER_YEAR)))?;
+
+    let n_minus_one = n.saturating_sub(Uint128::new(1));
+    let n_minus_two = n.saturating_sub(Uint128::new(2));
+
+    // n*(n-1)/2 * x^2 = (n*x)^2 * (n-1) / (2*n)
+    let second_term = rate_factor
+        .checked_pow(2)?
+        .checked_mul(Decimal::from_ratio(n_minus_one, n.checked_mul(Uint128::new(2))?))?;
+
+    // n*(n-1)*(n-2)/6 * x^3 = (n*x)^3 * (n-1)*(n-2) / (6*n^2)
+    let third_term = rate_factor.checked_pow(3)?.checked_mul(Decimal::from_ratio(
+        n_minus_one.checked_mul(n_minus_two)?,
+        n.checked_mul(n)?.checked_mul(Uint128::new(6))?,
+    ))?;
+
+    let compound_factor = Decimal::one() + rate_factor + second_term + third_term;
+    index.checked_mul(compound_factor).map_err(StdError::from)
+}
+
 /// Get scaled liquidity amount from an underlying amount, a Market and timestamp in seconds
 /// Liquidity amounts are always truncated to make sure rounding errors accumulate in favor of
 /// the protocol
@@ -234,7 +294,8 @@ pub fn get_updated_borrow_index(market: &Market, timestamp: u64) -> StdResult<De
         let time_elapsed = timestamp - market.indexes_last_updated;
 
         if !market.borrow_rate.is_zero() {
-            let updated_index = calculate_applied_linear_interest_rate(
+            let updated_index = calculate_applied_interest_rate(
+                market.interest_accrual,
                 market.borrow_index,
                 market.borrow_rate,
                 time_elapsed,
@@ -260,7 +321,8 @@ pub fn get_updated_liquidity_index(market: &Market, timestamp: u64) -> StdResult
         let time_elapsed = timestamp - market.indexes_last_updated;
 
         if !market.liquidity_rate.is_zero() {
-            let updated_index = calculate_applied_linear_interest_rate(
+            let updated_index = calculate_applied_interest_rate(
+                market.interest_accrual,
                 market.liquidity_index,
                 market.liquidity_rate,
                 time_elapsed,
@@ -316,10 +378,16 @@ pub fn build_interests_updated_event(denom: &str, market: &Market) -> Event {
 
 #[cfg(test)]
 mod tests {
-    use cosmwasm_std::{Decimal, Uint128};
-    use mars_red_bank_types::red_bank::Market;
+    use std::str::FromStr;
+
+    use cosmwasm_std::{
+        testing::{mock_env, MockStorage},
+        Addr, Decimal, Response, Timestamp, Uint128,
+    };
+    use mars_red_bank_types::red_bank::{InterestRateModel, Market};
 
     use crate::interest_rates::{
+        apply_accumulated_interests, calculate_applied_compound_interest_rate,
         calculate_applied_linear_interest_rate, get_scaled_debt_amount,
         get_scaled_liquidity_amount, get_underlying_debt_amount, get_underlying_liquidity_amount,
     };
@@ -335,6 +403,30 @@ mod tests {
         assert_eq!(accumulated, Decimal::from_ratio(11u128, 100u128));
     }
 
+    #[test]
+    fn compounded_index_calculation() {
+        let index = Decimal::from_ratio(1u128, 10u128);
+        let rate = Decimal::from_ratio(2u128, 10u128);
+
+        // a single second accrues the same interest as the linear accrual
+        let accumulated = calculate_applied_compound_interest_rate(index, rate, 1).unwrap();
+        assert_eq!(accumulated, calculate_applied_linear_interest_rate(index, rate, 1).unwrap());
+
+        // no time elapsed leaves the index unchanged
+        let accumulated = calculate_applied_compound_interest_rate(index, rate, 0).unwrap();
+        assert_eq!(accumulated, index);
+
+        // half a year compounded every second, approximating 0.1 * e^0.1 = 0.110517091...
+        let time_elapsed = 15768000;
+        let accumulated =
+            calculate_applied_compound_interest_rate(index, rate, time_elapsed).unwrap();
+        assert!(
+            accumulated
+                > calculate_applied_linear_interest_rate(index, rate, time_elapsed).unwrap()
+        );
+        assert_eq!(accumulated, Decimal::from_str("0.110516666631785895").unwrap());
+    }
+
     #[test]
     fn liquidity_and_debt_rounding() {
         let start = Uint128::from(100_000_000_000_u128);
@@ -357,4 +449,42 @@ mod tests {
         assert_eq!(Uint128::from(99_999_999_999_u128), back_to_underlying_liquidity);
         assert_eq!(Uint128::from(100_000_000_001_u128), back_to_underlying_debt);
     }
+
+    #[test]
+    fn adaptive_model_drifts_when_accruing_interests() {
+        let adaptive_model = |kink_rate: Decimal| InterestRateModel::Adaptive {
+            target_utilization_rate: Decimal::percent(80),
+            kink_rate,
+            min_kink_rate: Decimal::percent(1),
+            max_kink_rate: Decimal::one(),
+            proportional_gain: Decimal::percent(50),
+            integral_gain: Decimal::one(),
+            utilization_rate: Decimal::percent(90),
+        };
+
+        // utilization of 90%, above the target of 80%, since half a year
+        let mut market = Market {
+            interest_rate_model: adaptive_model(Decimal::percent(10)),
+            indexes_last_updated: 10_000_000,
+            ..Default::default()
+        };
+        let mut env = mock_env();
+        env.block.time = Timestamp::from_seconds(10_000_000 + 15_768_000);
+
+        apply_accumulated_interests(
+            &mut MockStorage::new(),
+            &env,
+            &mut market,
+            &Addr::unchecked("rewards_collector"),
+            &Addr::unchecked("incentives"),
+            Response::new(),
+        )
+        .unwrap();
+        market.update_interest_rates(Decimal::percent(90)).unwrap();
+
+        // the kink rate drifts up by 100% * (90% - 80%) / 2, and the borrow rate is set on the
+        // curve through it: 15% + 50% * (90% - 80%)
+        assert_eq!(market.interest_rate_model, adaptive_model(Decimal::percent(15)));
+        assert_eq!(market.borrow_rate, Decimal::percent(20));
+    }
 }
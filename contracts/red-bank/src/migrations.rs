@@ -0,0 +1,769 @@
+/// Migration logic for Red Bank contract with version: 1.0.1
+pub mod v1_0_1 {
+    use std::{cmp::Ordering, collections::BTreeMap, iter::Peekable, ops::Bound};
+
+    use cosmwasm_std::{
+        Addr, Decimal, DepsMut, MessageInfo, Order, Record, Response, StdResult, Storage, Uint128,
+    };
+    use cw_storage_plus::Map;
+    use mars_red_bank_types::{
+        address_provider::{self, MarsAddressType},
+        red_bank::{
+            Config, ExecuteMsg, InterestAccrual, InterestRateModel, LiquidationBonus, Market,
+            MarketStatus, QueryMsg,
+        },
+    };
+    use serde::{Deserialize, Serialize};
+
+    use crate::{
+        error::ContractError,
+        execute::{CONTRACT_NAME, CONTRACT_VERSION},
+        state::{COLLATERALS, CONFIG, DEBTS, MARKETS, MIGRATING_POSITIONS, OWNER},
+        user::{User, MAIN_ACCOUNT_ID},
+    };
+
+    const FROM_VERSION: &str = "1.0.1";
+
+    /// Config as stored by version 1.0.1
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+    pub struct ConfigV1 {
+        pub address_provider: Addr,
+        pub close_factor: Decimal,
+    }
+
+    impl From<ConfigV1> for Config<Addr> {
+        fn from(config: ConfigV1) -> Self {
+            Config {
+                address_provider: config.address_provider,
+                close_factor: config.close_factor,
+                timelock: 0,
+            }
+        }
+    }
+
+    /// Market as stored by version 1.0.1
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+    pub struct MarketV1 {
+        pub denom: String,
+        pub max_loan_to_value: Decimal,
+        pub liquidation_threshold: Decimal,
+        pub liquidation_bonus: Decimal,
+        pub reserve_factor: Decimal,
+        pub interest_rate_model: InterestRateModel,
+        pub borrow_index: Decimal,
+        pub liquidity_index: Decimal,
+        pub borrow_rate: Decimal,
+        pub liquidity_rate: Decimal,
+        pub indexes_last_updated: u64,
+        pub collateral_total_scaled: Uint128,
+        pub debt_total_scaled: Uint128,
+        pub deposit_enabled: bool,
+        pub borrow_enabled: bool,
+        pub deposit_cap: Uint128,
+    }
+
+    impl From<MarketV1> for Market {
+        fn from(market: MarketV1) -> Self {
+            Market {
+                denom: market.denom,
+                max_loan_to_value: market.max_loan_to_value,
+                liquidation_threshold: market.liquidation_threshold,
+                risk_params_ramp: None,
+                liquidation_bonus: LiquidationBonus::fixed(market.liquidation_bonus),
+                protocol_liquidation_fee: Decimal::zero(),
+                reserve_factor: market.reserve_factor,
+                flash_loan_fee: Decimal::zero(),
+                interest_rate_model: market.interest_rate_model,
+                interest_accrual: InterestAccrual::Linear,
+                borrow_index: market.borrow_index,
+                liquidity_index: market.liquidity_index,
+                borrow_rate: market.borrow_rate,
+                liquidity_rate: market.liquidity_rate,
+                indexes_last_updated: market.indexes_last_updated,
+                collateral_total_scaled: market.collateral_total_scaled,
+                debt_total_scaled: market.debt_total_scaled,
+                deposit_enabled: market.deposit_enabled,
+                borrow_enabled: market.borrow_enabled,
+                deposit_cap: market.deposit_cap,
+                borrow_cap: Uint128::MAX,
+                min_debt: Uint128::zero(),
+                isolated: false,
+                debt_ceiling: Uint128::zero(),
+                borrowable_in_isolation: false,
+                status: MarketStatus::Listed,
+            }
+        }
+    }
+
+    /// Storage layout of version 1.0.1
+    pub mod state {
+        use cosmwasm_std::Addr;
+        use cw_storage_plus::{Item, Map};
+        use mars_red_bank_types::red_bank::{Collateral, Debt};
+
+        use super::{ConfigV1, MarketV1};
+
+        pub const CONFIG: Item<ConfigV1> = Item::new("config");
+        pub const MARKETS: Map<&str, MarketV1> = Map::new("markets");
+        /// Positions were keyed by the user's address and the denom, as users had a single account
+        pub const COLLATERALS: Map<(&Addr, &str), Collateral> = Map::new("collaterals");
+        pub const DEBTS: Map<(&Addr, &str), Debt> = Map::new("debts");
+    }
+
+    /// Migrate the config and the markets. Positions are too many to be moved in a single
+    /// transaction: the account of a user is moved the first time it is used, and the owner moves
+    /// the rest in batches with `migrate_positions`. Until then, queries read the positions of a user
+    /// as if they had been moved.
+    pub fn migrate(deps: DepsMut) -> Result<Response, ContractError> {
+        // make sure we're migrating the correct contract and from the correct version
+        cw2::assert_contract_version(deps.as_ref().storage, CONTRACT_NAME, FROM_VERSION)?;
+
+        let config = state::CONFIG.load(deps.storage)?;
+        CONFIG.save(deps.storage, &config.into())?;
+
+        let markets = migrate_markets(deps.storage)?;
+
+        // The rewards collector receives collateral each time interests are accrued, it is moved
+        // right away so that its balance is never split between the two layouts
+        let rewards_collector_addr = address_provider::helpers::query_contract_addr(
+            deps.as_ref(),
+            &CONFIG.load(deps.storage)?.address_provider,
+            MarsAddressType::RewardsCollector,
+        )?;
+        migrate_account(deps.storage, &rewards_collector_addr)?;
+
+        MIGRATING_POSITIONS.save(deps.storage, &true)?;
+
+        // update contract version
+        cw2::set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "migrate")
+            .add_attribute("from_version", FROM_VERSION)
+            .add_attribute("to_version", CONTRACT_VERSION)
+            .add_attribute("markets", markets.to_string()))
+    }
+
+    /// Whether positions of version 1.0.1 may be left to move
+    pub fn is_migrating(store: &dyn Storage) -> StdResult<bool> {
+        Ok(MIGRATING_POSITIONS.may_load(store)?.unwrap_or_default())
+    }
+
+    /// Move the positions of version 1.0.1 owned by the user to the user's main account
+    pub fn migrate_account(store: &mut dyn Storage, user_addr: &Addr) -> StdResult<()> {
+        let collaterals = state::COLLATERALS
+            .prefix(user_addr)
+            .range(store, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for (denom, collateral) in collaterals {
+            COLLATERALS.save(store, (user_addr, MAIN_ACCOUNT_ID, &denom), &collateral)?;
+            state::COLLATERALS.remove(store, (user_addr, &denom));
+        }
+
+        let debts = state::DEBTS
+            .prefix(user_addr)
+            .range(store, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        if debts.is_empty() {
+            return Ok(());
+        }
+        for (denom, debt) in debts {
+            DEBTS.save(store, (user_addr, MAIN_ACCOUNT_ID, &denom), &debt)?;
+            state::DEBTS.remove(store, (user_addr, &denom));
+        }
+        User(user_addr, MAIN_ACCOUNT_ID).update_borrower_index(store)
+    }
+
+    /// Users whose positions may be used by the message, besides the sender
+    pub fn users_in(msg: &ExecuteMsg) -> Vec<&str> {
+        match msg {
+            ExecuteMsg::UpdateUncollateralizedLoanLimit {
+                user,
+                ..
+            } => vec![user],
+            ExecuteMsg::Deposit {
+                on_behalf_of,
+                ..
+            }
+            | ExecuteMsg::Withdraw {
+                on_behalf_of,
+                ..
+            }
+            | ExecuteMsg::Borrow {
+                on_behalf_of,
+                ..
+            }
+            | ExecuteMsg::Repay {
+                on_behalf_of,
+                ..
+            }
+            | ExecuteMsg::UpdateAssetCollateralStatus {
+                on_behalf_of,
+                ..
+            }
+            | ExecuteMsg::Deleverage {
+                on_behalf_of,
+                ..
+            } => on_behalf_of.iter().map(String::as_str).collect(),
+            // the recipient of a liquidation receives the collateral seized
+            ExecuteMsg::Liquidate {
+                user,
+                recipient,
+                ..
+            } => {
+                [Some(user), recipient.as_ref()].into_iter().flatten().map(String::as_str).collect()
+            }
+            ExecuteMsg::LiquidateMany {
+                liquidations,
+                recipient,
+            } => liquidations
+                .iter()
+                .map(|liquidation| &liquidation.user)
+                .chain(recipient)
+                .map(String::as_str)
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    /// User whose positions are read by the query
+    pub fn queried_user(msg: &QueryMsg) -> Option<&str> {
+        match msg {
+            QueryMsg::LiquidationPreview {
+                user,
+                ..
+            }
+            | QueryMsg::UserDebt {
+                user,
+                ..
+            }
+            | QueryMsg::UserDebts {
+                user,
+                ..
+            }
+            | QueryMsg::UserCollateral {
+                user,
+                ..
+            }
+            | QueryMsg::UserTotalCollateralScaled {
+                user,
+                ..
+            }
+            | QueryMsg::UserCollaterals {
+                user,
+                ..
+            }
+            | QueryMsg::UserPosition {
+                user,
+                ..
+            }
+            | QueryMsg::MaxBorrow {
+                user,
+                ..
+            }
+            | QueryMsg::MaxWithdraw {
+                user,
+                ..
+            } => Some(user),
+            _ => None,
+        }
+    }
+
+    /// Move up to `limit` positions to the main accounts of their owners, collaterals first, and
+    /// end the migration once none is left
+    pub fn migrate_positions(
+        deps: DepsMut,
+        info: MessageInfo,
+        limit: u32,
+    ) -> Result<Response, ContractError> {
+        OWNER.assert_owner(deps.storage, &info.sender)?;
+
+        if !is_migrating(deps.storage)? {
+            return Err(ContractError::NoMigrationInProgress {});
+        }
+
+        let limit = limit as usize;
+        let collaterals = move_positions(deps.storage, state::COLLATERALS, COLLATERALS, limit)?;
+        let debts = move_positions(deps.storage, state::DEBTS, DEBTS, limit - collaterals.len())?;
+
+        // debts moved to the main accounts were not indexed yet
+        let mut borrowers = debts.clone();
+        borrowers.dedup();
+        for user_addr in &borrowers {
+            User(user_addr, MAIN_ACCOUNT_ID).update_borrower_index(deps.storage)?;
+        }
+
+        let finished =
+            is_empty(deps.storage, state::COLLATERALS)? && is_empty(deps.storage, state::DEBTS)?;
+        if finished {
+            MIGRATING_POSITIONS.remove(deps.storage);
+        }
+
+        Ok(Response::new()
+            .add_attribute("action", "migrate_v1_positions")
+            .add_attribute("collaterals", collaterals.len().to_string())
+            .add_attribute("debts", debts.len().to_string())
+            .add_attribute("finished", finished.to_string()))
+    }
+
+    /// Rewrite every market in the current layout, returning the number of markets migrated
+    fn migrate_markets(store: &mut dyn Storage) -> StdResult<usize> {
+        let markets = state::MARKETS
+            .range(store, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+
+        for (denom, market) in &markets {
+            MARKETS.save(store, denom, &market.clone().into())?;
+        }
+
+        Ok(markets.len())
+    }
+
+    /// Move up to `limit` positions to the main account of their owner, returning the owners of
+    /// the positions moved
+    fn move_positions<T>(
+        store: &mut dyn Storage,
+        old_positions: Map<(&Addr, &str), T>,
+        new_positions: Map<(&Addr, u32, &str), T>,
+        limit: usize,
+    ) -> StdResult<Vec<Addr>>
+    where
+        T: Serialize + serde::de::DeserializeOwned,
+    {
+        // moved positions are removed, so each batch starts from the first position left
+        let positions = old_positions
+            .range(store, None, None, Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<Vec<_>>>()?;
+
+        let mut owners = vec![];
+        for ((user_addr, denom), position) in positions {
+            new_positions.save(store, (&user_addr, MAIN_ACCOUNT_ID, &denom), &position)?;
+            old_positions.remove(store, (&user_addr, &denom));
+            owners.push(user_addr);
+        }
+
+        Ok(owners)
+    }
+
+    fn is_empty<T>(store: &dyn Storage, positions: Map<(&Addr, &str), T>) -> StdResult<bool>
+    where
+        T: Serialize + serde::de::DeserializeOwned,
+    {
+        Ok(positions.keys_raw(store, None, None, Order::Ascending).next().is_none())
+    }
+
+    /// Read-only view of the storage in which the positions of a user have been moved, so that
+    /// queries can be answered before the user's account is migrated. Writes are kept in memory.
+    pub struct MigratedStorage<'a> {
+        storage: &'a dyn Storage,
+        changes: BTreeMap<Vec<u8>, Option<Vec<u8>>>,
+    }
+
+    impl<'a> MigratedStorage<'a> {
+        pub fn new(storage: &'a dyn Storage, user_addr: &Addr) -> StdResult<Self> {
+            let mut view = MigratedStorage {
+                storage,
+                changes: BTreeMap::new(),
+            };
+            migrate_account(&mut view, user_addr)?;
+            Ok(view)
+        }
+    }
+
+    impl Storage for MigratedStorage<'_> {
+        fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+            match self.changes.get(key) {
+                Some(value) => value.clone(),
+                None => self.storage.get(key),
+            }
+        }
+
+        fn range<'b>(
+            &'b self,
+            start: Option<&[u8]>,
+            end: Option<&[u8]>,
+            order: Order,
+        ) -> Box<dyn Iterator<Item = Record> + 'b> {
+            let mut changes = match (start, end) {
+                (Some(start), Some(end)) if start >= end => vec![],
+                _ => self
+                    .changes
+                    .range::<[u8], _>((
+                        start.map_or(Bound::Unbounded, Bound::Included),
+                        end.map_or(Bound::Unbounded, Bound::Excluded),
+                    ))
+                    .map(|(key, value)| (key.clone(), value.clone()))
+                    .collect::<Vec<_>>(),
+            };
+            if matches!(order, Order::Descending) {
+                changes.reverse();
+            }
+
+            Box::new(MergedRange {
+                records: self.storage.range(start, end, order).peekable(),
+                changes: changes.into_iter().peekable(),
+                order,
+            })
+        }
+
+        fn set(&mut self, key: &[u8], value: &[u8]) {
+            self.changes.insert(key.to_vec(), Some(value.to_vec()));
+        }
+
+        fn remove(&mut self, key: &[u8]) {
+            self.changes.insert(key.to_vec(), None);
+        }
+    }
+
+    /// A value written to the view, `None` if it was removed
+    type Change = (Vec<u8>, Option<Vec<u8>>);
+
+    /// Records of the storage, with the changes kept in memory applied
+    struct MergedRange<'a> {
+        records: Peekable<Box<dyn Iterator<Item = Record> + 'a>>,
+        changes: Peekable<std::vec::IntoIter<Change>>,
+        order: Order,
+    }
+
+    impl Iterator for MergedRange<'_> {
+        type Item = Record;
+
+        fn next(&mut self) -> Option<Record> {
+            loop {
+                let ordering = match (self.records.peek(), self.changes.peek()) {
+                    (None, None) => return None,
+                    (Some(_), None) => Ordering::Less,
+                    (None, Some(_)) => Ordering::Greater,
+                    (Some((key, _)), Some((changed_key, _))) => match self.order {
+                        Order::Ascending => key.cmp(changed_key),
+                        Order::Descending => changed_key.cmp(key),
+                    },
+                };
+                if ordering == Ordering::Less {
+                    return self.records.next();
+                }
+                if ordering == Ordering::Equal {
+                    self.records.next();
+                }
+                if let (key, Some(value)) = self.changes.next()? {
+                    return Some((key, value));
+                }
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use cosmwasm_std::{attr, from_binary, testing::mock_info};
+        use cw2::VersionError;
+        use mars_owner::{OwnerError, OwnerInit::SetInitialOwner};
+        use mars_red_bank_types::red_bank::{Collateral, Debt, UserDebtResponse};
+        use mars_testing::{mock_dependencies, mock_env_at_block_time};
+
+        use super::*;
+        use crate::state::BORROWERS;
+
+        /// Market as serialized by version 1.0.1
+        fn market_v1_json(denom: &str) -> String {
+            format!(
+                r#"{{
+                    "denom": "{denom}",
+                    "max_loan_to_value": "0.5",
+                    "liquidation_threshold": "0.6",
+                    "liquidation_bonus": "0.05",
+                    "reserve_factor": "0.1",
+                    "interest_rate_model": {{
+                        "optimal_utilization_rate": "0.8",
+                        "base": "0",
+                        "slope_1": "0.07",
+                        "slope_2": "0.45"
+                    }},
+                    "borrow_index": "1.1",
+                    "liquidity_index": "1.05",
+                    "borrow_rate": "0.04",
+                    "liquidity_rate": "0.02",
+                    "indexes_last_updated": 1670000000,
+                    "collateral_total_scaled": "3000000",
+                    "debt_total_scaled": "1500000",
+                    "deposit_enabled": true,
+                    "borrow_enabled": false,
+                    "deposit_cap": "10000000"
+                }}"#
+            )
+        }
+
+        #[test]
+        fn migrated_storage_applies_changes_to_ranges() {
+            let mut storage = cosmwasm_std::testing::MockStorage::new();
+            storage.set(b"a", b"1");
+            storage.set(b"c", b"3");
+            storage.set(b"d", b"4");
+
+            let mut view = MigratedStorage::new(&storage, &Addr::unchecked("alice")).unwrap();
+            view.set(b"b", b"2");
+            view.remove(b"c");
+            view.set(b"d", b"5");
+
+            assert_eq!(view.get(b"c"), None);
+            assert_eq!(view.get(b"d"), Some(b"5".to_vec()));
+            let records = view.range(None, None, Order::Ascending).collect::<Vec<_>>();
+            assert_eq!(
+                records,
+                vec![
+                    (b"a".to_vec(), b"1".to_vec()),
+                    (b"b".to_vec(), b"2".to_vec()),
+                    (b"d".to_vec(), b"5".to_vec())
+                ]
+            );
+            let keys = view
+                .range(Some(b"b"), Some(b"d"), Order::Descending)
+                .map(|(key, _)| key)
+                .collect::<Vec<_>>();
+            assert_eq!(keys, vec![b"b".to_vec()]);
+
+            // the underlying storage is left untouched
+            assert_eq!(storage.get(b"c"), Some(b"3".to_vec()));
+        }
+
+        #[test]
+        fn wrong_version() {
+            let mut deps = mock_dependencies(&[]);
+
+            cw2::set_contract_version(deps.as_mut().storage, "crates.io:mars-oracle", FROM_VERSION)
+                .unwrap();
+            let err = migrate(deps.as_mut()).unwrap_err();
+            assert_eq!(
+                err,
+                ContractError::Version(VersionError::WrongContract {
+                    expected: CONTRACT_NAME.to_string(),
+                    found: "crates.io:mars-oracle".to_string(),
+                })
+            );
+
+            cw2::set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "1.0.0").unwrap();
+            let err = migrate(deps.as_mut()).unwrap_err();
+            assert_eq!(
+                err,
+                ContractError::Version(VersionError::WrongVersion {
+                    expected: FROM_VERSION.to_string(),
+                    found: "1.0.0".to_string(),
+                })
+            );
+        }
+
+        #[test]
+        fn proper_migration() {
+            let mut deps = mock_dependencies(&[]);
+            deps.querier.set_oracle_price("uatom", Decimal::one());
+            deps.querier.set_oracle_price("uosmo", Decimal::one());
+            let env = mock_env_at_block_time(1_670_000_000);
+
+            cw2::set_contract_version(deps.as_mut().storage, CONTRACT_NAME, FROM_VERSION).unwrap();
+
+            let alice = Addr::unchecked("alice");
+            let bob = Addr::unchecked("bob");
+            let rewards_collector = Addr::unchecked("rewards_collector");
+            let collateral = Collateral {
+                amount_scaled: Uint128::new(1_000_000_000_000),
+                enabled: true,
+            };
+            let debt = Debt {
+                amount_scaled: Uint128::new(500_000_000_000),
+                uncollateralized: false,
+            };
+            let uncollateralized_debt = Debt {
+                amount_scaled: Uint128::new(200_000_000_000),
+                uncollateralized: true,
+            };
+
+            OWNER
+                .initialize(
+                    &mut deps.storage,
+                    &deps.api,
+                    SetInitialOwner {
+                        owner: "owner".to_string(),
+                    },
+                )
+                .unwrap();
+
+            for denom in ["uatom", "uosmo"] {
+                deps.storage.set(&state::MARKETS.key(denom), market_v1_json(denom).as_bytes());
+            }
+            state::COLLATERALS.save(deps.as_mut().storage, (&alice, "uosmo"), &collateral).unwrap();
+            state::COLLATERALS.save(deps.as_mut().storage, (&bob, "uatom"), &collateral).unwrap();
+            state::COLLATERALS
+                .save(deps.as_mut().storage, (&rewards_collector, "uatom"), &collateral)
+                .unwrap();
+            state::DEBTS.save(deps.as_mut().storage, (&alice, "uatom"), &debt).unwrap();
+            state::DEBTS
+                .save(deps.as_mut().storage, (&bob, "uosmo"), &uncollateralized_debt)
+                .unwrap();
+            deps.storage
+                .set(b"config", br#"{"address_provider":"address_provider","close_factor":"0.5"}"#);
+
+            let res = migrate(deps.as_mut()).unwrap();
+            assert_eq!(res.messages, vec![]);
+            assert_eq!(
+                res.attributes,
+                vec![
+                    attr("action", "migrate"),
+                    attr("from_version", "1.0.1"),
+                    attr("to_version", CONTRACT_VERSION),
+                    attr("markets", "2")
+                ]
+            );
+
+            let version = cw2::get_contract_version(deps.as_ref().storage).unwrap();
+            assert_eq!(version.contract, CONTRACT_NAME);
+            assert_eq!(version.version, CONTRACT_VERSION);
+
+            // markets keep their params and indexes, the new features are left off
+            let market = MARKETS.load(deps.as_ref().storage, "uatom").unwrap();
+            assert_eq!(
+                market,
+                Market {
+                    denom: "uatom".to_string(),
+                    max_loan_to_value: Decimal::percent(50),
+                    liquidation_threshold: Decimal::percent(60),
+                    risk_params_ramp: None,
+                    liquidation_bonus: LiquidationBonus::fixed(Decimal::percent(5)),
+                    protocol_liquidation_fee: Decimal::zero(),
+                    reserve_factor: Decimal::percent(10),
+                    flash_loan_fee: Decimal::zero(),
+                    interest_rate_model: InterestRateModel::Linear {
+                        optimal_utilization_rate: Decimal::percent(80),
+                        base: Decimal::zero(),
+                        slope_1: Decimal::percent(7),
+                        slope_2: Decimal::percent(45),
+                    },
+                    interest_accrual: InterestAccrual::Linear,
+                    borrow_index: Decimal::percent(110),
+                    liquidity_index: Decimal::percent(105),
+                    borrow_rate: Decimal::percent(4),
+                    liquidity_rate: Decimal::percent(2),
+                    indexes_last_updated: 1_670_000_000,
+                    collateral_total_scaled: Uint128::new(3_000_000),
+                    debt_total_scaled: Uint128::new(1_500_000),
+                    deposit_enabled: true,
+                    borrow_enabled: false,
+                    deposit_cap: Uint128::new(10_000_000),
+                    borrow_cap: Uint128::MAX,
+                    min_debt: Uint128::zero(),
+                    isolated: false,
+                    debt_ceiling: Uint128::zero(),
+                    borrowable_in_isolation: false,
+                    status: MarketStatus::Listed,
+                }
+            );
+            assert!(MARKETS.has(deps.as_ref().storage, "uosmo"));
+
+            // the rewards collector is moved right away
+            assert_eq!(
+                User(&rewards_collector, MAIN_ACCOUNT_ID)
+                    .collateral(deps.as_ref().storage, "uatom")
+                    .unwrap(),
+                collateral
+            );
+            assert!(!state::COLLATERALS.has(deps.as_ref().storage, (&rewards_collector, "uatom")));
+
+            // positions not moved yet can be queried
+            let res: UserDebtResponse = from_binary(
+                &crate::contract::query(
+                    deps.as_ref(),
+                    env.clone(),
+                    QueryMsg::UserDebt {
+                        user: alice.to_string(),
+                        denom: "uatom".to_string(),
+                        account_id: None,
+                    },
+                )
+                .unwrap(),
+            )
+            .unwrap();
+            assert_eq!(res.amount_scaled, debt.amount_scaled);
+            assert!(state::DEBTS.has(deps.as_ref().storage, (&alice, "uatom")));
+
+            // the account of a user is moved before it is used
+            crate::contract::execute(
+                deps.as_mut(),
+                env,
+                mock_info("alice", &[]),
+                ExecuteMsg::SetUserEMode {
+                    category_id: None,
+                    account_id: None,
+                },
+            )
+            .unwrap();
+            assert!(!state::COLLATERALS.has(deps.as_ref().storage, (&alice, "uosmo")));
+            assert!(!state::DEBTS.has(deps.as_ref().storage, (&alice, "uatom")));
+
+            // only the owner can move the remaining positions
+            let err = migrate_positions(deps.as_mut(), mock_info("alice", &[]), 10).unwrap_err();
+            assert_eq!(err, ContractError::Owner(OwnerError::NotOwner {}));
+
+            // they are moved in batches, collaterals first
+            let res = migrate_positions(deps.as_mut(), mock_info("owner", &[]), 1).unwrap();
+            assert_eq!(
+                res.attributes,
+                vec![
+                    attr("action", "migrate_v1_positions"),
+                    attr("collaterals", "1"),
+                    attr("debts", "0"),
+                    attr("finished", "false")
+                ]
+            );
+            assert!(is_migrating(deps.as_ref().storage).unwrap());
+
+            let res = migrate_positions(deps.as_mut(), mock_info("owner", &[]), 1).unwrap();
+            assert_eq!(
+                res.attributes,
+                vec![
+                    attr("action", "migrate_v1_positions"),
+                    attr("collaterals", "0"),
+                    attr("debts", "1"),
+                    attr("finished", "true")
+                ]
+            );
+            assert!(!is_migrating(deps.as_ref().storage).unwrap());
+
+            let err = migrate_positions(deps.as_mut(), mock_info("owner", &[]), 1).unwrap_err();
+            assert_eq!(err, ContractError::NoMigrationInProgress {});
+
+            // positions are moved to the main accounts
+            let alice_user = User(&alice, MAIN_ACCOUNT_ID);
+            let bob_user = User(&bob, MAIN_ACCOUNT_ID);
+            assert_eq!(alice_user.collateral(deps.as_ref().storage, "uosmo").unwrap(), collateral);
+            assert_eq!(bob_user.collateral(deps.as_ref().storage, "uatom").unwrap(), collateral);
+            assert_eq!(alice_user.debt(deps.as_ref().storage, "uatom").unwrap(), debt);
+            assert_eq!(
+                bob_user.debt(deps.as_ref().storage, "uosmo").unwrap(),
+                uncollateralized_debt
+            );
+            let old_collaterals = state::COLLATERALS
+                .range(deps.as_ref().storage, None, None, Order::Ascending)
+                .count();
+            assert_eq!(old_collaterals, 0);
+            let old_debts =
+                state::DEBTS.range(deps.as_ref().storage, None, None, Order::Ascending).count();
+            assert_eq!(old_debts, 0);
+
+            // only accounts with collateralized debt are indexed as borrowers
+            let borrowers = BORROWERS
+                .keys(deps.as_ref().storage, None, None, Order::Ascending)
+                .collect::<StdResult<Vec<_>>>()
+                .unwrap();
+            assert_eq!(borrowers, vec![(alice, MAIN_ACCOUNT_ID)]);
+
+            // the config is migrated with the timelock disabled
+            let config: Config<Addr> = CONFIG.load(deps.as_ref().storage).unwrap();
+            assert_eq!(config.address_provider, Addr::unchecked("address_provider"));
+            assert_eq!(config.close_factor, Decimal::percent(50));
+            assert_eq!(config.timelock, 0);
+
+            // the contract can't be migrated twice
+            let err = migrate(deps.as_mut()).unwrap_err();
+            assert!(matches!(err, ContractError::Version(_)));
+        }
+    }
+}
@@ -1,23 +1,34 @@
-use cosmwasm_std::{Addr, BlockInfo, Deps, Env, Order, StdError, StdResult, Uint128};
+use cosmwasm_std::{
+    Addr, BlockInfo, Decimal, Deps, Env, Fraction, Order, StdError, StdResult, Uint128,
+};
 use cw_storage_plus::Bound;
 use mars_red_bank_types::{
     address_provider::{self, MarsAddressType},
+    oracle,
     red_bank::{
-        Collateral, ConfigResponse, Debt, Market, UncollateralizedLoanLimitResponse,
-        UserCollateralResponse, UserDebtResponse, UserHealthStatus, UserPositionResponse,
+        BorrowerResponse, BorrowersResponse, Collateral, ConfigResponse, Debt, DeficitResponse,
+        EModeCategory, InterestRateModel, LiquidationBonus, LiquidationPreviewResponse, Market,
+        MarketLiveResponse, MarketStatus, OperatorResponse, PausableAction, PauseState,
+        PendingChange, UncollateralizedLoanLimitResponse, UserCollateralResponse, UserDebtResponse,
+        UserHealthStatus, UserPositionResponse,
     },
 };
+use mars_utils::math;
 
 use crate::{
     error::ContractError,
+    execute::{assert_not_paused, liquidation_close_factor, liquidation_compute_amounts},
     health,
     interest_rates::{
         get_scaled_debt_amount, get_scaled_liquidity_amount, get_underlying_debt_amount,
-        get_underlying_liquidity_amount,
+        get_underlying_liquidity_amount, get_updated_borrow_index, get_updated_liquidity_index,
     },
     state::{
-        COLLATERALS, CONFIG, DEBTS, EMERGENCY_OWNER, MARKETS, OWNER, UNCOLLATERALIZED_LOAN_LIMITS,
+        BORROWERS, BORROW_ALLOWANCES, COLLATERALS, CONFIG, DEBTS, DEFICITS, EMERGENCY_OWNER,
+        EMODE_CATEGORIES, GLOBAL_PAUSE_STATE, ISOLATED_DEBTS, MARKETS, OPERATORS, OWNER,
+        PAUSE_STATES, PENDING_CHANGES, UNCOLLATERALIZED_LOAN_LIMITS,
     },
+    user::{User, MAIN_ACCOUNT_ID},
 };
 
 const DEFAULT_LIMIT: u32 = 5;
@@ -34,9 +45,41 @@ pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
         proposed_new_emergency_owner: emergency_owner_state.proposed,
         address_provider: config.address_provider.to_string(),
         close_factor: config.close_factor,
+        timelock: config.timelock,
     })
 }
 
+pub fn query_pause_state(deps: Deps, denom: Option<String>) -> StdResult<PauseState> {
+    match denom {
+        Some(denom) => Ok(PAUSE_STATES.may_load(deps.storage, &denom)?.unwrap_or_default()),
+        None => Ok(GLOBAL_PAUSE_STATE.may_load(deps.storage)?.unwrap_or_default()),
+    }
+}
+
+pub fn query_pending_change(deps: Deps, id: u64) -> StdResult<PendingChange> {
+    PENDING_CHANGES
+        .load(deps.storage, id)
+        .map_err(|_| StdError::generic_err(format!("failed to load pending change: {id}")))
+}
+
+pub fn query_pending_changes(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<PendingChange>> {
+    let start = start_after.map(Bound::exclusive);
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    PENDING_CHANGES
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (_, pending_change) = item?;
+            Ok(pending_change)
+        })
+        .collect()
+}
+
 pub fn query_market(deps: Deps, denom: String) -> StdResult<Market> {
     MARKETS
         .load(deps.storage, &denom)
@@ -61,6 +104,292 @@ pub fn query_markets(
         .collect()
 }
 
+pub fn query_market_live(deps: Deps, env: Env, denom: String) -> StdResult<MarketLiveResponse> {
+    let market = query_market(deps, denom)?;
+    market_live(deps, &env, market)
+}
+
+pub fn query_markets_live(
+    deps: Deps,
+    env: Env,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<MarketLiveResponse>> {
+    let start = start_after.map(|denom| Bound::ExclusiveRaw(denom.into_bytes()));
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    MARKETS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (_, market) = item?;
+            market_live(deps, &env, market)
+        })
+        .collect()
+}
+
+/// Accrue the market's interest up to the current block, without distributing the protocol
+/// rewards, and compute its totals in underlying amounts
+fn market_live(deps: Deps, env: &Env, mut market: Market) -> StdResult<MarketLiveResponse> {
+    let block_time = env.block.time.seconds();
+    let total_collateral =
+        get_underlying_liquidity_amount(market.collateral_total_scaled, &market, block_time)?;
+    let total_debt = get_underlying_debt_amount(market.debt_total_scaled, &market, block_time)?;
+
+    market.borrow_index = get_updated_borrow_index(&market, block_time)?;
+    market.liquidity_index = get_updated_liquidity_index(&market, block_time)?;
+    if market.indexes_last_updated < block_time {
+        market.interest_rate_model.apply_drift(block_time - market.indexes_last_updated)?;
+    }
+    market.indexes_last_updated = block_time;
+
+    let available_liquidity =
+        deps.querier.query_balance(&env.contract.address, &market.denom)?.amount;
+    let utilization_rate = if !total_debt.is_zero() {
+        Decimal::from_ratio(total_debt, available_liquidity.checked_add(total_debt)?)
+    } else {
+        Decimal::zero()
+    };
+
+    // The adaptive model's curve drifted, so the rates have to be set on the new curve
+    if matches!(market.interest_rate_model, InterestRateModel::Adaptive { .. }) {
+        market.update_interest_rates(utilization_rate)?;
+    }
+
+    Ok(MarketLiveResponse {
+        market,
+        total_collateral,
+        total_debt,
+        available_liquidity,
+        utilization_rate,
+    })
+}
+
+pub fn query_liquidation_bonus(
+    deps: Deps,
+    denom: String,
+    liquidation_health_factor: Decimal,
+) -> StdResult<Decimal> {
+    let market = query_market(deps, denom)?;
+    market.liquidation_bonus.get_liquidation_bonus(liquidation_health_factor)
+}
+
+pub fn query_liquidation_preview(
+    deps: Deps,
+    env: Env,
+    user_addr: Addr,
+    account_id: u32,
+    collateral_denom: String,
+    debt_denom: String,
+    amount: Uint128,
+) -> Result<LiquidationPreviewResponse, ContractError> {
+    let block_time = env.block.time.seconds();
+    let user = User(&user_addr, account_id);
+
+    // Same checks as when liquidating
+    if !user.uncollateralized_loan_limit(deps.storage, &debt_denom)?.is_zero() {
+        return Err(ContractError::CannotLiquidateWhenPositiveUncollateralizedLoanLimit {});
+    };
+
+    let user_collateral = COLLATERALS
+        .may_load(deps.storage, (&user_addr, account_id, &collateral_denom))?
+        .ok_or(ContractError::CannotLiquidateWhenNoCollateralBalance {})?;
+    if !user_collateral.enabled {
+        return Err(ContractError::CannotLiquidateWhenCollateralUnset {
+            denom: collateral_denom,
+        });
+    }
+
+    let user_debt = DEBTS
+        .may_load(deps.storage, (&user_addr, account_id, &debt_denom))?
+        .ok_or(ContractError::CannotLiquidateWhenNoDebtBalance {})?;
+
+    let config = CONFIG.load(deps.storage)?;
+    let oracle_addr = address_provider::helpers::query_contract_addr(
+        deps,
+        &config.address_provider,
+        MarsAddressType::Oracle,
+    )?;
+
+    let mut positions = health::get_user_positions_map(&deps, &env, user, &oracle_addr)?;
+    let health = health::compute_position_health(&positions)?;
+    if !health.is_liquidatable() {
+        return Err(ContractError::CannotLiquidateHealthyPosition {});
+    }
+    let liquidation_health_factor = health.liquidation_health_factor.unwrap_or_default();
+
+    let collateral_price = positions
+        .get(&collateral_denom)
+        .ok_or(ContractError::CannotLiquidateWhenNoCollateralBalance {})?
+        .asset_price;
+    let debt_price = positions
+        .get(&debt_denom)
+        .ok_or(ContractError::CannotLiquidateWhenNoDebtBalance {})?
+        .asset_price;
+
+    let collateral_market = MARKETS.load(deps.storage, &collateral_denom)?;
+    let debt_market = MARKETS.load(deps.storage, &debt_denom)?;
+    let user_debt_amount =
+        get_underlying_debt_amount(user_debt.amount_scaled, &debt_market, block_time)?;
+
+    // Users in e-mode are liquidated with the liquidation bonus of their category
+    let mut liquidation_market = collateral_market.clone();
+    if let Some(category) = user.emode_category(deps.storage)? {
+        if category.denoms.contains(&collateral_denom) {
+            liquidation_market.liquidation_bonus =
+                LiquidationBonus::fixed(category.liquidation_bonus);
+        }
+    }
+
+    let (debt_amount, collateral_amount, collateral_amount_scaled, refund_amount) =
+        liquidation_compute_amounts(
+            user_collateral.amount_scaled,
+            user_debt_amount,
+            amount,
+            &liquidation_market,
+            collateral_price,
+            debt_price,
+            block_time,
+            liquidation_close_factor(config.close_factor, user_debt_amount, &debt_market),
+            liquidation_health_factor,
+        )?;
+
+    // Update the positions to compute the health factor after the liquidation
+    let collateral_amount_scaled_after =
+        user_collateral.amount_scaled.checked_sub(collateral_amount_scaled)?;
+    if let Some(position) = positions.get_mut(&collateral_denom) {
+        position.collateral_amount = get_underlying_liquidity_amount(
+            collateral_amount_scaled_after,
+            &collateral_market,
+            block_time,
+        )?;
+    }
+    if let Some(position) = positions.get_mut(&debt_denom) {
+        position.debt_amount = user_debt_amount.checked_sub(debt_amount)?;
+    }
+    let health_after = health::compute_position_health(&positions)?;
+
+    Ok(LiquidationPreviewResponse {
+        debt_amount,
+        collateral_amount,
+        collateral_amount_scaled,
+        refund_amount,
+        liquidation_health_factor: health_after.liquidation_health_factor,
+    })
+}
+
+pub fn query_emode_category(deps: Deps, id: u8) -> StdResult<EModeCategory> {
+    EMODE_CATEGORIES
+        .load(deps.storage, id)
+        .map_err(|_| StdError::generic_err(format!("failed to load e-mode category: {id}")))
+}
+
+pub fn query_emode_categories(
+    deps: Deps,
+    start_after: Option<u8>,
+    limit: Option<u32>,
+) -> StdResult<Vec<EModeCategory>> {
+    let start = start_after.map(Bound::exclusive);
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    EMODE_CATEGORIES
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (_, category) = item?;
+            Ok(category)
+        })
+        .collect()
+}
+
+pub fn query_user_emode(
+    deps: Deps,
+    user_addr: Addr,
+    account_id: u32,
+) -> StdResult<Option<EModeCategory>> {
+    User(&user_addr, account_id).emode_category(deps.storage)
+}
+
+pub fn query_deficit(deps: Deps, denom: String) -> StdResult<DeficitResponse> {
+    let amount = DEFICITS.may_load(deps.storage, &denom)?;
+    Ok(DeficitResponse {
+        denom,
+        amount: amount.unwrap_or_else(Uint128::zero),
+    })
+}
+
+pub fn query_deficits(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<DeficitResponse>> {
+    let start = start_after.map(|denom| Bound::ExclusiveRaw(denom.into_bytes()));
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    DEFICITS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (denom, amount) = item?;
+            Ok(DeficitResponse {
+                denom,
+                amount,
+            })
+        })
+        .collect()
+}
+
+pub fn query_borrow_allowance(
+    deps: Deps,
+    delegator_addr: Addr,
+    account_id: u32,
+    delegatee_addr: Addr,
+    denom: String,
+) -> StdResult<Uint128> {
+    let allowance = BORROW_ALLOWANCES
+        .may_load(deps.storage, ((&delegator_addr, account_id), &delegatee_addr, &denom))?;
+    Ok(allowance.unwrap_or_default())
+}
+
+pub fn query_operator(
+    deps: Deps,
+    user_addr: Addr,
+    account_id: u32,
+    operator_addr: Addr,
+) -> StdResult<OperatorResponse> {
+    let operator = OPERATORS.may_load(deps.storage, ((&user_addr, account_id), &operator_addr))?;
+    Ok(OperatorResponse {
+        operator: operator_addr.to_string(),
+        permissions: operator.as_ref().map(|op| op.permissions.clone()).unwrap_or_default(),
+        expires: operator.and_then(|op| op.expires),
+    })
+}
+
+pub fn query_operators(
+    deps: Deps,
+    user_addr: Addr,
+    account_id: u32,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<OperatorResponse>> {
+    let start = start_after.map(|operator| Bound::ExclusiveRaw(operator.into_bytes()));
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    OPERATORS
+        .prefix((&user_addr, account_id))
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (operator_addr, operator) = item?;
+            Ok(OperatorResponse {
+                operator: operator_addr.to_string(),
+                permissions: operator.permissions,
+                expires: operator.expires,
+            })
+        })
+        .collect()
+}
+
 pub fn query_uncollateralized_loan_limit(
     deps: Deps,
     user_addr: Addr,
@@ -100,12 +429,13 @@ pub fn query_user_debt(
     deps: Deps,
     block: &BlockInfo,
     user_addr: Addr,
+    account_id: u32,
     denom: String,
 ) -> StdResult<UserDebtResponse> {
     let Debt {
         amount_scaled,
         uncollateralized,
-    } = DEBTS.may_load(deps.storage, (&user_addr, &denom))?.unwrap_or_default();
+    } = DEBTS.may_load(deps.storage, (&user_addr, account_id, &denom))?.unwrap_or_default();
 
     let block_time = block.time.seconds();
     let market = MARKETS.load(deps.storage, &denom)?;
@@ -123,6 +453,7 @@ pub fn query_user_debts(
     deps: Deps,
     block: &BlockInfo,
     user_addr: Addr,
+    account_id: u32,
     start_after: Option<String>,
     limit: Option<u32>,
 ) -> StdResult<Vec<UserDebtResponse>> {
@@ -132,7 +463,7 @@ pub fn query_user_debts(
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
 
     DEBTS
-        .prefix(&user_addr)
+        .prefix((&user_addr, account_id))
         .range(deps.storage, start, None, Order::Ascending)
         .take(limit)
         .map(|item| {
@@ -157,12 +488,13 @@ pub fn query_user_collateral(
     deps: Deps,
     block: &BlockInfo,
     user_addr: Addr,
+    account_id: u32,
     denom: String,
 ) -> StdResult<UserCollateralResponse> {
     let Collateral {
         amount_scaled,
         enabled,
-    } = COLLATERALS.may_load(deps.storage, (&user_addr, &denom))?.unwrap_or_default();
+    } = COLLATERALS.may_load(deps.storage, (&user_addr, account_id, &denom))?.unwrap_or_default();
 
     let block_time = block.time.seconds();
     let market = MARKETS.load(deps.storage, &denom)?;
@@ -180,6 +512,7 @@ pub fn query_user_collaterals(
     deps: Deps,
     block: &BlockInfo,
     user_addr: Addr,
+    account_id: u32,
     start_after: Option<String>,
     limit: Option<u32>,
 ) -> StdResult<Vec<UserCollateralResponse>> {
@@ -189,7 +522,7 @@ pub fn query_user_collaterals(
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
 
     COLLATERALS
-        .prefix(&user_addr)
+        .prefix((&user_addr, account_id))
         .range(deps.storage, start, None, Order::Ascending)
         .take(limit)
         .map(|item| {
@@ -210,6 +543,14 @@ pub fn query_user_collaterals(
         .collect()
 }
 
+pub fn query_user_total_collateral_scaled(
+    deps: Deps,
+    user_addr: Addr,
+    denom: String,
+) -> StdResult<Uint128> {
+    User(&user_addr, MAIN_ACCOUNT_ID).address_collateral_amount_scaled(deps.storage, &denom)
+}
+
 pub fn query_scaled_liquidity_amount(
     deps: Deps,
     env: Env,
@@ -254,7 +595,9 @@ pub fn query_user_position(
     deps: Deps,
     env: Env,
     user_addr: Addr,
+    account_id: u32,
 ) -> Result<UserPositionResponse, ContractError> {
+    let user = User(&user_addr, account_id);
     let config = CONFIG.load(deps.storage)?;
     let oracle_addr = address_provider::helpers::query_contract_addr(
         deps,
@@ -262,7 +605,7 @@ pub fn query_user_position(
         MarsAddressType::Oracle,
     )?;
 
-    let positions = health::get_user_positions_map(&deps, &env, &user_addr, &oracle_addr)?;
+    let positions = health::get_user_positions_map(&deps, &env, user, &oracle_addr)?;
     let health = health::compute_position_health(&positions)?;
 
     let health_status = if let (Some(max_ltv_hf), Some(liq_threshold_hf)) =
@@ -284,3 +627,229 @@ pub fn query_user_position(
         health_status,
     })
 }
+
+pub fn query_borrowers(
+    deps: Deps,
+    env: Env,
+    start_after: Option<(String, u32)>,
+    limit: Option<u32>,
+    max_health_factor: Option<Decimal>,
+) -> Result<BorrowersResponse, ContractError> {
+    let start_after = start_after
+        .map(|(user, account_id)| deps.api.addr_validate(&user).map(|addr| (addr, account_id)))
+        .transpose()?;
+    let start =
+        start_after.as_ref().map(|(addr, account_id)| Bound::exclusive((addr, *account_id)));
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    let config = CONFIG.load(deps.storage)?;
+    let oracle_addr = address_provider::helpers::query_contract_addr(
+        deps,
+        &config.address_provider,
+        MarsAddressType::Oracle,
+    )?;
+
+    // Only `limit` borrowers are scanned, whether they match the filter or not, so that the query
+    // stays within the gas limit
+    let mut borrowers = vec![];
+    let mut last_scanned = None;
+    let mut scanned = 0;
+    for item in BORROWERS.keys(deps.storage, start, None, Order::Ascending).take(limit) {
+        let (user_addr, account_id) = item?;
+        scanned += 1;
+        last_scanned = Some((user_addr.to_string(), account_id));
+
+        let user = User(&user_addr, account_id);
+        let positions = health::get_user_positions_map(&deps, &env, user, &oracle_addr)?;
+        let health = health::compute_position_health(&positions)?;
+
+        let below_max = match (max_health_factor, health.liquidation_health_factor) {
+            (Some(max_hf), Some(hf)) => hf <= max_hf,
+            (Some(_), None) => false,
+            (None, _) => true,
+        };
+        if below_max {
+            borrowers.push(BorrowerResponse {
+                user: user_addr.to_string(),
+                account_id,
+                liquidation_health_factor: health.liquidation_health_factor,
+            });
+        }
+    }
+
+    // Fewer borrowers than the limit were left to scan
+    if scanned < limit {
+        last_scanned = None;
+    }
+
+    Ok(BorrowersResponse {
+        borrowers,
+        last_scanned,
+    })
+}
+
+pub fn query_max_borrow(
+    deps: Deps,
+    env: Env,
+    user_addr: Addr,
+    account_id: u32,
+    denom: String,
+) -> Result<Uint128, ContractError> {
+    let block_time = env.block.time.seconds();
+    let user = User(&user_addr, account_id);
+    let market = MARKETS.load(deps.storage, &denom)?;
+
+    if market.status != MarketStatus::Listed
+        || !market.borrow_enabled
+        || assert_not_paused(deps.storage, &denom, PausableAction::Borrow).is_err()
+    {
+        return Ok(Uint128::zero());
+    }
+
+    // Users in e-mode can only borrow assets in their category
+    if let Some(category) = user.emode_category(deps.storage)? {
+        if !category.denoms.contains(&denom) {
+            return Ok(Uint128::zero());
+        }
+    }
+
+    // Borrows are limited by the coins held by the contract and by the borrow cap
+    let available_liquidity = deps.querier.query_balance(&env.contract.address, &denom)?.amount;
+    let total_debt = get_underlying_debt_amount(market.debt_total_scaled, &market, block_time)?;
+    let mut max_borrow = available_liquidity.min(market.borrow_cap.saturating_sub(total_debt));
+
+    // Uncollateralized loans are only limited by the user's limit
+    let uncollateralized_loan_limit = user.uncollateralized_loan_limit(deps.storage, &denom)?;
+    let debt_amount_scaled = user.debt_amount_scaled(deps.storage, &denom)?;
+    let total_debt_amount = get_underlying_debt_amount(debt_amount_scaled, &market, block_time)?;
+    if !uncollateralized_loan_limit.is_zero() {
+        max_borrow = max_borrow.min(uncollateralized_loan_limit.saturating_sub(total_debt_amount));
+        return above_min_debt(&market, total_debt_amount, max_borrow);
+    }
+
+    let config = CONFIG.load(deps.storage)?;
+    let oracle_addr = address_provider::helpers::query_contract_addr(
+        deps,
+        &config.address_provider,
+        MarsAddressType::Oracle,
+    )?;
+    let price = oracle::helpers::query_price(&deps.querier, &oracle_addr, &denom)?;
+
+    let mut positions = health::get_user_positions_map(&deps, &env, user, &oracle_addr)?;
+
+    // Users in isolation mode can only borrow assets borrowable in isolation, against their
+    // isolated collateral and up to its debt ceiling
+    if let Some(isolated_market) = user.isolated_collateral_market(deps.storage)? {
+        if !market.borrowable_in_isolation {
+            return Ok(Uint128::zero());
+        }
+
+        positions
+            .values_mut()
+            .filter(|p| p.denom != isolated_market.denom)
+            .for_each(|p| p.collateral_amount = Uint128::zero());
+
+        let isolated_debt =
+            ISOLATED_DEBTS.may_load(deps.storage, &isolated_market.denom)?.unwrap_or_default();
+        let debt_ceiling_left = isolated_market.debt_ceiling.saturating_sub(isolated_debt);
+        max_borrow = max_borrow.min(math::divide_uint128_by_decimal(debt_ceiling_left, price)?);
+    }
+
+    // The debt in the asset can grow up to the max LTV adjusted collateral not used by other debts
+    let health = health::compute_position_health(&positions)?;
+    let debt_amount = match positions.get(&denom) {
+        Some(position) if !position.uncollateralized_debt => position.debt_amount,
+        _ => Uint128::zero(),
+    };
+    let debt_value = debt_amount.checked_multiply_ratio(price.numerator(), price.denominator())?;
+    let other_debts_value = health.total_debt_value.checked_sub(debt_value)?;
+    let debt_value_allowed = health.max_ltv_adjusted_collateral.saturating_sub(other_debts_value);
+    let debt_amount_allowed = math::divide_uint128_by_decimal(debt_value_allowed, price)?;
+
+    max_borrow = max_borrow.min(debt_amount_allowed.saturating_sub(debt_amount));
+    above_min_debt(&market, total_debt_amount, max_borrow)
+}
+
+/// A borrow can't leave the user's debt below the market's min debt, in which case nothing can be
+/// borrowed
+fn above_min_debt(
+    market: &Market,
+    debt_amount: Uint128,
+    max_borrow: Uint128,
+) -> Result<Uint128, ContractError> {
+    if debt_amount.checked_add(max_borrow)? < market.min_debt {
+        return Ok(Uint128::zero());
+    }
+    Ok(max_borrow)
+}
+
+pub fn query_max_withdraw(
+    deps: Deps,
+    env: Env,
+    user_addr: Addr,
+    account_id: u32,
+    denom: String,
+) -> Result<Uint128, ContractError> {
+    let user = User(&user_addr, account_id);
+    let market = MARKETS.load(deps.storage, &denom)?;
+
+    let collateral = match COLLATERALS.may_load(deps.storage, (&user_addr, account_id, &denom))? {
+        Some(collateral) => collateral,
+        None => return Ok(Uint128::zero()),
+    };
+    let collateral_amount = get_underlying_liquidity_amount(
+        collateral.amount_scaled,
+        &market,
+        env.block.time.seconds(),
+    )?;
+
+    // Withdrawals are limited by the coins held by the contract
+    let available_liquidity = deps.querier.query_balance(&env.contract.address, &denom)?.amount;
+    let max_withdraw = available_liquidity.min(collateral_amount);
+
+    // The health factor only needs to be checked if the asset backs some debt
+    if !collateral.enabled || !user.is_borrowing(deps.storage) {
+        return Ok(max_withdraw);
+    }
+
+    let config = CONFIG.load(deps.storage)?;
+    let oracle_addr = address_provider::helpers::query_contract_addr(
+        deps,
+        &config.address_provider,
+        MarsAddressType::Oracle,
+    )?;
+
+    let positions = health::get_user_positions_map(&deps, &env, user, &oracle_addr)?;
+    let health = health::compute_position_health(&positions)?;
+    let position = positions.get(&denom).ok_or_else(|| StdError::not_found("Position"))?;
+
+    // The collateral left must keep the liquidation threshold adjusted collateral above the debts
+    let collateral_value = position.collateral_amount.checked_multiply_ratio(
+        position.asset_price.numerator(),
+        position.asset_price.denominator(),
+    )?;
+    let adjusted_collateral_value = collateral_value.checked_multiply_ratio(
+        position.liquidation_threshold.numerator(),
+        position.liquidation_threshold.denominator(),
+    )?;
+    let other_adjusted_collaterals_value =
+        health.liquidation_threshold_adjusted_collateral.checked_sub(adjusted_collateral_value)?;
+    let adjusted_collateral_value_required =
+        health.total_debt_value.saturating_sub(other_adjusted_collaterals_value);
+
+    if adjusted_collateral_value_required.is_zero() {
+        return Ok(max_withdraw);
+    }
+    if position.liquidation_threshold.is_zero() || position.asset_price.is_zero() {
+        return Ok(Uint128::zero());
+    }
+
+    let collateral_value_required = math::divide_uint128_by_decimal_and_ceil(
+        adjusted_collateral_value_required,
+        position.liquidation_threshold,
+    )?;
+    let collateral_amount_required =
+        math::divide_uint128_by_decimal_and_ceil(collateral_value_required, position.asset_price)?;
+
+    Ok(max_withdraw.min(collateral_amount.saturating_sub(collateral_amount_required)))
+}
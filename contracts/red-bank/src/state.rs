@@ -1,12 +1,40 @@
-use cosmwasm_std::{Addr, Uint128};
+use cosmwasm_std::{Addr, Empty, Uint128};
 use cw_storage_plus::{Item, Map};
 use mars_owner::Owner;
-use mars_red_bank_types::red_bank::{Collateral, Config, Debt, Market};
+use mars_red_bank_types::red_bank::{
+    Collateral, Config, Debt, EModeCategory, FlashLoan, Market, Operator, PauseState, PendingChange,
+};
 
 pub const OWNER: Owner = Owner::new("owner");
 pub const EMERGENCY_OWNER: Owner = Owner::new("emergency_owner");
 pub const CONFIG: Item<Config<Addr>> = Item::new("config");
 pub const MARKETS: Map<&str, Market> = Map::new("markets");
-pub const COLLATERALS: Map<(&Addr, &str), Collateral> = Map::new("collaterals");
-pub const DEBTS: Map<(&Addr, &str), Debt> = Map::new("debts");
+/// Positions are keyed by the user's address, the id of the user's account and the denom
+pub const COLLATERALS: Map<(&Addr, u32, &str), Collateral> = Map::new("account_collaterals");
+pub const DEBTS: Map<(&Addr, u32, &str), Debt> = Map::new("account_debts");
+/// Accounts with a non-zero collateralized debt in any asset, keyed by the user's address and the id
+/// of the account
+pub const BORROWERS: Map<(&Addr, u32), Empty> = Map::new("borrowers");
 pub const UNCOLLATERALIZED_LOAN_LIMITS: Map<(&Addr, &str), Uint128> = Map::new("limits");
+pub const ISOLATED_DEBTS: Map<&str, Uint128> = Map::new("isolated_debts");
+pub const DEFICITS: Map<&str, Uint128> = Map::new("deficits");
+pub const EMODE_CATEGORIES: Map<u8, EModeCategory> = Map::new("emode_categories");
+pub const USER_EMODES: Map<(&Addr, u32), u8> = Map::new("account_emodes");
+/// Amounts delegatees can borrow against a delegator's account, keyed by the delegator's address
+/// and account id, the delegatee's address and the denom
+pub const BORROW_ALLOWANCES: Map<((&Addr, u32), &Addr, &str), Uint128> =
+    Map::new("borrow_allowances");
+/// Operators approved to act on an account, keyed by the user's address and account id and the
+/// operator's address
+pub const OPERATORS: Map<((&Addr, u32), &Addr), Operator> = Map::new("operators");
+/// Actions paused in all markets
+pub const GLOBAL_PAUSE_STATE: Item<PauseState> = Item::new("global_pause_state");
+/// Actions paused in a single market, keyed by the denom of the market
+pub const PAUSE_STATES: Map<&str, PauseState> = Map::new("pause_states");
+/// Changes to the config and the markets' params waiting for the timelock to pass, keyed by id
+pub const PENDING_CHANGES: Map<u64, PendingChange> = Map::new("pending_changes");
+pub const NEXT_PENDING_CHANGE_ID: Item<u64> = Item::new("next_pending_change_id");
+pub const FLASH_LOANS: Item<Vec<FlashLoan>> = Item::new("flash_loans");
+/// Set by the migration from version 1.0.1 until all the positions of that version are moved to
+/// their owners' main accounts
+pub const MIGRATING_POSITIONS: Item<bool> = Item::new("migrating_positions");
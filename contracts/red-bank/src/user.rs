@@ -1,19 +1,28 @@
 use cosmwasm_std::{
-    to_binary, Addr, CosmosMsg, Order, Response, StdResult, Storage, Uint128, WasmMsg,
+    to_binary, Addr, CosmosMsg, Empty, Order, Response, StdResult, Storage, Uint128, WasmMsg,
 };
 use mars_red_bank_types::{
     incentives,
-    red_bank::{Collateral, Debt, Market},
+    red_bank::{Collateral, Debt, EModeCategory, Market, OperatorPermission},
 };
 
-use crate::state::{COLLATERALS, DEBTS, UNCOLLATERALIZED_LOAN_LIMITS};
+use crate::state::{
+    BORROWERS, COLLATERALS, DEBTS, EMODE_CATEGORIES, MARKETS, OPERATORS,
+    UNCOLLATERALIZED_LOAN_LIMITS, USER_EMODES,
+};
+
+/// Id of the account every address has by default. Other ids are sub-accounts of the address, each
+/// with its own isolated position.
+pub const MAIN_ACCOUNT_ID: u32 = 0;
 
 /// A helper class providing an intuitive API for managing user positions in the contract store.
+/// A position belongs to one of the user's accounts, identified by the user's address and the id
+/// of the account.
 ///
 /// For example, to increase a user's debt shares, instead of:
 ///
 /// ```rust
-/// DEBTS.update(deps.storage, &user_addr, |opt| -> StdResult<_> {
+/// DEBTS.update(deps.storage, (&user_addr, account_id, denom), |opt| -> StdResult<_> {
 ///     let mut debt = opt.unwrap_or_default();
 ///     debt.amount_scaled = debt.amount_scaled.checked_add(new_debt)?;
 ///     Ok(debt)
@@ -23,16 +32,16 @@ use crate::state::{COLLATERALS, DEBTS, UNCOLLATERALIZED_LOAN_LIMITS};
 /// The `User` struct allows you simply do
 ///
 /// ```rust
-/// let user = User(&user_addr);
-/// user.increase_debt(deps.storage, new_debt)?;
+/// let user = User(&user_addr, account_id);
+/// user.increase_debt(deps.storage, denom, new_debt)?;
 /// ```
 #[derive(Clone, Copy)]
-pub struct User<'a>(pub &'a Addr);
+pub struct User<'a>(pub &'a Addr, pub u32);
 
 // Implement Into<String> for User so that it can be easily used in event attributes, e.g.
 //
 // ```rust
-// let user = User(&user_addr);
+// let user = User(&user_addr, MAIN_ACCOUNT_ID);
 // let res = Response::new().add_attribute("user", user);
 // ```
 impl<'a> From<User<'a>> for String {
@@ -47,52 +56,144 @@ impl<'a> User<'a> {
         self.0
     }
 
+    /// Returns the id of the user's account
+    pub fn account_id(&self) -> u32 {
+        self.1
+    }
+
     /// Load the user's collateral
     pub fn collateral(&self, store: &dyn Storage, denom: &str) -> StdResult<Collateral> {
-        COLLATERALS.load(store, (self.0, denom))
+        COLLATERALS.load(store, (self.0, self.1, denom))
     }
 
     /// Load the user's debt
     pub fn debt(&self, store: &dyn Storage, denom: &str) -> StdResult<Debt> {
-        DEBTS.load(store, (self.0, denom))
+        DEBTS.load(store, (self.0, self.1, denom))
     }
 
     /// Load the user's scaled debt amount; default to zero if not borrowing.
     pub fn debt_amount_scaled(&self, store: &dyn Storage, denom: &str) -> StdResult<Uint128> {
         let amount_scaled = DEBTS
-            .may_load(store, (self.0, denom))?
+            .may_load(store, (self.0, self.1, denom))?
             .map(|debt| debt.amount_scaled)
             .unwrap_or_else(Uint128::zero);
         Ok(amount_scaled)
     }
 
     /// Load the user's uncollateralized loan limit. Return zero if the user has not been given an
-    /// uncollateralized loan limit.
+    /// uncollateralized loan limit. Limits are given to addresses and only apply to their main
+    /// account.
     pub fn uncollateralized_loan_limit(
         &self,
         store: &dyn Storage,
         denom: &str,
     ) -> StdResult<Uint128> {
+        if self.1 != MAIN_ACCOUNT_ID {
+            return Ok(Uint128::zero());
+        }
+
         let limit = UNCOLLATERALIZED_LOAN_LIMITS
             .may_load(store, (self.0, denom))?
             .unwrap_or_else(Uint128::zero);
         Ok(limit)
     }
 
+    /// Load the e-mode category the user is opted into. Return `None` if the user is not in e-mode.
+    pub fn emode_category(&self, store: &dyn Storage) -> StdResult<Option<EModeCategory>> {
+        USER_EMODES
+            .may_load(store, (self.0, self.1))?
+            .map(|id| EMODE_CATEGORIES.load(store, id))
+            .transpose()
+    }
+
+    /// Return `true` if the address is approved by the user as an operator of the account, with the
+    /// given permission and not expired at the given block time.
+    pub fn is_operator(
+        &self,
+        store: &dyn Storage,
+        operator: &Addr,
+        permission: OperatorPermission,
+        block_time: u64,
+    ) -> StdResult<bool> {
+        let allowed = OPERATORS
+            .may_load(store, ((self.0, self.1), operator))?
+            .map(|op| op.is_allowed(permission, block_time))
+            .unwrap_or(false);
+        Ok(allowed)
+    }
+
     /// Return `true` if the user is borrowing a non-zero amount in _any_ asset; return `false` if
     /// the user is not borrowing any asset.
     ///
     /// The user is borrowing if, in the `DEBTS` map, there is at least one denom stored under the
-    /// user address prefix.
+    /// user address and account prefix.
     pub fn is_borrowing(&self, store: &dyn Storage) -> bool {
-        DEBTS.prefix(self.0).range(store, None, None, Order::Ascending).next().is_some()
+        DEBTS.prefix((self.0, self.1)).range(store, None, None, Order::Ascending).next().is_some()
+    }
+
+    /// Return `true` if the user has a non-zero collateral amount in any asset enabled as
+    /// collateral; return `false` if no collateral backs the user's debts anymore.
+    pub fn has_enabled_collateral(&self, store: &dyn Storage) -> StdResult<bool> {
+        for item in COLLATERALS.prefix((self.0, self.1)).range(store, None, None, Order::Ascending)
+        {
+            let (_, collateral) = item?;
+            if collateral.enabled {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Return the market of the isolated asset the user has enabled as collateral, if any. A user
+    /// with an isolated asset enabled as collateral is in isolation mode.
+    pub fn isolated_collateral_market(&self, store: &dyn Storage) -> StdResult<Option<Market>> {
+        for item in COLLATERALS.prefix((self.0, self.1)).range(store, None, None, Order::Ascending)
+        {
+            let (denom, collateral) = item?;
+            if !collateral.enabled {
+                continue;
+            }
+
+            let market = MARKETS.load(store, &denom)?;
+            if market.isolated {
+                return Ok(Some(market));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Return `true` if the asset can be enabled as collateral by the user; return `false` if this
+    /// would mix isolated collateral with any other collateral, or back existing debts with isolated
+    /// collateral which weren't checked against the isolation mode limits when borrowed.
+    pub fn can_enable_collateral(&self, store: &dyn Storage, market: &Market) -> StdResult<bool> {
+        if market.isolated && self.is_borrowing(store) {
+            return Ok(false);
+        }
+
+        for item in COLLATERALS.prefix((self.0, self.1)).range(store, None, None, Order::Ascending)
+        {
+            let (denom, collateral) = item?;
+            if !collateral.enabled || denom == market.denom {
+                continue;
+            }
+
+            // An isolated asset can only be enabled as the sole collateral
+            if market.isolated || MARKETS.load(store, &denom)?.isolated {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
     }
 
     /// Increase a user's collateral shares by the specified amount. Returns a message to inform the
     /// incentives contract to update the user's index.
     ///
     /// If the user does not already have a collateral amount, the asset is enabled as collateral by
-    /// default. To disable, send a separate `update_asset_collateral_status` execute message.
+    /// default, unless this would mix isolated collateral with any other collateral. To disable,
+    /// send a separate `update_asset_collateral_status` execute message.
     ///
     /// This may be invoked if a user makes a deposit, or when a liquidator liquidates a position.
     pub fn increase_collateral(
@@ -103,26 +204,25 @@ impl<'a> User<'a> {
         incentives_addr: &Addr,
         response: Response,
     ) -> StdResult<Response> {
-        let mut amount_scaled_before = Uint128::zero();
+        let address_amount_scaled_before =
+            self.address_collateral_amount_scaled(store, &market.denom)?;
 
-        COLLATERALS.update(store, (self.0, &market.denom), |opt| -> StdResult<_> {
-            match opt {
-                Some(mut col) => {
-                    amount_scaled_before = col.amount_scaled;
-                    col.amount_scaled = col.amount_scaled.checked_add(amount_scaled)?;
-                    Ok(col)
-                }
-                None => Ok(Collateral {
-                    amount_scaled,
-                    enabled: true, // enable by default
-                }),
+        let collateral = match COLLATERALS.may_load(store, (self.0, self.1, &market.denom))? {
+            Some(mut col) => {
+                col.amount_scaled = col.amount_scaled.checked_add(amount_scaled)?;
+                col
             }
-        })?;
+            None => Collateral {
+                amount_scaled,
+                enabled: self.can_enable_collateral(store, market)?, // enable by default
+            },
+        };
+        COLLATERALS.save(store, (self.0, self.1, &market.denom), &collateral)?;
 
         let msg = self.build_incentives_balance_changed_msg(
             incentives_addr,
             market,
-            amount_scaled_before,
+            address_amount_scaled_before,
         )?;
 
         Ok(response.add_message(msg))
@@ -142,26 +242,48 @@ impl<'a> User<'a> {
         incentives_addr: &Addr,
         response: Response,
     ) -> StdResult<Response> {
-        let mut collateral = COLLATERALS.load(store, (self.0, &market.denom))?;
+        let address_amount_scaled_before =
+            self.address_collateral_amount_scaled(store, &market.denom)?;
 
-        let amount_scaled_before = collateral.amount_scaled;
+        let mut collateral = COLLATERALS.load(store, (self.0, self.1, &market.denom))?;
         collateral.amount_scaled = collateral.amount_scaled.checked_sub(amount_scaled)?;
 
         if collateral.amount_scaled.is_zero() {
-            COLLATERALS.remove(store, (self.0, &market.denom));
+            COLLATERALS.remove(store, (self.0, self.1, &market.denom));
         } else {
-            COLLATERALS.save(store, (self.0, &market.denom), &collateral)?;
+            COLLATERALS.save(store, (self.0, self.1, &market.denom), &collateral)?;
         }
 
         let msg = self.build_incentives_balance_changed_msg(
             incentives_addr,
             market,
-            amount_scaled_before,
+            address_amount_scaled_before,
         )?;
 
         Ok(response.add_message(msg))
     }
 
+    /// Load the scaled collateral amount of the user's address summed across all of its accounts.
+    ///
+    /// The incentives contract tracks collateral balances per address, regardless of the account.
+    pub fn address_collateral_amount_scaled(
+        &self,
+        store: &dyn Storage,
+        denom: &str,
+    ) -> StdResult<Uint128> {
+        COLLATERALS.sub_prefix(self.0).range(store, None, None, Order::Ascending).try_fold(
+            Uint128::zero(),
+            |total, item| {
+                let ((_, collateral_denom), collateral) = item?;
+                if collateral_denom == denom {
+                    Ok(total.checked_add(collateral.amount_scaled)?)
+                } else {
+                    Ok(total)
+                }
+            },
+        )
+    }
+
     /// For internal use by the struct only.
     ///
     /// Create an execute message to inform the incentive contract to update the user's index upon a
@@ -195,7 +317,7 @@ impl<'a> User<'a> {
         amount_scaled: Uint128,
         uncollateralized: bool,
     ) -> StdResult<()> {
-        DEBTS.update(store, (self.0, denom), |opt| -> StdResult<_> {
+        DEBTS.update(store, (self.0, self.1, denom), |opt| -> StdResult<_> {
             match opt {
                 Some(debt) => Ok(Debt {
                     amount_scaled: debt.amount_scaled.checked_add(amount_scaled)?,
@@ -207,7 +329,7 @@ impl<'a> User<'a> {
                 }),
             }
         })?;
-        Ok(())
+        self.update_borrower_index(store)
     }
 
     /// Decrease a user's debt shares by the specified amount. If reduced to zero, delete the debt
@@ -220,16 +342,38 @@ impl<'a> User<'a> {
         denom: &str,
         amount_scaled: Uint128,
     ) -> StdResult<()> {
-        let mut debt = DEBTS.load(store, (self.0, denom))?;
+        let mut debt = DEBTS.load(store, (self.0, self.1, denom))?;
 
         debt.amount_scaled = debt.amount_scaled.checked_sub(amount_scaled)?;
 
         if debt.amount_scaled.is_zero() {
-            DEBTS.remove(store, (self.0, denom));
+            DEBTS.remove(store, (self.0, self.1, denom));
         } else {
-            DEBTS.save(store, (self.0, denom), &debt)?;
+            DEBTS.save(store, (self.0, self.1, denom), &debt)?;
         }
 
-        Ok(())
+        self.update_borrower_index(store)
+    }
+
+    /// Add the user to the `BORROWERS` index if the user has a non-zero collateralized debt in any
+    /// asset; remove the user from the index otherwise.
+    ///
+    /// This must be invoked whenever the user's debts, or whether they are uncollateralized, change.
+    pub fn update_borrower_index(&self, store: &mut dyn Storage) -> StdResult<()> {
+        let mut is_borrower = false;
+        for item in DEBTS.prefix((self.0, self.1)).range(store, None, None, Order::Ascending) {
+            let (_, debt) = item?;
+            if !debt.uncollateralized && !debt.amount_scaled.is_zero() {
+                is_borrower = true;
+                break;
+            }
+        }
+
+        if is_borrower {
+            BORROWERS.save(store, (self.0, self.1), &Empty {})
+        } else {
+            BORROWERS.remove(store, (self.0, self.1));
+            Ok(())
+        }
     }
 }
@@ -4,18 +4,21 @@ use cosmwasm_schema::serde;
 use cosmwasm_std::{
     from_binary,
     testing::{MockApi, MockStorage},
-    Addr, Coin, Decimal, Deps, DepsMut, Event, OwnedDeps, Uint128,
+    Addr, Coin, Decimal, Deps, DepsMut, Event, OwnedDeps, Response, Uint128,
 };
 use mars_red_bank::{
-    contract::{instantiate, query},
+    contract::{execute, instantiate, query},
+    error::ContractError,
     interest_rates::{
-        calculate_applied_linear_interest_rate, compute_scaled_amount, compute_underlying_amount,
-        ScalingOperation,
+        calculate_applied_interest_rate, compute_scaled_amount, compute_underlying_amount,
+        ScalingOperation, SCALING_FACTOR,
     },
     state::{COLLATERALS, DEBTS, MARKETS},
+    user::MAIN_ACCOUNT_ID,
 };
 use mars_red_bank_types::red_bank::{
-    Collateral, CreateOrUpdateConfig, Debt, InstantiateMsg, Market, QueryMsg,
+    BorrowersResponse, Collateral, CreateOrUpdateConfig, Debt, ExecuteMsg, InstantiateMsg, Market,
+    PauseState, PendingChange, QueryMsg, UserDebtResponse,
 };
 use mars_testing::{mock_dependencies, mock_env, mock_info, MarsMockQuerier, MockEnvParams};
 
@@ -30,11 +33,11 @@ pub fn set_collateral(
         amount_scaled,
         enabled,
     };
-    COLLATERALS.save(deps.storage, (user_addr, denom), &collateral).unwrap();
+    COLLATERALS.save(deps.storage, (user_addr, MAIN_ACCOUNT_ID, denom), &collateral).unwrap();
 }
 
 pub fn unset_collateral(deps: DepsMut, user_addr: &Addr, denom: &str) {
-    COLLATERALS.remove(deps.storage, (user_addr, denom));
+    COLLATERALS.remove(deps.storage, (user_addr, MAIN_ACCOUNT_ID, denom));
 }
 
 pub fn set_debt(
@@ -48,23 +51,23 @@ pub fn set_debt(
         amount_scaled: amount_scaled.into(),
         uncollateralized,
     };
-    DEBTS.save(deps.storage, (user_addr, denom), &debt).unwrap();
+    DEBTS.save(deps.storage, (user_addr, MAIN_ACCOUNT_ID, denom), &debt).unwrap();
 }
 
 /// Find if a user has a debt position in the specified asset
 pub fn has_debt_position(deps: Deps, user_addr: &Addr, denom: &str) -> bool {
-    DEBTS.may_load(deps.storage, (user_addr, denom)).unwrap().is_some()
+    DEBTS.may_load(deps.storage, (user_addr, MAIN_ACCOUNT_ID, denom)).unwrap().is_some()
 }
 
 /// Find if a user has a collateral position in the specified asset, regardless of whether enabled
 pub fn has_collateral_position(deps: Deps, user_addr: &Addr, denom: &str) -> bool {
-    COLLATERALS.may_load(deps.storage, (user_addr, denom)).unwrap().is_some()
+    COLLATERALS.may_load(deps.storage, (user_addr, MAIN_ACCOUNT_ID, denom)).unwrap().is_some()
 }
 
 /// Find whether a user has a collateral position AND has it enabled in the specified asset
 pub fn has_collateral_enabled(deps: Deps, user_addr: &Addr, denom: &str) -> bool {
     COLLATERALS
-        .may_load(deps.storage, (user_addr, denom))
+        .may_load(deps.storage, (user_addr, MAIN_ACCOUNT_ID, denom))
         .unwrap()
         .map(|collateral| collateral.enabled)
         .unwrap_or(false)
@@ -77,6 +80,7 @@ pub fn th_setup(contract_balances: &[Coin]) -> OwnedDeps<MockStorage, MockApi, M
     let config = CreateOrUpdateConfig {
         address_provider: Some("address_provider".to_string()),
         close_factor: Some(Decimal::from_ratio(1u128, 2u128)),
+        timelock: None,
     };
     let msg = InstantiateMsg {
         owner: "owner".to_string(),
@@ -105,6 +109,129 @@ pub fn th_init_market(deps: DepsMut, denom: &str, market: &Market) -> Market {
     new_market
 }
 
+/// Set up the contract with the given markets, each asset priced at one by the oracle
+pub fn th_setup_markets(
+    contract_balances: &[Coin],
+    markets: &[(&str, Market)],
+) -> OwnedDeps<MockStorage, MockApi, MarsMockQuerier> {
+    let mut deps = th_setup(contract_balances);
+
+    for (denom, market) in markets {
+        deps.querier.set_oracle_price(denom, Decimal::one());
+        th_init_market(deps.as_mut(), denom, market);
+    }
+
+    deps
+}
+
+/// Market with a max LTV of 50% and a liquidation threshold of 60%, holding `collateral_total` of
+/// deposits
+pub fn th_collateral_market(collateral_total: u128) -> Market {
+    Market {
+        max_loan_to_value: Decimal::percent(50),
+        liquidation_threshold: Decimal::percent(60),
+        collateral_total_scaled: Uint128::new(collateral_total) * SCALING_FACTOR,
+        ..Default::default()
+    }
+}
+
+pub fn th_execute(
+    deps: &mut OwnedDeps<MockStorage, MockApi, MarsMockQuerier>,
+    sender: &str,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    execute(deps.as_mut(), mock_env(MockEnvParams::default()), mock_info(sender), msg)
+}
+
+pub fn th_borrow(
+    deps: &mut OwnedDeps<MockStorage, MockApi, MarsMockQuerier>,
+    sender: &str,
+    denom: &str,
+    amount: u128,
+) -> Result<Response, ContractError> {
+    let msg = ExecuteMsg::Borrow {
+        denom: denom.to_string(),
+        amount: Uint128::new(amount),
+        recipient: None,
+        on_behalf_of: None,
+        account_id: None,
+    };
+    th_execute(deps, sender, msg)
+}
+
+pub fn th_query_market(deps: Deps, denom: &str) -> Market {
+    th_query(
+        deps,
+        QueryMsg::Market {
+            denom: denom.to_string(),
+        },
+    )
+}
+
+pub fn th_query_user_debt(deps: Deps, user: &str, denom: &str) -> Uint128 {
+    let debt: UserDebtResponse = th_query(
+        deps,
+        QueryMsg::UserDebt {
+            user: user.to_string(),
+            account_id: None,
+            denom: denom.to_string(),
+        },
+    );
+    debt.amount
+}
+
+pub fn th_query_borrowers(
+    deps: Deps,
+    start_after: Option<(&str, u32)>,
+    limit: Option<u32>,
+    max_health_factor: Option<Decimal>,
+) -> BorrowersResponse {
+    th_query(
+        deps,
+        QueryMsg::Borrowers {
+            start_after: start_after.map(|(user, account_id)| (user.to_string(), account_id)),
+            limit,
+            max_health_factor,
+        },
+    )
+}
+
+pub fn th_query_borrow_allowance(
+    deps: Deps,
+    delegator: &str,
+    delegatee: &str,
+    denom: &str,
+) -> Uint128 {
+    th_query(
+        deps,
+        QueryMsg::BorrowAllowance {
+            delegator: delegator.to_string(),
+            account_id: None,
+            delegatee: delegatee.to_string(),
+            denom: denom.to_string(),
+        },
+    )
+}
+
+pub fn th_query_pause_state(deps: Deps, denom: Option<&str>) -> PauseState {
+    th_query(
+        deps,
+        QueryMsg::PauseState {
+            denom: denom.map(|denom| denom.to_string()),
+        },
+    )
+}
+
+pub fn th_query_pending_changes(deps: Deps) -> Vec<PendingChange> {
+    th_query(
+        deps,
+        QueryMsg::PendingChanges {
+            start_after: None,
+            limit: None,
+        },
+    )
+}
+
 #[derive(Default, Debug)]
 pub struct TestInterestResults {
     pub borrow_index: Decimal,
@@ -257,14 +384,16 @@ pub struct TestExpectedIndices {
 pub fn th_get_expected_indices(market: &Market, block_time: u64) -> TestExpectedIndices {
     let seconds_elapsed = block_time - market.indexes_last_updated;
     // market indices
-    let expected_liquidity_index = calculate_applied_linear_interest_rate(
+    let expected_liquidity_index = calculate_applied_interest_rate(
+        market.interest_accrual,
         market.liquidity_index,
         market.liquidity_rate,
         seconds_elapsed,
     )
     .unwrap();
 
-    let expected_borrow_index = calculate_applied_linear_interest_rate(
+    let expected_borrow_index = calculate_applied_interest_rate(
+        market.interest_accrual,
         market.borrow_index,
         market.borrow_rate,
         seconds_elapsed,
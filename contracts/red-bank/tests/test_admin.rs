@@ -1,23 +1,30 @@
-use cosmwasm_std::{attr, coin, from_binary, testing::mock_info, Addr, Decimal, Event, Uint128};
+use cosmwasm_std::{
+    attr, coin, from_binary, testing::mock_info, Addr, Decimal, Deps, Event, Uint128,
+};
 use mars_owner::OwnerError::NotOwner;
 use mars_red_bank::{
     contract::{execute, instantiate, query},
     error::ContractError,
-    interest_rates::{compute_scaled_amount, compute_underlying_amount, ScalingOperation},
+    interest_rates::{
+        calculate_applied_linear_interest_rate, compute_scaled_amount, compute_underlying_amount,
+        ScalingOperation, SCALING_FACTOR,
+    },
     state::{COLLATERALS, MARKETS},
+    user::MAIN_ACCOUNT_ID,
 };
 use mars_red_bank_types::{
     address_provider::MarsAddressType,
     error::MarsError,
     red_bank::{
         ConfigResponse, CreateOrUpdateConfig, ExecuteMsg, InitOrUpdateAssetParams, InstantiateMsg,
-        InterestRateModel, Market, QueryMsg,
+        InterestAccrual, InterestRateKink, InterestRateModel, LiquidationBonus, Market, QueryMsg,
+        RiskParamsRamp, UserPositionResponse,
     },
 };
 use mars_testing::{mock_dependencies, mock_env, mock_env_at_block_time, MockEnvParams};
 use mars_utils::error::ValidationError;
 
-use crate::helpers::{th_get_expected_indices, th_init_market, th_setup};
+use crate::helpers::{set_collateral, th_get_expected_indices, th_init_market, th_setup};
 
 mod helpers;
 
@@ -30,6 +37,7 @@ fn proper_initialization() {
     let base_config = CreateOrUpdateConfig {
         address_provider: Some("address_provider".to_string()),
         close_factor: None,
+        timelock: None,
     };
 
     // *
@@ -38,6 +46,7 @@ fn proper_initialization() {
     let empty_config = CreateOrUpdateConfig {
         address_provider: None,
         close_factor: None,
+        timelock: None,
     };
     let msg = InstantiateMsg {
         owner: "owner".to_string(),
@@ -54,6 +63,7 @@ fn proper_initialization() {
     let mut close_factor = Decimal::from_ratio(13u128, 10u128);
     let config = CreateOrUpdateConfig {
         close_factor: Some(close_factor),
+        timelock: None,
         ..base_config.clone()
     };
     let msg = InstantiateMsg {
@@ -79,6 +89,7 @@ fn proper_initialization() {
     close_factor = Decimal::from_ratio(1u128, 2u128);
     let config = CreateOrUpdateConfig {
         close_factor: Some(close_factor),
+        timelock: None,
         ..base_config
     };
     let msg = InstantiateMsg {
@@ -111,6 +122,7 @@ fn update_config() {
     let init_config = CreateOrUpdateConfig {
         address_provider: Some("address_provider".to_string()),
         close_factor: Some(close_factor),
+        timelock: None,
     };
     let msg = InstantiateMsg {
         owner: "owner".to_string(),
@@ -137,6 +149,7 @@ fn update_config() {
     close_factor = Decimal::from_ratio(13u128, 10u128);
     let config = CreateOrUpdateConfig {
         close_factor: Some(close_factor),
+        timelock: None,
         ..init_config
     };
     let msg = ExecuteMsg::UpdateConfig {
@@ -161,6 +174,7 @@ fn update_config() {
     let config = CreateOrUpdateConfig {
         address_provider: Some("new_address_provider".to_string()),
         close_factor: Some(close_factor),
+        timelock: None,
     };
     let msg = ExecuteMsg::UpdateConfig {
         config: config.clone(),
@@ -188,6 +202,7 @@ fn init_asset() {
     let config = CreateOrUpdateConfig {
         address_provider: Some("address_provider".to_string()),
         close_factor: Some(Decimal::from_ratio(1u128, 2u128)),
+        timelock: None,
     };
     let msg = InstantiateMsg {
         owner: "owner".to_string(),
@@ -197,7 +212,7 @@ fn init_asset() {
     let info = mock_info("owner", &[]);
     instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
 
-    let ir_model = InterestRateModel {
+    let ir_model = InterestRateModel::Linear {
         optimal_utilization_rate: Decimal::one(),
         base: Decimal::percent(5),
         slope_1: Decimal::zero(),
@@ -208,11 +223,20 @@ fn init_asset() {
         max_loan_to_value: Some(Decimal::from_ratio(8u128, 10u128)),
         reserve_factor: Some(Decimal::from_ratio(1u128, 100u128)),
         liquidation_threshold: Some(Decimal::one()),
-        liquidation_bonus: Some(Decimal::zero()),
+        liquidation_bonus: Some(LiquidationBonus::fixed(Decimal::zero())),
         interest_rate_model: Some(ir_model.clone()),
         deposit_enabled: Some(true),
         borrow_enabled: Some(true),
         deposit_cap: None,
+        borrow_cap: None,
+        min_debt: None,
+        isolated: None,
+        debt_ceiling: None,
+        borrowable_in_isolation: None,
+        flash_loan_fee: None,
+        interest_accrual: None,
+        protocol_liquidation_fee: None,
+        ramp_duration: None,
     };
 
     // non owner is not authorized
@@ -364,7 +388,7 @@ fn init_asset() {
     // init asset with liquidation_bonus greater than 1
     {
         let invalid_asset_params = InitOrUpdateAssetParams {
-            liquidation_bonus: Some(Decimal::from_ratio(11u128, 10u128)),
+            liquidation_bonus: Some(LiquidationBonus::fixed(Decimal::from_ratio(11u128, 10u128))),
             ..params.clone()
         };
         let msg = ExecuteMsg::InitAsset {
@@ -376,7 +400,7 @@ fn init_asset() {
         assert_eq!(
             error_res,
             ValidationError::InvalidParam {
-                param_name: "liquidation_bonus".to_string(),
+                param_name: "min_lb".to_string(),
                 invalid_value: "1.1".to_string(),
                 predicate: "<= 1".to_string(),
             }
@@ -411,9 +435,11 @@ fn init_asset() {
     // init asset where optimal utilization rate > 1
     {
         let invalid_asset_params = InitOrUpdateAssetParams {
-            interest_rate_model: Some(InterestRateModel {
+            interest_rate_model: Some(InterestRateModel::Linear {
                 optimal_utilization_rate: Decimal::percent(110),
-                ..ir_model
+                base: Decimal::percent(5),
+                slope_1: Decimal::zero(),
+                slope_2: Decimal::zero(),
             }),
             ..params
         };
@@ -474,6 +500,7 @@ fn update_asset() {
     let config = CreateOrUpdateConfig {
         address_provider: Some("address_provider".to_string()),
         close_factor: Some(Decimal::from_ratio(1u128, 2u128)),
+        timelock: None,
     };
     let msg = InstantiateMsg {
         owner: "owner".to_string(),
@@ -483,7 +510,7 @@ fn update_asset() {
     let info = mock_info("owner", &[]);
     instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
 
-    let ir_model = InterestRateModel {
+    let ir_model = InterestRateModel::Linear {
         optimal_utilization_rate: Decimal::one(),
         base: Decimal::percent(5),
         slope_1: Decimal::zero(),
@@ -494,11 +521,20 @@ fn update_asset() {
         max_loan_to_value: Some(Decimal::from_ratio(50u128, 100u128)),
         reserve_factor: Some(Decimal::from_ratio(1u128, 100u128)),
         liquidation_threshold: Some(Decimal::from_ratio(80u128, 100u128)),
-        liquidation_bonus: Some(Decimal::from_ratio(10u128, 100u128)),
+        liquidation_bonus: Some(LiquidationBonus::fixed(Decimal::from_ratio(10u128, 100u128))),
         interest_rate_model: Some(ir_model.clone()),
         deposit_enabled: Some(true),
         borrow_enabled: Some(true),
         deposit_cap: None,
+        borrow_cap: None,
+        min_debt: None,
+        isolated: None,
+        debt_ceiling: None,
+        borrowable_in_isolation: None,
+        flash_loan_fee: None,
+        interest_accrual: None,
+        protocol_liquidation_fee: None,
+        ramp_duration: None,
     };
 
     // non owner is not authorized
@@ -582,7 +618,7 @@ fn update_asset() {
     // update asset with liquidation_bonus greater than 1
     {
         let invalid_asset_params = InitOrUpdateAssetParams {
-            liquidation_bonus: Some(Decimal::from_ratio(11u128, 10u128)),
+            liquidation_bonus: Some(LiquidationBonus::fixed(Decimal::from_ratio(11u128, 10u128))),
             ..params.clone()
         };
         let msg = ExecuteMsg::UpdateAsset {
@@ -594,7 +630,7 @@ fn update_asset() {
         assert_eq!(
             error_res,
             ValidationError::InvalidParam {
-                param_name: "liquidation_bonus".to_string(),
+                param_name: "min_lb".to_string(),
                 invalid_value: "1.1".to_string(),
                 predicate: "<= 1".to_string(),
             }
@@ -629,9 +665,11 @@ fn update_asset() {
     // update asset where optimal utilization rate > 1
     {
         let invalid_asset_params = InitOrUpdateAssetParams {
-            interest_rate_model: Some(InterestRateModel {
+            interest_rate_model: Some(InterestRateModel::Linear {
                 optimal_utilization_rate: Decimal::percent(110),
-                ..ir_model
+                base: Decimal::percent(5),
+                slope_1: Decimal::zero(),
+                slope_2: Decimal::zero(),
             }),
             ..params
         };
@@ -652,17 +690,55 @@ fn update_asset() {
         );
     }
 
+    // update asset where the multi-kink model doesn't cover all utilization rates
+    {
+        let invalid_asset_params = InitOrUpdateAssetParams {
+            interest_rate_model: Some(InterestRateModel::MultiKink {
+                base: Decimal::zero(),
+                kinks: vec![InterestRateKink {
+                    utilization_rate: Decimal::percent(80),
+                    borrow_rate: Decimal::percent(10),
+                }],
+            }),
+            ..params
+        };
+        let msg = ExecuteMsg::UpdateAsset {
+            denom: "someasset".to_string(),
+            params: invalid_asset_params,
+        };
+        let info = mock_info("owner", &[]);
+        let error_res = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+        assert_eq!(
+            error_res,
+            ValidationError::InvalidParam {
+                param_name: "kinks".to_string(),
+                invalid_value: "0.8".to_string(),
+                predicate: "1 (last kink)".to_string()
+            }
+            .into()
+        );
+    }
+
     // update asset with new params
     {
         let params = InitOrUpdateAssetParams {
             max_loan_to_value: Some(Decimal::from_ratio(60u128, 100u128)),
             reserve_factor: Some(Decimal::from_ratio(10u128, 100u128)),
             liquidation_threshold: Some(Decimal::from_ratio(90u128, 100u128)),
-            liquidation_bonus: Some(Decimal::from_ratio(12u128, 100u128)),
+            liquidation_bonus: Some(LiquidationBonus::fixed(Decimal::from_ratio(8u128, 100u128))),
             interest_rate_model: Some(ir_model),
             deposit_enabled: Some(true),
             borrow_enabled: Some(true),
             deposit_cap: Some(Uint128::new(10_000_000)),
+            borrow_cap: None,
+            min_debt: None,
+            isolated: None,
+            debt_ceiling: None,
+            borrowable_in_isolation: None,
+            flash_loan_fee: None,
+            interest_accrual: None,
+            protocol_liquidation_fee: None,
+            ramp_duration: None,
         };
         let msg = ExecuteMsg::UpdateAsset {
             denom: "someasset".to_string(),
@@ -698,6 +774,15 @@ fn update_asset() {
             deposit_enabled: None,
             borrow_enabled: None,
             deposit_cap: None,
+            borrow_cap: None,
+            min_debt: None,
+            isolated: None,
+            debt_ceiling: None,
+            borrowable_in_isolation: None,
+            flash_loan_fee: None,
+            interest_accrual: None,
+            protocol_liquidation_fee: None,
+            ramp_duration: None,
         };
         let msg = ExecuteMsg::UpdateAsset {
             denom: "someasset".to_string(),
@@ -728,6 +813,7 @@ fn update_asset_with_new_interest_rate_model_params() {
     let config = CreateOrUpdateConfig {
         address_provider: Some("address_provider".to_string()),
         close_factor: Some(Decimal::from_ratio(1u128, 2u128)),
+        timelock: None,
     };
     let msg = InstantiateMsg {
         owner: "owner".to_string(),
@@ -738,7 +824,7 @@ fn update_asset_with_new_interest_rate_model_params() {
     let env = mock_env(MockEnvParams::default());
     instantiate(deps.as_mut(), env, info, msg).unwrap();
 
-    let ir_model = InterestRateModel {
+    let ir_model = InterestRateModel::Linear {
         optimal_utilization_rate: Decimal::one(),
         base: Decimal::percent(5),
         slope_1: Decimal::zero(),
@@ -749,11 +835,20 @@ fn update_asset_with_new_interest_rate_model_params() {
         max_loan_to_value: Some(Decimal::from_ratio(50u128, 100u128)),
         reserve_factor: Some(Decimal::from_ratio(2u128, 100u128)),
         liquidation_threshold: Some(Decimal::from_ratio(80u128, 100u128)),
-        liquidation_bonus: Some(Decimal::from_ratio(10u128, 100u128)),
+        liquidation_bonus: Some(LiquidationBonus::fixed(Decimal::from_ratio(10u128, 100u128))),
         interest_rate_model: Some(ir_model.clone()),
         deposit_enabled: Some(true),
         borrow_enabled: Some(true),
         deposit_cap: None,
+        borrow_cap: None,
+        min_debt: None,
+        isolated: None,
+        debt_ceiling: None,
+        borrowable_in_isolation: None,
+        flash_loan_fee: None,
+        interest_accrual: None,
+        protocol_liquidation_fee: None,
+        ramp_duration: None,
     };
 
     let msg = ExecuteMsg::InitAsset {
@@ -769,9 +864,8 @@ fn update_asset_with_new_interest_rate_model_params() {
     assert_eq!(market_before.interest_rate_model, ir_model);
 
     // new IR model has a fixed borrow rate of 69%
-    let new_ir_model = InterestRateModel {
-        base: Decimal::percent(69),
-        ..ir_model
+    let new_ir_model = InterestRateModel::Fixed {
+        borrow_rate: Decimal::percent(69),
     };
     let asset_params_with_new_ir_model = InitOrUpdateAssetParams {
         interest_rate_model: Some(new_ir_model.clone()),
@@ -818,12 +912,114 @@ fn update_asset_with_new_interest_rate_model_params() {
     assert_eq!(res.messages, vec![])
 }
 
+#[test]
+fn update_asset_new_interest_accrual_accrues_interest_rate() {
+    let mut deps = th_setup(&[]);
+
+    let market_before = th_init_market(
+        deps.as_mut(),
+        "someasset",
+        &Market {
+            indexes_last_updated: 1_000_000,
+            borrow_rate: Decimal::percent(20),
+            liquidity_rate: Decimal::percent(10),
+            interest_rate_model: InterestRateModel::Linear {
+                optimal_utilization_rate: Decimal::one(),
+                base: Decimal::percent(20),
+                slope_1: Decimal::zero(),
+                slope_2: Decimal::zero(),
+            },
+            ..Default::default()
+        },
+    );
+    assert_eq!(market_before.interest_accrual, InterestAccrual::Linear);
+
+    let params = InitOrUpdateAssetParams {
+        max_loan_to_value: None,
+        reserve_factor: None,
+        liquidation_threshold: None,
+        liquidation_bonus: None,
+        interest_rate_model: None,
+        deposit_enabled: None,
+        borrow_enabled: None,
+        deposit_cap: None,
+        borrow_cap: None,
+        min_debt: None,
+        isolated: None,
+        debt_ceiling: None,
+        borrowable_in_isolation: None,
+        flash_loan_fee: None,
+        interest_accrual: Some(InterestAccrual::Compounding),
+        protocol_liquidation_fee: None,
+        ramp_duration: None,
+    };
+    let msg = ExecuteMsg::UpdateAsset {
+        denom: "someasset".to_string(),
+        params: params.clone(),
+    };
+    let info = mock_info("owner", &[]);
+    let env = mock_env_at_block_time(2_000_000);
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+
+    // Indices should have been updated using the previous (linear) accrual
+    let new_market = MARKETS.load(&deps.storage, "someasset").unwrap();
+    assert_eq!(new_market.interest_accrual, InterestAccrual::Compounding);
+    let expected_indices = th_get_expected_indices(&market_before, 2_000_000);
+    assert_eq!(new_market.liquidity_index, expected_indices.liquidity);
+    assert_eq!(new_market.borrow_index, expected_indices.borrow);
+    assert_eq!(new_market.indexes_last_updated, 2_000_000);
+    assert_eq!(res.events.len(), 1);
+
+    // Interest from now on is compounded, on both the execute and the query side
+    let underlying_debt: Uint128 = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env_at_block_time(3_000_000),
+            QueryMsg::UnderlyingDebtAmount {
+                denom: "someasset".to_string(),
+                amount_scaled: Uint128::new(1_000_000_000_000),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    let expected_indices = th_get_expected_indices(&new_market, 3_000_000);
+    assert_eq!(
+        underlying_debt,
+        compute_underlying_amount(
+            Uint128::new(1_000_000_000_000),
+            expected_indices.borrow,
+            ScalingOperation::Ceil
+        )
+        .unwrap()
+    );
+    let linear_borrow_index = calculate_applied_linear_interest_rate(
+        new_market.borrow_index,
+        new_market.borrow_rate,
+        1_000_000,
+    )
+    .unwrap();
+    assert!(expected_indices.borrow > linear_borrow_index);
+
+    // Setting the same accrual again doesn't touch the indices
+    let msg = ExecuteMsg::UpdateAsset {
+        denom: "someasset".to_string(),
+        params,
+    };
+    let info = mock_info("owner", &[]);
+    let env = mock_env_at_block_time(2_500_000);
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+    assert!(res.events.is_empty());
+    let market = MARKETS.load(&deps.storage, "someasset").unwrap();
+    assert_eq!(market.indexes_last_updated, 2_000_000);
+}
+
 #[test]
 fn update_asset_new_reserve_factor_accrues_interest_rate() {
     let asset_liquidity = Uint128::from(10_000_000_000_000_u128);
     let mut deps = th_setup(&[coin(asset_liquidity.into(), "somecoin")]);
 
-    let ir_model = InterestRateModel {
+    let ir_model = InterestRateModel::Linear {
         optimal_utilization_rate: Decimal::from_ratio(80u128, 100u128),
         base: Decimal::zero(),
         slope_1: Decimal::from_ratio(1_u128, 2_u128),
@@ -861,6 +1057,15 @@ fn update_asset_new_reserve_factor_accrues_interest_rate() {
         deposit_enabled: None,
         borrow_enabled: None,
         deposit_cap: None,
+        borrow_cap: None,
+        min_debt: None,
+        isolated: None,
+        debt_ceiling: None,
+        borrowable_in_isolation: None,
+        flash_loan_fee: None,
+        interest_accrual: None,
+        protocol_liquidation_fee: None,
+        ramp_duration: None,
     };
     let msg = ExecuteMsg::UpdateAsset {
         denom: "somecoin".to_string(),
@@ -933,7 +1138,11 @@ fn update_asset_new_reserve_factor_accrues_interest_rate() {
     let collateral = COLLATERALS
         .load(
             deps.as_ref().storage,
-            (&Addr::unchecked(MarsAddressType::RewardsCollector.to_string()), "somecoin"),
+            (
+                &Addr::unchecked(MarsAddressType::RewardsCollector.to_string()),
+                MAIN_ACCOUNT_ID,
+                "somecoin",
+            ),
         )
         .unwrap();
     assert_eq!(collateral.amount_scaled, expected_rewards_scaled);
@@ -948,6 +1157,7 @@ fn update_asset_by_emergency_owner() {
     let config = CreateOrUpdateConfig {
         address_provider: Some("address_provider".to_string()),
         close_factor: Some(Decimal::from_ratio(1u128, 2u128)),
+        timelock: None,
     };
     let msg = InstantiateMsg {
         owner: "owner".to_string(),
@@ -957,7 +1167,7 @@ fn update_asset_by_emergency_owner() {
     let info = mock_info("owner", &[]);
     instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
 
-    let ir_model = InterestRateModel {
+    let ir_model = InterestRateModel::Linear {
         optimal_utilization_rate: Decimal::one(),
         base: Decimal::percent(5),
         slope_1: Decimal::zero(),
@@ -968,11 +1178,20 @@ fn update_asset_by_emergency_owner() {
         max_loan_to_value: Some(Decimal::from_ratio(50u128, 100u128)),
         reserve_factor: Some(Decimal::from_ratio(1u128, 100u128)),
         liquidation_threshold: Some(Decimal::from_ratio(80u128, 100u128)),
-        liquidation_bonus: Some(Decimal::from_ratio(10u128, 100u128)),
+        liquidation_bonus: Some(LiquidationBonus::fixed(Decimal::from_ratio(10u128, 100u128))),
         interest_rate_model: Some(ir_model.clone()),
         deposit_enabled: Some(true),
         borrow_enabled: Some(true),
         deposit_cap: None,
+        borrow_cap: None,
+        min_debt: None,
+        isolated: None,
+        debt_ceiling: None,
+        borrowable_in_isolation: None,
+        flash_loan_fee: None,
+        interest_accrual: None,
+        protocol_liquidation_fee: None,
+        ramp_duration: None,
     };
 
     // emergency owner is authorized but can't update asset if not initialized first
@@ -1002,7 +1221,7 @@ fn update_asset_by_emergency_owner() {
 
         let new_asset_params = InitOrUpdateAssetParams {
             borrow_enabled: Some(true),
-            ..params
+            ..params.clone()
         };
         let msg = ExecuteMsg::UpdateAsset {
             denom: "someasset".to_string(),
@@ -1024,18 +1243,27 @@ fn update_asset_by_emergency_owner() {
             max_loan_to_value: Some(Decimal::from_ratio(60u128, 100u128)),
             reserve_factor: Some(Decimal::from_ratio(10u128, 100u128)),
             liquidation_threshold: Some(Decimal::from_ratio(90u128, 100u128)),
-            liquidation_bonus: Some(Decimal::from_ratio(12u128, 100u128)),
+            liquidation_bonus: Some(LiquidationBonus::fixed(Decimal::from_ratio(12u128, 100u128))),
             interest_rate_model: Some(ir_model),
             deposit_enabled: Some(false),
             borrow_enabled: Some(false),
             deposit_cap: Some(Uint128::new(10_000_000)),
+            borrow_cap: None,
+            min_debt: None,
+            isolated: None,
+            debt_ceiling: None,
+            borrowable_in_isolation: None,
+            flash_loan_fee: None,
+            interest_accrual: None,
+            protocol_liquidation_fee: None,
+            ramp_duration: None,
         };
         let msg = ExecuteMsg::UpdateAsset {
             denom: "someasset".to_string(),
             params,
         };
         let info = mock_info("emergency_owner", &[]);
-        let res = execute(deps.as_mut(), env, info, msg).unwrap();
+        let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
         assert!(res.messages.is_empty());
         assert_eq!(
             res.attributes,
@@ -1047,4 +1275,166 @@ fn update_asset_by_emergency_owner() {
         old_market.borrow_enabled = false;
         assert_eq!(old_market, new_market);
     }
+
+    // update asset with a lower borrow cap, should have effect on the saved market
+    {
+        let mut old_market = MARKETS.load(&deps.storage, "someasset").unwrap();
+
+        let params = InitOrUpdateAssetParams {
+            borrow_enabled: None,
+            borrow_cap: Some(Uint128::new(1_000_000)),
+            min_debt: None,
+            ..params.clone()
+        };
+        let msg = ExecuteMsg::UpdateAsset {
+            denom: "someasset".to_string(),
+            params,
+        };
+        let info = mock_info("emergency_owner", &[]);
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let new_market = MARKETS.load(&deps.storage, "someasset").unwrap();
+        old_market.borrow_cap = Uint128::new(1_000_000);
+        assert_eq!(old_market, new_market);
+    }
+
+    // update asset with a higher borrow cap, should have not effect on the saved market
+    {
+        let old_market = MARKETS.load(&deps.storage, "someasset").unwrap();
+
+        let params = InitOrUpdateAssetParams {
+            borrow_enabled: None,
+            borrow_cap: Some(Uint128::new(2_000_000)),
+            min_debt: None,
+            ..params
+        };
+        let msg = ExecuteMsg::UpdateAsset {
+            denom: "someasset".to_string(),
+            params,
+        };
+        let info = mock_info("emergency_owner", &[]);
+        let res_err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert_eq!(res_err, ContractError::Mars(MarsError::Unauthorized {}));
+
+        let new_market = MARKETS.load(&deps.storage, "someasset").unwrap();
+        assert_eq!(old_market, new_market);
+    }
+}
+
+#[test]
+fn update_asset_with_ramp() {
+    let mut deps = th_setup(&[]);
+    deps.querier.set_oracle_price("someasset", Decimal::one());
+
+    th_init_market(
+        deps.as_mut(),
+        "someasset",
+        &Market {
+            max_loan_to_value: Decimal::percent(50),
+            liquidation_threshold: Decimal::percent(60),
+            ..Default::default()
+        },
+    );
+    set_collateral(
+        deps.as_mut(),
+        &Addr::unchecked("user"),
+        "someasset",
+        Uint128::new(1_000) * SCALING_FACTOR,
+        true,
+    );
+
+    let params = InitOrUpdateAssetParams {
+        max_loan_to_value: Some(Decimal::percent(30)),
+        reserve_factor: None,
+        liquidation_threshold: Some(Decimal::percent(40)),
+        liquidation_bonus: None,
+        interest_rate_model: None,
+        deposit_enabled: None,
+        borrow_enabled: None,
+        deposit_cap: None,
+        borrow_cap: None,
+        min_debt: None,
+        isolated: None,
+        debt_ceiling: None,
+        borrowable_in_isolation: None,
+        flash_loan_fee: None,
+        interest_accrual: None,
+        protocol_liquidation_fee: None,
+        ramp_duration: Some(1_000),
+    };
+    let msg = ExecuteMsg::UpdateAsset {
+        denom: "someasset".to_string(),
+        params: params.clone(),
+    };
+    execute(deps.as_mut(), mock_env_at_block_time(10_000), mock_info("owner", &[]), msg).unwrap();
+
+    let market = MARKETS.load(&deps.storage, "someasset").unwrap();
+    assert_eq!(market.max_loan_to_value, Decimal::percent(30));
+    assert_eq!(market.liquidation_threshold, Decimal::percent(40));
+    assert_eq!(
+        market.risk_params_ramp,
+        Some(RiskParamsRamp {
+            initial_max_loan_to_value: Decimal::percent(50),
+            initial_liquidation_threshold: Decimal::percent(60),
+            start_time: 10_000,
+            end_time: 11_000,
+        })
+    );
+
+    // positions are valued with the interpolated LTV and liquidation threshold
+    let query_position = |deps: Deps, block_time: u64| -> UserPositionResponse {
+        let msg = QueryMsg::UserPosition {
+            user: "user".to_string(),
+            account_id: None,
+        };
+        from_binary(&query(deps, mock_env_at_block_time(block_time), msg).unwrap()).unwrap()
+    };
+    for (block_time, weighted_max_ltv, weighted_liq_threshold) in
+        [(10_000, 500, 600), (10_250, 450, 550), (10_500, 400, 500), (11_000, 300, 400)]
+    {
+        let position = query_position(deps.as_ref(), block_time);
+        assert_eq!(position.weighted_max_ltv_collateral, Uint128::new(weighted_max_ltv));
+        assert_eq!(
+            position.weighted_liquidation_threshold_collateral,
+            Uint128::new(weighted_liq_threshold)
+        );
+    }
+
+    // updating the LTV in the middle of the ramp starts a new ramp from the current values
+    let msg = ExecuteMsg::UpdateAsset {
+        denom: "someasset".to_string(),
+        params: InitOrUpdateAssetParams {
+            max_loan_to_value: Some(Decimal::percent(20)),
+            liquidation_threshold: None,
+            ramp_duration: Some(500),
+            ..params.clone()
+        },
+    };
+    execute(deps.as_mut(), mock_env_at_block_time(10_500), mock_info("owner", &[]), msg).unwrap();
+
+    let market = MARKETS.load(&deps.storage, "someasset").unwrap();
+    assert_eq!(
+        market.risk_params_ramp,
+        Some(RiskParamsRamp {
+            initial_max_loan_to_value: Decimal::percent(40),
+            initial_liquidation_threshold: Decimal::percent(50),
+            start_time: 10_500,
+            end_time: 11_000,
+        })
+    );
+
+    // without a ramp duration the new values apply immediately
+    let msg = ExecuteMsg::UpdateAsset {
+        denom: "someasset".to_string(),
+        params: InitOrUpdateAssetParams {
+            ramp_duration: None,
+            ..params
+        },
+    };
+    execute(deps.as_mut(), mock_env_at_block_time(10_600), mock_info("owner", &[]), msg).unwrap();
+
+    let market = MARKETS.load(&deps.storage, "someasset").unwrap();
+    assert_eq!(market.risk_params_ramp, None);
+    let position = query_position(deps.as_ref(), 10_600);
+    assert_eq!(position.weighted_max_ltv_collateral, Uint128::new(300));
 }
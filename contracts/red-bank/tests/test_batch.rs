@@ -0,0 +1,260 @@
+use cosmwasm_std::{
+    attr, coin,
+    testing::{mock_info, MockApi, MockStorage},
+    Addr, Decimal, OwnedDeps, Uint128,
+};
+use helpers::{set_collateral, set_debt, th_collateral_market, th_init_market, th_setup_markets};
+use mars_red_bank::{
+    contract::execute,
+    error::ContractError,
+    interest_rates::SCALING_FACTOR,
+    state::{COLLATERALS, DEBTS},
+    user::MAIN_ACCOUNT_ID,
+};
+use mars_red_bank_types::red_bank::{Action, ExecuteMsg, Market};
+use mars_testing::{mock_env, MarsMockQuerier, MockEnvParams};
+
+mod helpers;
+
+fn setup_test() -> OwnedDeps<MockStorage, MockApi, MarsMockQuerier> {
+    let mut deps = th_setup_markets(
+        &[coin(10_000, "uosmo"), coin(10_000, "ujuno"), coin(1_000, "uatom")],
+        &[
+            ("uosmo", th_collateral_market(10_000)),
+            ("ujuno", th_collateral_market(10_000)),
+            (
+                "uatom",
+                Market {
+                    debt_total_scaled: Uint128::new(5_000) * SCALING_FACTOR,
+                    ..Default::default()
+                },
+            ),
+        ],
+    );
+
+    // collateral of 10_000 uosmo, with 5_000 uatom of debt
+    let user_addr = Addr::unchecked("user");
+    set_collateral(deps.as_mut(), &user_addr, "uosmo", Uint128::new(10_000) * SCALING_FACTOR, true);
+    set_debt(deps.as_mut(), &user_addr, "uatom", Uint128::new(5_000) * SCALING_FACTOR, false);
+
+    deps
+}
+
+#[test]
+fn cannot_execute_batch_if_funds_do_not_match() {
+    let mut deps = setup_test();
+    let env = mock_env(MockEnvParams::default());
+
+    let msg = ExecuteMsg::Batch {
+        actions: vec![],
+        account_id: None,
+    };
+    let info = mock_info("user", &[]);
+    let error_res = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert_eq!(error_res, ContractError::EmptyBatch {});
+
+    let msg = ExecuteMsg::Batch {
+        actions: vec![
+            Action::Deposit {
+                denom: "ujuno".to_string(),
+                amount: Uint128::new(1_000),
+            },
+            Action::Repay {
+                denom: "uatom".to_string(),
+                amount: Uint128::new(100),
+            },
+        ],
+        account_id: None,
+    };
+    let info = mock_info("user", &[coin(1_000, "ujuno")]);
+    let error_res = execute(deps.as_mut(), env, info, msg).unwrap_err();
+    assert_eq!(error_res, ContractError::InvalidBatchFunds {});
+}
+
+#[test]
+fn swap_collateral_in_batch() {
+    let mut deps = setup_test();
+    let env = mock_env(MockEnvParams::default());
+    let user_addr = Addr::unchecked("user");
+
+    // withdrawing all the uosmo on its own breaks the position
+    let msg = ExecuteMsg::Withdraw {
+        denom: "uosmo".to_string(),
+        amount: None,
+        recipient: None,
+        on_behalf_of: None,
+        account_id: None,
+    };
+    let info = mock_info(user_addr.as_str(), &[]);
+    let error_res = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert_eq!(error_res, ContractError::InvalidHealthFactorAfterWithdraw {});
+
+    // replacing it with ujuno in the same batch keeps the position healthy at the end
+    let msg = ExecuteMsg::Batch {
+        actions: vec![
+            Action::Withdraw {
+                denom: "uosmo".to_string(),
+                amount: None,
+                recipient: None,
+            },
+            Action::Deposit {
+                denom: "ujuno".to_string(),
+                amount: Uint128::new(9_000),
+            },
+            Action::Repay {
+                denom: "uatom".to_string(),
+                amount: Uint128::new(1_000),
+            },
+        ],
+        account_id: None,
+    };
+    let info = mock_info(user_addr.as_str(), &[coin(9_000, "ujuno"), coin(1_000, "uatom")]);
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![attr("action", "batch"), attr("user", "user"), attr("account_id", "0")]
+    );
+
+    let batch_actions: Vec<_> = res
+        .events
+        .iter()
+        .filter(|event| event.ty == "batch_action")
+        .map(|event| event.attributes[0].value.clone())
+        .collect();
+    assert_eq!(batch_actions, vec!["withdraw", "deposit", "repay"]);
+
+    assert!(COLLATERALS
+        .may_load(&deps.storage, (&user_addr, MAIN_ACCOUNT_ID, "uosmo"))
+        .unwrap()
+        .is_none());
+    let collateral =
+        COLLATERALS.load(&deps.storage, (&user_addr, MAIN_ACCOUNT_ID, "ujuno")).unwrap();
+    assert_eq!(collateral.amount_scaled, Uint128::new(9_000) * SCALING_FACTOR);
+    let debt = DEBTS.load(&deps.storage, (&user_addr, MAIN_ACCOUNT_ID, "uatom")).unwrap();
+    assert_eq!(debt.amount_scaled, Uint128::new(4_000) * SCALING_FACTOR);
+}
+
+#[test]
+fn cannot_end_batch_with_unhealthy_position() {
+    let mut deps = setup_test();
+    let env = mock_env(MockEnvParams::default());
+
+    // the position is above max LTV after borrowing
+    let msg = ExecuteMsg::Batch {
+        actions: vec![
+            Action::Deposit {
+                denom: "ujuno".to_string(),
+                amount: Uint128::new(1_000),
+            },
+            Action::Borrow {
+                denom: "uatom".to_string(),
+                amount: Uint128::new(600),
+                recipient: None,
+            },
+        ],
+        account_id: None,
+    };
+    let info = mock_info("user", &[coin(1_000, "ujuno")]);
+    let error_res = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert_eq!(error_res, ContractError::InvalidHealthFactorAfterBatch {});
+
+    // the position is liquidatable after disabling collateral
+    let msg = ExecuteMsg::Batch {
+        actions: vec![
+            Action::Deposit {
+                denom: "ujuno".to_string(),
+                amount: Uint128::new(1_000),
+            },
+            Action::UpdateAssetCollateralStatus {
+                denom: "uosmo".to_string(),
+                enable: false,
+            },
+        ],
+        account_id: None,
+    };
+    let info = mock_info("user", &[coin(1_000, "ujuno")]);
+    let error_res = execute(deps.as_mut(), env, info, msg).unwrap_err();
+    assert_eq!(error_res, ContractError::InvalidHealthFactorAfterBatch {});
+}
+
+#[test]
+fn batch_cannot_take_more_than_available_liquidity() {
+    let mut deps = setup_test();
+    let env = mock_env(MockEnvParams::default());
+
+    let lender_addr = Addr::unchecked("lender");
+    set_collateral(
+        deps.as_mut(),
+        &lender_addr,
+        "ujuno",
+        Uint128::new(10_000) * SCALING_FACTOR,
+        true,
+    );
+
+    // each borrow fits in the 1_000 uatom held by the contract, but not both of them
+    let borrow = Action::Borrow {
+        denom: "uatom".to_string(),
+        amount: Uint128::new(600),
+        recipient: None,
+    };
+    let msg = ExecuteMsg::Batch {
+        actions: vec![borrow.clone(), borrow],
+        account_id: None,
+    };
+    let info = mock_info(lender_addr.as_str(), &[]);
+    let error_res = execute(deps.as_mut(), env, info, msg).unwrap_err();
+    assert_eq!(error_res, ContractError::OperationExceedsAvailableLiquidity {});
+}
+
+#[test]
+fn cannot_enable_isolated_collateral_after_borrowing_in_batch() {
+    let mut deps = setup_test();
+    let env = mock_env(MockEnvParams::default());
+
+    deps.querier.set_oracle_price("ulong", Decimal::one());
+    th_init_market(
+        deps.as_mut(),
+        "ulong",
+        &Market {
+            max_loan_to_value: Decimal::percent(50),
+            liquidation_threshold: Decimal::percent(60),
+            collateral_total_scaled: Uint128::new(10_000) * SCALING_FACTOR,
+            isolated: true,
+            ..Default::default()
+        },
+    );
+
+    let borrower_addr = Addr::unchecked("borrower");
+    set_collateral(
+        deps.as_mut(),
+        &borrower_addr,
+        "ulong",
+        Uint128::new(10_000) * SCALING_FACTOR,
+        false,
+    );
+
+    // uatom isn't borrowable in isolation, so borrowing it against the isolated collateral must not
+    // be possible by enabling the collateral only after the borrow
+    let msg = ExecuteMsg::Batch {
+        actions: vec![
+            Action::Borrow {
+                denom: "uatom".to_string(),
+                amount: Uint128::new(100),
+                recipient: None,
+            },
+            Action::UpdateAssetCollateralStatus {
+                denom: "ulong".to_string(),
+                enable: true,
+            },
+        ],
+        account_id: None,
+    };
+    let info = mock_info(borrower_addr.as_str(), &[]);
+    let error_res = execute(deps.as_mut(), env, info, msg).unwrap_err();
+    assert_eq!(
+        error_res,
+        ContractError::IsolatedCollateralWithDebt {
+            denom: "ulong".to_string()
+        }
+    );
+}
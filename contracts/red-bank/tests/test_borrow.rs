@@ -1,5 +1,6 @@
 use cosmwasm_std::{
-    attr, coin, coins, testing::mock_info, Addr, BankMsg, CosmosMsg, Decimal, SubMsg, Uint128,
+    attr, coin, coins, testing::mock_info, Addr, BankMsg, CosmosMsg, Decimal, StdResult, SubMsg,
+    Uint128,
 };
 use cw_utils::PaymentError;
 use helpers::{
@@ -13,7 +14,8 @@ use mars_red_bank::{
         calculate_applied_linear_interest_rate, compute_scaled_amount, compute_underlying_amount,
         ScalingOperation, SCALING_FACTOR,
     },
-    state::{DEBTS, MARKETS, UNCOLLATERALIZED_LOAN_LIMITS},
+    state::{DEBTS, ISOLATED_DEBTS, MARKETS, UNCOLLATERALIZED_LOAN_LIMITS},
+    user::MAIN_ACCOUNT_ID,
 };
 use mars_red_bank_types::red_bank::{ExecuteMsg, Market};
 use mars_testing::{mock_env, mock_env_at_block_time, MockEnvParams};
@@ -92,6 +94,8 @@ fn borrow_and_repay() {
         denom: "uosmo".to_string(),
         amount: borrow_amount,
         recipient: None,
+        on_behalf_of: None,
+        account_id: None,
     };
 
     let env = mock_env_at_block_time(block_time);
@@ -130,6 +134,8 @@ fn borrow_and_repay() {
         vec![
             attr("action", "borrow"),
             attr("sender", "borrower"),
+            attr("on_behalf_of", "borrower"),
+            attr("account_id", "0"),
             attr("recipient", "borrower"),
             attr("denom", "uosmo"),
             attr("amount", borrow_amount.to_string()),
@@ -142,7 +148,7 @@ fn borrow_and_repay() {
     assert!(has_debt_position(deps.as_ref(), &borrower_addr, "uosmo"));
     assert!(!has_debt_position(deps.as_ref(), &borrower_addr, "uusd"));
 
-    let debt = DEBTS.load(&deps.storage, (&borrower_addr, "uosmo")).unwrap();
+    let debt = DEBTS.load(&deps.storage, (&borrower_addr, MAIN_ACCOUNT_ID, "uosmo")).unwrap();
     assert_eq!(expected_debt_scaled_1_after_borrow, debt.amount_scaled);
 
     let market_1_after_borrow = MARKETS.load(&deps.storage, "uosmo").unwrap();
@@ -160,6 +166,8 @@ fn borrow_and_repay() {
         denom: "uosmo".to_string(),
         amount: borrow_amount,
         recipient: None,
+        on_behalf_of: None,
+        account_id: None,
     };
 
     let env = mock_env_at_block_time(block_time);
@@ -181,7 +189,7 @@ fn borrow_and_repay() {
             ..Default::default()
         },
     );
-    let debt = DEBTS.load(&deps.storage, (&borrower_addr, "uosmo")).unwrap();
+    let debt = DEBTS.load(&deps.storage, (&borrower_addr, MAIN_ACCOUNT_ID, "uosmo")).unwrap();
     let market_1_after_borrow_again = MARKETS.load(&deps.storage, "uosmo").unwrap();
 
     let expected_debt_scaled_1_after_borrow_again = expected_debt_scaled_1_after_borrow
@@ -211,6 +219,8 @@ fn borrow_and_repay() {
         denom: String::from("uusd"),
         amount: borrow_amount,
         recipient: None,
+        on_behalf_of: None,
+        account_id: None,
     };
     let res = execute(deps.as_mut(), env, info, msg).unwrap();
 
@@ -249,6 +259,8 @@ fn borrow_and_repay() {
         vec![
             attr("action", "borrow"),
             attr("sender", "borrower"),
+            attr("on_behalf_of", "borrower"),
+            attr("account_id", "0"),
             attr("recipient", "borrower"),
             attr("denom", "uusd"),
             attr("amount", borrow_amount.to_string()),
@@ -257,7 +269,7 @@ fn borrow_and_repay() {
     );
     assert_eq!(res.events, vec![th_build_interests_updated_event("uusd", &expected_params_uusd)]);
 
-    let debt2 = DEBTS.load(&deps.storage, (&borrower_addr, "uusd")).unwrap();
+    let debt2 = DEBTS.load(&deps.storage, (&borrower_addr, MAIN_ACCOUNT_ID, "uusd")).unwrap();
     assert_eq!(expected_debt_scaled_2_after_borrow_2, debt2.amount_scaled);
 
     let market_2_after_borrow_2 = MARKETS.load(&deps.storage, "uusd").unwrap();
@@ -274,6 +286,8 @@ fn borrow_and_repay() {
         denom: String::from("uusd"),
         amount: Uint128::from(83968_u128),
         recipient: None,
+        on_behalf_of: None,
+        account_id: None,
     };
     let error_res = execute(deps.as_mut(), env, info, msg).unwrap_err();
     assert_eq!(error_res, ContractError::BorrowAmountExceedsGivenCollateral {});
@@ -285,6 +299,7 @@ fn borrow_and_repay() {
     let info = mock_info("borrower", &[]);
     let msg = ExecuteMsg::Repay {
         on_behalf_of: None,
+        account_id: None,
     };
     let error_res = execute(deps.as_mut(), env, info, msg).unwrap_err();
     assert_eq!(error_res, PaymentError::NoFunds {}.into());
@@ -298,6 +313,7 @@ fn borrow_and_repay() {
     let info = cosmwasm_std::testing::mock_info("borrower", &[coin(repay_amount.into(), "uusd")]);
     let msg = ExecuteMsg::Repay {
         on_behalf_of: None,
+        account_id: None,
     };
     let res = execute(deps.as_mut(), env, info, msg).unwrap();
 
@@ -326,6 +342,7 @@ fn borrow_and_repay() {
             attr("action", "repay"),
             attr("sender", "borrower"),
             attr("on_behalf_of", "borrower"),
+            attr("account_id", "0"),
             attr("denom", "uusd"),
             attr("amount", repay_amount.to_string()),
             attr("amount_scaled", expected_repay_amount_scaled),
@@ -337,7 +354,7 @@ fn borrow_and_repay() {
     assert!(has_debt_position(deps.as_ref(), &borrower_addr, "uosmo"));
     assert!(has_debt_position(deps.as_ref(), &borrower_addr, "uusd"));
 
-    let debt2 = DEBTS.load(&deps.storage, (&borrower_addr, "uusd")).unwrap();
+    let debt2 = DEBTS.load(&deps.storage, (&borrower_addr, MAIN_ACCOUNT_ID, "uusd")).unwrap();
     let market_2_after_repay_some_2 = MARKETS.load(&deps.storage, "uusd").unwrap();
 
     let expected_debt_scaled_2_after_repay_some_2 =
@@ -378,6 +395,7 @@ fn borrow_and_repay() {
     let info = cosmwasm_std::testing::mock_info("borrower", &[coin(repay_amount, "uusd")]);
     let msg = ExecuteMsg::Repay {
         on_behalf_of: None,
+        account_id: None,
     };
     let res = execute(deps.as_mut(), env, info, msg).unwrap();
 
@@ -388,6 +406,7 @@ fn borrow_and_repay() {
             attr("action", "repay"),
             attr("sender", "borrower"),
             attr("on_behalf_of", "borrower"),
+            attr("account_id", "0"),
             attr("denom", "uusd"),
             attr("amount", repay_amount.to_string()),
             attr("amount_scaled", expected_debt_scaled_2_after_repay_some_2),
@@ -409,6 +428,7 @@ fn borrow_and_repay() {
     let info = cosmwasm_std::testing::mock_info("borrower", &[coin(2000, "uusd")]);
     let msg = ExecuteMsg::Repay {
         on_behalf_of: None,
+        account_id: None,
     };
     let error_res = execute(deps.as_mut(), env, info, msg).unwrap_err();
     assert_eq!(error_res, ContractError::CannotRepayZeroDebt {});
@@ -434,6 +454,7 @@ fn borrow_and_repay() {
     let info = cosmwasm_std::testing::mock_info("borrower", &[coin(repay_amount.u128(), "uosmo")]);
     let msg = ExecuteMsg::Repay {
         on_behalf_of: None,
+        account_id: None,
     };
     let res = execute(deps.as_mut(), env, info, msg).unwrap();
 
@@ -458,6 +479,7 @@ fn borrow_and_repay() {
             attr("action", "repay"),
             attr("sender", "borrower"),
             attr("on_behalf_of", "borrower"),
+            attr("account_id", "0"),
             attr("denom", "uosmo"),
             attr("amount", (repay_amount - expected_refund_amount).to_string()),
             attr("amount_scaled", expected_debt_scaled_1_after_borrow_again),
@@ -515,6 +537,8 @@ fn repay_without_refund_on_behalf_of() {
         denom: String::from("borrowedcoinnative"),
         amount: Uint128::from(borrow_amount),
         recipient: None,
+        on_behalf_of: None,
+        account_id: None,
     };
     let _res = execute(deps.as_mut(), env, info, msg).unwrap();
 
@@ -531,6 +555,7 @@ fn repay_without_refund_on_behalf_of() {
     );
     let msg = ExecuteMsg::Repay {
         on_behalf_of: Some(borrower_addr.to_string()),
+        account_id: None,
     };
     let res = execute(deps.as_mut(), env, info, msg).unwrap();
 
@@ -549,6 +574,7 @@ fn repay_without_refund_on_behalf_of() {
             attr("action", "repay"),
             attr("sender", "user"),
             attr("on_behalf_of", "borrower"),
+            attr("account_id", "0"),
             attr("denom", "borrowedcoinnative"),
             attr("amount", repay_amount.to_string()),
             attr("amount_scaled", Uint128::new(repay_amount) * SCALING_FACTOR),
@@ -595,6 +621,8 @@ fn repay_with_refund_on_behalf_of() {
         denom: String::from("borrowedcoinnative"),
         amount: Uint128::from(borrow_amount),
         recipient: None,
+        on_behalf_of: None,
+        account_id: None,
     };
     let _res = execute(deps.as_mut(), env, info, msg).unwrap();
 
@@ -612,6 +640,7 @@ fn repay_with_refund_on_behalf_of() {
     );
     let msg = ExecuteMsg::Repay {
         on_behalf_of: Some(borrower_addr.to_string()),
+        account_id: None,
     };
     let res = execute(deps.as_mut(), env, info, msg).unwrap();
 
@@ -636,6 +665,7 @@ fn repay_with_refund_on_behalf_of() {
             attr("action", "repay"),
             attr("sender", "user"),
             attr("on_behalf_of", "borrower"),
+            attr("account_id", "0"),
             attr("denom", "borrowedcoinnative"),
             attr("amount", borrow_amount.to_string()),
             attr("amount_scaled", Uint128::new(borrow_amount) * SCALING_FACTOR),
@@ -658,6 +688,7 @@ fn repay_uncollateralized_loan_on_behalf_of() {
     let info = cosmwasm_std::testing::mock_info(repayer_addr.as_str(), &[coin(110000, "somecoin")]);
     let msg = ExecuteMsg::Repay {
         on_behalf_of: Some(another_user_addr.to_string()),
+        account_id: None,
     };
     let error_res = execute(deps.as_mut(), env, info, msg).unwrap_err();
     assert_eq!(error_res, ContractError::CannotRepayUncollateralizedLoanOnBehalfOf {});
@@ -708,6 +739,8 @@ fn borrow_uusd() {
         denom: "uusd".to_string(),
         amount: max_to_borrow + Uint128::from(1u128),
         recipient: None,
+        on_behalf_of: None,
+        account_id: None,
     };
     let env = mock_env_at_block_time(new_block_time);
     let info = mock_info("borrower", &[]);
@@ -719,6 +752,8 @@ fn borrow_uusd() {
         denom: "uusd".to_string(),
         amount: valid_amount,
         recipient: None,
+        on_behalf_of: None,
+        account_id: None,
     };
     let env = mock_env_at_block_time(block_time);
     let info = mock_info("borrower", &[]);
@@ -728,7 +763,7 @@ fn borrow_uusd() {
 
     assert!(has_debt_position(deps.as_ref(), &borrower_addr, "uusd"));
 
-    let debt = DEBTS.load(&deps.storage, (&borrower_addr, "uusd")).unwrap();
+    let debt = DEBTS.load(&deps.storage, (&borrower_addr, MAIN_ACCOUNT_ID, "uusd")).unwrap();
 
     assert_eq!(
         valid_amount,
@@ -780,6 +815,8 @@ fn borrow_full_liquidity_and_then_repay() {
             denom: "uusd".to_string(),
             amount: initial_liquidity.into(),
             recipient: None,
+            on_behalf_of: None,
+            account_id: None,
         };
         let _res = execute(deps.as_mut(), env, info.clone(), msg).unwrap();
 
@@ -804,6 +841,8 @@ fn borrow_full_liquidity_and_then_repay() {
             denom: "uusd".to_string(),
             amount: 100u128.into(),
             recipient: None,
+            on_behalf_of: None,
+            account_id: None,
         };
         let error_res = execute(deps.as_mut(), env, info, msg).unwrap_err();
         assert_eq!(error_res, ContractError::OperationExceedsAvailableLiquidity {});
@@ -815,6 +854,7 @@ fn borrow_full_liquidity_and_then_repay() {
         let info = cosmwasm_std::testing::mock_info("borrower", &[coin(2000, "uusd")]);
         let msg = ExecuteMsg::Repay {
             on_behalf_of: None,
+            account_id: None,
         };
         // check that repay succeeds
         execute(deps.as_mut(), env, info, msg).unwrap();
@@ -918,6 +958,8 @@ fn borrow_collateral_check() {
         denom: "uosmo".to_string(),
         amount: exceeding_borrow_amount,
         recipient: None,
+        on_behalf_of: None,
+        account_id: None,
     };
     let env = mock_env(MockEnvParams::default());
     let info = mock_info("borrower", &[]);
@@ -929,6 +971,8 @@ fn borrow_collateral_check() {
         denom: "uosmo".to_string(),
         amount: permissible_borrow_amount,
         recipient: None,
+        on_behalf_of: None,
+        account_id: None,
     };
     execute(deps.as_mut(), env, info, borrow_msg).unwrap();
 }
@@ -950,6 +994,8 @@ fn cannot_borrow_if_market_not_enabled() {
         denom: "somecoin".to_string(),
         amount: Uint128::new(1000),
         recipient: None,
+        on_behalf_of: None,
+        account_id: None,
     };
     let error_res = execute(deps.as_mut(), env, info, msg).unwrap_err();
     assert_eq!(
@@ -960,6 +1006,168 @@ fn cannot_borrow_if_market_not_enabled() {
     );
 }
 
+#[test]
+fn cannot_borrow_above_cap() {
+    let mut deps = th_setup(&[coin(10_000_000, "somecoin")]);
+
+    let mock_market = Market {
+        debt_total_scaled: Uint128::new(9_000_000) * SCALING_FACTOR,
+        borrow_cap: Uint128::new(10_000_000),
+        indexes_last_updated: 10_000_000,
+        ..Default::default()
+    };
+    th_init_market(deps.as_mut(), "somecoin", &mock_market);
+
+    // Check error when total debt after borrowing is above the cap
+    let env = mock_env_at_block_time(10_000_000);
+    let info = mock_info("borrower", &[]);
+    let msg = ExecuteMsg::Borrow {
+        denom: "somecoin".to_string(),
+        amount: Uint128::new(1_000_001),
+        recipient: None,
+        on_behalf_of: None,
+        account_id: None,
+    };
+    let error_res = execute(deps.as_mut(), env, info, msg).unwrap_err();
+    assert_eq!(
+        error_res,
+        ContractError::BorrowCapExceeded {
+            denom: "somecoin".to_string()
+        }
+    );
+}
+
+#[test]
+fn borrow_in_isolation_mode() {
+    let mut deps = th_setup(&[coin(1_000_000, "uosmo"), coin(1_000_000, "uusd")]);
+
+    deps.querier.set_oracle_price("ulong", Decimal::one());
+    deps.querier.set_oracle_price("uatom", Decimal::one());
+    deps.querier.set_oracle_price("uosmo", Decimal::one());
+    deps.querier.set_oracle_price("uusd", Decimal::one());
+
+    let isolated_market = th_init_market(
+        deps.as_mut(),
+        "ulong",
+        &Market {
+            max_loan_to_value: Decimal::percent(50),
+            isolated: true,
+            debt_ceiling: Uint128::new(6_000),
+            ..Default::default()
+        },
+    );
+    th_init_market(
+        deps.as_mut(),
+        "uatom",
+        &Market {
+            max_loan_to_value: Decimal::percent(80),
+            ..Default::default()
+        },
+    );
+    th_init_market(deps.as_mut(), "uosmo", &Market::default());
+    th_init_market(
+        deps.as_mut(),
+        "uusd",
+        &Market {
+            borrowable_in_isolation: true,
+            ..Default::default()
+        },
+    );
+
+    // The borrower has both an isolated and a regular asset enabled as collateral. Only the
+    // isolated asset counts towards the max LTV
+    let borrower_addr = Addr::unchecked("borrower");
+    set_collateral(
+        deps.as_mut(),
+        &borrower_addr,
+        &isolated_market.denom,
+        Uint128::new(10_000) * SCALING_FACTOR,
+        true,
+    );
+    set_collateral(
+        deps.as_mut(),
+        &borrower_addr,
+        "uatom",
+        Uint128::new(10_000) * SCALING_FACTOR,
+        true,
+    );
+
+    let env = mock_env(MockEnvParams::default());
+    let info = mock_info("borrower", &[]);
+
+    // cannot borrow an asset which is not borrowable in isolation
+    let msg = ExecuteMsg::Borrow {
+        denom: "uosmo".to_string(),
+        amount: Uint128::new(100),
+        recipient: None,
+        on_behalf_of: None,
+        account_id: None,
+    };
+    let error_res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+    assert_eq!(
+        error_res,
+        ContractError::BorrowNotAllowedInIsolationMode {
+            denom: "uosmo".to_string()
+        }
+    );
+
+    // cannot borrow above the max LTV of the isolated collateral
+    let msg = ExecuteMsg::Borrow {
+        denom: "uusd".to_string(),
+        amount: Uint128::new(5_001),
+        recipient: None,
+        on_behalf_of: None,
+        account_id: None,
+    };
+    let error_res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+    assert_eq!(error_res, ContractError::BorrowAmountExceedsGivenCollateral {});
+
+    // borrow within the max LTV of the isolated collateral, the debt is tracked against it
+    let msg = ExecuteMsg::Borrow {
+        denom: "uusd".to_string(),
+        amount: Uint128::new(4_000),
+        recipient: None,
+        on_behalf_of: None,
+        account_id: None,
+    };
+    execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    let isolated_debt = ISOLATED_DEBTS.load(&deps.storage, "ulong").unwrap();
+    assert_eq!(isolated_debt, Uint128::new(4_000));
+
+    // cannot borrow above the debt ceiling
+    MARKETS
+        .update(deps.as_mut().storage, "ulong", |opt| -> StdResult<_> {
+            let mut market = opt.unwrap();
+            market.max_loan_to_value = Decimal::one();
+            Ok(market)
+        })
+        .unwrap();
+    let msg = ExecuteMsg::Borrow {
+        denom: "uusd".to_string(),
+        amount: Uint128::new(2_001),
+        recipient: None,
+        on_behalf_of: None,
+        account_id: None,
+    };
+    let error_res = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert_eq!(
+        error_res,
+        ContractError::DebtCeilingExceeded {
+            denom: "ulong".to_string()
+        }
+    );
+
+    // repaying releases the debt from the ceiling
+    let info = mock_info("borrower", &coins(1_000, "uusd"));
+    let msg = ExecuteMsg::Repay {
+        on_behalf_of: None,
+        account_id: None,
+    };
+    execute(deps.as_mut(), env, info, msg).unwrap();
+    let isolated_debt = ISOLATED_DEBTS.load(&deps.storage, "ulong").unwrap();
+    assert_eq!(isolated_debt, Uint128::new(3_000));
+}
+
 #[test]
 fn borrow_and_send_funds_to_another_user() {
     let initial_liquidity = 10000000;
@@ -986,6 +1194,8 @@ fn borrow_and_send_funds_to_another_user() {
         denom: "uusd".to_string(),
         amount: borrow_amount,
         recipient: Some(another_user_addr.to_string()),
+        on_behalf_of: None,
+        account_id: None,
     };
     let env = mock_env(MockEnvParams::default());
     let info = mock_info("borrower", &[]);
@@ -997,7 +1207,7 @@ fn borrow_and_send_funds_to_another_user() {
     assert!(has_debt_position(deps.as_ref(), &borrower_addr, &market.denom));
 
     // Debt for 'borrower' should exist
-    let debt = DEBTS.load(&deps.storage, (&borrower_addr, "uusd")).unwrap();
+    let debt = DEBTS.load(&deps.storage, (&borrower_addr, MAIN_ACCOUNT_ID, "uusd")).unwrap();
     assert_eq!(
         borrow_amount,
         compute_underlying_amount(
@@ -1009,7 +1219,8 @@ fn borrow_and_send_funds_to_another_user() {
     );
 
     // Debt for 'another_user' should not exist
-    let debt = DEBTS.may_load(&deps.storage, (&another_user_addr, "uusd")).unwrap();
+    let debt =
+        DEBTS.may_load(&deps.storage, (&another_user_addr, MAIN_ACCOUNT_ID, "uusd")).unwrap();
     assert!(debt.is_none());
 
     // Check msgs and attributes (funds should be sent to 'another_user')
@@ -1024,7 +1235,9 @@ fn borrow_and_send_funds_to_another_user() {
         res.attributes,
         vec![
             attr("action", "borrow"),
-            attr("sender", borrower_addr),
+            attr("sender", &borrower_addr),
+            attr("on_behalf_of", borrower_addr),
+            attr("account_id", "0"),
             attr("recipient", another_user_addr),
             attr("denom", "uusd"),
             attr("amount", borrow_amount.to_string()),
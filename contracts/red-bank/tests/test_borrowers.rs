@@ -0,0 +1,119 @@
+use cosmwasm_std::{
+    coin, coins,
+    testing::{mock_info, MockApi, MockStorage},
+    Addr, Decimal, OwnedDeps, Uint128,
+};
+use helpers::{
+    set_collateral, th_borrow, th_collateral_market, th_execute, th_query_borrowers,
+    th_setup_markets,
+};
+use mars_red_bank::{contract::execute, interest_rates::SCALING_FACTOR};
+use mars_red_bank_types::red_bank::{BorrowerResponse, ExecuteMsg, Market};
+use mars_testing::{mock_env, MarsMockQuerier, MockEnvParams};
+
+mod helpers;
+
+fn setup_test() -> OwnedDeps<MockStorage, MockApi, MarsMockQuerier> {
+    let mut deps = th_setup_markets(
+        &[coin(10_000, "uatom")],
+        &[("uosmo", th_collateral_market(3_000)), ("uatom", Market::default())],
+    );
+
+    for user in ["alice", "bob", "carol"] {
+        set_collateral(
+            deps.as_mut(),
+            &Addr::unchecked(user),
+            "uosmo",
+            Uint128::new(1_000) * SCALING_FACTOR,
+            true,
+        );
+    }
+
+    deps
+}
+
+#[test]
+fn borrowers_are_indexed() {
+    let mut deps = setup_test();
+
+    assert!(th_query_borrowers(deps.as_ref(), None, None, None).borrowers.is_empty());
+
+    // liquidation health factors: 1_000 * 0.6 / 500 = 1.2 and 1_000 * 0.6 / 400 = 1.5
+    th_borrow(&mut deps, "bob", "uatom", 500).unwrap();
+    th_borrow(&mut deps, "alice", "uatom", 400).unwrap();
+
+    let borrowers = th_query_borrowers(deps.as_ref(), None, None, None).borrowers;
+    assert_eq!(
+        borrowers,
+        vec![
+            BorrowerResponse {
+                user: "bob".to_string(),
+                account_id: 0,
+                liquidation_health_factor: Some(Decimal::from_ratio(6u128, 5u128)),
+            },
+            BorrowerResponse {
+                user: "alice".to_string(),
+                account_id: 0,
+                liquidation_health_factor: Some(Decimal::from_ratio(3u128, 2u128)),
+            },
+        ]
+    );
+
+    let borrowers = th_query_borrowers(deps.as_ref(), Some(("bob", 0)), None, None).borrowers;
+    assert_eq!(borrowers.len(), 1);
+    assert_eq!(borrowers[0].user, "alice");
+
+    let borrowers =
+        th_query_borrowers(deps.as_ref(), None, None, Some(Decimal::percent(130))).borrowers;
+    assert_eq!(borrowers.len(), 1);
+    assert_eq!(borrowers[0].user, "bob");
+
+    // repaying the whole debt removes the borrower from the index
+    let msg = ExecuteMsg::Repay {
+        on_behalf_of: None,
+        account_id: None,
+    };
+    let info = mock_info("bob", &coins(500, "uatom"));
+    execute(deps.as_mut(), mock_env(MockEnvParams::default()), info, msg).unwrap();
+
+    let borrowers = th_query_borrowers(deps.as_ref(), None, None, None).borrowers;
+    assert_eq!(borrowers.len(), 1);
+    assert_eq!(borrowers[0].user, "alice");
+}
+
+#[test]
+fn uncollateralized_borrowers_are_not_indexed() {
+    let mut deps = setup_test();
+
+    let msg = ExecuteMsg::UpdateUncollateralizedLoanLimit {
+        user: "carol".to_string(),
+        denom: "uatom".to_string(),
+        new_limit: Uint128::new(1_000),
+    };
+    th_execute(&mut deps, "owner", msg).unwrap();
+
+    th_borrow(&mut deps, "carol", "uatom", 800).unwrap();
+    assert!(th_query_borrowers(deps.as_ref(), None, None, None).borrowers.is_empty());
+}
+
+#[test]
+fn borrowers_are_scanned_up_to_the_limit() {
+    let mut deps = setup_test();
+
+    // liquidation health factors: bob 1_000 * 0.6 / 500 = 1.2, alice 2 and carol 1.5
+    th_borrow(&mut deps, "bob", "uatom", 500).unwrap();
+    th_borrow(&mut deps, "alice", "uatom", 300).unwrap();
+    th_borrow(&mut deps, "carol", "uatom", 400).unwrap();
+
+    // the first page scans bob and alice, and only bob is at or below the max health factor
+    let page = th_query_borrowers(deps.as_ref(), None, Some(2), Some(Decimal::percent(130)));
+    assert_eq!(page.borrowers.len(), 1);
+    assert_eq!(page.borrowers[0].user, "bob");
+    assert_eq!(page.last_scanned, Some(("alice".to_string(), 0)));
+
+    // carol doesn't match either, and there's nobody left to scan after her
+    let page =
+        th_query_borrowers(deps.as_ref(), Some(("alice", 0)), Some(2), Some(Decimal::percent(130)));
+    assert!(page.borrowers.is_empty());
+    assert_eq!(page.last_scanned, None);
+}
@@ -0,0 +1,201 @@
+use cosmwasm_std::{
+    attr, coin,
+    testing::{mock_info, MockApi, MockStorage},
+    Addr, Decimal, OwnedDeps, Uint128,
+};
+use helpers::{set_collateral, th_query, th_setup_markets};
+use mars_owner::OwnerError::NotOwner;
+use mars_red_bank::{
+    contract::execute,
+    error::ContractError,
+    interest_rates::SCALING_FACTOR,
+    state::{COLLATERALS, DEFICITS, MARKETS},
+    user::MAIN_ACCOUNT_ID,
+};
+use mars_red_bank_types::{
+    address_provider::MarsAddressType,
+    red_bank::{DeficitResponse, ExecuteMsg, Market, QueryMsg},
+};
+use mars_testing::{mock_env, MarsMockQuerier, MockEnvParams};
+
+mod helpers;
+
+fn setup_test(deficit: Uint128) -> OwnedDeps<MockStorage, MockApi, MarsMockQuerier> {
+    // 10_000 uosmo deposited by users and 400 uosmo of protocol reserves
+    let market = Market {
+        collateral_total_scaled: Uint128::new(10_400) * SCALING_FACTOR,
+        ..Default::default()
+    };
+    let mut deps = th_setup_markets(&[coin(1_000_000, "uosmo")], &[("uosmo", market)]);
+    let rewards_collector_addr = Addr::unchecked(MarsAddressType::RewardsCollector.to_string());
+    set_collateral(
+        deps.as_mut(),
+        &rewards_collector_addr,
+        "uosmo",
+        Uint128::new(400) * SCALING_FACTOR,
+        true,
+    );
+
+    DEFICITS.save(deps.as_mut().storage, "uosmo", &deficit).unwrap();
+
+    deps
+}
+
+#[test]
+fn cannot_cover_deficit_if_not_owner() {
+    let mut deps = setup_test(Uint128::new(1_000));
+
+    let msg = ExecuteMsg::CoverDeficit {
+        denom: "uosmo".to_string(),
+    };
+    let info = mock_info("somebody", &[]);
+    let error_res =
+        execute(deps.as_mut(), mock_env(MockEnvParams::default()), info, msg).unwrap_err();
+    assert_eq!(error_res, ContractError::Owner(NotOwner {}));
+}
+
+#[test]
+fn cannot_cover_missing_deficit() {
+    let mut deps = setup_test(Uint128::new(1_000));
+
+    let msg = ExecuteMsg::CoverDeficit {
+        denom: "uatom".to_string(),
+    };
+    let info = mock_info("owner", &[]);
+    let error_res =
+        execute(deps.as_mut(), mock_env(MockEnvParams::default()), info, msg).unwrap_err();
+    assert_eq!(
+        error_res,
+        ContractError::NoDeficit {
+            denom: "uatom".to_string()
+        }
+    );
+}
+
+#[test]
+fn deficit_covered_by_reserves() {
+    let mut deps = setup_test(Uint128::new(100));
+
+    let deficits: Vec<DeficitResponse> = th_query(
+        deps.as_ref(),
+        QueryMsg::Deficits {
+            start_after: None,
+            limit: None,
+        },
+    );
+    assert_eq!(
+        deficits,
+        vec![DeficitResponse {
+            denom: "uosmo".to_string(),
+            amount: Uint128::new(100),
+        }]
+    );
+
+    let msg = ExecuteMsg::CoverDeficit {
+        denom: "uosmo".to_string(),
+    };
+    let info = mock_info("owner", &[]);
+    let res = execute(deps.as_mut(), mock_env(MockEnvParams::default()), info, msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "cover_deficit"),
+            attr("denom", "uosmo"),
+            attr("covered_by_reserves", "100"),
+            attr("socialized", "0"),
+            attr("remaining", "0"),
+        ]
+    );
+
+    // reserves are burned, depositors are not affected
+    let rewards_collector_addr = Addr::unchecked(MarsAddressType::RewardsCollector.to_string());
+    let reserves = COLLATERALS
+        .load(&deps.storage, (&rewards_collector_addr, MAIN_ACCOUNT_ID, "uosmo"))
+        .unwrap();
+    assert_eq!(reserves.amount_scaled, Uint128::new(300) * SCALING_FACTOR);
+
+    let market = MARKETS.load(&deps.storage, "uosmo").unwrap();
+    assert_eq!(market.collateral_total_scaled, Uint128::new(10_300) * SCALING_FACTOR);
+    assert_eq!(market.liquidity_index, Decimal::one());
+
+    let deficit: DeficitResponse = th_query(
+        deps.as_ref(),
+        QueryMsg::Deficit {
+            denom: "uosmo".to_string(),
+        },
+    );
+    assert_eq!(deficit.amount, Uint128::zero());
+}
+
+#[test]
+fn deficit_in_excess_of_reserves_is_socialized() {
+    let mut deps = setup_test(Uint128::new(1_000));
+
+    let msg = ExecuteMsg::CoverDeficit {
+        denom: "uosmo".to_string(),
+    };
+    let info = mock_info("owner", &[]);
+    let res = execute(deps.as_mut(), mock_env(MockEnvParams::default()), info, msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "cover_deficit"),
+            attr("denom", "uosmo"),
+            attr("covered_by_reserves", "400"),
+            attr("socialized", "600"),
+            attr("remaining", "0"),
+        ]
+    );
+
+    // all the reserves are burned
+    let rewards_collector_addr = Addr::unchecked(MarsAddressType::RewardsCollector.to_string());
+    assert!(COLLATERALS
+        .may_load(&deps.storage, (&rewards_collector_addr, MAIN_ACCOUNT_ID, "uosmo"))
+        .unwrap()
+        .is_none());
+
+    // depositors lose 600 out of 10_000 uosmo
+    let market = MARKETS.load(&deps.storage, "uosmo").unwrap();
+    assert_eq!(market.collateral_total_scaled, Uint128::new(10_000) * SCALING_FACTOR);
+    assert_eq!(market.liquidity_index, Decimal::percent(94));
+
+    assert!(DEFICITS.may_load(&deps.storage, "uosmo").unwrap().is_none());
+}
+
+#[test]
+fn deficit_exceeding_deposits_is_partially_covered() {
+    let mut deps = setup_test(Uint128::new(10_500));
+
+    let msg = ExecuteMsg::CoverDeficit {
+        denom: "uosmo".to_string(),
+    };
+    let info = mock_info("owner", &[]);
+    let res = execute(deps.as_mut(), mock_env(MockEnvParams::default()), info.clone(), msg.clone())
+        .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "cover_deficit"),
+            attr("denom", "uosmo"),
+            attr("covered_by_reserves", "400"),
+            attr("socialized", "9999"),
+            attr("remaining", "101"),
+        ]
+    );
+
+    // depositors are left with 1 uosmo, the rest of the deficit is kept
+    let market = MARKETS.load(&deps.storage, "uosmo").unwrap();
+    assert_eq!(market.liquidity_index, Decimal::from_ratio(1u128, 10_000u128));
+    assert_eq!(DEFICITS.load(&deps.storage, "uosmo").unwrap(), Uint128::new(101));
+
+    // nothing more can be covered until reserves or deposits grow
+    let error_res =
+        execute(deps.as_mut(), mock_env(MockEnvParams::default()), info, msg).unwrap_err();
+    assert_eq!(
+        error_res,
+        ContractError::DeficitExceedsDeposits {
+            denom: "uosmo".to_string()
+        }
+    );
+    assert_eq!(DEFICITS.load(&deps.storage, "uosmo").unwrap(), Uint128::new(101));
+}
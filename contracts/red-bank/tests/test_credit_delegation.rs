@@ -0,0 +1,197 @@
+use cosmwasm_std::{
+    attr, coin, coins,
+    testing::{mock_info, MockApi, MockStorage},
+    Addr, BankMsg, CosmosMsg, OwnedDeps, SubMsg, Uint128,
+};
+use helpers::{
+    set_collateral, th_collateral_market, th_execute, th_query_borrow_allowance, th_setup_markets,
+};
+use mars_red_bank::{
+    contract::execute,
+    error::ContractError,
+    interest_rates::SCALING_FACTOR,
+    state::{DEBTS, UNCOLLATERALIZED_LOAN_LIMITS},
+    user::MAIN_ACCOUNT_ID,
+};
+use mars_red_bank_types::red_bank::{ExecuteMsg, Market};
+use mars_testing::{mock_env, MarsMockQuerier, MockEnvParams};
+
+mod helpers;
+
+fn setup_test() -> OwnedDeps<MockStorage, MockApi, MarsMockQuerier> {
+    let mut deps = th_setup_markets(
+        &[coin(10_000, "uatom")],
+        &[("uosmo", th_collateral_market(10_000)), ("uatom", Market::default())],
+    );
+
+    // collateral of 10_000 uosmo, allowing up to 5_000 uatom of debt
+    let delegator_addr = Addr::unchecked("delegator");
+    set_collateral(
+        deps.as_mut(),
+        &delegator_addr,
+        "uosmo",
+        Uint128::new(10_000) * SCALING_FACTOR,
+        true,
+    );
+
+    deps
+}
+
+fn approve_delegation(
+    deps: &mut OwnedDeps<MockStorage, MockApi, MarsMockQuerier>,
+    denom: &str,
+    amount: u128,
+) -> Result<(), ContractError> {
+    let msg = ExecuteMsg::ApproveDelegation {
+        delegatee: "delegatee".to_string(),
+        denom: denom.to_string(),
+        amount: Uint128::new(amount),
+        account_id: None,
+    };
+    th_execute(deps, "delegator", msg)?;
+    Ok(())
+}
+
+fn borrow_on_behalf_of_delegator(
+    deps: &mut OwnedDeps<MockStorage, MockApi, MarsMockQuerier>,
+    amount: u128,
+) -> Result<(), ContractError> {
+    let msg = ExecuteMsg::Borrow {
+        denom: "uatom".to_string(),
+        amount: Uint128::new(amount),
+        recipient: None,
+        on_behalf_of: Some("delegator".to_string()),
+        account_id: None,
+    };
+    th_execute(deps, "delegatee", msg)?;
+    Ok(())
+}
+
+#[test]
+fn cannot_approve_delegation_for_unknown_asset() {
+    let mut deps = setup_test();
+
+    let error_res = approve_delegation(&mut deps, "unknown", 1_000).unwrap_err();
+    assert_eq!(error_res, ContractError::AssetNotInitialized {});
+}
+
+#[test]
+fn borrowing_on_behalf_of_requires_allowance() {
+    let mut deps = setup_test();
+
+    let error_res = borrow_on_behalf_of_delegator(&mut deps, 100).unwrap_err();
+    assert_eq!(
+        error_res,
+        ContractError::BorrowAmountExceedsAllowance {
+            denom: "uatom".to_string()
+        }
+    );
+
+    // approving zero revokes the allowance
+    approve_delegation(&mut deps, "uatom", 1_000).unwrap();
+    assert_eq!(
+        th_query_borrow_allowance(deps.as_ref(), "delegator", "delegatee", "uatom"),
+        Uint128::new(1_000)
+    );
+    approve_delegation(&mut deps, "uatom", 0).unwrap();
+    assert_eq!(
+        th_query_borrow_allowance(deps.as_ref(), "delegator", "delegatee", "uatom"),
+        Uint128::zero()
+    );
+
+    let error_res = borrow_on_behalf_of_delegator(&mut deps, 100).unwrap_err();
+    assert_eq!(
+        error_res,
+        ContractError::BorrowAmountExceedsAllowance {
+            denom: "uatom".to_string()
+        }
+    );
+}
+
+#[test]
+fn borrowing_on_behalf_of() {
+    let mut deps = setup_test();
+    let delegator_addr = Addr::unchecked("delegator");
+
+    approve_delegation(&mut deps, "uatom", 1_000).unwrap();
+
+    let msg = ExecuteMsg::Borrow {
+        denom: "uatom".to_string(),
+        amount: Uint128::new(600),
+        recipient: None,
+        on_behalf_of: Some(delegator_addr.to_string()),
+        account_id: None,
+    };
+    let info = mock_info("delegatee", &[]);
+    let res = execute(deps.as_mut(), mock_env(MockEnvParams::default()), info, msg).unwrap();
+
+    // the funds go to the delegatee, while the debt is the delegator's
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "delegatee".to_string(),
+            amount: coins(600, "uatom"),
+        }))]
+    );
+    assert_eq!(
+        res.attributes[..4],
+        [
+            attr("action", "borrow"),
+            attr("sender", "delegatee"),
+            attr("on_behalf_of", "delegator"),
+            attr("account_id", "0"),
+        ]
+    );
+    let debt = DEBTS.load(&deps.storage, (&delegator_addr, MAIN_ACCOUNT_ID, "uatom")).unwrap();
+    assert_eq!(debt.amount_scaled, Uint128::new(600) * SCALING_FACTOR);
+    assert!(DEBTS
+        .may_load(&deps.storage, (&Addr::unchecked("delegatee"), MAIN_ACCOUNT_ID, "uatom"))
+        .unwrap()
+        .is_none());
+
+    // the allowance is spent
+    assert_eq!(
+        th_query_borrow_allowance(deps.as_ref(), "delegator", "delegatee", "uatom"),
+        Uint128::new(400)
+    );
+    let error_res = borrow_on_behalf_of_delegator(&mut deps, 401).unwrap_err();
+    assert_eq!(
+        error_res,
+        ContractError::BorrowAmountExceedsAllowance {
+            denom: "uatom".to_string()
+        }
+    );
+    borrow_on_behalf_of_delegator(&mut deps, 400).unwrap();
+    assert_eq!(
+        th_query_borrow_allowance(deps.as_ref(), "delegator", "delegatee", "uatom"),
+        Uint128::zero()
+    );
+}
+
+#[test]
+fn borrowing_on_behalf_of_checks_delegator_health() {
+    let mut deps = setup_test();
+
+    approve_delegation(&mut deps, "uatom", 10_000).unwrap();
+
+    // the allowance is above what the delegator's collateral allows to borrow
+    let error_res = borrow_on_behalf_of_delegator(&mut deps, 5_001).unwrap_err();
+    assert_eq!(error_res, ContractError::BorrowAmountExceedsGivenCollateral {});
+}
+
+#[test]
+fn cannot_borrow_uncollateralized_loan_on_behalf_of() {
+    let mut deps = setup_test();
+
+    UNCOLLATERALIZED_LOAN_LIMITS
+        .save(
+            deps.as_mut().storage,
+            (&Addr::unchecked("delegator"), "uatom"),
+            &Uint128::new(10_000),
+        )
+        .unwrap();
+    approve_delegation(&mut deps, "uatom", 1_000).unwrap();
+
+    let error_res = borrow_on_behalf_of_delegator(&mut deps, 100).unwrap_err();
+    assert_eq!(error_res, ContractError::CannotBorrowUncollateralizedLoanOnBehalfOf {});
+}
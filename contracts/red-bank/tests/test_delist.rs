@@ -0,0 +1,369 @@
+use cosmwasm_std::{
+    coin, coins,
+    testing::{mock_info, MockApi, MockStorage},
+    Addr, Decimal, OwnedDeps, StdResult, Uint128,
+};
+use helpers::{
+    set_collateral, th_borrow, th_collateral_market, th_execute, th_init_market, th_setup_markets,
+};
+use mars_owner::OwnerError;
+use mars_red_bank::{
+    contract::execute,
+    error::ContractError,
+    interest_rates::SCALING_FACTOR,
+    state::{
+        BORROW_ALLOWANCES, CONFIG, DEFICITS, EMODE_CATEGORIES, MARKETS, PENDING_CHANGES,
+        UNCOLLATERALIZED_LOAN_LIMITS,
+    },
+    user::MAIN_ACCOUNT_ID,
+};
+use mars_red_bank_types::red_bank::{
+    EModeCategory, ExecuteMsg, InitOrUpdateAssetParams, InterestRateModel, Market, MarketStatus,
+    ParamsChange, PendingChange, RiskParamsRamp,
+};
+use mars_testing::{mock_env, MarsMockQuerier, MockEnvParams};
+use mars_utils::error::ValidationError;
+
+mod helpers;
+
+fn setup_test() -> OwnedDeps<MockStorage, MockApi, MarsMockQuerier> {
+    let mut deps = th_setup_markets(
+        &[coin(10_000, "uatom")],
+        &[
+            ("uosmo", th_collateral_market(1_000)),
+            (
+                "uatom",
+                Market {
+                    interest_rate_model: InterestRateModel::Fixed {
+                        borrow_rate: Decimal::percent(10),
+                    },
+                    borrow_rate: Decimal::percent(10),
+                    indexes_last_updated: mock_env(MockEnvParams::default()).block.time.seconds(),
+                    ..th_collateral_market(1_000)
+                },
+            ),
+        ],
+    );
+
+    set_collateral(
+        deps.as_mut(),
+        &Addr::unchecked("alice"),
+        "uosmo",
+        Uint128::new(1_000) * SCALING_FACTOR,
+        true,
+    );
+    set_collateral(
+        deps.as_mut(),
+        &Addr::unchecked("bob"),
+        "uatom",
+        Uint128::new(1_000) * SCALING_FACTOR,
+        true,
+    );
+
+    th_borrow(&mut deps, "alice", "uatom", 300).unwrap();
+
+    deps
+}
+
+fn delist(denom: &str, ramp_duration: u64) -> ExecuteMsg {
+    ExecuteMsg::DelistAsset {
+        denom: denom.to_string(),
+        ramp_duration,
+    }
+}
+
+#[test]
+fn delisting_market() {
+    let mut deps = setup_test();
+    let block_time = mock_env(MockEnvParams::default()).block.time.seconds();
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(MockEnvParams::default()),
+        mock_info("alice", &[]),
+        delist("uosmo", 1_000),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Owner(OwnerError::NotOwner {}));
+
+    // the collateral weight can't drop to zero at once, nor faster than the timelock allows
+    let err = th_execute(&mut deps, "owner", delist("uosmo", 0)).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Validation(ValidationError::InvalidParam {
+            param_name: "ramp_duration".to_string(),
+            invalid_value: "0".to_string(),
+            predicate: ">= 1".to_string(),
+        })
+    );
+
+    CONFIG
+        .update(deps.as_mut().storage, |mut config| -> StdResult<_> {
+            config.timelock = 1_000;
+            Ok(config)
+        })
+        .unwrap();
+    let err = th_execute(&mut deps, "owner", delist("uosmo", 999)).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Validation(ValidationError::InvalidParam {
+            param_name: "ramp_duration".to_string(),
+            invalid_value: "999".to_string(),
+            predicate: ">= 1000".to_string(),
+        })
+    );
+
+    let category = EModeCategory {
+        id: 1,
+        denoms: vec!["uatom".to_string(), "uosmo".to_string()],
+        max_loan_to_value: Decimal::percent(90),
+        liquidation_threshold: Decimal::percent(95),
+        liquidation_bonus: Decimal::percent(2),
+    };
+    EMODE_CATEGORIES.save(deps.as_mut().storage, 1, &category).unwrap();
+
+    th_execute(&mut deps, "owner", delist("uosmo", 1_000)).unwrap();
+
+    let market = MARKETS.load(&deps.storage, "uosmo").unwrap();
+    assert_eq!(market.status, MarketStatus::Delisted);
+    assert!(!market.deposit_enabled);
+    assert!(!market.borrow_enabled);
+    assert_eq!(market.max_loan_to_value, Decimal::zero());
+    assert_eq!(market.liquidation_threshold, Decimal::zero());
+    assert_eq!(
+        market.risk_params_ramp,
+        Some(RiskParamsRamp {
+            initial_max_loan_to_value: Decimal::percent(50),
+            initial_liquidation_threshold: Decimal::percent(60),
+            start_time: block_time,
+            end_time: block_time + 1_000,
+        })
+    );
+
+    // the params of e-mode categories no longer apply to the asset, which can't be added back
+    let category = EMODE_CATEGORIES.load(&deps.storage, 1).unwrap();
+    assert_eq!(category.denoms, vec!["uatom".to_string()]);
+
+    let msg = ExecuteMsg::SetEModeCategory {
+        category: EModeCategory {
+            denoms: vec!["uatom".to_string(), "uosmo".to_string()],
+            ..category
+        },
+    };
+    let err = th_execute(&mut deps, "owner", msg).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::MarketDelisted {
+            denom: "uosmo".to_string(),
+        }
+    );
+
+    // the params of a delisted market can no longer be updated
+    let err = th_execute(&mut deps, "owner", delist("uosmo", 1_000)).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::MarketDelisted {
+            denom: "uosmo".to_string(),
+        }
+    );
+
+    let msg = ExecuteMsg::UpdateAsset {
+        denom: "uosmo".to_string(),
+        params: InitOrUpdateAssetParams {
+            max_loan_to_value: None,
+            reserve_factor: None,
+            liquidation_threshold: None,
+            liquidation_bonus: None,
+            interest_rate_model: None,
+            deposit_enabled: Some(true),
+            borrow_enabled: Some(true),
+            deposit_cap: None,
+            borrow_cap: None,
+            min_debt: None,
+            isolated: None,
+            debt_ceiling: None,
+            borrowable_in_isolation: None,
+            flash_loan_fee: None,
+            interest_accrual: None,
+            protocol_liquidation_fee: None,
+            ramp_duration: None,
+        },
+    };
+    let err = th_execute(&mut deps, "owner", msg).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::MarketDelisted {
+            denom: "uosmo".to_string(),
+        }
+    );
+}
+
+#[test]
+fn sunsetting_and_removing_market() {
+    let mut deps = setup_test();
+
+    let sunset = ExecuteMsg::SunsetAsset {
+        denom: "uatom".to_string(),
+    };
+    let remove = ExecuteMsg::RemoveAsset {
+        denom: "uatom".to_string(),
+    };
+
+    let err = th_execute(&mut deps, "owner", sunset.clone()).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::MarketNotDelisted {
+            denom: "uatom".to_string(),
+        }
+    );
+
+    th_execute(&mut deps, "owner", delist("uatom", 1)).unwrap();
+
+    let err = th_execute(&mut deps, "owner", remove.clone()).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::MarketNotSunset {
+            denom: "uatom".to_string(),
+        }
+    );
+
+    // the market can only be sunset once all debt is repaid
+    let err = th_execute(&mut deps, "owner", sunset.clone()).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::MarketHasDebt {
+            denom: "uatom".to_string(),
+        }
+    );
+
+    let msg = ExecuteMsg::Repay {
+        on_behalf_of: None,
+        account_id: None,
+    };
+    let info = mock_info("alice", &coins(300, "uatom"));
+    execute(deps.as_mut(), mock_env(MockEnvParams::default()), info, msg).unwrap();
+
+    th_execute(&mut deps, "owner", sunset).unwrap();
+
+    let market = MARKETS.load(&deps.storage, "uatom").unwrap();
+    assert_eq!(market.status, MarketStatus::Sunset);
+    assert_eq!(market.borrow_rate, Decimal::zero());
+    assert_eq!(market.liquidity_rate, Decimal::zero());
+
+    // the market can only be removed once all collateral is withdrawn
+    let err = th_execute(&mut deps, "owner", remove.clone()).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::MarketHasCollateral {
+            denom: "uatom".to_string(),
+        }
+    );
+
+    let msg = ExecuteMsg::Withdraw {
+        denom: "uatom".to_string(),
+        amount: None,
+        recipient: None,
+        on_behalf_of: None,
+        account_id: None,
+    };
+    th_execute(&mut deps, "bob", msg).unwrap();
+
+    // withdrawing from a sunset market doesn't restart interest accrual
+    let market = MARKETS.load(&deps.storage, "uatom").unwrap();
+    assert_eq!(market.borrow_rate, Decimal::zero());
+
+    th_execute(&mut deps, "owner", remove).unwrap();
+    assert!(!MARKETS.has(&deps.storage, "uatom"));
+}
+
+#[test]
+fn removing_market_clears_its_state() {
+    let mut deps = setup_test();
+    let carol_addr = Addr::unchecked("carol");
+
+    th_init_market(
+        deps.as_mut(),
+        "uusdc",
+        &Market {
+            status: MarketStatus::Sunset,
+            ..Default::default()
+        },
+    );
+    let category = EModeCategory {
+        id: 1,
+        denoms: vec!["uatom".to_string(), "uusdc".to_string()],
+        max_loan_to_value: Decimal::percent(90),
+        liquidation_threshold: Decimal::percent(95),
+        liquidation_bonus: Decimal::percent(2),
+    };
+    EMODE_CATEGORIES.save(deps.as_mut().storage, 1, &category).unwrap();
+    for denom in ["uatom", "uusdc"] {
+        UNCOLLATERALIZED_LOAN_LIMITS
+            .save(deps.as_mut().storage, (&carol_addr, denom), &Uint128::new(1_000))
+            .unwrap();
+        BORROW_ALLOWANCES
+            .save(
+                deps.as_mut().storage,
+                ((&carol_addr, MAIN_ACCOUNT_ID), &Addr::unchecked("dave"), denom),
+                &Uint128::new(100),
+            )
+            .unwrap();
+    }
+    let pending_change = PendingChange {
+        id: 0,
+        change: ParamsChange::UpdateAsset {
+            denom: "uusdc".to_string(),
+            params: InitOrUpdateAssetParams {
+                reserve_factor: None,
+                max_loan_to_value: None,
+                liquidation_threshold: None,
+                liquidation_bonus: None,
+                interest_rate_model: None,
+                deposit_enabled: None,
+                borrow_enabled: Some(false),
+                deposit_cap: None,
+                borrow_cap: None,
+                min_debt: None,
+                isolated: None,
+                debt_ceiling: None,
+                borrowable_in_isolation: None,
+                flash_loan_fee: None,
+                interest_accrual: None,
+                protocol_liquidation_fee: None,
+                ramp_duration: None,
+            },
+        },
+        executable_at: 0,
+        expires_at: 0,
+    };
+    PENDING_CHANGES.save(deps.as_mut().storage, 0, &pending_change).unwrap();
+
+    let remove = ExecuteMsg::RemoveAsset {
+        denom: "uusdc".to_string(),
+    };
+
+    // the market can only be removed once its deficit is covered
+    DEFICITS.save(deps.as_mut().storage, "uusdc", &Uint128::new(10)).unwrap();
+    let err = th_execute(&mut deps, "owner", remove.clone()).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::MarketHasDeficit {
+            denom: "uusdc".to_string(),
+        }
+    );
+
+    DEFICITS.save(deps.as_mut().storage, "uusdc", &Uint128::zero()).unwrap();
+    th_execute(&mut deps, "owner", remove).unwrap();
+
+    // only the state of the removed market is dropped
+    assert!(!DEFICITS.has(&deps.storage, "uusdc"));
+    let category = EMODE_CATEGORIES.load(&deps.storage, 1).unwrap();
+    assert_eq!(category.denoms, vec!["uatom".to_string()]);
+    assert!(UNCOLLATERALIZED_LOAN_LIMITS.has(&deps.storage, (&carol_addr, "uatom")));
+    assert!(!UNCOLLATERALIZED_LOAN_LIMITS.has(&deps.storage, (&carol_addr, "uusdc")));
+    assert!(BORROW_ALLOWANCES
+        .has(&deps.storage, ((&carol_addr, MAIN_ACCOUNT_ID), &Addr::unchecked("dave"), "uatom")));
+    assert!(!BORROW_ALLOWANCES
+        .has(&deps.storage, ((&carol_addr, MAIN_ACCOUNT_ID), &Addr::unchecked("dave"), "uusdc")));
+    assert!(!PENDING_CHANGES.has(&deps.storage, 0));
+}
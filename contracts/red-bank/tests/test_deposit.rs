@@ -14,6 +14,7 @@ use mars_red_bank::{
     error::ContractError,
     interest_rates::{compute_scaled_amount, ScalingOperation, SCALING_FACTOR},
     state::{COLLATERALS, MARKETS},
+    user::MAIN_ACCOUNT_ID,
 };
 use mars_red_bank_types::{
     address_provider::MarsAddressType,
@@ -78,6 +79,7 @@ fn depositing_with_no_coin_sent() {
         mock_info(depositor_addr.as_str(), &[]),
         ExecuteMsg::Deposit {
             on_behalf_of: None,
+            account_id: None,
         },
     )
     .unwrap_err();
@@ -100,6 +102,7 @@ fn depositing_with_multiple_coins_sent() {
         mock_info(depositor_addr.as_str(), &sent_coins),
         ExecuteMsg::Deposit {
             on_behalf_of: None,
+            account_id: None,
         },
     )
     .unwrap_err();
@@ -123,6 +126,7 @@ fn depositing_to_non_existent_market() {
         mock_info(depositor_addr.as_str(), &coins(123, false_denom)),
         ExecuteMsg::Deposit {
             on_behalf_of: None,
+            account_id: None,
         },
     )
     .unwrap_err();
@@ -153,6 +157,7 @@ fn depositing_to_disabled_market() {
         mock_info(depositor_addr.as_str(), &coins(123, denom)),
         ExecuteMsg::Deposit {
             on_behalf_of: None,
+            account_id: None,
         },
     )
     .unwrap_err();
@@ -190,6 +195,7 @@ fn depositing_above_cap() {
         mock_info(depositor_addr.as_str(), &coins(1_000_001, denom)),
         ExecuteMsg::Deposit {
             on_behalf_of: None,
+            account_id: None,
         },
     )
     .unwrap_err();
@@ -207,6 +213,7 @@ fn depositing_above_cap() {
         mock_info(depositor_addr.as_str(), &coins(123, denom)),
         ExecuteMsg::Deposit {
             on_behalf_of: None,
+            account_id: None,
         },
     );
     assert!(result.is_ok());
@@ -245,6 +252,7 @@ fn depositing_without_existing_position() {
         mock_info(depositor_addr.as_str(), &coins(deposit_amount, denom)),
         ExecuteMsg::Deposit {
             on_behalf_of: None,
+            account_id: None,
         },
     )
     .unwrap();
@@ -273,6 +281,7 @@ fn depositing_without_existing_position() {
             attr("action", "deposit"),
             attr("sender", &depositor_addr),
             attr("on_behalf_of", &depositor_addr),
+            attr("account_id", "0"),
             attr("denom", denom),
             attr("amount", deposit_amount.to_string()),
             attr("amount_scaled", expected_mint_amount),
@@ -293,7 +302,8 @@ fn depositing_without_existing_position() {
 
     // the depositor previously did not have a collateral position
     // a position should have been created with the correct scaled amount, and enabled by default
-    let collateral = COLLATERALS.load(deps.as_ref().storage, (&depositor_addr, denom)).unwrap();
+    let collateral =
+        COLLATERALS.load(deps.as_ref().storage, (&depositor_addr, MAIN_ACCOUNT_ID, denom)).unwrap();
     assert_eq!(
         collateral,
         Collateral {
@@ -340,6 +350,7 @@ fn depositing_with_existing_position() {
         mock_info(depositor_addr.as_str(), &coins(deposit_amount, denom)),
         ExecuteMsg::Deposit {
             on_behalf_of: None,
+            account_id: None,
         },
     )
     .unwrap();
@@ -365,7 +376,8 @@ fn depositing_with_existing_position() {
 
     // the depositor's scaled collateral amount should have been increased
     // however, the `enabled` status should not been affected
-    let collateral = COLLATERALS.load(deps.as_ref().storage, (&depositor_addr, denom)).unwrap();
+    let collateral =
+        COLLATERALS.load(deps.as_ref().storage, (&depositor_addr, MAIN_ACCOUNT_ID, denom)).unwrap();
     let expected = collateral_amount_scaled + expected_mint_amount;
     assert_eq!(
         collateral,
@@ -416,6 +428,7 @@ fn depositing_on_behalf_of() {
         mock_info(depositor_addr.as_str(), &coins(deposit_amount, denom)),
         ExecuteMsg::Deposit {
             on_behalf_of: Some(on_behalf_of_addr.clone().into()),
+            account_id: None,
         },
     )
     .unwrap();
@@ -454,11 +467,15 @@ fn depositing_on_behalf_of() {
     );
 
     // depositor should not have created a new collateral position
-    let opt = COLLATERALS.may_load(deps.as_ref().storage, (&depositor_addr, denom)).unwrap();
+    let opt = COLLATERALS
+        .may_load(deps.as_ref().storage, (&depositor_addr, MAIN_ACCOUNT_ID, denom))
+        .unwrap();
     assert!(opt.is_none());
 
     // the recipient should have created a new collateral position
-    let collateral = COLLATERALS.load(deps.as_ref().storage, (&on_behalf_of_addr, denom)).unwrap();
+    let collateral = COLLATERALS
+        .load(deps.as_ref().storage, (&on_behalf_of_addr, MAIN_ACCOUNT_ID, denom))
+        .unwrap();
     assert_eq!(
         collateral,
         Collateral {
@@ -490,12 +507,15 @@ fn depositing_on_behalf_of_cannot_enable_collateral() {
         mock_info(on_behalf_of_addr.as_str(), &coins(1u128, denom)),
         ExecuteMsg::Deposit {
             on_behalf_of: None,
+            account_id: None,
         },
     )
     .unwrap();
 
     // 'on_behalf_of_addr' should have collateral enabled
-    let collateral = COLLATERALS.load(deps.as_ref().storage, (&on_behalf_of_addr, denom)).unwrap();
+    let collateral = COLLATERALS
+        .load(deps.as_ref().storage, (&on_behalf_of_addr, MAIN_ACCOUNT_ID, denom))
+        .unwrap();
     assert!(collateral.enabled);
 
     // 'on_behalf_of_addr' disables asset as collateral
@@ -506,12 +526,16 @@ fn depositing_on_behalf_of_cannot_enable_collateral() {
         ExecuteMsg::UpdateAssetCollateralStatus {
             denom: denom.to_string(),
             enable: false,
+            on_behalf_of: None,
+            account_id: None,
         },
     )
     .unwrap();
 
     // verify asset is disabled as collateral for 'on_behalf_of_addr'
-    let collateral = COLLATERALS.load(deps.as_ref().storage, (&on_behalf_of_addr, denom)).unwrap();
+    let collateral = COLLATERALS
+        .load(deps.as_ref().storage, (&on_behalf_of_addr, MAIN_ACCOUNT_ID, denom))
+        .unwrap();
     assert!(!collateral.enabled);
 
     // 'depositor_addr' deposits a small amount of funds to 'on_behalf_of_addr' to enable his asset as collateral
@@ -521,11 +545,49 @@ fn depositing_on_behalf_of_cannot_enable_collateral() {
         mock_info(depositor_addr.as_str(), &coins(1u128, denom)),
         ExecuteMsg::Deposit {
             on_behalf_of: Some(on_behalf_of_addr.to_string()),
+            account_id: None,
         },
     )
     .unwrap();
 
     // 'on_behalf_of_addr' doesn't have the asset enabled as collateral
-    let collateral = COLLATERALS.load(deps.as_ref().storage, (&on_behalf_of_addr, denom)).unwrap();
+    let collateral = COLLATERALS
+        .load(deps.as_ref().storage, (&on_behalf_of_addr, MAIN_ACCOUNT_ID, denom))
+        .unwrap();
+    assert!(!collateral.enabled);
+}
+
+#[test]
+fn depositing_in_isolation_mode_does_not_enable_collateral() {
+    let TestSuite {
+        mut deps,
+        denom,
+        depositor_addr,
+        ..
+    } = setup_test();
+
+    // the depositor has an isolated asset enabled as collateral
+    let isolated_market = Market {
+        denom: "ulong".to_string(),
+        isolated: true,
+        ..Default::default()
+    };
+    MARKETS.save(deps.as_mut().storage, "ulong", &isolated_market).unwrap();
+    set_collateral(deps.as_mut(), &depositor_addr, "ulong", Uint128::new(100_000), true);
+
+    execute(
+        deps.as_mut(),
+        mock_env_at_block_time(10000100),
+        mock_info(depositor_addr.as_str(), &coins(123, denom)),
+        ExecuteMsg::Deposit {
+            on_behalf_of: None,
+            account_id: None,
+        },
+    )
+    .unwrap();
+
+    // the deposited asset is not enabled as collateral alongside the isolated asset
+    let collateral =
+        COLLATERALS.load(deps.as_ref().storage, (&depositor_addr, MAIN_ACCOUNT_ID, denom)).unwrap();
     assert!(!collateral.enabled);
 }
@@ -0,0 +1,251 @@
+use std::any::type_name;
+
+use cosmwasm_std::{
+    attr,
+    testing::{mock_info, MockApi, MockStorage},
+    Addr, Decimal, OwnedDeps, StdError, Uint128,
+};
+use helpers::{set_collateral, set_debt, th_collateral_market, th_query, th_setup_markets};
+use mars_owner::OwnerError::NotOwner;
+use mars_red_bank::{
+    contract::execute,
+    error::ContractError,
+    health,
+    interest_rates::SCALING_FACTOR,
+    state::{DEBTS, USER_EMODES},
+    user::{User, MAIN_ACCOUNT_ID},
+};
+use mars_red_bank_types::red_bank::{EModeCategory, ExecuteMsg, Market, QueryMsg};
+use mars_testing::{mock_env, MarsMockQuerier, MockEnvParams};
+use mars_utils::error::ValidationError;
+
+mod helpers;
+
+fn setup_test() -> OwnedDeps<MockStorage, MockApi, MarsMockQuerier> {
+    th_setup_markets(
+        &[],
+        &[
+            ("uatom", th_collateral_market(0)),
+            (
+                "ustatom",
+                Market {
+                    max_loan_to_value: Decimal::percent(40),
+                    liquidation_threshold: Decimal::percent(50),
+                    ..Default::default()
+                },
+            ),
+            ("uosmo", th_collateral_market(0)),
+        ],
+    )
+}
+
+fn atom_category() -> EModeCategory {
+    EModeCategory {
+        id: 1,
+        denoms: vec!["uatom".to_string(), "ustatom".to_string()],
+        max_loan_to_value: Decimal::percent(90),
+        liquidation_threshold: Decimal::percent(95),
+        liquidation_bonus: Decimal::percent(2),
+    }
+}
+
+#[test]
+fn set_emode_category() {
+    let mut deps = setup_test();
+    let env = mock_env(MockEnvParams::default());
+
+    // non owner is not authorized
+    let msg = ExecuteMsg::SetEModeCategory {
+        category: atom_category(),
+    };
+    let info = mock_info("somebody", &[]);
+    let error_res = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert_eq!(error_res, ContractError::Owner(NotOwner {}));
+
+    // liquidation threshold must be greater than max LTV
+    let msg = ExecuteMsg::SetEModeCategory {
+        category: EModeCategory {
+            liquidation_threshold: Decimal::percent(90),
+            ..atom_category()
+        },
+    };
+    let info = mock_info("owner", &[]);
+    let error_res = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert_eq!(
+        error_res,
+        ValidationError::InvalidParam {
+            param_name: "liquidation_threshold".to_string(),
+            invalid_value: "0.9".to_string(),
+            predicate: "> 0.9 (max LTV)".to_string(),
+        }
+        .into()
+    );
+
+    // the bonus must fit in the collateral left at the liquidation threshold
+    let msg = ExecuteMsg::SetEModeCategory {
+        category: EModeCategory {
+            liquidation_threshold: Decimal::percent(97),
+            liquidation_bonus: Decimal::percent(5),
+            ..atom_category()
+        },
+    };
+    let info = mock_info("owner", &[]);
+    let error_res = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert_eq!(
+        error_res,
+        ValidationError::InvalidParam {
+            param_name: "liquidation_threshold".to_string(),
+            invalid_value: "0.97".to_string(),
+            predicate: "* (1 + 0.05 (LB)) <= 1".to_string(),
+        }
+        .into()
+    );
+
+    // all denoms must have a market
+    let msg = ExecuteMsg::SetEModeCategory {
+        category: EModeCategory {
+            denoms: vec!["uatom".to_string(), "someasset".to_string()],
+            ..atom_category()
+        },
+    };
+    let info = mock_info("owner", &[]);
+    let error_res = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert_eq!(error_res, ContractError::AssetNotInitialized {});
+
+    // owner can set a valid category
+    let msg = ExecuteMsg::SetEModeCategory {
+        category: atom_category(),
+    };
+    let info = mock_info("owner", &[]);
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "set_emode_category"),
+            attr("id", "1"),
+            attr("denoms", "uatom,ustatom"),
+        ]
+    );
+
+    let category: EModeCategory = th_query(
+        deps.as_ref(),
+        QueryMsg::EModeCategory {
+            id: 1,
+        },
+    );
+    assert_eq!(category, atom_category());
+
+    let categories: Vec<EModeCategory> = th_query(
+        deps.as_ref(),
+        QueryMsg::EModeCategories {
+            start_after: None,
+            limit: None,
+        },
+    );
+    assert_eq!(categories, vec![atom_category()]);
+}
+
+#[test]
+fn set_user_emode() {
+    let mut deps = setup_test();
+    let env = mock_env(MockEnvParams::default());
+
+    let msg = ExecuteMsg::SetEModeCategory {
+        category: atom_category(),
+    };
+    execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap();
+
+    let user_addr = Addr::unchecked("user");
+    let info = mock_info(user_addr.as_str(), &[]);
+
+    // cannot opt into a category which doesn't exist
+    let msg = ExecuteMsg::SetUserEMode {
+        category_id: Some(2),
+        account_id: None,
+    };
+    let error_res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+    assert_eq!(error_res, StdError::not_found(type_name::<EModeCategory>()).into());
+
+    // cannot opt in while having debt outside of the category
+    set_collateral(
+        deps.as_mut(),
+        &user_addr,
+        "ustatom",
+        Uint128::new(10_000) * SCALING_FACTOR,
+        true,
+    );
+    set_debt(deps.as_mut(), &user_addr, "uosmo", Uint128::new(100) * SCALING_FACTOR, false);
+
+    let msg = ExecuteMsg::SetUserEMode {
+        category_id: Some(1),
+        account_id: None,
+    };
+    let error_res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap_err();
+    assert_eq!(
+        error_res,
+        ContractError::DebtOutsideEModeCategory {
+            denom: "uosmo".to_string()
+        }
+    );
+
+    // opting in makes a position above the market liquidation thresholds healthy
+    DEBTS.remove(deps.as_mut().storage, (&user_addr, MAIN_ACCOUNT_ID, "uosmo"));
+    set_debt(deps.as_mut(), &user_addr, "uatom", Uint128::new(8_000) * SCALING_FACTOR, false);
+
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "set_user_emode"),
+            attr("user", "user"),
+            attr("account_id", "0"),
+            attr("category_id", "1"),
+        ]
+    );
+    assert_eq!(USER_EMODES.load(&deps.storage, (&user_addr, MAIN_ACCOUNT_ID)).unwrap(), 1);
+
+    let user_emode: Option<EModeCategory> = th_query(
+        deps.as_ref(),
+        QueryMsg::UserEMode {
+            user: user_addr.to_string(),
+            account_id: None,
+        },
+    );
+    assert_eq!(user_emode, Some(atom_category()));
+
+    // category parameters are used for the assets in the category
+    let positions = health::get_user_positions_map(
+        &deps.as_ref(),
+        &env,
+        User(&user_addr, MAIN_ACCOUNT_ID),
+        &Addr::unchecked("oracle"),
+    )
+    .unwrap();
+    let position = positions.get("ustatom").unwrap();
+    assert_eq!(position.max_ltv, Decimal::percent(90));
+    assert_eq!(position.liquidation_threshold, Decimal::percent(95));
+
+    // cannot borrow outside of the category
+    let msg = ExecuteMsg::Borrow {
+        denom: "uosmo".to_string(),
+        amount: Uint128::new(100),
+        recipient: None,
+        on_behalf_of: None,
+        account_id: None,
+    };
+    let error_res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+    assert_eq!(
+        error_res,
+        ContractError::BorrowOutsideEModeCategory {
+            denom: "uosmo".to_string()
+        }
+    );
+
+    // cannot opt out if the position becomes liquidatable
+    let msg = ExecuteMsg::SetUserEMode {
+        category_id: None,
+        account_id: None,
+    };
+    let error_res = execute(deps.as_mut(), env, info, msg).unwrap_err();
+    assert_eq!(error_res, ContractError::InvalidHealthFactorAfterSettingEMode {});
+}
@@ -0,0 +1,266 @@
+use cosmwasm_std::{
+    attr, coin, coins,
+    testing::{mock_info, MockApi, MockStorage, MOCK_CONTRACT_ADDR},
+    to_binary, Addr, BankMsg, Decimal, OwnedDeps, SubMsg, Uint128, WasmMsg,
+};
+use cw_utils::PaymentError;
+use helpers::th_setup_markets;
+use mars_red_bank::{
+    contract::execute,
+    error::ContractError,
+    interest_rates::{compute_scaled_amount, ScalingOperation},
+    state::{COLLATERALS, FLASH_LOANS, MARKETS},
+    user::MAIN_ACCOUNT_ID,
+};
+use mars_red_bank_types::{
+    address_provider::MarsAddressType,
+    error::MarsError,
+    red_bank::{CallbackMsg, ExecuteMsg, FlashLoan, Market},
+};
+use mars_testing::{mock_env_at_block_time, MarsMockQuerier};
+
+mod helpers;
+
+fn setup_test() -> OwnedDeps<MockStorage, MockApi, MarsMockQuerier> {
+    th_setup_markets(
+        &[coin(1_000_000, "uosmo"), coin(500_000, "uatom")],
+        &[
+            (
+                "uosmo",
+                Market {
+                    flash_loan_fee: Decimal::percent(1),
+                    indexes_last_updated: 10_000_000,
+                    ..Default::default()
+                },
+            ),
+            (
+                "uatom",
+                Market {
+                    borrow_enabled: false,
+                    indexes_last_updated: 10_000_000,
+                    ..Default::default()
+                },
+            ),
+        ],
+    )
+}
+
+#[test]
+fn flash_loan_validation() {
+    let mut deps = setup_test();
+    let env = mock_env_at_block_time(10_000_100);
+
+    // cannot send funds along with the request
+    let msg = ExecuteMsg::FlashLoan {
+        coins: coins(1_000, "uosmo"),
+        callback: to_binary("callback").unwrap(),
+    };
+    let info = mock_info("receiver", &coins(1_000, "uosmo"));
+    let err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert_eq!(err, PaymentError::NonPayable {}.into());
+
+    // cannot borrow zero or duplicate coins
+    let info = mock_info("receiver", &[]);
+    for loan_coins in [vec![], coins(0, "uosmo"), vec![coin(1, "uosmo"), coin(2, "uosmo")]] {
+        let msg = ExecuteMsg::FlashLoan {
+            coins: loan_coins,
+            callback: to_binary("callback").unwrap(),
+        };
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidFlashLoanCoins {});
+    }
+
+    // cannot borrow from a market with borrowing disabled
+    let msg = ExecuteMsg::FlashLoan {
+        coins: coins(1_000, "uatom"),
+        callback: to_binary("callback").unwrap(),
+    };
+    let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::BorrowNotEnabled {
+            denom: "uatom".to_string()
+        }
+    );
+
+    // cannot borrow more than the available liquidity
+    let msg = ExecuteMsg::FlashLoan {
+        coins: coins(1_000_001, "uosmo"),
+        callback: to_binary("callback").unwrap(),
+    };
+    let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+    assert_eq!(err, ContractError::OperationExceedsAvailableLiquidity {});
+}
+
+#[test]
+fn flash_loan_sends_coins_and_callbacks() {
+    let mut deps = setup_test();
+    let env = mock_env_at_block_time(10_000_100);
+
+    let callback = to_binary("callback").unwrap();
+    let msg = ExecuteMsg::FlashLoan {
+        coins: coins(100_000, "uosmo"),
+        callback: callback.clone(),
+    };
+    let info = mock_info("receiver", &[]);
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: "receiver".to_string(),
+                msg: callback,
+                funds: coins(100_000, "uosmo"),
+            }),
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                msg: to_binary(&ExecuteMsg::Callback(CallbackMsg::FinalizeFlashLoan {})).unwrap(),
+                funds: vec![],
+            }),
+        ]
+    );
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "flash_loan"),
+            attr("sender", "receiver"),
+            attr("coins", "100000uosmo"),
+            attr("fees", "1000uosmo"),
+        ]
+    );
+
+    let flash_loans = FLASH_LOANS.load(&deps.storage).unwrap();
+    assert_eq!(
+        flash_loans,
+        vec![FlashLoan {
+            denom: "uosmo".to_string(),
+            amount: Uint128::new(100_000),
+            fee: Uint128::new(1_000),
+            repaid: Uint128::zero(),
+        }]
+    );
+
+    // flash loans can't be nested
+    let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+    assert_eq!(err, ContractError::FlashLoanInProgress {});
+}
+
+#[test]
+fn repaying_flash_loan() {
+    let mut deps = setup_test();
+    let env = mock_env_at_block_time(10_000_100);
+
+    // nothing to repay
+    let info = mock_info("receiver", &coins(1_000, "uosmo"));
+    let err = execute(deps.as_mut(), env.clone(), info, ExecuteMsg::RepayFlashLoan {}).unwrap_err();
+    assert_eq!(err, ContractError::NoFlashLoanInProgress {});
+
+    let msg = ExecuteMsg::FlashLoan {
+        coins: coins(100_000, "uosmo"),
+        callback: to_binary("callback").unwrap(),
+    };
+    execute(deps.as_mut(), env.clone(), mock_info("receiver", &[]), msg).unwrap();
+
+    // repay with no funds
+    let info = mock_info("receiver", &[]);
+    let err = execute(deps.as_mut(), env.clone(), info, ExecuteMsg::RepayFlashLoan {}).unwrap_err();
+    assert_eq!(err, PaymentError::NoFunds {}.into());
+
+    // repay in a denom that was not loaned
+    let info = mock_info("receiver", &coins(1_000, "uatom"));
+    let err = execute(deps.as_mut(), env.clone(), info, ExecuteMsg::RepayFlashLoan {}).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::UnexpectedFlashLoanRepayment {
+            denom: "uatom".to_string()
+        }
+    );
+
+    // partial repayment
+    let info = mock_info("receiver", &coins(60_000, "uosmo"));
+    let res = execute(deps.as_mut(), env.clone(), info, ExecuteMsg::RepayFlashLoan {}).unwrap();
+    assert!(res.messages.is_empty());
+    assert_eq!(FLASH_LOANS.load(&deps.storage).unwrap()[0].repaid, Uint128::new(60_000));
+
+    // repayment in excess of the amount owed is refunded
+    let info = mock_info("receiver", &coins(50_000, "uosmo"));
+    let res = execute(deps.as_mut(), env, info, ExecuteMsg::RepayFlashLoan {}).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(BankMsg::Send {
+            to_address: "receiver".to_string(),
+            amount: coins(9_000, "uosmo"),
+        })]
+    );
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "repay_flash_loan"),
+            attr("sender", "receiver"),
+            attr("coins", "41000uosmo"),
+            attr("refund", "9000uosmo"),
+        ]
+    );
+    assert_eq!(FLASH_LOANS.load(&deps.storage).unwrap()[0].repaid, Uint128::new(101_000));
+}
+
+#[test]
+fn finalizing_flash_loan() {
+    let mut deps = setup_test();
+    let env = mock_env_at_block_time(10_000_100);
+
+    let msg = ExecuteMsg::FlashLoan {
+        coins: coins(100_000, "uosmo"),
+        callback: to_binary("callback").unwrap(),
+    };
+    execute(deps.as_mut(), env.clone(), mock_info("receiver", &[]), msg).unwrap();
+
+    let finalize_msg = ExecuteMsg::Callback(CallbackMsg::FinalizeFlashLoan {});
+
+    // only the contract itself can finalize
+    let info = mock_info("receiver", &[]);
+    let err = execute(deps.as_mut(), env.clone(), info, finalize_msg.clone()).unwrap_err();
+    assert_eq!(err, MarsError::Unauthorized {}.into());
+
+    // principal is returned but not the fee
+    let info = mock_info("receiver", &coins(100_000, "uosmo"));
+    execute(deps.as_mut(), env.clone(), info, ExecuteMsg::RepayFlashLoan {}).unwrap();
+
+    let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+    let err = execute(deps.as_mut(), env.clone(), info, finalize_msg.clone()).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::FlashLoanNotRepaid {
+            denom: "uosmo".to_string()
+        }
+    );
+
+    // fee is returned as well
+    let info = mock_info("receiver", &coins(1_000, "uosmo"));
+    execute(deps.as_mut(), env.clone(), info, ExecuteMsg::RepayFlashLoan {}).unwrap();
+
+    let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+    let res = execute(deps.as_mut(), env, info, finalize_msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![attr("action", "finalize_flash_loan"), attr("fees", "1000uosmo")]
+    );
+
+    // the fee is minted to the rewards collector as collateral
+    let market = MARKETS.load(&deps.storage, "uosmo").unwrap();
+    let expected_fee_scaled = compute_scaled_amount(
+        Uint128::new(1_000),
+        market.liquidity_index,
+        ScalingOperation::Truncate,
+    )
+    .unwrap();
+    let rewards_collector_addr = Addr::unchecked(MarsAddressType::RewardsCollector.to_string());
+    let collateral = COLLATERALS
+        .load(&deps.storage, (&rewards_collector_addr, MAIN_ACCOUNT_ID, "uosmo"))
+        .unwrap();
+    assert_eq!(collateral.amount_scaled, expected_fee_scaled);
+    assert_eq!(market.collateral_total_scaled, expected_fee_scaled);
+
+    assert!(FLASH_LOANS.may_load(&deps.storage).unwrap().is_none());
+}
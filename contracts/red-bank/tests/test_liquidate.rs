@@ -1,31 +1,35 @@
 use std::cmp::min;
 
 use cosmwasm_std::{
-    attr, coin, coins,
+    attr, coin, coins, from_binary,
     testing::{mock_info, MockApi, MockStorage},
-    to_binary, Addr, BankMsg, Coin, CosmosMsg, Decimal, Deps, OwnedDeps, StdError, StdResult,
-    SubMsg, Uint128, WasmMsg,
+    to_binary, Addr, BankMsg, Coin, CosmosMsg, Decimal, Deps, Event, OwnedDeps, StdError,
+    StdResult, SubMsg, Uint128, WasmMsg,
 };
 use cw_utils::PaymentError;
 use helpers::{
     has_collateral_position, set_collateral, th_build_interests_updated_event,
-    th_get_expected_indices, th_get_expected_indices_and_rates, th_init_market, th_setup,
+    th_get_expected_indices, th_get_expected_indices_and_rates, th_init_market, th_query, th_setup,
     TestUtilizationDeltaInfo,
 };
 use mars_red_bank::{
-    contract::execute,
+    contract::{execute, query},
     error::ContractError,
     execute::liquidation_compute_amounts,
     interest_rates::{
         compute_scaled_amount, compute_underlying_amount, get_scaled_liquidity_amount,
         ScalingOperation, SCALING_FACTOR,
     },
-    state::{COLLATERALS, CONFIG, DEBTS, MARKETS},
+    state::{COLLATERALS, CONFIG, DEBTS, DEFICITS, ISOLATED_DEBTS, MARKETS},
+    user::MAIN_ACCOUNT_ID,
 };
 use mars_red_bank_types::{
     address_provider::MarsAddressType,
     incentives,
-    red_bank::{Collateral, Debt, ExecuteMsg, InterestRateModel, Market},
+    red_bank::{
+        Collateral, Debt, ExecuteMsg, InterestRateModel, LiquidationBonus,
+        LiquidationPreviewResponse, Market, QueryMsg, UserHealthStatus, UserPositionResponse,
+    },
 };
 use mars_testing::{mock_env, mock_env_at_block_time, MarsMockQuerier, MockEnvParams};
 use mars_utils::math;
@@ -68,7 +72,7 @@ fn setup_test() -> TestSuite {
     deps.querier.set_oracle_price(uncollateralized_denom, uncollateralized_debt_price);
 
     // for the test to pass, we need an interest rate model that gives non-zero rates
-    let mock_ir_model = InterestRateModel {
+    let mock_ir_model = InterestRateModel::Linear {
         optimal_utilization_rate: Decimal::one(),
         base: Decimal::percent(5),
         slope_1: Decimal::zero(),
@@ -78,7 +82,7 @@ fn setup_test() -> TestSuite {
     let collateral_market = Market {
         max_loan_to_value: Decimal::from_ratio(5u128, 10u128),
         liquidation_threshold: Decimal::from_ratio(6u128, 10u128),
-        liquidation_bonus: Decimal::from_ratio(1u128, 10u128),
+        liquidation_bonus: LiquidationBonus::fixed(Decimal::from_ratio(1u128, 10u128)),
         collateral_total_scaled: Uint128::new(1_500_000_000) * SCALING_FACTOR,
         debt_total_scaled: Uint128::new(800_000_000) * SCALING_FACTOR,
         liquidity_index: Decimal::one(),
@@ -132,7 +136,11 @@ fn rewards_collector_collateral(deps: Deps, denom: &str) -> Collateral {
     COLLATERALS
         .load(
             deps.storage,
-            (&Addr::unchecked(MarsAddressType::RewardsCollector.to_string()), denom),
+            (
+                &Addr::unchecked(MarsAddressType::RewardsCollector.to_string()),
+                MAIN_ACCOUNT_ID,
+                denom,
+            ),
         )
         .unwrap()
 }
@@ -184,7 +192,7 @@ fn expected_amounts(
     let expected_liquidated_collateral_amount = math::divide_uint128_by_decimal(
         amount_to_repay
             * test_suite.debt_price
-            * (Decimal::one() + test_suite.collateral_market.liquidation_bonus),
+            * (Decimal::one() + test_suite.collateral_market.liquidation_bonus.min_lb),
         test_suite.collateral_price,
     )
     .unwrap();
@@ -292,6 +300,7 @@ fn liquidate_if_no_coins_sent() {
         user: "user".to_string(),
         collateral_denom: "collateral".to_string(),
         recipient: None,
+        account_id: None,
     };
     let error_res = execute(deps.as_mut(), env, info, msg).unwrap_err();
     assert_eq!(error_res, PaymentError::NoFunds {}.into());
@@ -310,6 +319,7 @@ fn liquidate_if_many_coins_sent() {
         user: "user".to_string(),
         collateral_denom: "collateral".to_string(),
         recipient: None,
+        account_id: None,
     };
     let error_res = execute(deps.as_mut(), env, info, msg).unwrap_err();
     assert_eq!(error_res, PaymentError::MultipleDenoms {}.into());
@@ -328,6 +338,7 @@ fn liquidate_if_no_collateral() {
         user: "user".to_string(),
         collateral_denom: collateral_coin.denom,
         recipient: None,
+        account_id: None,
     };
 
     let env = mock_env(MockEnvParams::default());
@@ -362,6 +373,7 @@ fn liquidate_if_only_uncollateralized_debt_exists() {
         user: user_addr.to_string(),
         collateral_denom: collateral_coin.denom,
         recipient: None,
+        account_id: None,
     };
 
     let env = mock_env(MockEnvParams::default());
@@ -406,6 +418,7 @@ fn liquidate_partially() {
         user: user_addr.to_string(),
         collateral_denom: ts.collateral_market.denom.clone(),
         recipient: None,
+        account_id: None,
     };
 
     let debt_to_repay = Uint128::from(400_000_u64);
@@ -442,11 +455,14 @@ fn liquidate_partially() {
         vec![
             attr("action", "liquidate"),
             attr("user", user_addr.as_str()),
+            attr("account_id", "0"),
             attr("liquidator", liquidator_addr.as_str()),
             attr("recipient", liquidator_addr.as_str()),
             attr("collateral_denom", ts.collateral_market.denom.as_str()),
             attr("collateral_amount", expected_liquidated_collateral_amount),
             attr("collateral_amount_scaled", expected_liquidated_collateral_amount_scaled),
+            attr("protocol_fee_amount", "0"),
+            attr("protocol_fee_amount_scaled", "0"),
             attr("debt_denom", ts.debt_market.denom.as_str()),
             attr("debt_amount", user_debt_repayed),
             attr("debt_amount_scaled", user_debt_repayed_scaled),
@@ -461,7 +477,7 @@ fn liquidate_partially() {
 
     // user's collateral scaled amount should have been correctly decreased
     let collateral = COLLATERALS
-        .load(ts.deps.as_ref().storage, (&user_addr, &ts.collateral_market.denom))
+        .load(ts.deps.as_ref().storage, (&user_addr, MAIN_ACCOUNT_ID, &ts.collateral_market.denom))
         .unwrap();
     assert_eq!(
         collateral.amount_scaled,
@@ -470,12 +486,16 @@ fn liquidate_partially() {
 
     // liquidator's collateral scaled amount should have been correctly increased
     let collateral = COLLATERALS
-        .load(ts.deps.as_ref().storage, (&liquidator_addr, &ts.collateral_market.denom))
+        .load(
+            ts.deps.as_ref().storage,
+            (&liquidator_addr, MAIN_ACCOUNT_ID, &ts.collateral_market.denom),
+        )
         .unwrap();
     assert_eq!(collateral.amount_scaled, expected_liquidated_collateral_amount_scaled);
 
     // check user's debt decreased by the appropriate amount
-    let debt = DEBTS.load(&ts.deps.storage, (&user_addr, &ts.debt_market.denom)).unwrap();
+    let debt =
+        DEBTS.load(&ts.deps.storage, (&user_addr, MAIN_ACCOUNT_ID, &ts.debt_market.denom)).unwrap();
     assert_eq!(debt.amount_scaled, user_debt_scaled_before - user_debt_repayed_scaled);
 
     // check global debt decreased by the appropriate amount
@@ -523,6 +543,7 @@ fn liquidate_up_to_close_factor_with_refund() {
         user: user_addr.to_string(),
         collateral_denom: ts.collateral_market.denom.clone(),
         recipient: None,
+        account_id: None,
     };
 
     let debt_to_repay = Uint128::from(10_000_000_u64);
@@ -563,11 +584,14 @@ fn liquidate_up_to_close_factor_with_refund() {
         vec![
             attr("action", "liquidate"),
             attr("user", user_addr.as_str()),
+            attr("account_id", "0"),
             attr("liquidator", liquidator_addr.as_str()),
             attr("recipient", liquidator_addr.as_str()),
             attr("collateral_denom", ts.collateral_market.denom.as_str()),
             attr("collateral_amount", expected_liquidated_collateral_amount),
             attr("collateral_amount_scaled", expected_liquidated_collateral_amount_scaled),
+            attr("protocol_fee_amount", "0"),
+            attr("protocol_fee_amount_scaled", "0"),
             attr("debt_denom", ts.debt_market.denom.as_str()),
             attr("debt_amount", user_debt_repayed),
             attr("debt_amount_scaled", user_debt_repayed_scaled),
@@ -583,7 +607,7 @@ fn liquidate_up_to_close_factor_with_refund() {
 
     // user's collateral scaled amount should have been correctly decreased
     let collateral = COLLATERALS
-        .load(ts.deps.as_ref().storage, (&user_addr, &ts.collateral_market.denom))
+        .load(ts.deps.as_ref().storage, (&user_addr, MAIN_ACCOUNT_ID, &ts.collateral_market.denom))
         .unwrap();
     assert_eq!(
         collateral.amount_scaled,
@@ -592,12 +616,16 @@ fn liquidate_up_to_close_factor_with_refund() {
 
     // liquidator's collateral scaled amount should have been correctly increased
     let collateral = COLLATERALS
-        .load(ts.deps.as_ref().storage, (&liquidator_addr, &ts.collateral_market.denom))
+        .load(
+            ts.deps.as_ref().storage,
+            (&liquidator_addr, MAIN_ACCOUNT_ID, &ts.collateral_market.denom),
+        )
         .unwrap();
     assert_eq!(collateral.amount_scaled, expected_liquidated_collateral_amount_scaled);
 
     // check user's debt decreased by the appropriate amount
-    let debt = DEBTS.load(&ts.deps.storage, (&user_addr, &ts.debt_market.denom)).unwrap();
+    let debt =
+        DEBTS.load(&ts.deps.storage, (&user_addr, MAIN_ACCOUNT_ID, &ts.debt_market.denom)).unwrap();
     assert_eq!(debt.amount_scaled, user_debt_scaled_before - expected_debt_rates.less_debt_scaled);
 
     // check global debt decreased by the appropriate amount
@@ -648,6 +676,7 @@ fn liquidate_fully() {
         user: user_addr.to_string(),
         collateral_denom: collateral_market.denom.clone(),
         recipient: None,
+        account_id: None,
     };
 
     let debt_to_repay = Uint128::from(300u128);
@@ -672,7 +701,7 @@ fn liquidate_fully() {
     let expected_less_debt = math::divide_uint128_by_decimal(
         math::divide_uint128_by_decimal(collateral_price * user_collateral_balance, debt_price)
             .unwrap(),
-        Decimal::one() + collateral_market.liquidation_bonus,
+        Decimal::one() + collateral_market.liquidation_bonus.min_lb,
     )
     .unwrap();
 
@@ -714,20 +743,47 @@ fn liquidate_fully() {
         vec![
             attr("action", "liquidate"),
             attr("user", user_addr.as_str()),
+            attr("account_id", "0"),
             attr("liquidator", liquidator_addr.as_str()),
             attr("recipient", liquidator_addr.as_str()),
             attr("collateral_denom", collateral_market.denom.as_str()),
             attr("collateral_amount", user_collateral_balance),
             attr("collateral_amount_scaled", expected_liquidated_collateral_amount_scaled),
+            attr("protocol_fee_amount", "0"),
+            attr("protocol_fee_amount_scaled", "0"),
             attr("debt_denom", debt_market.denom.as_str()),
             attr("debt_amount", expected_less_debt),
             attr("debt_amount_scaled", expected_debt_rates.less_debt_scaled),
         ],
         res.attributes,
     );
+    // the user has no collateral left, so the remaining debt is written off
+    let expected_bad_debt = compute_underlying_amount(
+        user_debt_scaled_before - expected_debt_rates.less_debt_scaled,
+        expected_debt_rates.borrow_index,
+        ScalingOperation::Ceil,
+    )
+    .unwrap();
+    assert_eq!(res.events.len(), 3);
     assert_eq!(
-        res.events,
-        vec![th_build_interests_updated_event(&debt_market.denom, &expected_debt_rates)],
+        res.events[0],
+        th_build_interests_updated_event(&debt_market.denom, &expected_debt_rates)
+    );
+    assert_eq!(
+        res.events[1],
+        Event::new("interests_updated")
+            .add_attribute("denom", debt_market.denom.as_str())
+            .add_attribute("borrow_index", debt_market_after.borrow_index.to_string())
+            .add_attribute("liquidity_index", debt_market_after.liquidity_index.to_string())
+            .add_attribute("borrow_rate", debt_market_after.borrow_rate.to_string())
+            .add_attribute("liquidity_rate", debt_market_after.liquidity_rate.to_string())
+    );
+    assert_eq!(
+        res.events[2],
+        Event::new("bad_debt_written_off")
+            .add_attribute("user", user_addr.as_str())
+            .add_attribute("denom", debt_market.denom.as_str())
+            .add_attribute("amount", expected_bad_debt)
     );
 
     // since this is a full liquidation, the user's collateral position should have been deleted
@@ -735,19 +791,140 @@ fn liquidate_fully() {
 
     // liquidator's collateral scaled amount should have been correctly increased
     let collateral = COLLATERALS
-        .load(deps.as_ref().storage, (&liquidator_addr, &collateral_market.denom))
+        .load(deps.as_ref().storage, (&liquidator_addr, MAIN_ACCOUNT_ID, &collateral_market.denom))
         .unwrap();
     assert_eq!(collateral.amount_scaled, expected_liquidated_collateral_amount_scaled);
 
-    // check user's debt decreased by the appropriate amount
-    let debt = DEBTS.load(&deps.storage, (&user_addr, &debt_market.denom)).unwrap();
-    assert_eq!(debt.amount_scaled, user_debt_scaled_before - expected_debt_rates.less_debt_scaled);
+    // user's debt position should have been deleted, the remaining debt being added to the deficit
+    assert!(DEBTS
+        .may_load(&deps.storage, (&user_addr, MAIN_ACCOUNT_ID, &debt_market.denom))
+        .unwrap()
+        .is_none());
+    assert_eq!(DEFICITS.load(&deps.storage, &debt_market.denom).unwrap(), expected_bad_debt);
 
-    // check global debt decreased by the appropriate amount
+    // check global debt decreased by the whole user's debt
     assert_eq!(
         debt_market_after.debt_total_scaled,
-        debt_market.debt_total_scaled - expected_debt_rates.less_debt_scaled
+        debt_market.debt_total_scaled - user_debt_scaled_before
+    );
+}
+
+#[test]
+fn bad_debt_write_off_releases_isolated_debt() {
+    let TestSuite {
+        mut deps,
+        collateral_market,
+        debt_market,
+        ..
+    } = setup_test();
+
+    let user_addr = Addr::unchecked("user");
+
+    MARKETS
+        .update(deps.as_mut().storage, &collateral_market.denom, |market| -> StdResult<_> {
+            let mut market = market.unwrap();
+            market.isolated = true;
+            market.debt_ceiling = Uint128::new(10_000);
+            Ok(market)
+        })
+        .unwrap();
+    set_collateral(
+        deps.as_mut(),
+        &user_addr,
+        &collateral_market.denom,
+        Uint128::new(100) * SCALING_FACTOR,
+        true,
+    );
+    set_debt(
+        deps.as_mut(),
+        &user_addr,
+        &debt_market.denom,
+        Uint128::new(400) * SCALING_FACTOR,
+        false,
+    );
+    // value of the debt when it was borrowed: 400 * 1.4 (borrow index) * 1.1 (price)
+    ISOLATED_DEBTS
+        .save(deps.as_mut().storage, &collateral_market.denom, &Uint128::new(616))
+        .unwrap();
+
+    let liquidate_msg = ExecuteMsg::Liquidate {
+        user: user_addr.to_string(),
+        collateral_denom: collateral_market.denom.clone(),
+        recipient: None,
+        account_id: None,
+    };
+    let info = mock_info("liquidator", &coins(300, debt_market.denom.clone()));
+    execute(deps.as_mut(), mock_env_at_block_time(16_000_000), info, liquidate_msg).unwrap();
+
+    // the whole collateral is seized and the remaining debt written off, so nothing is left
+    // borrowed against the isolated collateral
+    assert!(!has_collateral_position(deps.as_ref(), &user_addr, &collateral_market.denom));
+    assert!(DEFICITS.load(&deps.storage, &debt_market.denom).unwrap() > Uint128::zero());
+    assert_eq!(
+        ISOLATED_DEBTS.load(&deps.storage, &collateral_market.denom).unwrap(),
+        Uint128::zero()
+    );
+}
+
+#[test]
+fn bad_debt_written_off_if_only_disabled_collateral_is_left() {
+    let TestSuite {
+        mut deps,
+        uncollateralized_denom,
+        collateral_market,
+        debt_market,
+        ..
+    } = setup_test();
+
+    let user_addr = Addr::unchecked("user");
+    let disabled_collateral_scaled = Uint128::new(50) * SCALING_FACTOR;
+
+    set_collateral(
+        deps.as_mut(),
+        &user_addr,
+        &collateral_market.denom,
+        Uint128::new(100) * SCALING_FACTOR,
+        true,
+    );
+    set_collateral(
+        deps.as_mut(),
+        &user_addr,
+        uncollateralized_denom,
+        disabled_collateral_scaled,
+        false,
+    );
+    set_debt(
+        deps.as_mut(),
+        &user_addr,
+        &debt_market.denom,
+        Uint128::new(400) * SCALING_FACTOR,
+        false,
     );
+
+    let liquidate_msg = ExecuteMsg::Liquidate {
+        user: user_addr.to_string(),
+        collateral_denom: collateral_market.denom.clone(),
+        recipient: None,
+        account_id: None,
+    };
+    let info = mock_info("liquidator", &coins(300, debt_market.denom.clone()));
+    let res =
+        execute(deps.as_mut(), mock_env_at_block_time(16_000_000), info, liquidate_msg).unwrap();
+
+    // the disabled collateral doesn't back the debt, so the remaining debt is written off
+    assert!(res.events.iter().any(|event| event.ty == "bad_debt_written_off"));
+    assert!(DEBTS
+        .may_load(&deps.storage, (&user_addr, MAIN_ACCOUNT_ID, &debt_market.denom))
+        .unwrap()
+        .is_none());
+    assert!(DEFICITS.load(&deps.storage, &debt_market.denom).unwrap() > Uint128::zero());
+
+    // and the user keeps it
+    let collateral = COLLATERALS
+        .load(&deps.storage, (&user_addr, MAIN_ACCOUNT_ID, uncollateralized_denom))
+        .unwrap();
+    assert_eq!(collateral.amount_scaled, disabled_collateral_scaled);
+    assert!(!collateral.enabled);
 }
 
 #[test]
@@ -787,6 +964,7 @@ fn liquidate_partially_if_same_asset_for_debt_and_collateral() {
         user: user_addr.to_string(),
         collateral_denom: collateral_market.denom.clone(),
         recipient: None,
+        account_id: None,
     };
 
     let debt_to_repay = Uint128::from(400_000_u64);
@@ -814,7 +992,7 @@ fn liquidate_partially_if_same_asset_for_debt_and_collateral() {
     let debt_market_after = MARKETS.load(&deps.storage, &debt_market.denom).unwrap();
 
     let expected_liquidated_collateral_amount = math::divide_uint128_by_decimal(
-        debt_to_repay * debt_price * (Decimal::one() + collateral_market.liquidation_bonus),
+        debt_to_repay * debt_price * (Decimal::one() + collateral_market.liquidation_bonus.min_lb),
         collateral_price,
     )
     .unwrap();
@@ -848,11 +1026,14 @@ fn liquidate_partially_if_same_asset_for_debt_and_collateral() {
         vec![
             attr("action", "liquidate"),
             attr("user", user_addr.as_str()),
+            attr("account_id", "0"),
             attr("liquidator", liquidator_addr.as_str()),
             attr("recipient", liquidator_addr.as_str()),
             attr("collateral_denom", collateral_market.denom.as_str()),
             attr("collateral_amount", expected_liquidated_collateral_amount),
             attr("collateral_amount_scaled", expected_liquidated_collateral_amount_scaled),
+            attr("protocol_fee_amount", "0"),
+            attr("protocol_fee_amount_scaled", "0"),
             attr("debt_denom", debt_market.denom.as_str()),
             attr("debt_amount", debt_to_repay),
             attr("debt_amount_scaled", expected_debt_rates.less_debt_scaled),
@@ -864,8 +1045,9 @@ fn liquidate_partially_if_same_asset_for_debt_and_collateral() {
     );
 
     // user's collateral scaled amount should have been correctly decreased
-    let collateral =
-        COLLATERALS.load(deps.as_ref().storage, (&user_addr, &collateral_market.denom)).unwrap();
+    let collateral = COLLATERALS
+        .load(deps.as_ref().storage, (&user_addr, MAIN_ACCOUNT_ID, &collateral_market.denom))
+        .unwrap();
     assert_eq!(
         collateral.amount_scaled,
         user_collateral_scaled_before - expected_liquidated_collateral_amount_scaled
@@ -873,12 +1055,13 @@ fn liquidate_partially_if_same_asset_for_debt_and_collateral() {
 
     // liquidator's collateral scaled amount should have been correctly increased
     let collateral = COLLATERALS
-        .load(deps.as_ref().storage, (&liquidator_addr, &collateral_market.denom))
+        .load(deps.as_ref().storage, (&liquidator_addr, MAIN_ACCOUNT_ID, &collateral_market.denom))
         .unwrap();
     assert_eq!(collateral.amount_scaled, expected_liquidated_collateral_amount_scaled);
 
     // check user's debt decreased by the appropriate amount
-    let debt = DEBTS.load(&deps.storage, (&user_addr, &debt_market.denom)).unwrap();
+    let debt =
+        DEBTS.load(&deps.storage, (&user_addr, MAIN_ACCOUNT_ID, &debt_market.denom)).unwrap();
     assert_eq!(debt.amount_scaled, user_debt_scaled_before - expected_debt_rates.less_debt_scaled);
 
     // check global debt decreased by the appropriate amount
@@ -936,6 +1119,7 @@ fn liquidate_with_refund_if_same_asset_for_debt_and_collateral() {
         user: user_addr.to_string(),
         collateral_denom: collateral_market.denom.clone(),
         recipient: None,
+        account_id: None,
     };
 
     let debt_to_repay = Uint128::from(10_000_000_u64);
@@ -973,7 +1157,9 @@ fn liquidate_with_refund_if_same_asset_for_debt_and_collateral() {
     );
 
     let expected_liquidated_collateral_amount = math::divide_uint128_by_decimal(
-        expected_less_debt * debt_price * (Decimal::one() + collateral_market.liquidation_bonus),
+        expected_less_debt
+            * debt_price
+            * (Decimal::one() + collateral_market.liquidation_bonus.min_lb),
         collateral_price,
     )
     .unwrap();
@@ -1022,11 +1208,14 @@ fn liquidate_with_refund_if_same_asset_for_debt_and_collateral() {
         vec![
             attr("action", "liquidate"),
             attr("user", user_addr.as_str()),
+            attr("account_id", "0"),
             attr("liquidator", liquidator_addr.as_str()),
             attr("recipient", liquidator_addr.as_str()),
             attr("collateral_denom", collateral_market.denom.as_str()),
             attr("collateral_amount", expected_liquidated_collateral_amount),
             attr("collateral_amount_scaled", expected_liquidated_collateral_amount_scaled),
+            attr("protocol_fee_amount", "0"),
+            attr("protocol_fee_amount_scaled", "0"),
             attr("debt_denom", debt_market.denom.as_str()),
             attr("debt_amount", expected_less_debt),
             attr("debt_amount_scaled", expected_debt_rates.less_debt_scaled),
@@ -1039,8 +1228,9 @@ fn liquidate_with_refund_if_same_asset_for_debt_and_collateral() {
     );
 
     // user's collateral scaled amount should have been correctly decreased
-    let collateral =
-        COLLATERALS.load(deps.as_ref().storage, (&user_addr, &collateral_market.denom)).unwrap();
+    let collateral = COLLATERALS
+        .load(deps.as_ref().storage, (&user_addr, MAIN_ACCOUNT_ID, &collateral_market.denom))
+        .unwrap();
     assert_eq!(
         collateral.amount_scaled,
         user_collateral_scaled_before - expected_liquidated_collateral_amount_scaled
@@ -1048,12 +1238,13 @@ fn liquidate_with_refund_if_same_asset_for_debt_and_collateral() {
 
     // liquidator's collateral scaled amount should have been correctly increased
     let collateral = COLLATERALS
-        .load(deps.as_ref().storage, (&liquidator_addr, &collateral_market.denom))
+        .load(deps.as_ref().storage, (&liquidator_addr, MAIN_ACCOUNT_ID, &collateral_market.denom))
         .unwrap();
     assert_eq!(collateral.amount_scaled, expected_liquidated_collateral_amount_scaled);
 
     // check user's debt decreased by the appropriate amount
-    let debt = DEBTS.load(&deps.storage, (&user_addr, &debt_market.denom)).unwrap();
+    let debt =
+        DEBTS.load(&deps.storage, (&user_addr, MAIN_ACCOUNT_ID, &debt_market.denom)).unwrap();
     assert_eq!(debt.amount_scaled, user_debt_scaled_before - expected_debt_rates.less_debt_scaled);
 
     // check global debt decreased by the appropriate amount
@@ -1102,6 +1293,7 @@ fn liquidate_with_recipient_for_underlying_collateral() {
         user: user_addr.to_string(),
         collateral_denom: ts.collateral_market.denom.clone(),
         recipient: Some(recipient_addr.to_string()),
+        account_id: None,
     };
 
     let debt_to_repay = Uint128::from(10_000_000_u64);
@@ -1142,11 +1334,14 @@ fn liquidate_with_recipient_for_underlying_collateral() {
         vec![
             attr("action", "liquidate"),
             attr("user", user_addr.as_str()),
+            attr("account_id", "0"),
             attr("liquidator", liquidator_addr.as_str()),
             attr("recipient", recipient_addr.as_str()),
             attr("collateral_denom", ts.collateral_market.denom.as_str()),
             attr("collateral_amount", expected_liquidated_collateral_amount),
             attr("collateral_amount_scaled", expected_liquidated_collateral_amount_scaled),
+            attr("protocol_fee_amount", "0"),
+            attr("protocol_fee_amount_scaled", "0"),
             attr("debt_denom", ts.debt_market.denom.as_str()),
             attr("debt_amount", user_debt_repayed),
             attr("debt_amount_scaled", user_debt_repayed_scaled),
@@ -1162,7 +1357,7 @@ fn liquidate_with_recipient_for_underlying_collateral() {
 
     // user's collateral scaled amount should have been correctly decreased
     let collateral = COLLATERALS
-        .load(ts.deps.as_ref().storage, (&user_addr, &ts.collateral_market.denom))
+        .load(ts.deps.as_ref().storage, (&user_addr, MAIN_ACCOUNT_ID, &ts.collateral_market.denom))
         .unwrap();
     assert_eq!(
         collateral.amount_scaled,
@@ -1171,17 +1366,24 @@ fn liquidate_with_recipient_for_underlying_collateral() {
 
     // liquidator's collateral should be empty
     COLLATERALS
-        .load(ts.deps.as_ref().storage, (&liquidator_addr, &ts.collateral_market.denom))
+        .load(
+            ts.deps.as_ref().storage,
+            (&liquidator_addr, MAIN_ACCOUNT_ID, &ts.collateral_market.denom),
+        )
         .unwrap_err();
 
     // recipient's collateral scaled amount should have been correctly increased
     let collateral = COLLATERALS
-        .load(ts.deps.as_ref().storage, (&recipient_addr, &ts.collateral_market.denom))
+        .load(
+            ts.deps.as_ref().storage,
+            (&recipient_addr, MAIN_ACCOUNT_ID, &ts.collateral_market.denom),
+        )
         .unwrap();
     assert_eq!(collateral.amount_scaled, expected_liquidated_collateral_amount_scaled);
 
     // check user's debt decreased by the appropriate amount
-    let debt = DEBTS.load(&ts.deps.storage, (&user_addr, &ts.debt_market.denom)).unwrap();
+    let debt =
+        DEBTS.load(&ts.deps.storage, (&user_addr, MAIN_ACCOUNT_ID, &ts.debt_market.denom)).unwrap();
     assert_eq!(debt.amount_scaled, user_debt_scaled_before - expected_debt_rates.less_debt_scaled);
 
     // check global debt decreased by the appropriate amount
@@ -1222,7 +1424,7 @@ fn liquidation_health_factor_check() {
     let collateral_market = Market {
         max_loan_to_value: collateral_ltv,
         liquidation_threshold: collateral_liquidation_threshold,
-        liquidation_bonus: collateral_liquidation_bonus,
+        liquidation_bonus: LiquidationBonus::fixed(collateral_liquidation_bonus),
         debt_total_scaled: Uint128::zero(),
         liquidity_index: Decimal::one(),
         borrow_index: Decimal::one(),
@@ -1269,11 +1471,17 @@ fn liquidation_health_factor_check() {
         amount_scaled: Uint128::new(10_000) * SCALING_FACTOR,
         uncollateralized: true,
     };
-    DEBTS.save(deps.as_mut().storage, (&healthy_user_addr, "debt"), &healthy_user_debt).unwrap();
     DEBTS
         .save(
             deps.as_mut().storage,
-            (&healthy_user_addr, "uncollateralized_debt"),
+            (&healthy_user_addr, MAIN_ACCOUNT_ID, "debt"),
+            &healthy_user_debt,
+        )
+        .unwrap();
+    DEBTS
+        .save(
+            deps.as_mut().storage,
+            (&healthy_user_addr, MAIN_ACCOUNT_ID, "uncollateralized_debt"),
             &uncollateralized_debt,
         )
         .unwrap();
@@ -1286,6 +1494,7 @@ fn liquidation_health_factor_check() {
         user: healthy_user_addr.to_string(),
         collateral_denom: "collateral".to_string(),
         recipient: None,
+        account_id: None,
     };
 
     let env = mock_env(MockEnvParams::default());
@@ -1327,6 +1536,7 @@ fn liquidate_if_collateral_disabled() {
         user: user_addr.to_string(),
         collateral_denom: "collateral2".to_string(),
         recipient: None,
+        account_id: None,
     };
 
     let env = mock_env(MockEnvParams::default());
@@ -1344,7 +1554,7 @@ fn liquidate_if_collateral_disabled() {
 fn liquidator_cannot_receive_collaterals_without_spending_coins() {
     let market = Market {
         liquidity_index: Decimal::one(),
-        liquidation_bonus: Decimal::from_ratio(1u128, 10u128),
+        liquidation_bonus: LiquidationBonus::fixed(Decimal::from_ratio(1u128, 10u128)),
         ..Default::default()
     };
     let res_err = liquidation_compute_amounts(
@@ -1356,6 +1566,7 @@ fn liquidator_cannot_receive_collaterals_without_spending_coins() {
         Decimal::from_ratio(300u128, 1u128),
         0,
         Decimal::from_ratio(1u128, 2u128),
+        Decimal::percent(90),
     )
     .unwrap_err();
     assert_eq!(res_err, StdError::generic_err("Can't process liquidation. Invalid collateral_amount_to_liquidate (320) and debt_amount_to_repay (0)"))
@@ -1365,7 +1576,7 @@ fn liquidator_cannot_receive_collaterals_without_spending_coins() {
 fn cannot_liquidate_without_receiving_collaterals() {
     let market = Market {
         liquidity_index: Decimal::one(),
-        liquidation_bonus: Decimal::from_ratio(1u128, 10u128),
+        liquidation_bonus: LiquidationBonus::fixed(Decimal::from_ratio(1u128, 10u128)),
         ..Default::default()
     };
     let res_err = liquidation_compute_amounts(
@@ -1377,7 +1588,281 @@ fn cannot_liquidate_without_receiving_collaterals() {
         Decimal::one(),
         0,
         Decimal::from_ratio(1u128, 2u128),
+        Decimal::percent(90),
     )
     .unwrap_err();
     assert_eq!(res_err, StdError::generic_err("Can't process liquidation. Invalid collateral_amount_to_liquidate (0) and debt_amount_to_repay (10)"))
 }
+
+#[test]
+fn liquidation_bonus_grows_as_health_factor_falls() {
+    let mut deps = th_setup(&[coin(1_000_000, "debt")]);
+
+    deps.querier.set_oracle_price("collateral", Decimal::one());
+    deps.querier.set_oracle_price("debt", Decimal::one());
+
+    // bonus = min(20%, 5% + (1 - health factor))
+    let liquidation_bonus = LiquidationBonus {
+        min_lb: Decimal::percent(5),
+        max_lb: Decimal::percent(20),
+        slope: Decimal::one(),
+    };
+    th_init_market(
+        deps.as_mut(),
+        "collateral",
+        &Market {
+            max_loan_to_value: Decimal::percent(70),
+            liquidation_threshold: Decimal::percent(80),
+            liquidation_bonus,
+            ..Default::default()
+        },
+    );
+    th_init_market(
+        deps.as_mut(),
+        "debt",
+        &Market {
+            debt_total_scaled: Uint128::new(9_000) * SCALING_FACTOR,
+            ..Default::default()
+        },
+    );
+
+    // bonus preview for positions right below, slightly below and far below the threshold
+    for (health_factor, expected_bonus) in [
+        (Decimal::percent(100), Decimal::percent(5)),
+        (Decimal::percent(95), Decimal::percent(10)),
+        (Decimal::percent(40), Decimal::percent(20)),
+    ] {
+        let bonus: Decimal = th_query(
+            deps.as_ref(),
+            QueryMsg::LiquidationBonus {
+                denom: "collateral".to_string(),
+                liquidation_health_factor: health_factor,
+            },
+        );
+        assert_eq!(bonus, expected_bonus);
+    }
+
+    // health factor = 10_000 * 0.8 / 9_000
+    let user_addr = Addr::unchecked("user");
+    set_collateral(
+        deps.as_mut(),
+        &user_addr,
+        "collateral",
+        Uint128::new(10_000) * SCALING_FACTOR,
+        true,
+    );
+    set_debt(deps.as_mut(), &user_addr, "debt", Uint128::new(9_000) * SCALING_FACTOR, false);
+
+    let health_factor = Decimal::from_ratio(8_000u128, 9_000u128);
+    let expected_bonus = Decimal::percent(5) + (Decimal::one() - health_factor);
+    assert_eq!(liquidation_bonus.get_liquidation_bonus(health_factor).unwrap(), expected_bonus);
+
+    let liquidate_msg = ExecuteMsg::Liquidate {
+        user: user_addr.to_string(),
+        collateral_denom: "collateral".to_string(),
+        recipient: None,
+        account_id: None,
+    };
+    let info = mock_info("liquidator", &coins(1_000, "debt"));
+    let res =
+        execute(deps.as_mut(), mock_env(MockEnvParams::default()), info, liquidate_msg).unwrap();
+
+    // 1_000 * (1 + 0.161111...)
+    let expected_collateral_amount = Uint128::new(1_161);
+    assert_eq!(expected_collateral_amount, Uint128::new(1_000) * (Decimal::one() + expected_bonus));
+    assert!(res.attributes.contains(&attr("collateral_amount", expected_collateral_amount)));
+    assert!(res.attributes.contains(&attr("debt_amount", "1000")));
+}
+
+#[test]
+fn liquidation_protocol_fee_goes_to_rewards_collector() {
+    let mut deps = th_setup(&[coin(1_000_000, "debt")]);
+
+    deps.querier.set_oracle_price("collateral", Decimal::one());
+    deps.querier.set_oracle_price("debt", Decimal::one());
+
+    th_init_market(
+        deps.as_mut(),
+        "collateral",
+        &Market {
+            max_loan_to_value: Decimal::percent(70),
+            liquidation_threshold: Decimal::percent(80),
+            liquidation_bonus: LiquidationBonus::fixed(Decimal::percent(10)),
+            protocol_liquidation_fee: Decimal::percent(50),
+            ..Default::default()
+        },
+    );
+    th_init_market(
+        deps.as_mut(),
+        "debt",
+        &Market {
+            debt_total_scaled: Uint128::new(9_000) * SCALING_FACTOR,
+            ..Default::default()
+        },
+    );
+
+    let user_addr = Addr::unchecked("user");
+    set_collateral(
+        deps.as_mut(),
+        &user_addr,
+        "collateral",
+        Uint128::new(10_000) * SCALING_FACTOR,
+        true,
+    );
+    set_debt(deps.as_mut(), &user_addr, "debt", Uint128::new(9_000) * SCALING_FACTOR, false);
+
+    let liquidate_msg = ExecuteMsg::Liquidate {
+        user: user_addr.to_string(),
+        collateral_denom: "collateral".to_string(),
+        recipient: None,
+        account_id: None,
+    };
+    let info = mock_info("liquidator", &coins(1_000, "debt"));
+    let res =
+        execute(deps.as_mut(), mock_env(MockEnvParams::default()), info, liquidate_msg).unwrap();
+
+    // 1_100 collateral is seized, of which 100 is the bonus and half of it goes to the protocol
+    let seized_scaled = Uint128::new(1_100) * SCALING_FACTOR;
+    let protocol_fee_scaled = Uint128::new(50) * SCALING_FACTOR;
+    assert!(res.attributes.contains(&attr("collateral_amount", "1100")));
+    assert!(res.attributes.contains(&attr("protocol_fee_amount", "50")));
+    assert!(res.attributes.contains(&attr("protocol_fee_amount_scaled", protocol_fee_scaled)));
+
+    let user_collateral = COLLATERALS
+        .load(deps.as_ref().storage, (&user_addr, MAIN_ACCOUNT_ID, "collateral"))
+        .unwrap();
+    assert_eq!(
+        user_collateral.amount_scaled,
+        Uint128::new(10_000) * SCALING_FACTOR - seized_scaled
+    );
+
+    let liquidator_collateral = COLLATERALS
+        .load(
+            deps.as_ref().storage,
+            (&Addr::unchecked("liquidator"), MAIN_ACCOUNT_ID, "collateral"),
+        )
+        .unwrap();
+    assert_eq!(liquidator_collateral.amount_scaled, seized_scaled - protocol_fee_scaled);
+
+    let rewards_collector_addr = Addr::unchecked(MarsAddressType::RewardsCollector.to_string());
+    let rewards_collector_collateral = COLLATERALS
+        .load(deps.as_ref().storage, (&rewards_collector_addr, MAIN_ACCOUNT_ID, "collateral"))
+        .unwrap();
+    assert_eq!(rewards_collector_collateral.amount_scaled, protocol_fee_scaled);
+}
+
+#[test]
+fn liquidation_preview_matches_liquidation() {
+    let mut ts = setup_test();
+
+    let user_addr = Addr::unchecked("user");
+    set_collateral(
+        ts.deps.as_mut(),
+        &user_addr,
+        &ts.collateral_market.denom,
+        Uint128::new(2_000_000) * SCALING_FACTOR,
+        true,
+    );
+    set_debt(
+        ts.deps.as_mut(),
+        &user_addr,
+        &ts.debt_market.denom,
+        Uint128::new(2_200_000) * SCALING_FACTOR,
+        false,
+    );
+
+    // more coins than the close factor allows are sent, so part of them is refunded
+    let sent_amount = Uint128::new(5_000_000);
+    let env = mock_env_at_block_time(15_000_000);
+    let preview: LiquidationPreviewResponse = from_binary(
+        &query(
+            ts.deps.as_ref(),
+            env.clone(),
+            QueryMsg::LiquidationPreview {
+                user: user_addr.to_string(),
+                collateral_denom: ts.collateral_market.denom.clone(),
+                debt_denom: ts.debt_market.denom.clone(),
+                amount: sent_amount,
+                account_id: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert!(!preview.refund_amount.is_zero());
+    assert_eq!(preview.debt_amount + preview.refund_amount, sent_amount);
+
+    let liquidate_msg = ExecuteMsg::Liquidate {
+        user: user_addr.to_string(),
+        collateral_denom: ts.collateral_market.denom.clone(),
+        recipient: None,
+        account_id: None,
+    };
+    let info = mock_info("liquidator", &[coin(sent_amount.u128(), ts.debt_market.denom.clone())]);
+    let res = execute(ts.deps.as_mut(), env.clone(), info, liquidate_msg).unwrap();
+
+    assert!(res.attributes.contains(&attr("debt_amount", preview.debt_amount)));
+    assert!(res.attributes.contains(&attr("collateral_amount", preview.collateral_amount)));
+    assert!(res
+        .attributes
+        .contains(&attr("collateral_amount_scaled", preview.collateral_amount_scaled)));
+    assert!(res.messages.contains(&SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+        to_address: "liquidator".to_string(),
+        amount: vec![coin(preview.refund_amount.u128(), ts.debt_market.denom.clone())],
+    }))));
+
+    let position: UserPositionResponse = from_binary(
+        &query(
+            ts.deps.as_ref(),
+            env,
+            QueryMsg::UserPosition {
+                user: user_addr.to_string(),
+                account_id: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    match position.health_status {
+        UserHealthStatus::Borrowing {
+            liq_threshold_hf,
+            ..
+        } => assert_eq!(preview.liquidation_health_factor, Some(liq_threshold_hf)),
+        UserHealthStatus::NotBorrowing => panic!("position should still have debt"),
+    }
+}
+
+#[test]
+fn cannot_preview_liquidation_of_healthy_position() {
+    let mut ts = setup_test();
+
+    let user_addr = Addr::unchecked("user");
+    set_collateral(
+        ts.deps.as_mut(),
+        &user_addr,
+        &ts.collateral_market.denom,
+        Uint128::new(2_000_000) * SCALING_FACTOR,
+        true,
+    );
+    set_debt(
+        ts.deps.as_mut(),
+        &user_addr,
+        &ts.debt_market.denom,
+        Uint128::new(100_000) * SCALING_FACTOR,
+        false,
+    );
+
+    let error_res = query(
+        ts.deps.as_ref(),
+        mock_env(MockEnvParams::default()),
+        QueryMsg::LiquidationPreview {
+            user: user_addr.to_string(),
+            collateral_denom: ts.collateral_market.denom.clone(),
+            debt_denom: ts.debt_market.denom.clone(),
+            amount: Uint128::new(50_000),
+            account_id: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(error_res, ContractError::CannotLiquidateHealthyPosition {});
+}
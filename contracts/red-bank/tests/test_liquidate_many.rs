@@ -0,0 +1,230 @@
+use cosmwasm_std::{
+    attr, coin, coins,
+    testing::{mock_info, MockApi, MockStorage},
+    Addr, BankMsg, CosmosMsg, Decimal, OwnedDeps, Uint128,
+};
+use helpers::{set_collateral, set_debt, th_collateral_market, th_setup_markets};
+use mars_red_bank::{
+    contract::execute,
+    error::ContractError,
+    interest_rates::SCALING_FACTOR,
+    state::{COLLATERALS, DEBTS, MARKETS},
+    user::MAIN_ACCOUNT_ID,
+};
+use mars_red_bank_types::red_bank::{
+    ExecuteMsg, InterestRateModel, Liquidation, LiquidationBonus, Market,
+};
+use mars_testing::{mock_env, MarsMockQuerier, MockEnvParams};
+
+mod helpers;
+
+fn setup_test() -> OwnedDeps<MockStorage, MockApi, MarsMockQuerier> {
+    let mut deps = th_setup_markets(
+        &[coin(1_000_000, "collateral"), coin(1_000_000, "debt")],
+        &[
+            (
+                "collateral",
+                Market {
+                    liquidation_bonus: LiquidationBonus::fixed(Decimal::percent(10)),
+                    ..th_collateral_market(2_000)
+                },
+            ),
+            (
+                "debt",
+                Market {
+                    debt_total_scaled: Uint128::new(800) * SCALING_FACTOR,
+                    ..Default::default()
+                },
+            ),
+        ],
+    );
+
+    // unhealthy position: liquidation health factor = 1000 * 0.6 / 700 < 1
+    let unhealthy_user_addr = Addr::unchecked("unhealthy_user");
+    set_collateral(
+        deps.as_mut(),
+        &unhealthy_user_addr,
+        "collateral",
+        Uint128::new(1_000) * SCALING_FACTOR,
+        true,
+    );
+    set_debt(
+        deps.as_mut(),
+        &unhealthy_user_addr,
+        "debt",
+        Uint128::new(700) * SCALING_FACTOR,
+        false,
+    );
+
+    // healthy position: liquidation health factor = 1000 * 0.6 / 100 > 1
+    let healthy_user_addr = Addr::unchecked("healthy_user");
+    set_collateral(
+        deps.as_mut(),
+        &healthy_user_addr,
+        "collateral",
+        Uint128::new(1_000) * SCALING_FACTOR,
+        true,
+    );
+    set_debt(deps.as_mut(), &healthy_user_addr, "debt", Uint128::new(100) * SCALING_FACTOR, false);
+
+    deps
+}
+
+fn liquidation(user: &str, amount: u128) -> Liquidation {
+    Liquidation {
+        user: user.to_string(),
+        account_id: None,
+        collateral_denom: "collateral".to_string(),
+        debt_denom: "debt".to_string(),
+        amount: Uint128::new(amount),
+    }
+}
+
+#[test]
+fn cannot_liquidate_many_if_funds_do_not_match() {
+    let mut deps = setup_test();
+    let env = mock_env(MockEnvParams::default());
+
+    // no liquidations
+    let msg = ExecuteMsg::LiquidateMany {
+        liquidations: vec![],
+        recipient: None,
+    };
+    let info = mock_info("liquidator", &[]);
+    let error_res = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert_eq!(error_res, ContractError::InvalidLiquidationFunds {});
+
+    // less coins sent than the amounts to repay
+    let msg = ExecuteMsg::LiquidateMany {
+        liquidations: vec![liquidation("unhealthy_user", 200), liquidation("healthy_user", 50)],
+        recipient: None,
+    };
+    let info = mock_info("liquidator", &coins(200, "debt"));
+    let error_res = execute(deps.as_mut(), env.clone(), info, msg.clone()).unwrap_err();
+    assert_eq!(error_res, ContractError::InvalidLiquidationFunds {});
+
+    // coins sent in a denom not being repaid
+    let info = mock_info("liquidator", &[coin(250, "debt"), coin(10, "collateral")]);
+    let error_res = execute(deps.as_mut(), env, info, msg).unwrap_err();
+    assert_eq!(error_res, ContractError::InvalidLiquidationFunds {});
+}
+
+#[test]
+fn liquidate_many_skips_positions_which_cannot_be_liquidated() {
+    let mut deps = setup_test();
+    let env = mock_env(MockEnvParams::default());
+
+    let msg = ExecuteMsg::LiquidateMany {
+        liquidations: vec![
+            liquidation("unhealthy_user", 200),
+            liquidation("healthy_user", 50),
+            liquidation("unhealthy_user", 400),
+        ],
+        recipient: None,
+    };
+    let info = mock_info("liquidator", &coins(650, "debt"));
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "liquidate_many"),
+            attr("liquidator", "liquidator"),
+            attr("recipient", "liquidator"),
+            attr("liquidated", "2"),
+        ]
+    );
+
+    let liquidation_events: Vec<_> =
+        res.events.iter().filter(|event| event.ty == "liquidation").collect();
+    assert_eq!(liquidation_events.len(), 2);
+
+    let skipped_events: Vec<_> =
+        res.events.iter().filter(|event| event.ty == "liquidation_skipped").collect();
+    assert_eq!(skipped_events.len(), 1);
+    assert_eq!(
+        skipped_events[0].attributes,
+        vec![
+            attr("user", "healthy_user"),
+            attr("account_id", "0"),
+            attr("debt_denom", "debt"),
+            attr("reason", ContractError::CannotLiquidateHealthyPosition {}.to_string()),
+        ]
+    );
+
+    // first liquidation repays 200 debt out of 700, seizing 220 collateral.
+    // second liquidation is capped by the close factor at 250 debt out of 500, seizing 275
+    // collateral and refunding 150 debt
+    let unhealthy_user_addr = Addr::unchecked("unhealthy_user");
+    let debt = DEBTS.load(&deps.storage, (&unhealthy_user_addr, MAIN_ACCOUNT_ID, "debt")).unwrap();
+    assert_eq!(debt.amount_scaled, Uint128::new(250) * SCALING_FACTOR);
+    let collateral = COLLATERALS
+        .load(&deps.storage, (&unhealthy_user_addr, MAIN_ACCOUNT_ID, "collateral"))
+        .unwrap();
+    assert_eq!(collateral.amount_scaled, Uint128::new(505) * SCALING_FACTOR);
+
+    // healthy position is untouched
+    let healthy_user_addr = Addr::unchecked("healthy_user");
+    let debt = DEBTS.load(&deps.storage, (&healthy_user_addr, MAIN_ACCOUNT_ID, "debt")).unwrap();
+    assert_eq!(debt.amount_scaled, Uint128::new(100) * SCALING_FACTOR);
+
+    // refunds are sent back in a single transfer
+    let refunds: Vec<_> = res
+        .messages
+        .iter()
+        .filter_map(|sub_msg| match &sub_msg.msg {
+            CosmosMsg::Bank(bank_msg) => Some(bank_msg.clone()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        refunds,
+        vec![BankMsg::Send {
+            to_address: "liquidator".to_string(),
+            amount: coins(200, "debt"),
+        }]
+    );
+}
+
+#[test]
+fn liquidate_many_updates_rates_with_all_refunds() {
+    let mut deps = setup_test();
+    let env = mock_env(MockEnvParams::default());
+
+    let mut debt_market = MARKETS.load(&deps.storage, "debt").unwrap();
+    debt_market.interest_rate_model = InterestRateModel::Linear {
+        optimal_utilization_rate: Decimal::percent(80),
+        base: Decimal::zero(),
+        slope_1: Decimal::percent(20),
+        slope_2: Decimal::one(),
+    };
+    MARKETS.save(deps.as_mut().storage, "debt", &debt_market).unwrap();
+
+    let msg = ExecuteMsg::LiquidateMany {
+        liquidations: vec![
+            liquidation("healthy_user", 50),
+            liquidation("unhealthy_user", 200),
+            liquidation("unhealthy_user", 400),
+        ],
+        recipient: None,
+    };
+    let info = mock_info("liquidator", &coins(650, "debt"));
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+
+    // the rates are computed with the 200 debt coins refunded in total, not only with the 150
+    // refunded by the last liquidation
+    let market = MARKETS.load(&deps.storage, "debt").unwrap();
+    assert_eq!(market.debt_total_scaled, Uint128::new(350) * SCALING_FACTOR);
+    let mut expected_market = market.clone();
+    expected_market
+        .update_interest_rates(Decimal::from_ratio(350u128, 350u128 + 1_000_000 - 200))
+        .unwrap();
+    assert_eq!(market.borrow_rate, expected_market.borrow_rate);
+    assert_eq!(market.liquidity_rate, expected_market.liquidity_rate);
+
+    let last_rates_event =
+        res.events.iter().rev().find(|event| event.ty == "interests_updated").unwrap();
+    assert!(last_rates_event
+        .attributes
+        .contains(&attr("borrow_rate", expected_market.borrow_rate.to_string())));
+}
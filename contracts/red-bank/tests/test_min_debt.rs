@@ -0,0 +1,118 @@
+use cosmwasm_std::{
+    coin, coins,
+    testing::{mock_info, MockApi, MockStorage},
+    Addr, Decimal, OwnedDeps, Response, Uint128,
+};
+use helpers::{
+    has_debt_position, set_collateral, th_borrow, th_collateral_market, th_query,
+    th_query_user_debt, th_setup_markets,
+};
+use mars_red_bank::{contract::execute, error::ContractError, interest_rates::SCALING_FACTOR};
+use mars_red_bank_types::red_bank::{ExecuteMsg, Market, QueryMsg, UserCollateralResponse};
+use mars_testing::{mock_env, MarsMockQuerier, MockEnvParams};
+
+mod helpers;
+
+const MIN_DEBT: Uint128 = Uint128::new(300);
+
+fn setup_test() -> OwnedDeps<MockStorage, MockApi, MarsMockQuerier> {
+    let mut deps = th_setup_markets(
+        &[coin(10_000, "uatom")],
+        &[
+            ("uosmo", th_collateral_market(1_000)),
+            (
+                "uatom",
+                Market {
+                    min_debt: MIN_DEBT,
+                    indexes_last_updated: mock_env(MockEnvParams::default()).block.time.seconds(),
+                    ..Default::default()
+                },
+            ),
+        ],
+    );
+
+    set_collateral(
+        deps.as_mut(),
+        &Addr::unchecked("alice"),
+        "uosmo",
+        Uint128::new(1_000) * SCALING_FACTOR,
+        true,
+    );
+
+    deps
+}
+
+fn repay(
+    deps: &mut OwnedDeps<MockStorage, MockApi, MarsMockQuerier>,
+    amount: u128,
+) -> Result<Response, ContractError> {
+    let msg = ExecuteMsg::Repay {
+        on_behalf_of: None,
+        account_id: None,
+    };
+    let info = mock_info("alice", &coins(amount, "uatom"));
+    execute(deps.as_mut(), mock_env(MockEnvParams::default()), info, msg)
+}
+
+fn debt_below_minimum() -> ContractError {
+    ContractError::DebtBelowMinimum {
+        denom: "uatom".to_string(),
+        min_debt: MIN_DEBT,
+    }
+}
+
+#[test]
+fn debt_cannot_be_left_below_min_debt() {
+    let mut deps = setup_test();
+
+    let err = th_borrow(&mut deps, "alice", "uatom", 299).unwrap_err();
+    assert_eq!(err, debt_below_minimum());
+
+    th_borrow(&mut deps, "alice", "uatom", 300).unwrap();
+    // once above the min debt, any amount can be borrowed
+    th_borrow(&mut deps, "alice", "uatom", 50).unwrap();
+    assert_eq!(th_query_user_debt(deps.as_ref(), "alice", "uatom"), Uint128::new(350));
+
+    let err = repay(&mut deps, 100).unwrap_err();
+    assert_eq!(err, debt_below_minimum());
+
+    repay(&mut deps, 50).unwrap();
+    assert_eq!(th_query_user_debt(deps.as_ref(), "alice", "uatom"), MIN_DEBT);
+
+    // the debt can always be repaid in full, including with excess funds being refunded
+    repay(&mut deps, 400).unwrap();
+    assert!(!has_debt_position(deps.as_ref(), &Addr::unchecked("alice"), "uatom"));
+}
+
+#[test]
+fn liquidators_can_close_out_dust() {
+    let mut deps = setup_test();
+
+    th_borrow(&mut deps, "alice", "uatom", 450).unwrap();
+
+    // liquidation health factor: 1_000 * 0.5 * 0.6 / 450 < 1
+    deps.querier.set_oracle_price("uosmo", Decimal::percent(50));
+
+    // repaying half of the debt, as allowed by the close factor, would leave 225 uatom of debt,
+    // which is below the min debt, so the whole debt can be repaid
+    let msg = ExecuteMsg::Liquidate {
+        user: "alice".to_string(),
+        account_id: None,
+        collateral_denom: "uosmo".to_string(),
+        recipient: None,
+    };
+    let info = mock_info("liquidator", &coins(450, "uatom"));
+    execute(deps.as_mut(), mock_env(MockEnvParams::default()), info, msg).unwrap();
+    assert!(!has_debt_position(deps.as_ref(), &Addr::unchecked("alice"), "uatom"));
+
+    // 450 uatom of debt are worth 900 uosmo of collateral
+    let collateral: UserCollateralResponse = th_query(
+        deps.as_ref(),
+        QueryMsg::UserCollateral {
+            user: "liquidator".to_string(),
+            account_id: None,
+            denom: "uosmo".to_string(),
+        },
+    );
+    assert_eq!(collateral.amount, Uint128::new(900));
+}
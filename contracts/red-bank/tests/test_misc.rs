@@ -16,6 +16,7 @@ use mars_red_bank::{
         get_updated_liquidity_index, ScalingOperation, SCALING_FACTOR,
     },
     state::{DEBTS, MARKETS, UNCOLLATERALIZED_LOAN_LIMITS},
+    user::{User, MAIN_ACCOUNT_ID},
 };
 use mars_red_bank_types::red_bank::{Debt, ExecuteMsg, Market};
 use mars_testing::{mock_env, mock_env_at_block_time, MockEnvParams};
@@ -85,7 +86,7 @@ fn uncollateralized_loan_limits() {
     assert_eq!(limit, initial_uncollateralized_loan_limit);
 
     // check user's uncollateralized debt flag is true (limit > 0)
-    let debt = DEBTS.load(&deps.storage, (&borrower_addr, "somecoin")).unwrap();
+    let debt = DEBTS.load(&deps.storage, (&borrower_addr, MAIN_ACCOUNT_ID, "somecoin")).unwrap();
     assert!(debt.uncollateralized);
 
     // Borrow asset
@@ -95,6 +96,8 @@ fn uncollateralized_loan_limits() {
         denom: "somecoin".to_string(),
         amount: initial_borrow_amount,
         recipient: None,
+        on_behalf_of: None,
+        account_id: None,
     };
     let borrow_env = mock_env_at_block_time(block_time);
     let info = mock_info("borrower", &[]);
@@ -128,6 +131,8 @@ fn uncollateralized_loan_limits() {
         vec![
             attr("action", "borrow"),
             attr("sender", "borrower"),
+            attr("on_behalf_of", "borrower"),
+            attr("account_id", "0"),
             attr("recipient", "borrower"),
             attr("denom", "somecoin"),
             attr("amount", initial_borrow_amount),
@@ -139,7 +144,7 @@ fn uncollateralized_loan_limits() {
     // Check debt
     assert!(has_debt_position(deps.as_ref(), &borrower_addr, "somecoin"));
 
-    let debt = DEBTS.load(&deps.storage, (&borrower_addr, "somecoin")).unwrap();
+    let debt = DEBTS.load(&deps.storage, (&borrower_addr, MAIN_ACCOUNT_ID, "somecoin")).unwrap();
 
     let expected_debt_scaled_after_borrow = compute_scaled_amount(
         initial_borrow_amount,
@@ -159,6 +164,8 @@ fn uncollateralized_loan_limits() {
         denom: "somecoin".to_string(),
         amount: exceeding_limit,
         recipient: None,
+        on_behalf_of: None,
+        account_id: None,
     };
     let borrow_env = mock_env_at_block_time(block_time);
     let info = mock_info("borrower", &[]);
@@ -171,6 +178,8 @@ fn uncollateralized_loan_limits() {
         denom: "somecoin".to_string(),
         amount: remaining_limit - Uint128::from(20_u128),
         recipient: None,
+        on_behalf_of: None,
+        account_id: None,
     };
     let borrow_env = mock_env_at_block_time(block_time);
     let info = mock_info("borrower", &[]);
@@ -193,7 +202,7 @@ fn uncollateralized_loan_limits() {
     assert!(!allowance.is_zero());
 
     // check user's uncollateralized debt flag is true (limit > 0)
-    let debt = DEBTS.load(&deps.storage, (&borrower_addr, "somecoin")).unwrap();
+    let debt = DEBTS.load(&deps.storage, (&borrower_addr, MAIN_ACCOUNT_ID, "somecoin")).unwrap();
     assert!(debt.uncollateralized);
 }
 
@@ -251,6 +260,8 @@ fn update_asset_collateral() {
         let update_msg = ExecuteMsg::UpdateAssetCollateralStatus {
             denom: denom_1.to_string(),
             enable: true,
+            on_behalf_of: None,
+            account_id: None,
         };
         let error_res =
             execute(deps.as_mut(), env.clone(), info.clone(), update_msg.clone()).unwrap_err();
@@ -276,6 +287,8 @@ fn update_asset_collateral() {
         let update_msg = ExecuteMsg::UpdateAssetCollateralStatus {
             denom: denom_2.to_string(),
             enable: false,
+            on_behalf_of: None,
+            account_id: None,
         };
         execute(deps.as_mut(), env.clone(), info.clone(), update_msg).unwrap();
         assert!(!has_collateral_enabled(deps.as_ref(), &user_addr, &market_2_initial.denom));
@@ -338,12 +351,12 @@ fn update_asset_collateral() {
             amount_scaled: token_3_debt_scaled,
             uncollateralized: false,
         };
-        DEBTS.save(deps.as_mut().storage, (&user_addr, denom_3), &debt).unwrap();
+        DEBTS.save(deps.as_mut().storage, (&user_addr, MAIN_ACCOUNT_ID, denom_3), &debt).unwrap();
 
         let positions = health::get_user_positions_map(
             &deps.as_ref(),
             &env,
-            &user_addr,
+            User(&user_addr, MAIN_ACCOUNT_ID),
             &Addr::unchecked("oracle"),
         )
         .unwrap();
@@ -356,8 +369,78 @@ fn update_asset_collateral() {
         let update_msg = ExecuteMsg::UpdateAssetCollateralStatus {
             denom: denom_2.to_string(),
             enable: false,
+            on_behalf_of: None,
+            account_id: None,
         };
         let res_error = execute(deps.as_mut(), env, info, update_msg).unwrap_err();
         assert_eq!(res_error, ContractError::InvalidHealthFactorAfterDisablingCollateral {})
     }
 }
+
+#[test]
+fn update_asset_collateral_in_isolation_mode() {
+    let mut deps = th_setup(&[]);
+
+    let user_addr = Addr::unchecked("user");
+
+    th_init_market(
+        deps.as_mut(),
+        "ulong",
+        &Market {
+            isolated: true,
+            ..Default::default()
+        },
+    );
+    th_init_market(deps.as_mut(), "uatom", &Market::default());
+
+    let env = mock_env(MockEnvParams::default());
+    let info = mock_info(user_addr.as_str(), &[]);
+
+    // Cannot enable a regular asset while the isolated asset is enabled
+    set_collateral(deps.as_mut(), &user_addr, "ulong", Uint128::new(100_000), true);
+    set_collateral(deps.as_mut(), &user_addr, "uatom", Uint128::new(100_000), false);
+
+    let update_msg = ExecuteMsg::UpdateAssetCollateralStatus {
+        denom: "uatom".to_string(),
+        enable: true,
+        on_behalf_of: None,
+        account_id: None,
+    };
+    let error_res = execute(deps.as_mut(), env.clone(), info.clone(), update_msg).unwrap_err();
+    assert_eq!(
+        error_res,
+        ContractError::IsolatedCollateralConflict {
+            denom: "uatom".to_string()
+        }
+    );
+
+    // Cannot enable the isolated asset while a regular asset is enabled
+    set_collateral(deps.as_mut(), &user_addr, "ulong", Uint128::new(100_000), false);
+    set_collateral(deps.as_mut(), &user_addr, "uatom", Uint128::new(100_000), true);
+
+    let update_msg = ExecuteMsg::UpdateAssetCollateralStatus {
+        denom: "ulong".to_string(),
+        enable: true,
+        on_behalf_of: None,
+        account_id: None,
+    };
+    let error_res = execute(deps.as_mut(), env.clone(), info.clone(), update_msg).unwrap_err();
+    assert_eq!(
+        error_res,
+        ContractError::IsolatedCollateralConflict {
+            denom: "ulong".to_string()
+        }
+    );
+
+    // Can enable the isolated asset as the only collateral
+    set_collateral(deps.as_mut(), &user_addr, "uatom", Uint128::new(100_000), false);
+
+    let update_msg = ExecuteMsg::UpdateAssetCollateralStatus {
+        denom: "ulong".to_string(),
+        enable: true,
+        on_behalf_of: None,
+        account_id: None,
+    };
+    execute(deps.as_mut(), env, info, update_msg).unwrap();
+    assert!(has_collateral_enabled(deps.as_ref(), &user_addr, "ulong"));
+}
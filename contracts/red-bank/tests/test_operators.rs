@@ -0,0 +1,374 @@
+use cosmwasm_std::{
+    attr, coin, coins,
+    testing::{mock_info, MockApi, MockStorage},
+    Addr, BankMsg, CosmosMsg, Decimal, OwnedDeps, SubMsg, Timestamp, Uint128,
+};
+use helpers::{
+    has_collateral_enabled, set_collateral, set_debt, th_execute, th_query, th_setup_markets,
+};
+use mars_red_bank::{
+    contract::execute,
+    error::ContractError,
+    interest_rates::SCALING_FACTOR,
+    state::{COLLATERALS, DEBTS, UNCOLLATERALIZED_LOAN_LIMITS},
+    user::MAIN_ACCOUNT_ID,
+};
+use mars_red_bank_types::red_bank::{
+    ExecuteMsg, Market, OperatorPermission, OperatorResponse, QueryMsg,
+};
+use mars_testing::{mock_env, MarsMockQuerier, MockEnvParams};
+
+mod helpers;
+
+fn setup_test() -> OwnedDeps<MockStorage, MockApi, MarsMockQuerier> {
+    let mut deps = th_setup_markets(
+        &[coin(10_000, "uatom"), coin(10_000, "uosmo")],
+        &[
+            (
+                "uatom",
+                Market {
+                    max_loan_to_value: Decimal::percent(70),
+                    liquidation_threshold: Decimal::percent(80),
+                    collateral_total_scaled: Uint128::new(10_000) * SCALING_FACTOR,
+                    debt_total_scaled: Uint128::new(1_000) * SCALING_FACTOR,
+                    ..Default::default()
+                },
+            ),
+            (
+                "uosmo",
+                Market {
+                    debt_total_scaled: Uint128::new(1_000) * SCALING_FACTOR,
+                    ..Default::default()
+                },
+            ),
+        ],
+    );
+
+    // collateral of 1_000 uatom and debt of 100 uatom
+    let user_addr = Addr::unchecked("user");
+    set_collateral(deps.as_mut(), &user_addr, "uatom", Uint128::new(1_000) * SCALING_FACTOR, true);
+    set_debt(deps.as_mut(), &user_addr, "uatom", Uint128::new(100) * SCALING_FACTOR, false);
+
+    deps
+}
+
+fn set_operator(
+    deps: &mut OwnedDeps<MockStorage, MockApi, MarsMockQuerier>,
+    permissions: Vec<OperatorPermission>,
+    expires: Option<u64>,
+) -> Result<(), ContractError> {
+    let msg = ExecuteMsg::SetOperator {
+        operator: "operator".to_string(),
+        permissions,
+        expires,
+        account_id: None,
+    };
+    th_execute(deps, "user", msg)?;
+    Ok(())
+}
+
+fn deleverage(
+    deps: &mut OwnedDeps<MockStorage, MockApi, MarsMockQuerier>,
+    amount: Option<u128>,
+) -> Result<(), ContractError> {
+    let msg = ExecuteMsg::Deleverage {
+        denom: "uatom".to_string(),
+        amount: amount.map(Uint128::new),
+        on_behalf_of: Some("user".to_string()),
+        account_id: None,
+    };
+    th_execute(deps, "operator", msg)?;
+    Ok(())
+}
+
+#[test]
+fn setting_operator() {
+    let mut deps = setup_test();
+    let block_time = mock_env(MockEnvParams::default()).block.time.seconds();
+
+    let msg = ExecuteMsg::SetOperator {
+        operator: "operator".to_string(),
+        permissions: vec![
+            OperatorPermission::Repay,
+            OperatorPermission::Withdraw,
+            OperatorPermission::Repay,
+        ],
+        expires: Some(block_time + 100),
+        account_id: None,
+    };
+    let info = mock_info("user", &[]);
+    let res = execute(deps.as_mut(), mock_env(MockEnvParams::default()), info, msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "set_operator"),
+            attr("user", "user"),
+            attr("account_id", "0"),
+            attr("operator", "operator"),
+            attr("permissions", "withdraw,repay"),
+            attr("expires", (block_time + 100).to_string()),
+        ]
+    );
+
+    let operator: OperatorResponse = th_query(
+        deps.as_ref(),
+        QueryMsg::Operator {
+            user: "user".to_string(),
+            account_id: None,
+            operator: "operator".to_string(),
+        },
+    );
+    assert_eq!(
+        operator,
+        OperatorResponse {
+            operator: "operator".to_string(),
+            permissions: vec![OperatorPermission::Withdraw, OperatorPermission::Repay],
+            expires: Some(block_time + 100),
+        }
+    );
+    let operators: Vec<OperatorResponse> = th_query(
+        deps.as_ref(),
+        QueryMsg::Operators {
+            user: "user".to_string(),
+            account_id: None,
+            start_after: None,
+            limit: None,
+        },
+    );
+    assert_eq!(operators, vec![operator]);
+
+    // operators are approved per account
+    let operators: Vec<OperatorResponse> = th_query(
+        deps.as_ref(),
+        QueryMsg::Operators {
+            user: "user".to_string(),
+            account_id: Some(1),
+            start_after: None,
+            limit: None,
+        },
+    );
+    assert!(operators.is_empty());
+
+    // empty permissions revoke the operator
+    set_operator(&mut deps, vec![], None).unwrap();
+    let operator: OperatorResponse = th_query(
+        deps.as_ref(),
+        QueryMsg::Operator {
+            user: "user".to_string(),
+            account_id: None,
+            operator: "operator".to_string(),
+        },
+    );
+    assert!(operator.permissions.is_empty());
+}
+
+#[test]
+fn cannot_set_expired_operator() {
+    let mut deps = setup_test();
+    let block_time = mock_env(MockEnvParams::default()).block.time.seconds();
+
+    let error_res =
+        set_operator(&mut deps, vec![OperatorPermission::Withdraw], Some(block_time)).unwrap_err();
+    assert_eq!(error_res, ContractError::InvalidOperatorExpiration {});
+}
+
+#[test]
+fn withdrawing_on_behalf_of() {
+    let mut deps = setup_test();
+    let user_addr = Addr::unchecked("user");
+    let block_time = mock_env(MockEnvParams::default()).block.time.seconds();
+
+    let withdraw_msg = ExecuteMsg::Withdraw {
+        denom: "uatom".to_string(),
+        amount: Some(Uint128::new(100)),
+        recipient: None,
+        on_behalf_of: Some(user_addr.to_string()),
+        account_id: None,
+    };
+    let info = mock_info("operator", &[]);
+
+    // the operator needs the withdraw permission
+    set_operator(&mut deps, vec![OperatorPermission::Repay], None).unwrap();
+    let error_res = execute(
+        deps.as_mut(),
+        mock_env(MockEnvParams::default()),
+        info.clone(),
+        withdraw_msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(
+        error_res,
+        ContractError::OperatorNotApproved {
+            permission: "withdraw".to_string()
+        }
+    );
+
+    set_operator(&mut deps, vec![OperatorPermission::Withdraw], Some(block_time + 100)).unwrap();
+    let res = execute(
+        deps.as_mut(),
+        mock_env(MockEnvParams::default()),
+        info.clone(),
+        withdraw_msg.clone(),
+    )
+    .unwrap();
+
+    // the funds go to the user
+    assert_eq!(
+        res.messages[1],
+        SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: user_addr.to_string(),
+            amount: coins(100, "uatom"),
+        }))
+    );
+    assert_eq!(
+        res.attributes[..3],
+        [attr("action", "withdraw"), attr("sender", "operator"), attr("on_behalf_of", "user")]
+    );
+    let collateral =
+        COLLATERALS.load(&deps.storage, (&user_addr, MAIN_ACCOUNT_ID, "uatom")).unwrap();
+    assert_eq!(collateral.amount_scaled, Uint128::new(900) * SCALING_FACTOR);
+
+    // the operator can't send the funds anywhere else
+    let redirect_msg = ExecuteMsg::Withdraw {
+        denom: "uatom".to_string(),
+        amount: Some(Uint128::new(100)),
+        recipient: Some("operator".to_string()),
+        on_behalf_of: Some(user_addr.to_string()),
+        account_id: None,
+    };
+    let error_res =
+        execute(deps.as_mut(), mock_env(MockEnvParams::default()), info.clone(), redirect_msg)
+            .unwrap_err();
+    assert_eq!(error_res, ContractError::OperatorCannotSetRecipient {});
+    let collateral =
+        COLLATERALS.load(&deps.storage, (&user_addr, MAIN_ACCOUNT_ID, "uatom")).unwrap();
+    assert_eq!(collateral.amount_scaled, Uint128::new(900) * SCALING_FACTOR);
+
+    // the approval can't be used once expired
+    let env = mock_env(MockEnvParams {
+        block_time: Timestamp::from_seconds(block_time + 100),
+        ..Default::default()
+    });
+    let error_res = execute(deps.as_mut(), env, info, withdraw_msg).unwrap_err();
+    assert_eq!(
+        error_res,
+        ContractError::OperatorNotApproved {
+            permission: "withdraw".to_string()
+        }
+    );
+}
+
+#[test]
+fn updating_collateral_status_on_behalf_of() {
+    let mut deps = setup_test();
+    let user_addr = Addr::unchecked("user");
+    set_collateral(deps.as_mut(), &user_addr, "uosmo", Uint128::new(100) * SCALING_FACTOR, true);
+
+    let msg = ExecuteMsg::UpdateAssetCollateralStatus {
+        denom: "uosmo".to_string(),
+        enable: false,
+        on_behalf_of: Some(user_addr.to_string()),
+        account_id: None,
+    };
+    let info = mock_info("operator", &[]);
+
+    let error_res =
+        execute(deps.as_mut(), mock_env(MockEnvParams::default()), info.clone(), msg.clone())
+            .unwrap_err();
+    assert_eq!(
+        error_res,
+        ContractError::OperatorNotApproved {
+            permission: "update_asset_collateral_status".to_string()
+        }
+    );
+
+    set_operator(&mut deps, vec![OperatorPermission::UpdateAssetCollateralStatus], None).unwrap();
+    execute(deps.as_mut(), mock_env(MockEnvParams::default()), info, msg).unwrap();
+    assert!(!has_collateral_enabled(deps.as_ref(), &user_addr, "uosmo"));
+}
+
+#[test]
+fn repaying_uncollateralized_loan_on_behalf_of() {
+    let mut deps = setup_test();
+    let user_addr = Addr::unchecked("user");
+
+    UNCOLLATERALIZED_LOAN_LIMITS
+        .save(deps.as_mut().storage, (&user_addr, "uosmo"), &Uint128::new(1_000))
+        .unwrap();
+    set_debt(deps.as_mut(), &user_addr, "uosmo", Uint128::new(500) * SCALING_FACTOR, true);
+
+    let msg = ExecuteMsg::Repay {
+        on_behalf_of: Some(user_addr.to_string()),
+        account_id: None,
+    };
+    let info = mock_info("operator", &coins(200, "uosmo"));
+
+    let error_res =
+        execute(deps.as_mut(), mock_env(MockEnvParams::default()), info.clone(), msg.clone())
+            .unwrap_err();
+    assert_eq!(error_res, ContractError::CannotRepayUncollateralizedLoanOnBehalfOf {});
+
+    set_operator(&mut deps, vec![OperatorPermission::Repay], None).unwrap();
+    execute(deps.as_mut(), mock_env(MockEnvParams::default()), info, msg).unwrap();
+
+    let debt = DEBTS.load(&deps.storage, (&user_addr, MAIN_ACCOUNT_ID, "uosmo")).unwrap();
+    assert_eq!(debt.amount_scaled, Uint128::new(300) * SCALING_FACTOR);
+}
+
+#[test]
+fn deleveraging_on_behalf_of() {
+    let mut deps = setup_test();
+    let user_addr = Addr::unchecked("user");
+
+    let error_res = deleverage(&mut deps, Some(40)).unwrap_err();
+    assert_eq!(
+        error_res,
+        ContractError::OperatorNotApproved {
+            permission: "deleverage".to_string()
+        }
+    );
+
+    set_operator(&mut deps, vec![OperatorPermission::Deleverage], None).unwrap();
+
+    // the amount can't exceed the debt
+    let error_res = deleverage(&mut deps, Some(101)).unwrap_err();
+    assert_eq!(
+        error_res,
+        ContractError::InvalidDeleverageAmount {
+            denom: "uatom".to_string()
+        }
+    );
+
+    deleverage(&mut deps, Some(40)).unwrap();
+
+    let collateral =
+        COLLATERALS.load(&deps.storage, (&user_addr, MAIN_ACCOUNT_ID, "uatom")).unwrap();
+    assert_eq!(collateral.amount_scaled, Uint128::new(960) * SCALING_FACTOR);
+    let debt = DEBTS.load(&deps.storage, (&user_addr, MAIN_ACCOUNT_ID, "uatom")).unwrap();
+    assert_eq!(debt.amount_scaled, Uint128::new(60) * SCALING_FACTOR);
+
+    // without an amount, the whole debt is repaid
+    deleverage(&mut deps, None).unwrap();
+
+    let collateral =
+        COLLATERALS.load(&deps.storage, (&user_addr, MAIN_ACCOUNT_ID, "uatom")).unwrap();
+    assert_eq!(collateral.amount_scaled, Uint128::new(900) * SCALING_FACTOR);
+    assert!(DEBTS
+        .may_load(&deps.storage, (&user_addr, MAIN_ACCOUNT_ID, "uatom"))
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn deleveraging_cannot_lower_health_factor() {
+    let mut deps = setup_test();
+    let user_addr = Addr::unchecked("user");
+
+    // liquidation health factor = 1_000 * 0.8 / (100 + 1_000) ~ 0.727, below the liquidation
+    // threshold of uatom, so repaying uatom debt with uatom collateral would lower it further
+    set_debt(deps.as_mut(), &user_addr, "uosmo", Uint128::new(1_000) * SCALING_FACTOR, false);
+
+    set_operator(&mut deps, vec![OperatorPermission::Deleverage], None).unwrap();
+    let error_res = deleverage(&mut deps, Some(100)).unwrap_err();
+    assert_eq!(error_res, ContractError::InvalidHealthFactorAfterDeleverage {});
+}
@@ -0,0 +1,256 @@
+use cosmwasm_std::{
+    coin, coins,
+    testing::{mock_info, MockApi, MockStorage},
+    to_binary, Addr, OwnedDeps, Response, Uint128,
+};
+use helpers::{
+    set_collateral, th_borrow, th_collateral_market, th_execute, th_query_pause_state,
+    th_setup_markets,
+};
+use mars_owner::OwnerError;
+use mars_red_bank::{contract::execute, error::ContractError, interest_rates::SCALING_FACTOR};
+use mars_red_bank_types::{
+    error::MarsError,
+    red_bank::{ExecuteMsg, Market, PausableAction, PauseState},
+};
+use mars_testing::{mock_env, MarsMockQuerier, MockEnvParams};
+use mars_utils::error::ValidationError;
+
+mod helpers;
+
+fn setup_test() -> OwnedDeps<MockStorage, MockApi, MarsMockQuerier> {
+    let mut deps = th_setup_markets(
+        &[coin(10_000, "uatom"), coin(10_000, "uosmo")],
+        &[("uosmo", th_collateral_market(1_000)), ("uatom", Market::default())],
+    );
+
+    set_collateral(
+        deps.as_mut(),
+        &Addr::unchecked("alice"),
+        "uosmo",
+        Uint128::new(1_000) * SCALING_FACTOR,
+        true,
+    );
+
+    deps
+}
+
+fn set_pause_state(
+    deps: &mut OwnedDeps<MockStorage, MockApi, MarsMockQuerier>,
+    sender: &str,
+    denom: Option<&str>,
+    actions: Vec<PausableAction>,
+    paused: bool,
+) -> Result<Response, ContractError> {
+    let msg = ExecuteMsg::SetPauseState {
+        denom: denom.map(|denom| denom.to_string()),
+        actions,
+        paused,
+    };
+    th_execute(deps, sender, msg)
+}
+
+#[test]
+fn setting_pause_state_permissions() {
+    let mut deps = setup_test();
+
+    let err =
+        set_pause_state(&mut deps, "alice", None, vec![PausableAction::Borrow], true).unwrap_err();
+    assert_eq!(err, ContractError::Owner(OwnerError::NotOwner {}));
+
+    let err = set_pause_state(&mut deps, "owner", None, vec![], true).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Validation(ValidationError::InvalidParam {
+            param_name: "actions".to_string(),
+            invalid_value: "[]".to_string(),
+            predicate: "not empty".to_string(),
+        })
+    );
+
+    let err = set_pause_state(&mut deps, "owner", Some("uusd"), vec![PausableAction::Borrow], true)
+        .unwrap_err();
+    assert_eq!(err, ContractError::AssetNotInitialized {});
+
+    // emergency owner can pause...
+    let res = set_pause_state(
+        &mut deps,
+        "emergency_owner",
+        Some("uatom"),
+        vec![PausableAction::Borrow, PausableAction::Liquidate],
+        true,
+    )
+    .unwrap();
+    assert_eq!(res.attributes[1].value, "uatom");
+    assert_eq!(res.attributes[2].value, "borrow,liquidate");
+    assert_eq!(
+        th_query_pause_state(deps.as_ref(), Some("uatom")),
+        PauseState {
+            borrow: true,
+            liquidate: true,
+            ..Default::default()
+        }
+    );
+    assert_eq!(th_query_pause_state(deps.as_ref(), None), PauseState::default());
+
+    // ...but not unpause
+    let err = set_pause_state(
+        &mut deps,
+        "emergency_owner",
+        Some("uatom"),
+        vec![PausableAction::Borrow],
+        false,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Mars(MarsError::Unauthorized {}));
+
+    // owner can unpause
+    set_pause_state(&mut deps, "owner", Some("uatom"), vec![PausableAction::Borrow], false)
+        .unwrap();
+    assert_eq!(
+        th_query_pause_state(deps.as_ref(), Some("uatom")),
+        PauseState {
+            liquidate: true,
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn paused_actions_are_rejected() {
+    let mut deps = setup_test();
+
+    // stop borrows and liquidations everywhere, e.g. during an oracle incident
+    set_pause_state(
+        &mut deps,
+        "emergency_owner",
+        None,
+        vec![PausableAction::Borrow, PausableAction::Liquidate],
+        true,
+    )
+    .unwrap();
+
+    let err = th_borrow(&mut deps, "alice", "uatom", 100).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::ActionPaused {
+            action: "borrow".to_string(),
+            denom: "uatom".to_string(),
+        }
+    );
+
+    let msg = ExecuteMsg::Liquidate {
+        user: "alice".to_string(),
+        account_id: None,
+        collateral_denom: "uosmo".to_string(),
+        recipient: None,
+    };
+    let info = mock_info("liquidator", &coins(100, "uatom"));
+    let err = execute(deps.as_mut(), mock_env(MockEnvParams::default()), info, msg).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::ActionPaused {
+            action: "liquidate".to_string(),
+            denom: "uosmo".to_string(),
+        }
+    );
+
+    // unpausing borrows globally lets the user borrow again
+    set_pause_state(&mut deps, "owner", None, vec![PausableAction::Borrow], false).unwrap();
+    th_borrow(&mut deps, "alice", "uatom", 100).unwrap();
+
+    // repayments are still allowed while only withdrawals are paused in the market
+    set_pause_state(
+        &mut deps,
+        "emergency_owner",
+        Some("uatom"),
+        vec![PausableAction::Withdraw],
+        true,
+    )
+    .unwrap();
+    let msg = ExecuteMsg::Repay {
+        on_behalf_of: None,
+        account_id: None,
+    };
+    let info = mock_info("alice", &coins(50, "uatom"));
+    execute(deps.as_mut(), mock_env(MockEnvParams::default()), info, msg).unwrap();
+
+    // pausing an action in a market doesn't affect the other markets
+    set_pause_state(
+        &mut deps,
+        "emergency_owner",
+        Some("uatom"),
+        vec![PausableAction::Deposit],
+        true,
+    )
+    .unwrap();
+    let msg = ExecuteMsg::Deposit {
+        on_behalf_of: None,
+        account_id: None,
+    };
+    let info = mock_info("alice", &coins(100, "uatom"));
+    let err =
+        execute(deps.as_mut(), mock_env(MockEnvParams::default()), info, msg.clone()).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::ActionPaused {
+            action: "deposit".to_string(),
+            denom: "uatom".to_string(),
+        }
+    );
+    let info = mock_info("alice", &coins(100, "uosmo"));
+    execute(deps.as_mut(), mock_env(MockEnvParams::default()), info, msg).unwrap();
+
+    let msg = ExecuteMsg::UpdateAssetCollateralStatus {
+        denom: "uosmo".to_string(),
+        enable: false,
+        on_behalf_of: None,
+        account_id: None,
+    };
+    set_pause_state(
+        &mut deps,
+        "emergency_owner",
+        None,
+        vec![PausableAction::UpdateAssetCollateralStatus],
+        true,
+    )
+    .unwrap();
+    let err = th_execute(&mut deps, "alice", msg).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::ActionPaused {
+            action: "update_asset_collateral_status".to_string(),
+            denom: "uosmo".to_string(),
+        }
+    );
+}
+
+#[test]
+fn paused_borrows_block_flash_loans() {
+    let mut deps = setup_test();
+
+    let msg = ExecuteMsg::FlashLoan {
+        coins: coins(1_000, "uatom"),
+        callback: to_binary("callback").unwrap(),
+    };
+    let expected_err = ContractError::ActionPaused {
+        action: "borrow".to_string(),
+        denom: "uatom".to_string(),
+    };
+
+    for denom in [Some("uatom"), None] {
+        set_pause_state(&mut deps, "emergency_owner", denom, vec![PausableAction::Borrow], true)
+            .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            mock_env(MockEnvParams::default()),
+            mock_info("receiver", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, expected_err);
+        set_pause_state(&mut deps, "owner", denom, vec![PausableAction::Borrow], false).unwrap();
+    }
+
+    th_execute(&mut deps, "receiver", msg).unwrap();
+}
@@ -1,11 +1,27 @@
-use cosmwasm_std::{testing::mock_env, Addr, Decimal, Uint128};
-use helpers::{set_collateral, th_init_market, th_setup};
+use cosmwasm_std::{
+    coin,
+    testing::{mock_env, mock_info, MockApi, MockStorage},
+    Addr, Decimal, OwnedDeps, StdResult, Uint128,
+};
+use helpers::{
+    set_collateral, set_debt, th_collateral_market, th_init_market, th_setup, th_setup_markets,
+};
 use mars_red_bank::{
+    contract::execute,
+    error::ContractError,
     interest_rates::{get_scaled_debt_amount, get_underlying_debt_amount, SCALING_FACTOR},
-    query::{query_user_collaterals, query_user_debt, query_user_debts},
-    state::DEBTS,
+    query::{
+        query_market_live, query_max_borrow, query_max_withdraw, query_user_collaterals,
+        query_user_debt, query_user_debts,
+    },
+    state::{DEBTS, GLOBAL_PAUSE_STATE, MARKETS, PAUSE_STATES, UNCOLLATERALIZED_LOAN_LIMITS},
+    user::MAIN_ACCOUNT_ID,
 };
-use mars_red_bank_types::red_bank::{Debt, Market, UserCollateralResponse, UserDebtResponse};
+use mars_red_bank_types::red_bank::{
+    Debt, ExecuteMsg, InterestRateModel, Market, MarketLiveResponse, MarketStatus, PauseState,
+    UserCollateralResponse, UserDebtResponse,
+};
+use mars_testing::MarsMockQuerier;
 
 mod helpers;
 
@@ -30,8 +46,15 @@ fn query_collateral() {
     set_collateral(deps.as_mut(), &user_addr, &market_2.denom, amount_2 * SCALING_FACTOR, true);
 
     // Assert markets correctly return collateral status
-    let collaterals =
-        query_user_collaterals(deps.as_ref(), &env.block, user_addr.clone(), None, None).unwrap();
+    let collaterals = query_user_collaterals(
+        deps.as_ref(),
+        &env.block,
+        user_addr.clone(),
+        MAIN_ACCOUNT_ID,
+        None,
+        None,
+    )
+    .unwrap();
     assert_eq!(
         collaterals,
         vec![UserCollateralResponse {
@@ -47,7 +70,8 @@ fn query_collateral() {
 
     // Assert markets correctly return collateral status
     let collaterals =
-        query_user_collaterals(deps.as_ref(), &env.block, user_addr, None, None).unwrap();
+        query_user_collaterals(deps.as_ref(), &env.block, user_addr, MAIN_ACCOUNT_ID, None, None)
+            .unwrap();
     assert_eq!(
         collaterals,
         vec![
@@ -118,7 +142,7 @@ fn test_query_user_debt() {
         amount_scaled: debt_amount_scaled_1,
         uncollateralized: false,
     };
-    DEBTS.save(deps.as_mut().storage, (&user_addr, "coin_1"), &debt_1).unwrap();
+    DEBTS.save(deps.as_mut().storage, (&user_addr, MAIN_ACCOUNT_ID, "coin_1"), &debt_1).unwrap();
 
     // Save debt for market 3
     let debt_amount_3 = Uint128::new(2221u128);
@@ -134,9 +158,10 @@ fn test_query_user_debt() {
         amount_scaled: debt_amount_scaled_3,
         uncollateralized: false,
     };
-    DEBTS.save(deps.as_mut().storage, (&user_addr, "coin_3"), &debt_3).unwrap();
+    DEBTS.save(deps.as_mut().storage, (&user_addr, MAIN_ACCOUNT_ID, "coin_3"), &debt_3).unwrap();
 
-    let debts = query_user_debts(deps.as_ref(), &env.block, user_addr, None, None).unwrap();
+    let debts = query_user_debts(deps.as_ref(), &env.block, user_addr, MAIN_ACCOUNT_ID, None, None)
+        .unwrap();
     assert_eq!(debts.len(), 2);
     assert_eq!(
         debts[0],
@@ -200,13 +225,18 @@ fn query_user_asset_debt() {
         amount_scaled: debt_amount_scaled_1,
         uncollateralized: false,
     };
-    DEBTS.save(deps.as_mut().storage, (&user_addr, "coin_1"), &debt_1).unwrap();
+    DEBTS.save(deps.as_mut().storage, (&user_addr, MAIN_ACCOUNT_ID, "coin_1"), &debt_1).unwrap();
 
     // Check asset with existing debt
     {
-        let res =
-            query_user_debt(deps.as_ref(), &env.block, user_addr.clone(), "coin_1".to_string())
-                .unwrap();
+        let res = query_user_debt(
+            deps.as_ref(),
+            &env.block,
+            user_addr.clone(),
+            MAIN_ACCOUNT_ID,
+            "coin_1".to_string(),
+        )
+        .unwrap();
         assert_eq!(
             res,
             UserDebtResponse {
@@ -220,8 +250,14 @@ fn query_user_asset_debt() {
 
     // Check asset with no debt
     {
-        let res =
-            query_user_debt(deps.as_ref(), &env.block, user_addr, "coin_2".to_string()).unwrap();
+        let res = query_user_debt(
+            deps.as_ref(),
+            &env.block,
+            user_addr,
+            MAIN_ACCOUNT_ID,
+            "coin_2".to_string(),
+        )
+        .unwrap();
         assert_eq!(
             res,
             UserDebtResponse {
@@ -233,3 +269,337 @@ fn query_user_asset_debt() {
         );
     }
 }
+
+fn setup_max_amounts_test() -> OwnedDeps<MockStorage, MockApi, MarsMockQuerier> {
+    let mut deps = th_setup_markets(
+        &[coin(1_000_000, "uosmo"), coin(1_000_000, "uatom")],
+        &[
+            ("uosmo", th_collateral_market(10_000)),
+            (
+                "uatom",
+                Market {
+                    debt_total_scaled: Uint128::new(1_000) * SCALING_FACTOR,
+                    ..Default::default()
+                },
+            ),
+        ],
+    );
+    deps.querier.set_oracle_price("uatom", Decimal::from_ratio(2u128, 1u128));
+
+    // 10_000 uosmo of collateral backing 2_000 worth of uatom debt
+    let user_addr = Addr::unchecked("user");
+    set_collateral(deps.as_mut(), &user_addr, "uosmo", Uint128::new(10_000) * SCALING_FACTOR, true);
+    set_debt(deps.as_mut(), &user_addr, "uatom", Uint128::new(1_000) * SCALING_FACTOR, false);
+
+    deps
+}
+
+#[test]
+fn query_max_borrow_amount() {
+    let mut deps = setup_max_amounts_test();
+    let env = mock_env();
+    let user_addr = Addr::unchecked("user");
+
+    // 5_000 of max LTV adjusted collateral, out of which 2_000 is used
+    let max_borrow = query_max_borrow(
+        deps.as_ref(),
+        env.clone(),
+        user_addr.clone(),
+        MAIN_ACCOUNT_ID,
+        "uatom".to_string(),
+    )
+    .unwrap();
+    assert_eq!(max_borrow, Uint128::new(1_500));
+
+    // borrowing one more than the max fails, borrowing the max succeeds
+    let info = mock_info(user_addr.as_str(), &[]);
+    let msg = ExecuteMsg::Borrow {
+        denom: "uatom".to_string(),
+        amount: max_borrow + Uint128::new(1),
+        recipient: None,
+        on_behalf_of: None,
+        account_id: None,
+    };
+    let error_res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+    assert_eq!(error_res, ContractError::BorrowAmountExceedsGivenCollateral {});
+
+    let msg = ExecuteMsg::Borrow {
+        denom: "uatom".to_string(),
+        amount: max_borrow,
+        recipient: None,
+        on_behalf_of: None,
+        account_id: None,
+    };
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let max_borrow =
+        query_max_borrow(deps.as_ref(), env, user_addr, MAIN_ACCOUNT_ID, "uatom".to_string())
+            .unwrap();
+    assert_eq!(max_borrow, Uint128::zero());
+}
+
+#[test]
+fn query_max_borrow_amount_limits() {
+    let mut deps = setup_max_amounts_test();
+    let env = mock_env();
+    let user_addr = Addr::unchecked("user");
+
+    // limited by the available liquidity
+    deps.querier.set_contract_balances(&[coin(1_000_000, "uosmo"), coin(700, "uatom")]);
+    let max_borrow = query_max_borrow(
+        deps.as_ref(),
+        env.clone(),
+        user_addr.clone(),
+        MAIN_ACCOUNT_ID,
+        "uatom".to_string(),
+    )
+    .unwrap();
+    assert_eq!(max_borrow, Uint128::new(700));
+
+    // uncollateralized loans are limited by the loan limit
+    UNCOLLATERALIZED_LOAN_LIMITS
+        .save(deps.as_mut().storage, (&user_addr, "uatom"), &Uint128::new(1_200))
+        .unwrap();
+    DEBTS
+        .save(
+            deps.as_mut().storage,
+            (&user_addr, MAIN_ACCOUNT_ID, "uatom"),
+            &Debt {
+                amount_scaled: Uint128::new(1_000) * SCALING_FACTOR,
+                uncollateralized: true,
+            },
+        )
+        .unwrap();
+    let max_borrow = query_max_borrow(
+        deps.as_ref(),
+        env.clone(),
+        user_addr.clone(),
+        MAIN_ACCOUNT_ID,
+        "uatom".to_string(),
+    )
+    .unwrap();
+    assert_eq!(max_borrow, Uint128::new(200));
+
+    // nothing can be borrowed if borrowing is disabled
+    MARKETS
+        .update(deps.as_mut().storage, "uatom", |market| -> StdResult<_> {
+            let mut market = market.unwrap();
+            market.borrow_enabled = false;
+            Ok(market)
+        })
+        .unwrap();
+    let max_borrow =
+        query_max_borrow(deps.as_ref(), env, user_addr, MAIN_ACCOUNT_ID, "uatom".to_string())
+            .unwrap();
+    assert_eq!(max_borrow, Uint128::zero());
+}
+
+#[test]
+fn query_max_borrow_amount_follows_borrow_checks() {
+    let mut deps = setup_max_amounts_test();
+    let env = mock_env();
+    let user_addr = Addr::unchecked("user");
+    let max_borrow = |deps: &OwnedDeps<_, _, _>, user_addr: &Addr| {
+        query_max_borrow(
+            deps.as_ref(),
+            env.clone(),
+            user_addr.clone(),
+            MAIN_ACCOUNT_ID,
+            "uatom".to_string(),
+        )
+        .unwrap()
+    };
+    assert_eq!(max_borrow(&deps, &user_addr), Uint128::new(1_500));
+
+    // nothing can be borrowed while borrowing is paused, globally or in the market
+    let paused = PauseState {
+        borrow: true,
+        ..Default::default()
+    };
+    GLOBAL_PAUSE_STATE.save(deps.as_mut().storage, &paused).unwrap();
+    assert_eq!(max_borrow(&deps, &user_addr), Uint128::zero());
+    GLOBAL_PAUSE_STATE.remove(deps.as_mut().storage);
+
+    PAUSE_STATES.save(deps.as_mut().storage, "uatom", &paused).unwrap();
+    assert_eq!(max_borrow(&deps, &user_addr), Uint128::zero());
+    PAUSE_STATES.remove(deps.as_mut().storage, "uatom");
+
+    // nor from a market that isn't listed
+    let update_market = |deps: &mut OwnedDeps<_, _, _>, update: fn(&mut Market)| {
+        MARKETS
+            .update(deps.as_mut().storage, "uatom", |market| -> StdResult<_> {
+                let mut market = market.unwrap();
+                update(&mut market);
+                Ok(market)
+            })
+            .unwrap();
+    };
+    update_market(&mut deps, |market| market.status = MarketStatus::Delisted);
+    assert_eq!(max_borrow(&deps, &user_addr), Uint128::zero());
+    update_market(&mut deps, |market| market.status = MarketStatus::Listed);
+
+    // a user without debt can't borrow less than the min debt, 1_000 uosmo of collateral only
+    // allowing 250 uatom to be borrowed
+    let other_addr = Addr::unchecked("other");
+    set_collateral(deps.as_mut(), &other_addr, "uosmo", Uint128::new(1_000) * SCALING_FACTOR, true);
+    update_market(&mut deps, |market| market.min_debt = Uint128::new(300));
+    assert_eq!(max_borrow(&deps, &other_addr), Uint128::zero());
+    assert_eq!(max_borrow(&deps, &user_addr), Uint128::new(1_500));
+
+    let msg = ExecuteMsg::Borrow {
+        denom: "uatom".to_string(),
+        amount: Uint128::new(250),
+        recipient: None,
+        on_behalf_of: None,
+        account_id: None,
+    };
+    let error_res =
+        execute(deps.as_mut(), env.clone(), mock_info(other_addr.as_str(), &[]), msg).unwrap_err();
+    assert_eq!(
+        error_res,
+        ContractError::DebtBelowMinimum {
+            denom: "uatom".to_string(),
+            min_debt: Uint128::new(300),
+        }
+    );
+
+    update_market(&mut deps, |market| market.min_debt = Uint128::new(200));
+    assert_eq!(max_borrow(&deps, &other_addr), Uint128::new(250));
+}
+
+#[test]
+fn query_max_withdraw_amount() {
+    let mut deps = setup_max_amounts_test();
+    let env = mock_env();
+    let user_addr = Addr::unchecked("user");
+
+    // 2_000 of debt requires 3_334 uosmo to be kept, at a liquidation threshold of 60%
+    let max_withdraw = query_max_withdraw(
+        deps.as_ref(),
+        env.clone(),
+        user_addr.clone(),
+        MAIN_ACCOUNT_ID,
+        "uosmo".to_string(),
+    )
+    .unwrap();
+    assert_eq!(max_withdraw, Uint128::new(6_666));
+
+    // withdrawing one more than the max fails, withdrawing the max succeeds
+    let info = mock_info(user_addr.as_str(), &[]);
+    let msg = ExecuteMsg::Withdraw {
+        denom: "uosmo".to_string(),
+        amount: Some(max_withdraw + Uint128::new(1)),
+        recipient: None,
+        on_behalf_of: None,
+        account_id: None,
+    };
+    let error_res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+    assert_eq!(error_res, ContractError::InvalidHealthFactorAfterWithdraw {});
+
+    let msg = ExecuteMsg::Withdraw {
+        denom: "uosmo".to_string(),
+        amount: Some(max_withdraw),
+        recipient: None,
+        on_behalf_of: None,
+        account_id: None,
+    };
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let max_withdraw = query_max_withdraw(
+        deps.as_ref(),
+        env.clone(),
+        user_addr.clone(),
+        MAIN_ACCOUNT_ID,
+        "uosmo".to_string(),
+    )
+    .unwrap();
+    assert_eq!(max_withdraw, Uint128::zero());
+
+    // without debt the whole collateral can be withdrawn
+    DEBTS.remove(deps.as_mut().storage, (&user_addr, MAIN_ACCOUNT_ID, "uatom"));
+    let max_withdraw =
+        query_max_withdraw(deps.as_ref(), env, user_addr, MAIN_ACCOUNT_ID, "uosmo".to_string())
+            .unwrap();
+    assert_eq!(max_withdraw, Uint128::new(3_334));
+}
+
+#[test]
+fn query_market_with_interest_accrued() {
+    let mut deps = th_setup(&[coin(5_000, "uosmo")]);
+    let env = mock_env();
+
+    // indexes last updated a year ago
+    let market = th_init_market(
+        deps.as_mut(),
+        "uosmo",
+        &Market {
+            borrow_rate: Decimal::percent(10),
+            liquidity_rate: Decimal::percent(5),
+            indexes_last_updated: env.block.time.seconds() - 31_536_000,
+            collateral_total_scaled: Uint128::new(10_000) * SCALING_FACTOR,
+            debt_total_scaled: Uint128::new(5_000) * SCALING_FACTOR,
+            ..Default::default()
+        },
+    );
+
+    let res = query_market_live(deps.as_ref(), env.clone(), "uosmo".to_string()).unwrap();
+    assert_eq!(
+        res,
+        MarketLiveResponse {
+            market: Market {
+                borrow_index: Decimal::percent(110),
+                liquidity_index: Decimal::percent(105),
+                indexes_last_updated: env.block.time.seconds(),
+                ..market
+            },
+            total_collateral: Uint128::new(10_500),
+            total_debt: Uint128::new(5_500),
+            available_liquidity: Uint128::new(5_000),
+            utilization_rate: Decimal::from_ratio(5_500u128, 10_500u128),
+        }
+    );
+}
+
+#[test]
+fn query_market_with_adaptive_model_drifted() {
+    let mut deps = th_setup(&[coin(1_000, "uosmo")]);
+    let env = mock_env();
+
+    // utilization of 90%, above the target of 80%, since half a year
+    th_init_market(
+        deps.as_mut(),
+        "uosmo",
+        &Market {
+            interest_rate_model: InterestRateModel::Adaptive {
+                target_utilization_rate: Decimal::percent(80),
+                kink_rate: Decimal::percent(10),
+                min_kink_rate: Decimal::percent(1),
+                max_kink_rate: Decimal::one(),
+                proportional_gain: Decimal::percent(50),
+                integral_gain: Decimal::one(),
+                utilization_rate: Decimal::percent(90),
+            },
+            borrow_rate: Decimal::percent(15),
+            liquidity_rate: Decimal::percent(13),
+            indexes_last_updated: env.block.time.seconds() - 15_768_000,
+            collateral_total_scaled: Uint128::new(10_000) * SCALING_FACTOR,
+            debt_total_scaled: Uint128::new(9_000) * SCALING_FACTOR,
+            ..Default::default()
+        },
+    );
+
+    // the rates are set on the curve through the drifted kink rate of 15%, at the live utilization
+    let res = query_market_live(deps.as_ref(), env, "uosmo".to_string()).unwrap();
+    let utilization_rate = res.utilization_rate;
+    let expected_borrow_rate =
+        Decimal::percent(15) + Decimal::percent(50) * (utilization_rate - Decimal::percent(80));
+    assert_eq!(res.market.borrow_rate, expected_borrow_rate);
+    assert_eq!(res.market.liquidity_rate, expected_borrow_rate * utilization_rate);
+    match res.market.interest_rate_model {
+        InterestRateModel::Adaptive {
+            kink_rate,
+            ..
+        } => assert_eq!(kink_rate, Decimal::percent(15)),
+        _ => panic!("expected an adaptive model"),
+    }
+}
@@ -0,0 +1,217 @@
+use cosmwasm_std::{
+    coin, coins,
+    testing::{mock_info, MockApi, MockStorage},
+    to_binary, Addr, Decimal, OwnedDeps, SubMsg, Uint128, WasmMsg,
+};
+use helpers::{th_collateral_market, th_query, th_setup_markets};
+use mars_red_bank::{
+    contract::execute,
+    error::ContractError,
+    interest_rates::SCALING_FACTOR,
+    state::{COLLATERALS, DEBTS},
+    user::MAIN_ACCOUNT_ID,
+};
+use mars_red_bank_types::{
+    address_provider::MarsAddressType,
+    incentives,
+    red_bank::{
+        Collateral, Debt, ExecuteMsg, LiquidationBonus, Market, QueryMsg, UserDebtResponse,
+        UserHealthStatus, UserPositionResponse,
+    },
+};
+use mars_testing::{mock_env, MarsMockQuerier, MockEnvParams};
+
+mod helpers;
+
+fn setup_test() -> OwnedDeps<MockStorage, MockApi, MarsMockQuerier> {
+    th_setup_markets(
+        &[coin(10_000, "uosmo"), coin(10_000, "uatom")],
+        &[
+            (
+                "uosmo",
+                Market {
+                    liquidation_bonus: LiquidationBonus::fixed(Decimal::percent(10)),
+                    ..th_collateral_market(10_000)
+                },
+            ),
+            (
+                "uatom",
+                Market {
+                    debt_total_scaled: Uint128::new(1_000) * SCALING_FACTOR,
+                    ..Default::default()
+                },
+            ),
+        ],
+    )
+}
+
+fn set_position(
+    deps: &mut OwnedDeps<MockStorage, MockApi, MarsMockQuerier>,
+    user_addr: &Addr,
+    account_id: u32,
+    collateral_amount: u128,
+    debt_amount: u128,
+) {
+    let collateral = Collateral {
+        amount_scaled: Uint128::new(collateral_amount) * SCALING_FACTOR,
+        enabled: true,
+    };
+    COLLATERALS.save(&mut deps.storage, (user_addr, account_id, "uosmo"), &collateral).unwrap();
+
+    if debt_amount > 0 {
+        let debt = Debt {
+            amount_scaled: Uint128::new(debt_amount) * SCALING_FACTOR,
+            uncollateralized: false,
+        };
+        DEBTS.save(&mut deps.storage, (user_addr, account_id, "uatom"), &debt).unwrap();
+    }
+}
+
+#[test]
+fn positions_are_isolated_per_account() {
+    let mut deps = setup_test();
+    let env = mock_env(MockEnvParams::default());
+    let user_addr = Addr::unchecked("user");
+
+    // deposit into a sub-account
+    let msg = ExecuteMsg::Deposit {
+        on_behalf_of: None,
+        account_id: Some(1),
+    };
+    let info = mock_info(user_addr.as_str(), &coins(10_000, "uosmo"));
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let collateral = COLLATERALS.load(&deps.storage, (&user_addr, 1, "uosmo")).unwrap();
+    assert_eq!(collateral.amount_scaled, Uint128::new(10_000) * SCALING_FACTOR);
+    assert!(COLLATERALS
+        .may_load(&deps.storage, (&user_addr, MAIN_ACCOUNT_ID, "uosmo"))
+        .unwrap()
+        .is_none());
+
+    // the collateral doesn't back borrows from other accounts
+    let borrow_msg = |account_id| ExecuteMsg::Borrow {
+        denom: "uatom".to_string(),
+        amount: Uint128::new(1_000),
+        recipient: None,
+        on_behalf_of: None,
+        account_id,
+    };
+    let info = mock_info(user_addr.as_str(), &[]);
+    let error_res =
+        execute(deps.as_mut(), env.clone(), info.clone(), borrow_msg(None)).unwrap_err();
+    assert_eq!(error_res, ContractError::BorrowAmountExceedsGivenCollateral {});
+    let error_res =
+        execute(deps.as_mut(), env.clone(), info.clone(), borrow_msg(Some(2))).unwrap_err();
+    assert_eq!(error_res, ContractError::BorrowAmountExceedsGivenCollateral {});
+
+    execute(deps.as_mut(), env, info, borrow_msg(Some(1))).unwrap();
+
+    let debt: UserDebtResponse = th_query(
+        deps.as_ref(),
+        QueryMsg::UserDebt {
+            user: user_addr.to_string(),
+            account_id: Some(1),
+            denom: "uatom".to_string(),
+        },
+    );
+    assert_eq!(debt.amount, Uint128::new(1_000));
+    let debt: UserDebtResponse = th_query(
+        deps.as_ref(),
+        QueryMsg::UserDebt {
+            user: user_addr.to_string(),
+            account_id: None,
+            denom: "uatom".to_string(),
+        },
+    );
+    assert_eq!(debt.amount, Uint128::zero());
+
+    let position: UserPositionResponse = th_query(
+        deps.as_ref(),
+        QueryMsg::UserPosition {
+            user: user_addr.to_string(),
+            account_id: None,
+        },
+    );
+    assert_eq!(position.health_status, UserHealthStatus::NotBorrowing);
+}
+
+#[test]
+fn accounts_are_liquidated_separately() {
+    let mut deps = setup_test();
+    let env = mock_env(MockEnvParams::default());
+    let user_addr = Addr::unchecked("user");
+
+    // main account: liquidation health factor = 1000 * 0.6 / 700 < 1
+    set_position(&mut deps, &user_addr, MAIN_ACCOUNT_ID, 1_000, 700);
+    // sub-account: liquidation health factor = 1000 * 0.6 / 100 > 1
+    set_position(&mut deps, &user_addr, 1, 1_000, 100);
+
+    let liquidate_msg = |account_id| ExecuteMsg::Liquidate {
+        user: user_addr.to_string(),
+        account_id,
+        collateral_denom: "uosmo".to_string(),
+        recipient: None,
+    };
+    let info = mock_info("liquidator", &coins(100, "uatom"));
+
+    let error_res =
+        execute(deps.as_mut(), env.clone(), info.clone(), liquidate_msg(Some(1))).unwrap_err();
+    assert_eq!(error_res, ContractError::CannotLiquidateHealthyPosition {});
+
+    execute(deps.as_mut(), env, info, liquidate_msg(None)).unwrap();
+
+    // only the main account is liquidated: 100 debt repaid, 110 collateral seized
+    let debt = DEBTS.load(&deps.storage, (&user_addr, MAIN_ACCOUNT_ID, "uatom")).unwrap();
+    assert_eq!(debt.amount_scaled, Uint128::new(600) * SCALING_FACTOR);
+    let debt = DEBTS.load(&deps.storage, (&user_addr, 1, "uatom")).unwrap();
+    assert_eq!(debt.amount_scaled, Uint128::new(100) * SCALING_FACTOR);
+    let collateral = COLLATERALS.load(&deps.storage, (&user_addr, 1, "uosmo")).unwrap();
+    assert_eq!(collateral.amount_scaled, Uint128::new(1_000) * SCALING_FACTOR);
+
+    // the liquidator receives the collateral in their main account
+    let collateral = COLLATERALS
+        .load(&deps.storage, (&Addr::unchecked("liquidator"), MAIN_ACCOUNT_ID, "uosmo"))
+        .unwrap();
+    assert_eq!(collateral.amount_scaled, Uint128::new(110) * SCALING_FACTOR);
+}
+
+#[test]
+fn incentives_track_collateral_of_all_accounts() {
+    let mut deps = setup_test();
+    let env = mock_env(MockEnvParams::default());
+    let user_addr = Addr::unchecked("user");
+
+    set_position(&mut deps, &user_addr, MAIN_ACCOUNT_ID, 2_000, 0);
+
+    let msg = ExecuteMsg::Deposit {
+        on_behalf_of: None,
+        account_id: Some(1),
+    };
+    let info = mock_info(user_addr.as_str(), &coins(1_000, "uosmo"));
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+
+    // the balance before is the user's collateral across all accounts
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(WasmMsg::Execute {
+            contract_addr: MarsAddressType::Incentives.to_string(),
+            msg: to_binary(&incentives::ExecuteMsg::BalanceChange {
+                user_addr: user_addr.clone(),
+                denom: "uosmo".to_string(),
+                user_amount_scaled_before: Uint128::new(2_000) * SCALING_FACTOR,
+                total_amount_scaled_before: Uint128::new(10_000) * SCALING_FACTOR,
+            })
+            .unwrap(),
+            funds: vec![],
+        })]
+    );
+
+    let total_collateral_scaled: Uint128 = th_query(
+        deps.as_ref(),
+        QueryMsg::UserTotalCollateralScaled {
+            user: user_addr.to_string(),
+            denom: "uosmo".to_string(),
+        },
+    );
+    assert_eq!(total_collateral_scaled, Uint128::new(3_000) * SCALING_FACTOR);
+}
@@ -0,0 +1,386 @@
+use cosmwasm_std::{
+    testing::{mock_info, MockApi, MockStorage},
+    Decimal, OwnedDeps, Response,
+};
+use helpers::{
+    th_collateral_market, th_execute, th_init_market, th_query, th_query_market,
+    th_query_pending_changes, th_setup_markets,
+};
+use mars_owner::OwnerError;
+use mars_red_bank::{
+    contract::execute, error::ContractError, execute::PENDING_CHANGE_GRACE_PERIOD,
+    state::EMODE_CATEGORIES,
+};
+use mars_red_bank_types::red_bank::{
+    ConfigResponse, CreateOrUpdateConfig, EModeCategory, ExecuteMsg, InitOrUpdateAssetParams,
+    Market, ParamsChange, PendingChange, QueryMsg,
+};
+use mars_testing::{mock_env, mock_env_at_block_time, MarsMockQuerier, MockEnvParams};
+use mars_utils::error::ValidationError;
+
+mod helpers;
+
+const TIMELOCK: u64 = 86400;
+
+fn setup_test() -> OwnedDeps<MockStorage, MockApi, MarsMockQuerier> {
+    let mut deps = th_setup_markets(&[], &[("uosmo", th_collateral_market(0))]);
+
+    // no timelock is set yet, so the update is applied immediately
+    let msg = ExecuteMsg::UpdateConfig {
+        config: CreateOrUpdateConfig {
+            address_provider: None,
+            close_factor: None,
+            timelock: Some(TIMELOCK),
+        },
+    };
+    th_execute(&mut deps, "owner", msg).unwrap();
+
+    deps
+}
+
+fn ltv_params(max_loan_to_value: Decimal) -> InitOrUpdateAssetParams {
+    InitOrUpdateAssetParams {
+        max_loan_to_value: Some(max_loan_to_value),
+        reserve_factor: None,
+        liquidation_threshold: None,
+        liquidation_bonus: None,
+        interest_rate_model: None,
+        deposit_enabled: None,
+        borrow_enabled: None,
+        deposit_cap: None,
+        borrow_cap: None,
+        min_debt: None,
+        isolated: None,
+        debt_ceiling: None,
+        borrowable_in_isolation: None,
+        flash_loan_fee: None,
+        interest_accrual: None,
+        protocol_liquidation_fee: None,
+        ramp_duration: None,
+    }
+}
+
+fn update_ltv(
+    deps: &mut OwnedDeps<MockStorage, MockApi, MarsMockQuerier>,
+    max_loan_to_value: Decimal,
+) -> Result<Response, ContractError> {
+    let msg = ExecuteMsg::UpdateAsset {
+        denom: "uosmo".to_string(),
+        params: ltv_params(max_loan_to_value),
+    };
+    th_execute(deps, "owner", msg)
+}
+
+fn execute_pending_change(
+    deps: &mut OwnedDeps<MockStorage, MockApi, MarsMockQuerier>,
+    sender: &str,
+    block_time: u64,
+    id: u64,
+) -> Result<Response, ContractError> {
+    execute(
+        deps.as_mut(),
+        mock_env_at_block_time(block_time),
+        mock_info(sender, &[]),
+        ExecuteMsg::ExecutePendingChange {
+            id,
+        },
+    )
+}
+
+#[test]
+fn raising_risk_waits_for_timelock() {
+    let mut deps = setup_test();
+    let queued_at = mock_env(MockEnvParams::default()).block.time.seconds();
+
+    update_ltv(&mut deps, Decimal::percent(55)).unwrap();
+
+    // the change is queued, the market is left unchanged
+    assert_eq!(th_query_market(deps.as_ref(), "uosmo").max_loan_to_value, Decimal::percent(50));
+    let pending_change: PendingChange = th_query(
+        deps.as_ref(),
+        QueryMsg::PendingChange {
+            id: 0,
+        },
+    );
+    assert_eq!(
+        pending_change,
+        PendingChange {
+            id: 0,
+            change: ParamsChange::UpdateAsset {
+                denom: "uosmo".to_string(),
+                params: ltv_params(Decimal::percent(55)),
+            },
+            executable_at: queued_at + TIMELOCK,
+            expires_at: queued_at + TIMELOCK + PENDING_CHANGE_GRACE_PERIOD,
+        }
+    );
+
+    // nobody can apply it before the timelock has passed, not even the owner
+    for sender in ["alice", "owner"] {
+        let err =
+            execute_pending_change(&mut deps, sender, queued_at + TIMELOCK - 1, 0).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::PendingChangeNotReady {
+                id: 0,
+                executable_at: queued_at + TIMELOCK,
+            }
+        );
+    }
+
+    // anyone can apply it afterwards
+    execute_pending_change(&mut deps, "alice", queued_at + TIMELOCK, 0).unwrap();
+    assert_eq!(th_query_market(deps.as_ref(), "uosmo").max_loan_to_value, Decimal::percent(55));
+    assert!(th_query_pending_changes(deps.as_ref()).is_empty());
+
+    let err = execute_pending_change(&mut deps, "alice", queued_at + TIMELOCK, 0).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::PendingChangeNotFound {
+            id: 0,
+        }
+    );
+}
+
+#[test]
+fn lowering_risk_can_be_applied_immediately() {
+    let mut deps = setup_test();
+    let queued_at = mock_env(MockEnvParams::default()).block.time.seconds();
+
+    update_ltv(&mut deps, Decimal::percent(40)).unwrap();
+    assert_eq!(th_query_market(deps.as_ref(), "uosmo").max_loan_to_value, Decimal::percent(50));
+
+    // users can't fast-track it
+    let err = execute_pending_change(&mut deps, "alice", queued_at, 0).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::PendingChangeNotReady {
+            id: 0,
+            executable_at: queued_at + TIMELOCK,
+        }
+    );
+
+    execute_pending_change(&mut deps, "emergency_owner", queued_at, 0).unwrap();
+    assert_eq!(th_query_market(deps.as_ref(), "uosmo").max_loan_to_value, Decimal::percent(40));
+}
+
+#[test]
+fn lowering_liquidation_threshold_waits_for_timelock() {
+    let mut deps = setup_test();
+    let queued_at = mock_env(MockEnvParams::default()).block.time.seconds();
+
+    let msg = ExecuteMsg::UpdateAsset {
+        denom: "uosmo".to_string(),
+        params: InitOrUpdateAssetParams {
+            liquidation_threshold: Some(Decimal::percent(55)),
+            ..ltv_params(Decimal::percent(50))
+        },
+    };
+    th_execute(&mut deps, "owner", msg).unwrap();
+
+    // it can make positions liquidatable, so it isn't fast-tracked
+    let err = execute_pending_change(&mut deps, "owner", queued_at, 0).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::PendingChangeNotReady {
+            id: 0,
+            executable_at: queued_at + TIMELOCK,
+        }
+    );
+
+    execute_pending_change(&mut deps, "alice", queued_at + TIMELOCK, 0).unwrap();
+    let market: Market = th_query(
+        deps.as_ref(),
+        QueryMsg::Market {
+            denom: "uosmo".to_string(),
+        },
+    );
+    assert_eq!(market.liquidation_threshold, Decimal::percent(55));
+}
+
+#[test]
+fn emode_category_changes_are_queued() {
+    let mut deps = setup_test();
+    let queued_at = mock_env(MockEnvParams::default()).block.time.seconds();
+    th_init_market(deps.as_mut(), "uatom", &Market::default());
+
+    let category = EModeCategory {
+        id: 1,
+        denoms: vec!["uatom".to_string(), "uosmo".to_string()],
+        max_loan_to_value: Decimal::percent(80),
+        liquidation_threshold: Decimal::percent(85),
+        liquidation_bonus: Decimal::percent(2),
+    };
+    let set_category = |deps: &mut OwnedDeps<MockStorage, MockApi, MarsMockQuerier>,
+                        category: EModeCategory| {
+        let msg = ExecuteMsg::SetEModeCategory {
+            category,
+        };
+        th_execute(deps, "owner", msg).unwrap();
+    };
+
+    // a new category raises the params of its assets, so it waits for the timelock
+    set_category(&mut deps, category.clone());
+    assert!(!EMODE_CATEGORIES.has(&deps.storage, 1));
+    let err = execute_pending_change(&mut deps, "owner", queued_at, 0).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::PendingChangeNotReady {
+            id: 0,
+            executable_at: queued_at + TIMELOCK,
+        }
+    );
+    execute_pending_change(&mut deps, "alice", queued_at + TIMELOCK, 0).unwrap();
+    assert_eq!(EMODE_CATEGORIES.load(&deps.storage, 1).unwrap(), category);
+
+    // a lower LTV can be fast-tracked
+    let lower_ltv = EModeCategory {
+        max_loan_to_value: Decimal::percent(70),
+        ..category.clone()
+    };
+    set_category(&mut deps, lower_ltv.clone());
+    execute_pending_change(&mut deps, "owner", queued_at, 1).unwrap();
+    assert_eq!(EMODE_CATEGORIES.load(&deps.storage, 1).unwrap(), lower_ltv);
+
+    // a lower liquidation threshold or fewer assets can't
+    let lower_threshold = EModeCategory {
+        liquidation_threshold: Decimal::percent(80),
+        ..lower_ltv.clone()
+    };
+    let fewer_assets = EModeCategory {
+        denoms: vec!["uosmo".to_string()],
+        ..lower_ltv.clone()
+    };
+    for (id, category) in [(2, lower_threshold), (3, fewer_assets)] {
+        set_category(&mut deps, category);
+        let err = execute_pending_change(&mut deps, "owner", queued_at, id).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::PendingChangeNotReady {
+                id,
+                executable_at: queued_at + TIMELOCK,
+            }
+        );
+    }
+    assert_eq!(EMODE_CATEGORIES.load(&deps.storage, 1).unwrap(), lower_ltv);
+}
+
+#[test]
+fn expired_changes_cannot_be_applied() {
+    let mut deps = setup_test();
+    let queued_at = mock_env(MockEnvParams::default()).block.time.seconds();
+    let expires_at = queued_at + TIMELOCK + PENDING_CHANGE_GRACE_PERIOD;
+
+    update_ltv(&mut deps, Decimal::percent(55)).unwrap();
+
+    for sender in ["alice", "owner"] {
+        let err = execute_pending_change(&mut deps, sender, expires_at, 0).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::PendingChangeExpired {
+                id: 0,
+                expires_at,
+            }
+        );
+    }
+    assert_eq!(th_query_market(deps.as_ref(), "uosmo").max_loan_to_value, Decimal::percent(50));
+
+    // it can still be applied until then
+    execute_pending_change(&mut deps, "alice", expires_at - 1, 0).unwrap();
+    assert_eq!(th_query_market(deps.as_ref(), "uosmo").max_loan_to_value, Decimal::percent(55));
+}
+
+#[test]
+fn invalid_changes_are_not_queued() {
+    let mut deps = setup_test();
+
+    // LTV above the liquidation threshold
+    let err = update_ltv(&mut deps, Decimal::percent(70)).unwrap_err();
+    assert!(matches!(err, ContractError::Validation(ValidationError::InvalidParam { .. })));
+
+    let msg = ExecuteMsg::UpdateAsset {
+        denom: "uatom".to_string(),
+        params: ltv_params(Decimal::percent(40)),
+    };
+    let err = th_execute(&mut deps, "owner", msg).unwrap_err();
+    assert_eq!(err, ContractError::AssetNotInitialized {});
+
+    assert!(th_query_pending_changes(deps.as_ref()).is_empty());
+}
+
+#[test]
+fn config_changes_are_queued() {
+    let mut deps = setup_test();
+    let queued_at = mock_env(MockEnvParams::default()).block.time.seconds();
+
+    let msg = ExecuteMsg::UpdateConfig {
+        config: CreateOrUpdateConfig {
+            address_provider: None,
+            close_factor: None,
+            timelock: Some(0),
+        },
+    };
+    th_execute(&mut deps, "owner", msg).unwrap();
+
+    let config: ConfigResponse = th_query(deps.as_ref(), QueryMsg::Config {});
+    assert_eq!(config.timelock, TIMELOCK);
+
+    // config changes are never fast-tracked
+    let err = execute_pending_change(&mut deps, "owner", queued_at, 0).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::PendingChangeNotReady {
+            id: 0,
+            executable_at: queued_at + TIMELOCK,
+        }
+    );
+
+    execute_pending_change(&mut deps, "alice", queued_at + TIMELOCK, 0).unwrap();
+    let config: ConfigResponse = th_query(deps.as_ref(), QueryMsg::Config {});
+    assert_eq!(config.timelock, 0);
+
+    // without a timelock, changes are applied immediately
+    update_ltv(&mut deps, Decimal::percent(55)).unwrap();
+    assert_eq!(th_query_market(deps.as_ref(), "uosmo").max_loan_to_value, Decimal::percent(55));
+}
+
+#[test]
+fn cancelling_pending_changes() {
+    let mut deps = setup_test();
+
+    update_ltv(&mut deps, Decimal::percent(55)).unwrap();
+    update_ltv(&mut deps, Decimal::percent(45)).unwrap();
+
+    let ids: Vec<_> =
+        th_query_pending_changes(deps.as_ref()).into_iter().map(|change| change.id).collect();
+    assert_eq!(ids, vec![0, 1]);
+
+    let msg = ExecuteMsg::CancelPendingChange {
+        id: 0,
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(MockEnvParams::default()),
+        mock_info("alice", &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Owner(OwnerError::NotOwner {}));
+
+    th_execute(&mut deps, "emergency_owner", msg).unwrap();
+
+    let ids: Vec<_> =
+        th_query_pending_changes(deps.as_ref()).into_iter().map(|change| change.id).collect();
+    assert_eq!(ids, vec![1]);
+
+    let msg = ExecuteMsg::CancelPendingChange {
+        id: 0,
+    };
+    let err = th_execute(&mut deps, "owner", msg).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::PendingChangeNotFound {
+            id: 0,
+        }
+    );
+}
@@ -15,6 +15,7 @@ use mars_red_bank::{
         get_updated_borrow_index, get_updated_liquidity_index, ScalingOperation, SCALING_FACTOR,
     },
     state::{COLLATERALS, DEBTS, MARKETS},
+    user::MAIN_ACCOUNT_ID,
 };
 use mars_red_bank_types::{
     address_provider::MarsAddressType,
@@ -84,6 +85,8 @@ fn withdrawing_more_than_balance() {
             denom: denom.to_string(),
             amount: Some(Uint128::from(2000u128)),
             recipient: None,
+            on_behalf_of: None,
+            account_id: None,
         },
     )
     .unwrap_err();
@@ -128,6 +131,8 @@ fn withdrawing_partially() {
             denom: denom.to_string(),
             amount: Some(withdraw_amount),
             recipient: None,
+            on_behalf_of: None,
+            account_id: None,
         },
     )
     .unwrap();
@@ -212,6 +217,8 @@ fn withdrawing_partially() {
         vec![
             attr("action", "withdraw"),
             attr("sender", &withdrawer_addr),
+            attr("on_behalf_of", &withdrawer_addr),
+            attr("account_id", "0"),
             attr("recipient", &withdrawer_addr),
             attr("denom", denom),
             attr("amount", withdraw_amount),
@@ -230,12 +237,15 @@ fn withdrawing_partially() {
     assert_eq!(market.collateral_total_scaled, expected_total_collateral_amount_scaled);
 
     // the user's collateral scaled amount should have been decreased
-    let collateral = COLLATERALS.load(deps.as_ref().storage, (&withdrawer_addr, denom)).unwrap();
+    let collateral = COLLATERALS
+        .load(deps.as_ref().storage, (&withdrawer_addr, MAIN_ACCOUNT_ID, denom))
+        .unwrap();
     assert_eq!(collateral.amount_scaled, expected_withdraw_amount_scaled_remaining);
 
     // the reward collector's collateral scaled amount should have been increased
     let rewards_addr = Addr::unchecked(MarsAddressType::RewardsCollector.to_string());
-    let collateral = COLLATERALS.load(deps.as_ref().storage, (&rewards_addr, denom)).unwrap();
+    let collateral =
+        COLLATERALS.load(deps.as_ref().storage, (&rewards_addr, MAIN_ACCOUNT_ID, denom)).unwrap();
     assert_eq!(collateral.amount_scaled, expected_rewards_amount_scaled);
 }
 
@@ -263,6 +273,8 @@ fn withdrawing_completely() {
             denom: denom.to_string(),
             amount: None,
             recipient: None,
+            on_behalf_of: None,
+            account_id: None,
         },
     )
     .unwrap();
@@ -330,6 +342,8 @@ fn withdrawing_completely() {
         vec![
             attr("action", "withdraw"),
             attr("sender", &withdrawer_addr),
+            attr("on_behalf_of", &withdrawer_addr),
+            attr("account_id", "0"),
             attr("recipient", &withdrawer_addr),
             attr("denom", denom),
             attr("amount", withdrawer_balance.to_string()),
@@ -372,6 +386,8 @@ fn withdrawing_to_another_user() {
             denom: denom.to_string(),
             amount: None,
             recipient: Some(recipient_addr.to_string()),
+            on_behalf_of: None,
+            account_id: None,
         },
     )
     .unwrap();
@@ -440,6 +456,8 @@ fn withdrawing_to_another_user() {
         vec![
             attr("action", "withdraw"),
             attr("sender", &withdrawer_addr),
+            attr("on_behalf_of", &withdrawer_addr),
+            attr("account_id", "0"),
             attr("recipient", &recipient_addr),
             attr("denom", denom.to_string()),
             attr("amount", withdraw_amount.to_string()),
@@ -548,13 +566,17 @@ fn setup_health_check_test() -> HealthCheckTestSuite {
 
     denoms.iter().zip(collaterals.iter()).for_each(|(denom, collateral)| {
         if !collateral.amount_scaled.is_zero() {
-            COLLATERALS.save(deps.as_mut().storage, (&withdrawer_addr, denom), collateral).unwrap();
+            COLLATERALS
+                .save(deps.as_mut().storage, (&withdrawer_addr, MAIN_ACCOUNT_ID, denom), collateral)
+                .unwrap();
         }
     });
 
     denoms.iter().zip(debts.iter()).for_each(|(denom, debt)| {
         if !debt.amount_scaled.is_zero() {
-            DEBTS.save(deps.as_mut().storage, (&withdrawer_addr, denom), debt).unwrap();
+            DEBTS
+                .save(deps.as_mut().storage, (&withdrawer_addr, MAIN_ACCOUNT_ID, denom), debt)
+                .unwrap();
         }
     });
 
@@ -646,6 +668,8 @@ fn withdrawing_if_health_factor_not_met() {
             denom: denoms[2].to_string(),
             amount: Some(withdraw_amount),
             recipient: None,
+            on_behalf_of: None,
+            account_id: None,
         },
     )
     .unwrap_err();
@@ -682,6 +706,8 @@ fn withdrawing_if_health_factor_met() {
             denom: denoms[2].to_string(),
             amount: Some(withdraw_amount),
             recipient: None,
+            on_behalf_of: None,
+            account_id: None,
         },
     )
     .unwrap();
@@ -718,7 +744,9 @@ fn withdrawing_if_health_factor_met() {
     let expected_collateral_total_amount_scaled_after =
         markets[2].collateral_total_scaled - expected_withdraw_amount_scaled;
 
-    let col = COLLATERALS.load(deps.as_ref().storage, (&withdrawer_addr, denoms[2])).unwrap();
+    let col = COLLATERALS
+        .load(deps.as_ref().storage, (&withdrawer_addr, MAIN_ACCOUNT_ID, denoms[2]))
+        .unwrap();
     assert_eq!(col.amount_scaled, expected_withdrawer_balance_after);
 
     let market = MARKETS.load(deps.as_ref().storage, denoms[2]).unwrap();
@@ -220,6 +220,8 @@ where
                 denom: denom.clone(),
                 amount,
                 recipient: None,
+                on_behalf_of: None,
+                account_id: None,
             })?,
             funds: vec![],
         });
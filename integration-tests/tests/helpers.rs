@@ -5,7 +5,8 @@ use cosmwasm_std::{Coin, Decimal};
 use cw_multi_test::AppResponse;
 use mars_red_bank::error::ContractError;
 use mars_red_bank_types::red_bank::{
-    InitOrUpdateAssetParams, InterestRateModel, UserHealthStatus, UserPositionResponse,
+    InitOrUpdateAssetParams, InterestRateModel, LiquidationBonus, UserHealthStatus,
+    UserPositionResponse,
 };
 use osmosis_std::types::osmosis::gamm::v1beta1::{
     MsgSwapExactAmountIn, MsgSwapExactAmountInResponse, SwapAmountInRoute,
@@ -17,8 +18,8 @@ pub fn default_asset_params() -> InitOrUpdateAssetParams {
         reserve_factor: Some(Decimal::percent(20)),
         max_loan_to_value: Some(Decimal::percent(60)),
         liquidation_threshold: Some(Decimal::percent(80)),
-        liquidation_bonus: Some(Decimal::percent(10)),
-        interest_rate_model: Some(InterestRateModel {
+        liquidation_bonus: Some(LiquidationBonus::fixed(Decimal::percent(10))),
+        interest_rate_model: Some(InterestRateModel::Linear {
             optimal_utilization_rate: Decimal::percent(10),
             base: Decimal::percent(30),
             slope_1: Decimal::percent(25),
@@ -27,6 +28,15 @@ pub fn default_asset_params() -> InitOrUpdateAssetParams {
         deposit_enabled: Some(true),
         borrow_enabled: Some(true),
         deposit_cap: None,
+        borrow_cap: None,
+        min_debt: None,
+        isolated: None,
+        debt_ceiling: None,
+        borrowable_in_isolation: None,
+        flash_loan_fee: None,
+        interest_accrual: None,
+        protocol_liquidation_fee: None,
+        ramp_duration: None,
     }
 }
 
@@ -39,8 +49,8 @@ pub fn default_asset_params_with(
         reserve_factor: Some(Decimal::percent(20)),
         max_loan_to_value: Some(max_loan_to_value),
         liquidation_threshold: Some(liquidation_threshold),
-        liquidation_bonus: Some(liquidation_bonus),
-        interest_rate_model: Some(InterestRateModel {
+        liquidation_bonus: Some(LiquidationBonus::fixed(liquidation_bonus)),
+        interest_rate_model: Some(InterestRateModel::Linear {
             optimal_utilization_rate: Decimal::percent(10),
             base: Decimal::percent(30),
             slope_1: Decimal::percent(25),
@@ -49,6 +59,15 @@ pub fn default_asset_params_with(
         deposit_enabled: Some(true),
         borrow_enabled: Some(true),
         deposit_cap: None,
+        borrow_cap: None,
+        min_debt: None,
+        isolated: None,
+        debt_ceiling: None,
+        borrowable_in_isolation: None,
+        flash_loan_fee: None,
+        interest_accrual: None,
+        protocol_liquidation_fee: None,
+        ramp_duration: None,
     }
 }
 
@@ -1036,6 +1036,7 @@ fn setup_redbank(wasm: &Wasm<OsmosisTestApp>, signer: &SigningAccount) -> (Strin
             config: CreateOrUpdateConfig {
                 address_provider: Some(addr_provider_addr.clone()),
                 close_factor: Some(Decimal::percent(10)),
+                timelock: None,
             },
         },
     );
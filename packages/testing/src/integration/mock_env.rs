@@ -217,6 +217,7 @@ impl RedBank {
             self.contract_addr.clone(),
             &red_bank::ExecuteMsg::Deposit {
                 on_behalf_of: None,
+                account_id: None,
             },
             &[coin],
         )
@@ -236,6 +237,8 @@ impl RedBank {
                 denom: denom.to_string(),
                 amount: amount.into(),
                 recipient: None,
+                on_behalf_of: None,
+                account_id: None,
             },
             &[],
         )
@@ -247,6 +250,7 @@ impl RedBank {
             self.contract_addr.clone(),
             &red_bank::ExecuteMsg::Repay {
                 on_behalf_of: None,
+                account_id: None,
             },
             &[coin],
         )
@@ -266,6 +270,8 @@ impl RedBank {
                 denom: denom.to_string(),
                 amount,
                 recipient: None,
+                on_behalf_of: None,
+                account_id: None,
             },
             &[],
         )
@@ -286,6 +292,7 @@ impl RedBank {
                 user: user.to_string(),
                 collateral_denom: collateral_denom.to_string(),
                 recipient: None,
+                account_id: None,
             },
             &[coin],
         )
@@ -331,6 +338,7 @@ impl RedBank {
                 &red_bank::QueryMsg::UserDebt {
                     user: user.to_string(),
                     denom: denom.to_string(),
+                    account_id: None,
                 },
             )
             .unwrap()
@@ -349,6 +357,7 @@ impl RedBank {
                 &red_bank::QueryMsg::UserCollateral {
                     user: user.to_string(),
                     denom: denom.to_string(),
+                    account_id: None,
                 },
             )
             .unwrap()
@@ -361,6 +370,7 @@ impl RedBank {
                 self.contract_addr.clone(),
                 &red_bank::QueryMsg::UserPosition {
                     user: user.to_string(),
+                    account_id: None,
                 },
             )
             .unwrap()
@@ -625,6 +635,7 @@ impl MockEnvBuilder {
                     config: CreateOrUpdateConfig {
                         address_provider: Some(address_provider_addr.to_string()),
                         close_factor: Some(self.close_factor),
+                        timelock: None,
                     },
                 },
                 &[],
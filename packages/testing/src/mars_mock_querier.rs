@@ -148,6 +148,10 @@ impl MarsMockQuerier {
             .insert((user.into(), collateral.denom.clone()), collateral);
     }
 
+    pub fn set_red_bank_v1_queries_only(&mut self, v1_queries_only: bool) {
+        self.redbank_querier.v1_queries_only = v1_queries_only;
+    }
+
     pub fn set_redbank_user_position(
         &mut self,
         user_address: String,
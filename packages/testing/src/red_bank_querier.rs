@@ -10,6 +10,8 @@ pub struct RedBankQuerier {
     pub markets: HashMap<String, Market>,
     pub users_denoms_collaterals: HashMap<(String, String), UserCollateralResponse>,
     pub users_positions: HashMap<String, UserPositionResponse>,
+    /// Reject the queries red bank 1.0.1 doesn't have
+    pub v1_queries_only: bool,
 }
 
 impl RedBankQuerier {
@@ -24,12 +26,26 @@ impl RedBankQuerier {
             QueryMsg::UserCollateral {
                 user,
                 denom,
+                ..
             } => match self.users_denoms_collaterals.get(&(user.clone(), denom)) {
                 Some(collateral) => to_binary(&collateral).into(),
                 None => Err(format!("[mock]: could not find the collateral for {user}")).into(),
             },
+            QueryMsg::UserTotalCollateralScaled {
+                ..
+            } if self.v1_queries_only => {
+                Err("[mock]: Unsupported red_bank query".to_string()).into()
+            }
+            QueryMsg::UserTotalCollateralScaled {
+                user,
+                denom,
+            } => match self.users_denoms_collaterals.get(&(user.clone(), denom)) {
+                Some(collateral) => to_binary(&collateral.amount_scaled).into(),
+                None => Err(format!("[mock]: could not find the collateral for {user}")).into(),
+            },
             QueryMsg::UserPosition {
                 user,
+                ..
             } => match self.users_positions.get(&user) {
                 Some(market) => to_binary(&market).into(),
                 None => Err(format!("[mock]: could not find the position for {user}")).into(),
@@ -1,50 +1,261 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Decimal, StdError, StdResult};
+use cosmwasm_std::{Decimal, StdError, StdResult, Uint128};
 use mars_utils::{error::ValidationError, helpers::decimal_param_le_one, math};
 
+const SECONDS_PER_YEAR: u64 = 31536000u64;
+
+/// Highest integral gain of the adaptive model, at which the kink rate drifts by 1000% per year
+/// while the utilization rate is fully away from the target
+pub const MAX_INTEGRAL_GAIN: Decimal = Decimal::raw(10_000_000_000_000_000_000);
+
+/// Highest proportional gain of the adaptive model, at which the borrow rate moves by 1000% from the
+/// kink rate when the utilization rate is fully away from the target
+pub const MAX_PROPORTIONAL_GAIN: Decimal = Decimal::raw(10_000_000_000_000_000_000);
+
+/// Model computing a market's borrow rate from its utilization rate.
+///
+/// Models are serialized without a tag, each being told apart by its fields, so that the two-slope
+/// model keeps the layout it had before other models were added.
+#[cw_serde]
+#[serde(untagged)]
+#[derive(Eq)]
+pub enum InterestRateModel {
+    /// Two-slope curve, with a kink at the optimal utilization rate
+    Linear {
+        /// Optimal utilization rate
+        optimal_utilization_rate: Decimal,
+        /// Base rate
+        base: Decimal,
+        /// Slope parameter for interest rate model function when utilization_rate < optimal_utilization_rate
+        slope_1: Decimal,
+        /// Slope parameter for interest rate model function when utilization_rate >= optimal_utilization_rate
+        slope_2: Decimal,
+    },
+    /// Piecewise-linear curve from the base rate at zero utilization through each of the kinks
+    MultiKink {
+        /// Borrow rate at zero utilization
+        base: Decimal,
+        /// Points of the curve, in increasing order of utilization rate. The last kink must be at
+        /// a utilization rate of 1.
+        kinks: Vec<InterestRateKink>,
+    },
+    /// Borrow rate independent of the utilization rate
+    Fixed {
+        borrow_rate: Decimal,
+    },
+    /// Linear curve through the kink rate at the target utilization rate, with the kink rate
+    /// drifting up while utilization stays above the target and down while it stays below
+    Adaptive {
+        /// Utilization rate the model steers the market towards
+        target_utilization_rate: Decimal,
+        /// Borrow rate at the target utilization rate, drifting over time
+        kink_rate: Decimal,
+        /// Lowest rate the kink rate can drift to
+        min_kink_rate: Decimal,
+        /// Highest rate the kink rate can drift to
+        max_kink_rate: Decimal,
+        /// Change of the borrow rate per unit of utilization rate away from the target (at most
+        /// `MAX_PROPORTIONAL_GAIN`)
+        proportional_gain: Decimal,
+        /// Change of the kink rate per year, per unit of utilization rate away from the target (at
+        /// most `MAX_INTEGRAL_GAIN`)
+        integral_gain: Decimal,
+        /// Utilization rate when the borrow rate was last updated, which drives the drift until
+        /// the next update
+        #[serde(default)]
+        utilization_rate: Decimal,
+    },
+}
+
+/// Point of a multi-kink interest rate curve
 #[cw_serde]
-#[derive(Eq, Default)]
-pub struct InterestRateModel {
-    /// Optimal utilization rate
-    pub optimal_utilization_rate: Decimal,
-    /// Base rate
-    pub base: Decimal,
-    /// Slope parameter for interest rate model function when utilization_rate < optimal_utilization_rate
-    pub slope_1: Decimal,
-    /// Slope parameter for interest rate model function when utilization_rate >= optimal_utilization_rate
-    pub slope_2: Decimal,
+#[derive(Eq)]
+pub struct InterestRateKink {
+    pub utilization_rate: Decimal,
+    pub borrow_rate: Decimal,
+}
+
+impl Default for InterestRateModel {
+    fn default() -> Self {
+        InterestRateModel::Linear {
+            optimal_utilization_rate: Decimal::zero(),
+            base: Decimal::zero(),
+            slope_1: Decimal::zero(),
+            slope_2: Decimal::zero(),
+        }
+    }
 }
 
 impl InterestRateModel {
     pub fn validate(&self) -> Result<(), ValidationError> {
-        decimal_param_le_one(self.optimal_utilization_rate, "optimal_utilization_rate")?;
+        match self {
+            InterestRateModel::Linear {
+                optimal_utilization_rate,
+                ..
+            } => {
+                decimal_param_le_one(*optimal_utilization_rate, "optimal_utilization_rate")?;
+            }
+            InterestRateModel::MultiKink {
+                kinks,
+                ..
+            } => {
+                let mut previous_utilization_rate = Decimal::zero();
+                for kink in kinks {
+                    if kink.utilization_rate <= previous_utilization_rate {
+                        return Err(ValidationError::InvalidParam {
+                            param_name: "kinks".to_string(),
+                            invalid_value: kink.utilization_rate.to_string(),
+                            predicate: format!("> {previous_utilization_rate} (previous kink)"),
+                        });
+                    }
+                    decimal_param_le_one(kink.utilization_rate, "kinks")?;
+                    previous_utilization_rate = kink.utilization_rate;
+                }
+
+                // the curve must cover all utilization rates
+                if previous_utilization_rate != Decimal::one() {
+                    return Err(ValidationError::InvalidParam {
+                        param_name: "kinks".to_string(),
+                        invalid_value: previous_utilization_rate.to_string(),
+                        predicate: "1 (last kink)".to_string(),
+                    });
+                }
+            }
+            InterestRateModel::Fixed {
+                ..
+            } => {}
+            InterestRateModel::Adaptive {
+                target_utilization_rate,
+                kink_rate,
+                min_kink_rate,
+                max_kink_rate,
+                proportional_gain,
+                integral_gain,
+                ..
+            } => {
+                decimal_param_le_one(*target_utilization_rate, "target_utilization_rate")?;
+                if *proportional_gain > MAX_PROPORTIONAL_GAIN {
+                    return Err(ValidationError::InvalidParam {
+                        param_name: "proportional_gain".to_string(),
+                        invalid_value: proportional_gain.to_string(),
+                        predicate: format!("<= {MAX_PROPORTIONAL_GAIN}"),
+                    });
+                }
+                if *integral_gain > MAX_INTEGRAL_GAIN {
+                    return Err(ValidationError::InvalidParam {
+                        param_name: "integral_gain".to_string(),
+                        invalid_value: integral_gain.to_string(),
+                        predicate: format!("<= {MAX_INTEGRAL_GAIN}"),
+                    });
+                }
+                if min_kink_rate > max_kink_rate {
+                    return Err(ValidationError::InvalidParam {
+                        param_name: "min_kink_rate".to_string(),
+                        invalid_value: min_kink_rate.to_string(),
+                        predicate: format!("<= {max_kink_rate} (max kink rate)"),
+                    });
+                }
+                if kink_rate < min_kink_rate || kink_rate > max_kink_rate {
+                    return Err(ValidationError::InvalidParam {
+                        param_name: "kink_rate".to_string(),
+                        invalid_value: kink_rate.to_string(),
+                        predicate: format!(">= {min_kink_rate} and <= {max_kink_rate}"),
+                    });
+                }
+            }
+        }
         Ok(())
     }
 
     pub fn get_borrow_rate(&self, current_utilization_rate: Decimal) -> StdResult<Decimal> {
-        let new_borrow_rate = if current_utilization_rate <= self.optimal_utilization_rate {
-            if current_utilization_rate.is_zero() {
-                // prevent division by zero when optimal_utilization_rate is zero
-                self.base
+        match self {
+            InterestRateModel::Linear {
+                optimal_utilization_rate,
+                base,
+                slope_1,
+                slope_2,
+            } => linear_borrow_rate(
+                *optimal_utilization_rate,
+                *base,
+                *slope_1,
+                *slope_2,
+                current_utilization_rate,
+            ),
+            InterestRateModel::MultiKink {
+                base,
+                kinks,
+            } => multi_kink_borrow_rate(*base, kinks, current_utilization_rate),
+            InterestRateModel::Fixed {
+                borrow_rate,
+            } => Ok(*borrow_rate),
+            InterestRateModel::Adaptive {
+                target_utilization_rate,
+                kink_rate,
+                proportional_gain,
+                ..
+            } => {
+                let borrow_rate = if current_utilization_rate >= *target_utilization_rate {
+                    let rate_increase = proportional_gain
+                        .checked_mul(current_utilization_rate - *target_utilization_rate)?;
+                    math::decimal_checked_add(*kink_rate, rate_increase)?
+                } else {
+                    let rate_decrease = proportional_gain
+                        .checked_mul(*target_utilization_rate - current_utilization_rate)?;
+                    if *kink_rate > rate_decrease {
+                        *kink_rate - rate_decrease
+                    } else {
+                        Decimal::zero()
+                    }
+                };
+                Ok(borrow_rate)
+            }
+        }
+    }
+
+    /// Record the utilization rate the borrow rate was computed with, for models whose curve
+    /// drifts depending on it
+    pub fn set_utilization_rate(&mut self, current_utilization_rate: Decimal) {
+        if let InterestRateModel::Adaptive {
+            utilization_rate,
+            ..
+        } = self
+        {
+            *utilization_rate = current_utilization_rate;
+        }
+    }
+
+    /// Drift the kink rate of the adaptive model for the time elapsed since the last update, at a
+    /// speed proportional to the distance between the recorded and target utilization rates
+    pub fn apply_drift(&mut self, time_elapsed: u64) -> StdResult<()> {
+        if let InterestRateModel::Adaptive {
+            target_utilization_rate,
+            kink_rate,
+            min_kink_rate,
+            max_kink_rate,
+            integral_gain,
+            utilization_rate,
+            ..
+        } = self
+        {
+            let year_fraction =
+                Decimal::from_ratio(Uint128::from(time_elapsed), Uint128::from(SECONDS_PER_YEAR));
+            if *utilization_rate >= *target_utilization_rate {
+                let drift = integral_gain
+                    .checked_mul(*utilization_rate - *target_utilization_rate)?
+                    .checked_mul(year_fraction)?;
+                *kink_rate = math::decimal_checked_add(*kink_rate, drift)?.min(*max_kink_rate);
             } else {
-                // The borrow interest rates increase slowly with utilization
-                self.base
-                    + self.slope_1.checked_mul(math::divide_decimal_by_decimal(
-                        current_utilization_rate,
-                        self.optimal_utilization_rate,
-                    )?)?
+                let drift = integral_gain
+                    .checked_mul(*target_utilization_rate - *utilization_rate)?
+                    .checked_mul(year_fraction)?;
+                *kink_rate = if *kink_rate > math::decimal_checked_add(drift, *min_kink_rate)? {
+                    *kink_rate - drift
+                } else {
+                    *min_kink_rate
+                };
             }
-        } else {
-            // The borrow interest rates increase sharply with utilization
-            self.base
-                + self.slope_1
-                + math::divide_decimal_by_decimal(
-                    self.slope_2
-                        .checked_mul(current_utilization_rate - self.optimal_utilization_rate)?,
-                    Decimal::one() - self.optimal_utilization_rate,
-                )?
-        };
-        Ok(new_borrow_rate)
+        }
+        Ok(())
     }
 
     pub fn get_liquidity_rate(
@@ -61,28 +272,106 @@ impl InterestRateModel {
     }
 }
 
+fn linear_borrow_rate(
+    optimal_utilization_rate: Decimal,
+    base: Decimal,
+    slope_1: Decimal,
+    slope_2: Decimal,
+    current_utilization_rate: Decimal,
+) -> StdResult<Decimal> {
+    let new_borrow_rate = if current_utilization_rate <= optimal_utilization_rate {
+        if current_utilization_rate.is_zero() {
+            // prevent division by zero when optimal_utilization_rate is zero
+            base
+        } else {
+            // The borrow interest rates increase slowly with utilization
+            base + slope_1.checked_mul(math::divide_decimal_by_decimal(
+                current_utilization_rate,
+                optimal_utilization_rate,
+            )?)?
+        }
+    } else {
+        // The borrow interest rates increase sharply with utilization
+        base + slope_1
+            + math::divide_decimal_by_decimal(
+                slope_2.checked_mul(current_utilization_rate - optimal_utilization_rate)?,
+                Decimal::one() - optimal_utilization_rate,
+            )?
+    };
+    Ok(new_borrow_rate)
+}
+
+/// Interpolate the borrow rate between the kinks around the utilization rate
+fn multi_kink_borrow_rate(
+    base: Decimal,
+    kinks: &[InterestRateKink],
+    current_utilization_rate: Decimal,
+) -> StdResult<Decimal> {
+    let mut start = InterestRateKink {
+        utilization_rate: Decimal::zero(),
+        borrow_rate: base,
+    };
+    for kink in kinks {
+        if current_utilization_rate <= kink.utilization_rate {
+            let utilization_delta = current_utilization_rate - start.utilization_rate;
+            let utilization_span = kink.utilization_rate - start.utilization_rate;
+
+            // the borrow rate can decrease between kinks
+            let borrow_rate = if kink.borrow_rate >= start.borrow_rate {
+                let rate_delta = math::divide_decimal_by_decimal(
+                    (kink.borrow_rate - start.borrow_rate).checked_mul(utilization_delta)?,
+                    utilization_span,
+                )?;
+                start.borrow_rate + rate_delta
+            } else {
+                let rate_delta = math::divide_decimal_by_decimal(
+                    (start.borrow_rate - kink.borrow_rate).checked_mul(utilization_delta)?,
+                    utilization_span,
+                )?;
+                start.borrow_rate - rate_delta
+            };
+            return Ok(borrow_rate);
+        }
+        start = kink.clone();
+    }
+
+    // utilization above the last kink, which is only possible if the model wasn't validated
+    Ok(start.borrow_rate)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::red_bank::Market;
 
+    fn linear_model(
+        optimal_utilization_rate: Decimal,
+        base: Decimal,
+        slope_1: Decimal,
+        slope_2: Decimal,
+    ) -> InterestRateModel {
+        InterestRateModel::Linear {
+            optimal_utilization_rate,
+            base,
+            slope_1,
+            slope_2,
+        }
+    }
+
     #[test]
     fn model_lifecycle() {
         let optimal_utilization_rate = Decimal::percent(80);
         let reserve_factor = Decimal::percent(20);
+        let slope_1 = Decimal::percent(7);
 
-        let model = InterestRateModel {
-            optimal_utilization_rate,
-            base: Decimal::zero(),
-            slope_1: Decimal::percent(7),
-            slope_2: Decimal::percent(45),
-        };
+        let model =
+            linear_model(optimal_utilization_rate, Decimal::zero(), slope_1, Decimal::percent(45));
 
         let mut market = Market {
             borrow_rate: Decimal::percent(10),
             liquidity_rate: Decimal::zero(),
             reserve_factor,
-            interest_rate_model: model.clone(),
+            interest_rate_model: model,
             ..Default::default()
         };
 
@@ -91,12 +380,11 @@ mod tests {
 
         market.update_interest_rates(utilization_rate).unwrap();
 
-        let expected_borrow_rate = model.base
-            + math::divide_decimal_by_decimal(
-                model.slope_1.checked_mul(utilization_rate).unwrap(),
-                model.optimal_utilization_rate,
-            )
-            .unwrap();
+        let expected_borrow_rate = math::divide_decimal_by_decimal(
+            slope_1.checked_mul(utilization_rate).unwrap(),
+            optimal_utilization_rate,
+        )
+        .unwrap();
 
         assert_eq!(market.borrow_rate, expected_borrow_rate);
         assert_eq!(
@@ -111,22 +399,21 @@ mod tests {
 
     #[test]
     fn interest_rates_calculation() {
-        let model = InterestRateModel {
-            optimal_utilization_rate: Decimal::percent(80),
-            base: Decimal::zero(),
-            slope_1: Decimal::percent(7),
-            slope_2: Decimal::percent(45),
-        };
+        let optimal_utilization_rate = Decimal::percent(80);
+        let base = Decimal::zero();
+        let slope_1 = Decimal::percent(7);
+        let slope_2 = Decimal::percent(45);
+        let model = linear_model(optimal_utilization_rate, base, slope_1, slope_2);
 
         // current utilization rate < optimal utilization rate
         {
             let current_utilization_rate = Decimal::percent(79);
             let new_borrow_rate = model.get_borrow_rate(current_utilization_rate).unwrap();
 
-            let expected_borrow_rate = model.base
+            let expected_borrow_rate = base
                 + math::divide_decimal_by_decimal(
-                    model.slope_1.checked_mul(current_utilization_rate).unwrap(),
-                    model.optimal_utilization_rate,
+                    slope_1.checked_mul(current_utilization_rate).unwrap(),
+                    optimal_utilization_rate,
                 )
                 .unwrap();
 
@@ -138,10 +425,10 @@ mod tests {
             let current_utilization_rate = Decimal::percent(80);
             let new_borrow_rate = model.get_borrow_rate(current_utilization_rate).unwrap();
 
-            let expected_borrow_rate = model.base
+            let expected_borrow_rate = base
                 + math::divide_decimal_by_decimal(
-                    model.slope_1.checked_mul(current_utilization_rate).unwrap(),
-                    model.optimal_utilization_rate,
+                    slope_1.checked_mul(current_utilization_rate).unwrap(),
+                    optimal_utilization_rate,
                 )
                 .unwrap();
 
@@ -153,14 +440,13 @@ mod tests {
             let current_utilization_rate = Decimal::percent(81);
             let new_borrow_rate = model.get_borrow_rate(current_utilization_rate).unwrap();
 
-            let expected_borrow_rate = model.base
-                + model.slope_1
+            let expected_borrow_rate = base
+                + slope_1
                 + math::divide_decimal_by_decimal(
-                    model
-                        .slope_2
-                        .checked_mul(current_utilization_rate - model.optimal_utilization_rate)
+                    slope_2
+                        .checked_mul(current_utilization_rate - optimal_utilization_rate)
                         .unwrap(),
-                    Decimal::one() - model.optimal_utilization_rate,
+                    Decimal::one() - optimal_utilization_rate,
                 )
                 .unwrap();
 
@@ -169,12 +455,12 @@ mod tests {
 
         // current utilization rate == 100% and optimal utilization rate == 100%
         {
-            let model = InterestRateModel {
-                optimal_utilization_rate: Decimal::percent(100),
-                base: Decimal::zero(),
-                slope_1: Decimal::percent(7),
-                slope_2: Decimal::zero(),
-            };
+            let model = linear_model(
+                Decimal::percent(100),
+                Decimal::zero(),
+                Decimal::percent(7),
+                Decimal::zero(),
+            );
 
             let current_utilization_rate = Decimal::percent(100);
             let new_borrow_rate = model.get_borrow_rate(current_utilization_rate).unwrap();
@@ -186,12 +472,12 @@ mod tests {
 
         // current utilization rate == 0% and optimal utilization rate == 0%
         {
-            let model = InterestRateModel {
-                optimal_utilization_rate: Decimal::percent(0),
-                base: Decimal::percent(2),
-                slope_1: Decimal::percent(7),
-                slope_2: Decimal::zero(),
-            };
+            let model = linear_model(
+                Decimal::percent(0),
+                Decimal::percent(2),
+                Decimal::percent(7),
+                Decimal::zero(),
+            );
 
             let current_utilization_rate = Decimal::percent(0);
             let new_borrow_rate = model.get_borrow_rate(current_utilization_rate).unwrap();
@@ -203,21 +489,249 @@ mod tests {
 
         // current utilization rate == 20% and optimal utilization rate == 0%
         {
-            let model = InterestRateModel {
-                optimal_utilization_rate: Decimal::percent(0),
-                base: Decimal::percent(2),
-                slope_1: Decimal::percent(1),
-                slope_2: Decimal::percent(5),
-            };
+            let base = Decimal::percent(2);
+            let slope_1 = Decimal::percent(1);
+            let slope_2 = Decimal::percent(5);
+            let model = linear_model(Decimal::percent(0), base, slope_1, slope_2);
 
             let current_utilization_rate = Decimal::percent(20);
             let new_borrow_rate = model.get_borrow_rate(current_utilization_rate).unwrap();
 
-            let expected_borrow_rate = model.base
-                + model.slope_1
-                + model.slope_2.checked_mul(current_utilization_rate).unwrap();
+            let expected_borrow_rate =
+                base + slope_1 + slope_2.checked_mul(current_utilization_rate).unwrap();
 
             assert_eq!(new_borrow_rate, expected_borrow_rate);
         }
     }
+
+    #[test]
+    fn multi_kink_interest_rates_calculation() {
+        let model = InterestRateModel::MultiKink {
+            base: Decimal::percent(1),
+            kinks: vec![
+                InterestRateKink {
+                    utilization_rate: Decimal::percent(50),
+                    borrow_rate: Decimal::percent(5),
+                },
+                InterestRateKink {
+                    utilization_rate: Decimal::percent(80),
+                    borrow_rate: Decimal::percent(8),
+                },
+                InterestRateKink {
+                    utilization_rate: Decimal::one(),
+                    borrow_rate: Decimal::percent(108),
+                },
+            ],
+        };
+        model.validate().unwrap();
+
+        let cases = [
+            (Decimal::zero(), Decimal::percent(1)),
+            (Decimal::percent(25), Decimal::percent(3)),
+            (Decimal::percent(50), Decimal::percent(5)),
+            (Decimal::percent(60), Decimal::percent(6)),
+            (Decimal::percent(90), Decimal::percent(58)),
+            (Decimal::one(), Decimal::percent(108)),
+        ];
+        for (utilization_rate, expected_borrow_rate) in cases {
+            assert_eq!(model.get_borrow_rate(utilization_rate).unwrap(), expected_borrow_rate);
+        }
+    }
+
+    #[test]
+    fn validating_multi_kink_model() {
+        let kink = |utilization_rate| InterestRateKink {
+            utilization_rate,
+            borrow_rate: Decimal::percent(10),
+        };
+
+        // kinks must be in increasing order of utilization rate
+        let model = InterestRateModel::MultiKink {
+            base: Decimal::zero(),
+            kinks: vec![
+                kink(Decimal::percent(60)),
+                kink(Decimal::percent(60)),
+                kink(Decimal::one()),
+            ],
+        };
+        assert_eq!(
+            model.validate(),
+            Err(ValidationError::InvalidParam {
+                param_name: "kinks".to_string(),
+                invalid_value: "0.6".to_string(),
+                predicate: "> 0.6 (previous kink)".to_string(),
+            })
+        );
+
+        // the last kink must be at full utilization
+        let model = InterestRateModel::MultiKink {
+            base: Decimal::zero(),
+            kinks: vec![kink(Decimal::percent(60))],
+        };
+        assert_eq!(
+            model.validate(),
+            Err(ValidationError::InvalidParam {
+                param_name: "kinks".to_string(),
+                invalid_value: "0.6".to_string(),
+                predicate: "1 (last kink)".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn fixed_interest_rates_calculation() {
+        let model = InterestRateModel::Fixed {
+            borrow_rate: Decimal::percent(4),
+        };
+        model.validate().unwrap();
+
+        assert_eq!(model.get_borrow_rate(Decimal::zero()).unwrap(), Decimal::percent(4));
+        assert_eq!(model.get_borrow_rate(Decimal::one()).unwrap(), Decimal::percent(4));
+    }
+
+    #[test]
+    fn deserializing_models() {
+        // the two-slope model keeps the layout it had before other models were added
+        let model: InterestRateModel = cosmwasm_std::from_slice(
+            br#"{"optimal_utilization_rate":"0.8","base":"0","slope_1":"0.07","slope_2":"0.45"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            model,
+            linear_model(
+                Decimal::percent(80),
+                Decimal::zero(),
+                Decimal::percent(7),
+                Decimal::percent(45)
+            )
+        );
+
+        let model: InterestRateModel = cosmwasm_std::from_slice(
+            br#"{"base":"0","kinks":[{"utilization_rate":"1","borrow_rate":"0.1"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            model,
+            InterestRateModel::MultiKink {
+                base: Decimal::zero(),
+                kinks: vec![InterestRateKink {
+                    utilization_rate: Decimal::one(),
+                    borrow_rate: Decimal::percent(10),
+                }],
+            }
+        );
+
+        let model: InterestRateModel =
+            cosmwasm_std::from_slice(br#"{"borrow_rate":"0.04"}"#).unwrap();
+        assert_eq!(
+            model,
+            InterestRateModel::Fixed {
+                borrow_rate: Decimal::percent(4),
+            }
+        );
+    }
+
+    fn adaptive_model(kink_rate: Decimal, utilization_rate: Decimal) -> InterestRateModel {
+        InterestRateModel::Adaptive {
+            target_utilization_rate: Decimal::percent(80),
+            kink_rate,
+            min_kink_rate: Decimal::percent(2),
+            max_kink_rate: Decimal::percent(50),
+            proportional_gain: Decimal::percent(40),
+            integral_gain: Decimal::percent(200),
+            utilization_rate,
+        }
+    }
+
+    #[test]
+    fn adaptive_interest_rates_calculation() {
+        let model = adaptive_model(Decimal::percent(10), Decimal::zero());
+        model.validate().unwrap();
+
+        let cases = [
+            (Decimal::zero(), Decimal::zero()),
+            (Decimal::percent(70), Decimal::percent(6)),
+            (Decimal::percent(80), Decimal::percent(10)),
+            (Decimal::one(), Decimal::percent(18)),
+        ];
+        for (utilization_rate, expected_borrow_rate) in cases {
+            assert_eq!(model.get_borrow_rate(utilization_rate).unwrap(), expected_borrow_rate);
+        }
+    }
+
+    #[test]
+    fn adaptive_model_drift() {
+        let quarter = SECONDS_PER_YEAR / 4;
+
+        // above the target, the kink rate drifts up: 10% + 200% * 10% / 4
+        let mut model = adaptive_model(Decimal::percent(10), Decimal::percent(90));
+        model.apply_drift(quarter).unwrap();
+        assert_eq!(model, adaptive_model(Decimal::percent(15), Decimal::percent(90)));
+
+        // up to the max kink rate
+        model.apply_drift(SECONDS_PER_YEAR * 10).unwrap();
+        assert_eq!(model, adaptive_model(Decimal::percent(50), Decimal::percent(90)));
+
+        // below the target, the kink rate drifts down: 10% - 200% * 10% / 4
+        let mut model = adaptive_model(Decimal::percent(10), Decimal::percent(70));
+        model.apply_drift(quarter).unwrap();
+        assert_eq!(model, adaptive_model(Decimal::percent(5), Decimal::percent(70)));
+
+        // down to the min kink rate
+        model.apply_drift(SECONDS_PER_YEAR * 10).unwrap();
+        assert_eq!(model, adaptive_model(Decimal::percent(2), Decimal::percent(70)));
+
+        // updating the borrow rate records the utilization rate driving the drift
+        model.set_utilization_rate(Decimal::percent(85));
+        assert_eq!(model, adaptive_model(Decimal::percent(2), Decimal::percent(85)));
+    }
+
+    #[test]
+    fn validating_adaptive_model() {
+        let model = adaptive_model(Decimal::percent(60), Decimal::zero());
+        assert_eq!(
+            model.validate(),
+            Err(ValidationError::InvalidParam {
+                param_name: "kink_rate".to_string(),
+                invalid_value: "0.6".to_string(),
+                predicate: ">= 0.02 and <= 0.5".to_string(),
+            })
+        );
+
+        let model = InterestRateModel::Adaptive {
+            target_utilization_rate: Decimal::percent(80),
+            kink_rate: Decimal::percent(10),
+            min_kink_rate: Decimal::percent(2),
+            max_kink_rate: Decimal::percent(50),
+            proportional_gain: Decimal::percent(40),
+            integral_gain: Decimal::percent(1001),
+            utilization_rate: Decimal::zero(),
+        };
+        assert_eq!(
+            model.validate(),
+            Err(ValidationError::InvalidParam {
+                param_name: "integral_gain".to_string(),
+                invalid_value: "10.01".to_string(),
+                predicate: "<= 10".to_string(),
+            })
+        );
+
+        let model = InterestRateModel::Adaptive {
+            target_utilization_rate: Decimal::percent(80),
+            kink_rate: Decimal::percent(10),
+            min_kink_rate: Decimal::percent(2),
+            max_kink_rate: Decimal::percent(50),
+            proportional_gain: Decimal::percent(1001),
+            integral_gain: Decimal::percent(200),
+            utilization_rate: Decimal::zero(),
+        };
+        assert_eq!(
+            model.validate(),
+            Err(ValidationError::InvalidParam {
+                param_name: "proportional_gain".to_string(),
+                invalid_value: "10.01".to_string(),
+                predicate: "<= 10".to_string(),
+            })
+        );
+    }
 }
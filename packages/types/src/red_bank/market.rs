@@ -1,3 +1,5 @@
+use std::cmp::min;
+
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{Decimal, StdResult, Uint128};
 use mars_utils::{
@@ -7,6 +9,129 @@ use mars_utils::{
 
 use crate::red_bank::InterestRateModel;
 
+/// How interest is accrued on the market's indexes between updates
+#[cw_serde]
+#[derive(Copy, Eq, Default)]
+pub enum InterestAccrual {
+    /// Simple interest: index * (1 + rate * time_elapsed)
+    #[default]
+    Linear,
+    /// Interest compounded every second, approximated with the first terms of the binomial
+    /// expansion of (1 + rate_per_second)^time_elapsed
+    Compounding,
+}
+
+/// Bonus paid to liquidators, growing from `min_lb` to `max_lb` as the liquidation health factor
+/// of the liquidated position falls:
+/// bonus = min(max_lb, min_lb + slope * (1 - liquidation_health_factor))
+#[cw_serde]
+#[derive(Copy, Eq, Default)]
+pub struct LiquidationBonus {
+    /// Bonus paid for positions whose health factor is right below 1
+    pub min_lb: Decimal,
+    /// Bonus paid for deeply insolvent positions
+    pub max_lb: Decimal,
+    /// How fast the bonus grows as the health factor falls (at most `MAX_LB_SLOPE`)
+    pub slope: Decimal,
+}
+
+/// Highest slope of the liquidation bonus, at which the bonus grows by 5% for every 1% drop of the
+/// health factor
+pub const MAX_LB_SLOPE: Decimal = Decimal::raw(5_000_000_000_000_000_000);
+
+impl LiquidationBonus {
+    /// Bonus which doesn't depend on the health factor of the liquidated position
+    pub fn fixed(bonus: Decimal) -> Self {
+        LiquidationBonus {
+            min_lb: bonus,
+            max_lb: bonus,
+            slope: Decimal::zero(),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        decimal_param_le_one(self.min_lb, "min_lb")?;
+        decimal_param_le_one(self.max_lb, "max_lb")?;
+
+        if self.max_lb < self.min_lb {
+            return Err(ValidationError::InvalidParam {
+                param_name: "max_lb".to_string(),
+                invalid_value: self.max_lb.to_string(),
+                predicate: format!(">= {} (min LB)", self.min_lb),
+            });
+        }
+
+        if self.slope > MAX_LB_SLOPE {
+            return Err(ValidationError::InvalidParam {
+                param_name: "slope".to_string(),
+                invalid_value: self.slope.to_string(),
+                predicate: format!("<= {}", MAX_LB_SLOPE),
+            });
+        }
+
+        Ok(())
+    }
+
+    pub fn get_liquidation_bonus(&self, liquidation_health_factor: Decimal) -> StdResult<Decimal> {
+        if liquidation_health_factor >= Decimal::one() {
+            return Ok(self.min_lb);
+        }
+
+        let health_factor_drop = Decimal::one() - liquidation_health_factor;
+        let bonus_increase = self.slope.checked_mul(health_factor_drop)?;
+        Ok(self.min_lb + min(bonus_increase, self.max_lb - self.min_lb))
+    }
+}
+
+/// Stage in the lifecycle of a market
+#[cw_serde]
+#[derive(Copy, Eq, Default)]
+pub enum MarketStatus {
+    /// Deposits and borrows are governed by the market's params
+    #[default]
+    Listed,
+    /// Deposits and borrows are disabled for good and the max LTV and liquidation threshold ramp
+    /// to zero, so that positions using the asset are wound down
+    Delisted,
+    /// All debt has been repaid and interest no longer accrues. The market can be removed once all
+    /// collateral has been withdrawn
+    Sunset,
+}
+
+/// Linear change of the LTV and liquidation threshold of a market in block time, from their initial
+/// values to the market's `max_loan_to_value` and `liquidation_threshold`
+#[cw_serde]
+#[derive(Copy, Eq)]
+pub struct RiskParamsRamp {
+    /// Max LTV when the ramp started
+    pub initial_max_loan_to_value: Decimal,
+    /// Liquidation threshold when the ramp started
+    pub initial_liquidation_threshold: Decimal,
+    /// Timestamp (in seconds) when the ramp started
+    pub start_time: u64,
+    /// Timestamp (in seconds) from which the market's values apply
+    pub end_time: u64,
+}
+
+impl RiskParamsRamp {
+    fn interpolate(&self, initial: Decimal, target: Decimal, block_time: u64) -> Decimal {
+        if block_time >= self.end_time {
+            return target;
+        }
+        if block_time <= self.start_time {
+            return initial;
+        }
+
+        let progress =
+            Decimal::from_ratio(block_time - self.start_time, self.end_time - self.start_time);
+        if target >= initial {
+            initial + (target - initial) * progress
+        } else {
+            initial - (initial - target) * progress
+        }
+    }
+}
+
 #[cw_serde]
 pub struct Market {
     /// Denom of the asset
@@ -16,14 +141,22 @@ pub struct Market {
     pub max_loan_to_value: Decimal,
     /// Base asset amount in debt position per "base asset" of asset collateral that if surpassed makes the user's position liquidatable.
     pub liquidation_threshold: Decimal,
+    /// Ramp towards `max_loan_to_value` and `liquidation_threshold` in progress, if any
+    pub risk_params_ramp: Option<RiskParamsRamp>,
     /// Bonus amount of collateral liquidator get when repaying user's debt (Will get collateral
-    /// from user in an amount equal to debt repayed + bonus)
-    pub liquidation_bonus: Decimal,
+    /// from user in an amount equal to debt repayed + bonus), depending on the user's health factor
+    pub liquidation_bonus: LiquidationBonus,
+    /// Portion of the liquidation bonus that is kept as protocol rewards
+    pub protocol_liquidation_fee: Decimal,
     /// Portion of the borrow rate that is kept as protocol rewards
     pub reserve_factor: Decimal,
+    /// Fee charged on flash loans, as a portion of the amount loaned. It is kept as protocol rewards
+    pub flash_loan_fee: Decimal,
 
     /// model (params + internal state) that defines how interest rate behaves
     pub interest_rate_model: InterestRateModel,
+    /// How interest is accrued on the indexes
+    pub interest_accrual: InterestAccrual,
 
     /// Borrow index (Used to compute borrow interest)
     pub borrow_index: Decimal,
@@ -47,6 +180,23 @@ pub struct Market {
     pub borrow_enabled: bool,
     /// Deposit Cap (defined in terms of the asset)
     pub deposit_cap: Uint128,
+    /// Borrow Cap (defined in terms of the asset)
+    pub borrow_cap: Uint128,
+    /// Min debt (defined in terms of the asset) a position can be left with, unless the debt is
+    /// fully repaid
+    pub min_debt: Uint128,
+
+    /// If true, the asset can only be used as collateral on its own (isolation mode), and only
+    /// assets borrowable in isolation can be borrowed against it
+    pub isolated: bool,
+    /// Max total debt (defined in terms of the base asset) that can be borrowed against the asset
+    /// while it is used as isolated collateral
+    pub debt_ceiling: Uint128,
+    /// If true, the asset can be borrowed by users in isolation mode
+    pub borrowable_in_isolation: bool,
+
+    /// Stage in the lifecycle of the market
+    pub status: MarketStatus,
 }
 
 impl Default for Market {
@@ -59,16 +209,26 @@ impl Default for Market {
             liquidity_rate: Decimal::zero(),
             max_loan_to_value: Decimal::zero(),
             reserve_factor: Decimal::zero(),
+            flash_loan_fee: Decimal::zero(),
             indexes_last_updated: 0,
             collateral_total_scaled: Uint128::zero(),
             debt_total_scaled: Uint128::zero(),
             liquidation_threshold: Decimal::one(),
-            liquidation_bonus: Decimal::zero(),
+            risk_params_ramp: None,
+            liquidation_bonus: LiquidationBonus::default(),
+            protocol_liquidation_fee: Decimal::zero(),
             interest_rate_model: InterestRateModel::default(),
+            interest_accrual: InterestAccrual::Linear,
             deposit_enabled: true,
             borrow_enabled: true,
             // By default the cap should be unlimited (no cap)
             deposit_cap: Uint128::MAX,
+            borrow_cap: Uint128::MAX,
+            min_debt: Uint128::zero(),
+            isolated: false,
+            debt_ceiling: Uint128::zero(),
+            borrowable_in_isolation: false,
+            status: MarketStatus::Listed,
         }
     }
 }
@@ -76,9 +236,11 @@ impl Default for Market {
 impl Market {
     pub fn validate(&self) -> Result<(), ValidationError> {
         decimal_param_lt_one(self.reserve_factor, "reserve_factor")?;
+        decimal_param_lt_one(self.flash_loan_fee, "flash_loan_fee")?;
         decimal_param_le_one(self.max_loan_to_value, "max_loan_to_value")?;
         decimal_param_le_one(self.liquidation_threshold, "liquidation_threshold")?;
-        decimal_param_le_one(self.liquidation_bonus, "liquidation_bonus")?;
+        self.liquidation_bonus.validate()?;
+        decimal_param_lt_one(self.protocol_liquidation_fee, "protocol_liquidation_fee")?;
 
         // liquidation_threshold should be greater than max_loan_to_value
         if self.liquidation_threshold <= self.max_loan_to_value {
@@ -89,13 +251,55 @@ impl Market {
             });
         }
 
+        // seizing the debt plus the max bonus shouldn't take more collateral than the position
+        // holds at the liquidation threshold
+        if self.liquidation_threshold * (Decimal::one() + self.liquidation_bonus.max_lb)
+            > Decimal::one()
+        {
+            return Err(ValidationError::InvalidParam {
+                param_name: "liquidation_threshold".to_string(),
+                invalid_value: self.liquidation_threshold.to_string(),
+                predicate: format!("* (1 + {} (max LB)) <= 1", self.liquidation_bonus.max_lb),
+            });
+        }
+
         self.interest_rate_model.validate()?;
 
         Ok(())
     }
 
+    /// Max LTV at the given block time, following the ramp in progress if any
+    pub fn max_loan_to_value_at(&self, block_time: u64) -> Decimal {
+        match &self.risk_params_ramp {
+            Some(ramp) => {
+                ramp.interpolate(ramp.initial_max_loan_to_value, self.max_loan_to_value, block_time)
+            }
+            None => self.max_loan_to_value,
+        }
+    }
+
+    /// Liquidation threshold at the given block time, following the ramp in progress if any
+    pub fn liquidation_threshold_at(&self, block_time: u64) -> Decimal {
+        match &self.risk_params_ramp {
+            Some(ramp) => ramp.interpolate(
+                ramp.initial_liquidation_threshold,
+                self.liquidation_threshold,
+                block_time,
+            ),
+            None => self.liquidation_threshold,
+        }
+    }
+
     pub fn update_interest_rates(&mut self, current_utilization_rate: Decimal) -> StdResult<()> {
+        // Sunset markets have no debt left and don't accrue interest anymore
+        if self.status == MarketStatus::Sunset {
+            self.borrow_rate = Decimal::zero();
+            self.liquidity_rate = Decimal::zero();
+            return Ok(());
+        }
+
         self.borrow_rate = self.interest_rate_model.get_borrow_rate(current_utilization_rate)?;
+        self.interest_rate_model.set_utilization_rate(current_utilization_rate);
 
         self.liquidity_rate = self.interest_rate_model.get_liquidity_rate(
             self.borrow_rate,
@@ -126,3 +330,113 @@ impl Market {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn liquidation_bonus_validation() {
+        let liquidation_bonus = LiquidationBonus {
+            min_lb: Decimal::percent(10),
+            max_lb: Decimal::percent(5),
+            slope: Decimal::one(),
+        };
+        assert_eq!(
+            liquidation_bonus.validate(),
+            Err(ValidationError::InvalidParam {
+                param_name: "max_lb".to_string(),
+                invalid_value: "0.05".to_string(),
+                predicate: ">= 0.1 (min LB)".to_string(),
+            })
+        );
+
+        let liquidation_bonus = LiquidationBonus {
+            max_lb: Decimal::percent(101),
+            ..liquidation_bonus
+        };
+        assert_eq!(
+            liquidation_bonus.validate(),
+            Err(ValidationError::InvalidParam {
+                param_name: "max_lb".to_string(),
+                invalid_value: "1.01".to_string(),
+                predicate: "<= 1".to_string(),
+            })
+        );
+
+        let liquidation_bonus = LiquidationBonus {
+            min_lb: Decimal::percent(5),
+            max_lb: Decimal::percent(20),
+            slope: Decimal::percent(501),
+        };
+        assert_eq!(
+            liquidation_bonus.validate(),
+            Err(ValidationError::InvalidParam {
+                param_name: "slope".to_string(),
+                invalid_value: "5.01".to_string(),
+                predicate: "<= 5".to_string(),
+            })
+        );
+
+        assert_eq!(LiquidationBonus::fixed(Decimal::percent(5)).validate(), Ok(()));
+    }
+
+    #[test]
+    fn liquidation_threshold_leaves_room_for_max_bonus() {
+        let market = Market {
+            max_loan_to_value: Decimal::percent(80),
+            liquidation_threshold: Decimal::percent(90),
+            liquidation_bonus: LiquidationBonus {
+                min_lb: Decimal::percent(5),
+                max_lb: Decimal::percent(15),
+                slope: Decimal::one(),
+            },
+            ..Default::default()
+        };
+        assert_eq!(
+            market.validate(),
+            Err(ValidationError::InvalidParam {
+                param_name: "liquidation_threshold".to_string(),
+                invalid_value: "0.9".to_string(),
+                predicate: "* (1 + 0.15 (max LB)) <= 1".to_string(),
+            })
+        );
+
+        let market = Market {
+            liquidation_bonus: LiquidationBonus {
+                max_lb: Decimal::percent(10),
+                ..market.liquidation_bonus
+            },
+            ..market
+        };
+        assert_eq!(market.validate(), Ok(()));
+    }
+
+    #[test]
+    fn risk_params_ramp_interpolation() {
+        let market = Market {
+            max_loan_to_value: Decimal::percent(30),
+            liquidation_threshold: Decimal::percent(80),
+            risk_params_ramp: Some(RiskParamsRamp {
+                initial_max_loan_to_value: Decimal::percent(50),
+                initial_liquidation_threshold: Decimal::percent(60),
+                start_time: 100,
+                end_time: 200,
+            }),
+            ..Default::default()
+        };
+
+        assert_eq!(market.max_loan_to_value_at(50), Decimal::percent(50));
+        assert_eq!(market.liquidation_threshold_at(50), Decimal::percent(60));
+        assert_eq!(market.max_loan_to_value_at(125), Decimal::percent(45));
+        assert_eq!(market.liquidation_threshold_at(125), Decimal::percent(65));
+        assert_eq!(market.max_loan_to_value_at(200), Decimal::percent(30));
+        assert_eq!(market.liquidation_threshold_at(300), Decimal::percent(80));
+
+        let market = Market {
+            risk_params_ramp: None,
+            ..market
+        };
+        assert_eq!(market.max_loan_to_value_at(125), Decimal::percent(30));
+    }
+}
@@ -1,8 +1,11 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Decimal, Uint128};
+use cosmwasm_std::{Binary, Coin, Decimal, Uint128};
 use mars_owner::OwnerUpdate;
 
-use crate::red_bank::InterestRateModel;
+use crate::red_bank::{
+    EModeCategory, InterestAccrual, InterestRateModel, LiquidationBonus, OperatorPermission,
+    PausableAction,
+};
 
 #[cw_serde]
 pub struct InstantiateMsg {
@@ -22,7 +25,8 @@ pub enum ExecuteMsg {
     /// Manages emergency owner state
     UpdateEmergencyOwner(OwnerUpdate),
 
-    /// Update contract config (only owner can call)
+    /// Update contract config (only owner can call). Queued as a pending change while a timelock
+    /// is set.
     UpdateConfig {
         config: CreateOrUpdateConfig,
     },
@@ -35,7 +39,11 @@ pub enum ExecuteMsg {
         params: InitOrUpdateAssetParams,
     },
 
-    /// Update an asset on the money market (only owner can call)
+    /// Update an asset on the money market (only owner can call). Queued as a pending change while
+    /// a timelock is set.
+    ///
+    /// The emergency owner can also call it to disable borrowing or lower the borrow cap, which is
+    /// applied immediately.
     UpdateAsset {
         /// Asset related info
         denom: String,
@@ -43,10 +51,43 @@ pub enum ExecuteMsg {
         params: InitOrUpdateAssetParams,
     },
 
+    /// Apply a change queued while a timelock is set. Anyone can apply it once the timelock has
+    /// passed and until it expires, and the owner or emergency owner can apply it earlier if it
+    /// only lowers the risk taken by the protocol, e.g. lowers the LTV or disables borrowing.
+    ExecutePendingChange {
+        id: u64,
+    },
+
+    /// Drop a queued change (only owner or emergency owner can call)
+    CancelPendingChange {
+        id: u64,
+    },
+
+    /// Start winding down a market (only owner can call). Deposits and borrows are disabled for
+    /// good, the asset is removed from e-mode categories, and the max LTV and liquidation threshold
+    /// ramp to zero over the given duration, which can't be shorter than the timelock.
+    DelistAsset {
+        denom: String,
+        ramp_duration: u64,
+    },
+
+    /// Stop accruing interest on a delisted market once all of its debt has been repaid (only owner
+    /// can call)
+    SunsetAsset {
+        denom: String,
+    },
+
+    /// Remove a sunset market once all of its collateral has been withdrawn and its deficit covered,
+    /// along with the e-mode memberships, uncollateralized loan limits, borrow allowances and pending
+    /// changes of the asset (only owner can call)
+    RemoveAsset {
+        denom: String,
+    },
+
     /// Update uncollateralized loan limit for a given user and asset.
     /// Overrides previous value if any. A limit of zero means no
     /// uncollateralized limit and the debt in that asset needs to be
-    /// collateralized (only owner can call)
+    /// collateralized. The limit applies to the user's main account (only owner can call)
     UpdateUncollateralizedLoanLimit {
         /// Address that receives the credit
         user: String,
@@ -61,6 +102,8 @@ pub enum ExecuteMsg {
     Deposit {
         /// Address that will receive the coins
         on_behalf_of: Option<String>,
+        /// Sub-account of the user, the main account (0) if None is specified
+        account_id: Option<u32>,
     },
 
     /// Withdraw native coins
@@ -69,8 +112,14 @@ pub enum ExecuteMsg {
         denom: String,
         /// Amount to be withdrawn. If None is specified, the full amount will be withdrawn.
         amount: Option<Uint128>,
-        /// The address where the withdrawn amount is sent
+        /// The address where the withdrawn amount is sent, the position's owner if None is
+        /// specified. Can't be set when withdrawing on behalf of another user.
         recipient: Option<String>,
+        /// Withdraw from the position of a user who approved the caller as operator. The funds are
+        /// always sent to the user.
+        on_behalf_of: Option<String>,
+        /// Sub-account of the user, the main account (0) if None is specified
+        account_id: Option<u32>,
     },
 
     /// Borrow native coins. If borrow allowed, amount is added to caller's debt
@@ -82,6 +131,40 @@ pub enum ExecuteMsg {
         amount: Uint128,
         /// The address where the borrowed amount is sent
         recipient: Option<String>,
+        /// Borrow against the position of this address instead of the caller's, within the
+        /// allowance it approved for the caller with `ApproveDelegation`
+        on_behalf_of: Option<String>,
+        /// Sub-account of the user, the main account (0) if None is specified
+        account_id: Option<u32>,
+    },
+
+    /// Allow another address to borrow an asset against the caller's position, up to the given
+    /// amount. Overrides the previous allowance if any; an amount of zero revokes it.
+    ///
+    /// The debt is added to the caller's position, which must stay below its max LTV, and the
+    /// borrowed coins are sent to the delegatee.
+    ApproveDelegation {
+        /// Address allowed to borrow
+        delegatee: String,
+        /// Asset the delegatee is allowed to borrow
+        denom: String,
+        /// Amount the delegatee is allowed to borrow
+        amount: Uint128,
+        /// Sub-account of the user, the main account (0) if None is specified
+        account_id: Option<u32>,
+    },
+
+    /// Approve an operator to take the given actions on the caller's position, until the given
+    /// timestamp (in seconds) if specified. Empty permissions revoke the operator.
+    SetOperator {
+        /// Address of the operator, e.g. an automation contract
+        operator: String,
+        /// Actions the operator can take
+        permissions: Vec<OperatorPermission>,
+        /// Timestamp (in seconds) from which the operator can no longer act
+        expires: Option<u64>,
+        /// Sub-account of the user, the main account (0) if None is specified
+        account_id: Option<u32>,
     },
 
     /// Repay native coins loan. Coins used to repay must be sent in the
@@ -89,35 +172,195 @@ pub enum ExecuteMsg {
     Repay {
         /// Repay the funds for the user
         on_behalf_of: Option<String>,
+        /// Sub-account of the user, the main account (0) if None is specified
+        account_id: Option<u32>,
     },
 
     /// Liquidate under-collateralized native loans. Coins used to repay must be sent in the
     /// transaction this call is made.
     ///
-    /// The liquidator will receive collateral shares. To get the underlying asset, consider sending
+    /// The liquidator will receive collateral shares in their main account. To get the underlying asset, consider sending
     /// a separate `withdraw` execute message.
     Liquidate {
         /// The address of the borrower getting liquidated
         user: String,
+        /// Sub-account of the borrower getting liquidated, the main account (0) if None is
+        /// specified
+        account_id: Option<u32>,
         /// Denom of the collateral asset, which liquidator gets from the borrower
         collateral_denom: String,
         /// The address for receiving underlying collateral
         recipient: Option<String>,
     },
 
+    /// Liquidate several under-collateralized positions at once. Coins used to repay must be sent
+    /// in the transaction this call is made, adding up to the amounts of the liquidations in each
+    /// denom.
+    ///
+    /// Liquidations which can't be processed, e.g. because the position is healthy, are skipped
+    /// instead of failing the whole transaction. The coins sent for them, and in excess of the debt
+    /// repaid, are refunded in a single transfer.
+    LiquidateMany {
+        liquidations: Vec<Liquidation>,
+        /// The address for receiving underlying collateral
+        recipient: Option<String>,
+    },
+
     /// Update (enable / disable) asset as collateral for the caller
     UpdateAssetCollateralStatus {
         /// Asset to update status for
         denom: String,
         /// Option to enable (true) / disable (false) asset as collateral
         enable: bool,
+        /// Update the status for a user who approved the caller as operator
+        on_behalf_of: Option<String>,
+        /// Sub-account of the user, the main account (0) if None is specified
+        account_id: Option<u32>,
+    },
+
+    /// Repay debt with collateral in the same asset, lowering the position's exposure without
+    /// sending any coins. If None is specified as amount, as much as possible is repaid.
+    ///
+    /// The position's liquidation health factor can't decrease as a result.
+    Deleverage {
+        denom: String,
+        amount: Option<Uint128>,
+        /// Deleverage the position of a user who approved the caller as operator
+        on_behalf_of: Option<String>,
+        /// Sub-account of the user, the main account (0) if None is specified
+        account_id: Option<u32>,
+    },
+
+    /// Borrow coins for the duration of the transaction, without opening a position.
+    ///
+    /// The coins are sent to the caller together with the `callback` message, which is executed
+    /// on the caller. Before the transaction ends, the caller must return the coins plus the flash
+    /// loan fee of each market by sending a `RepayFlashLoan` message, otherwise the transaction
+    /// fails.
+    FlashLoan {
+        /// Coins to borrow
+        coins: Vec<Coin>,
+        /// Execute message invoked on the caller after the coins are sent
+        callback: Binary,
+    },
+
+    /// Return the coins of the flash loan currently in progress, plus the flash loan fee.
+    /// Coins used to repay must be sent in the transaction this call is made.
+    RepayFlashLoan {},
+
+    /// Create or update an e-mode category (only owner can call). Queued if a timelock is set.
+    SetEModeCategory {
+        category: EModeCategory,
+    },
+
+    /// Opt the caller into an e-mode category, or out of e-mode if `None` is specified.
+    ///
+    /// All of the caller's debt must be in assets of the category, and the caller's position must
+    /// be healthy after the change.
+    SetUserEMode {
+        category_id: Option<u8>,
+        /// Sub-account of the user, the main account (0) if None is specified
+        account_id: Option<u32>,
+    },
+
+    /// Pause or unpause actions in a market, or in all markets if no denom is specified. An action
+    /// is paused in a market if it is paused either in the market or globally.
+    ///
+    /// The emergency owner can only pause actions, the owner can also unpause them.
+    SetPauseState {
+        denom: Option<String>,
+        actions: Vec<PausableAction>,
+        paused: bool,
+    },
+
+    /// Cover the deficit of a market, left by debt written off when liquidating positions with no
+    /// collateral left (only owner can call).
+    ///
+    /// The deficit is covered from the protocol reserves (the rewards collector's collateral)
+    /// first, and what remains is spread across depositors by lowering the liquidity index. Any
+    /// part exceeding the deposits is kept as deficit.
+    CoverDeficit {
+        denom: String,
+    },
+
+    /// Execute several actions for the caller in order, checking the health of the caller's
+    /// position once after all of them instead of after each withdrawal, borrow and collateral
+    /// disabling. Coins used for deposits and repayments must be sent in the transaction this call
+    /// is made, adding up to the amounts of those actions in each denom.
+    Batch {
+        actions: Vec<Action>,
+        /// Sub-account of the user, the main account (0) if None is specified
+        account_id: Option<u32>,
+    },
+
+    /// Move up to `limit` positions stored by version 1.0.1 to the main accounts of their owners,
+    /// after the contract has been migrated from that version. Accounts are also moved the first
+    /// time they are used (only owner can call)
+    MigrateV1Positions {
+        limit: u32,
+    },
+
+    /// Messages the contract sends to itself (only the contract itself can call)
+    Callback(CallbackMsg),
+}
+
+#[cw_serde]
+pub enum CallbackMsg {
+    /// Check that the flash loan in progress has been repaid and distribute the fees
+    FinalizeFlashLoan {},
+}
+
+#[cw_serde]
+pub enum Action {
+    /// Deposit coins as the caller's collateral, out of the coins sent
+    Deposit {
+        denom: String,
+        amount: Uint128,
+    },
+    /// Withdraw collateral. If None is specified as amount, the full amount will be withdrawn.
+    Withdraw {
+        denom: String,
+        amount: Option<Uint128>,
+        recipient: Option<String>,
+    },
+    /// Borrow coins
+    Borrow {
+        denom: String,
+        amount: Uint128,
+        recipient: Option<String>,
+    },
+    /// Repay the caller's debt, out of the coins sent
+    Repay {
+        denom: String,
+        amount: Uint128,
+    },
+    /// Enable / disable an asset as collateral
+    UpdateAssetCollateralStatus {
+        denom: String,
+        enable: bool,
     },
 }
 
+#[cw_serde]
+pub struct Liquidation {
+    /// The address of the borrower getting liquidated
+    pub user: String,
+    /// Sub-account of the borrower getting liquidated, the main account (0) if None is specified
+    pub account_id: Option<u32>,
+    /// Denom of the collateral asset, which liquidator gets from the borrower
+    pub collateral_denom: String,
+    /// Denom of the debt asset to repay
+    pub debt_denom: String,
+    /// Amount of debt to repay, out of the coins sent
+    pub amount: Uint128,
+}
+
 #[cw_serde]
 pub struct CreateOrUpdateConfig {
     pub address_provider: Option<String>,
     pub close_factor: Option<Decimal>,
+    /// Delay (in seconds) before changes can be applied (Zero by default)
+    pub timelock: Option<u64>,
 }
 
 #[cw_serde]
@@ -129,8 +372,8 @@ pub struct InitOrUpdateAssetParams {
     /// uusd amount in debt position per uusd of asset collateral that if surpassed makes the user's position liquidatable.
     pub liquidation_threshold: Option<Decimal>,
     /// Bonus amount of collateral liquidator get when repaying user's debt (Will get collateral
-    /// from user in an amount equal to debt repayed + bonus)
-    pub liquidation_bonus: Option<Decimal>,
+    /// from user in an amount equal to debt repayed + bonus), depending on the user's health factor
+    pub liquidation_bonus: Option<LiquidationBonus>,
 
     /// Interest rate strategy to calculate borrow_rate and liquidity_rate
     pub interest_rate_model: Option<InterestRateModel>,
@@ -141,6 +384,28 @@ pub struct InitOrUpdateAssetParams {
     pub borrow_enabled: Option<bool>,
     /// Deposit Cap defined in terms of the asset (Unlimited by default)
     pub deposit_cap: Option<Uint128>,
+    /// Borrow Cap defined in terms of the asset (Unlimited by default)
+    pub borrow_cap: Option<Uint128>,
+    /// Min debt defined in terms of the asset a position can be left with, unless the debt is
+    /// fully repaid (Zero by default)
+    pub min_debt: Option<Uint128>,
+    /// If true, the asset can only be used as collateral in isolation mode (False by default)
+    pub isolated: Option<bool>,
+    /// Max total debt defined in terms of the base asset that can be borrowed against the asset
+    /// in isolation mode (Zero by default)
+    pub debt_ceiling: Option<Uint128>,
+    /// If true, the asset can be borrowed in isolation mode (False by default)
+    pub borrowable_in_isolation: Option<bool>,
+    /// Fee charged on flash loans, as a portion of the amount loaned (Zero by default)
+    pub flash_loan_fee: Option<Decimal>,
+    /// How interest is accrued on the indexes (Linear by default)
+    pub interest_accrual: Option<InterestAccrual>,
+    /// Portion of the liquidation bonus that is kept as protocol rewards (Zero by default)
+    pub protocol_liquidation_fee: Option<Decimal>,
+    /// Duration (in seconds) over which a new max LTV or liquidation threshold is phased in, linearly
+    /// in block time. If not specified, the new values apply immediately. Ignored when initializing
+    /// an asset
+    pub ramp_duration: Option<u64>,
 }
 
 #[cw_serde]
@@ -150,6 +415,26 @@ pub enum QueryMsg {
     #[returns(crate::red_bank::ConfigResponse)]
     Config {},
 
+    /// Get the actions paused in a market, or in all markets if no denom is specified. Actions
+    /// paused globally are not included in the pause state of a market.
+    #[returns(crate::red_bank::PauseState)]
+    PauseState {
+        denom: Option<String>,
+    },
+
+    /// Get a change waiting for the timelock to pass
+    #[returns(crate::red_bank::PendingChange)]
+    PendingChange {
+        id: u64,
+    },
+
+    /// Enumerate changes waiting for the timelock to pass, with pagination
+    #[returns(Vec<crate::red_bank::PendingChange>)]
+    PendingChanges {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+
     /// Get asset market
     #[returns(crate::red_bank::Market)]
     Market {
@@ -163,6 +448,103 @@ pub enum QueryMsg {
         limit: Option<u32>,
     },
 
+    /// Get asset market with the interest accrued up to the current block, and its totals in
+    /// underlying amounts
+    #[returns(crate::red_bank::MarketLiveResponse)]
+    MarketLive {
+        denom: String,
+    },
+
+    /// Enumerate markets with the interest accrued up to the current block, with pagination
+    #[returns(Vec<crate::red_bank::MarketLiveResponse>)]
+    MarketsLive {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    /// Get the liquidation bonus paid when liquidating the given asset as collateral of a position
+    /// with the given liquidation health factor
+    #[returns(Decimal)]
+    LiquidationBonus {
+        denom: String,
+        liquidation_health_factor: Decimal,
+    },
+
+    /// Simulate a liquidation against the current prices and indexes, without changing state.
+    /// Fails with the same errors as `ExecuteMsg::Liquidate` if the position can't be liquidated
+    #[returns(crate::red_bank::LiquidationPreviewResponse)]
+    LiquidationPreview {
+        user: String,
+        /// Sub-account of the user, the main account (0) if None is specified
+        account_id: Option<u32>,
+        collateral_denom: String,
+        debt_denom: String,
+        amount: Uint128,
+    },
+
+    /// Get e-mode category
+    #[returns(crate::red_bank::EModeCategory)]
+    EModeCategory {
+        id: u8,
+    },
+
+    /// Enumerate e-mode categories with pagination
+    #[returns(Vec<crate::red_bank::EModeCategory>)]
+    EModeCategories {
+        start_after: Option<u8>,
+        limit: Option<u32>,
+    },
+
+    /// Get the e-mode category the user is opted into, if any
+    #[returns(Option<crate::red_bank::EModeCategory>)]
+    UserEMode {
+        user: String,
+        /// Sub-account of the user, the main account (0) if None is specified
+        account_id: Option<u32>,
+    },
+
+    /// Get the deficit of a market, i.e. the written off debt which hasn't been covered yet
+    #[returns(crate::red_bank::DeficitResponse)]
+    Deficit {
+        denom: String,
+    },
+
+    /// Enumerate the deficits of all markets with pagination
+    #[returns(Vec<crate::red_bank::DeficitResponse>)]
+    Deficits {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    /// Get the amount of an asset a delegatee can still borrow against a delegator's position
+    #[returns(Uint128)]
+    BorrowAllowance {
+        delegator: String,
+        /// Sub-account of the delegator, the main account (0) if None is specified
+        account_id: Option<u32>,
+        delegatee: String,
+        denom: String,
+    },
+
+    /// Get the permissions a user granted to an operator
+    #[returns(crate::red_bank::OperatorResponse)]
+    Operator {
+        user: String,
+        /// Sub-account of the user, the main account (0) if None is specified
+        account_id: Option<u32>,
+        operator: String,
+    },
+
+    /// Enumerate the operators of a user with pagination
+    #[returns(Vec<crate::red_bank::OperatorResponse>)]
+    Operators {
+        user: String,
+        /// Sub-account of the user, the main account (0) if None is specified
+        account_id: Option<u32>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
     /// Get uncollateralized limit for given user and asset
     #[returns(crate::red_bank::UncollateralizedLoanLimitResponse)]
     UncollateralizedLoanLimit {
@@ -182,6 +564,8 @@ pub enum QueryMsg {
     #[returns(crate::red_bank::UserDebtResponse)]
     UserDebt {
         user: String,
+        /// Sub-account of the user, the main account (0) if None is specified
+        account_id: Option<u32>,
         denom: String,
     },
 
@@ -189,6 +573,8 @@ pub enum QueryMsg {
     #[returns(Vec<crate::red_bank::UserDebtResponse>)]
     UserDebts {
         user: String,
+        /// Sub-account of the user, the main account (0) if None is specified
+        account_id: Option<u32>,
         start_after: Option<String>,
         limit: Option<u32>,
     },
@@ -196,6 +582,16 @@ pub enum QueryMsg {
     /// Get user collateral position for a specific asset
     #[returns(crate::red_bank::UserCollateralResponse)]
     UserCollateral {
+        user: String,
+        /// Sub-account of the user, the main account (0) if None is specified
+        account_id: Option<u32>,
+        denom: String,
+    },
+
+    /// Get the scaled collateral amount of a user in an asset, summed across all of the user's
+    /// accounts. Used by the incentives contract, which tracks balances per address
+    #[returns(Uint128)]
+    UserTotalCollateralScaled {
         user: String,
         denom: String,
     },
@@ -204,6 +600,8 @@ pub enum QueryMsg {
     #[returns(Vec<crate::red_bank::UserCollateralResponse>)]
     UserCollaterals {
         user: String,
+        /// Sub-account of the user, the main account (0) if None is specified
+        account_id: Option<u32>,
         start_after: Option<String>,
         limit: Option<u32>,
     },
@@ -212,6 +610,42 @@ pub enum QueryMsg {
     #[returns(crate::red_bank::UserPositionResponse)]
     UserPosition {
         user: String,
+        /// Sub-account of the user, the main account (0) if None is specified
+        account_id: Option<u32>,
+    },
+
+    /// Enumerate the accounts with collateralized debt with pagination. If `max_health_factor` is
+    /// specified, only the accounts with a liquidation health factor at or below it are returned,
+    /// e.g. 1 to find liquidatable positions. At most `limit` accounts are scanned per page, so a
+    /// page can hold fewer matching accounts even though more are left to scan.
+    #[returns(crate::red_bank::BorrowersResponse)]
+    Borrowers {
+        /// Last borrower scanned by the previous page
+        start_after: Option<(String, u32)>,
+        limit: Option<u32>,
+        max_health_factor: Option<Decimal>,
+    },
+
+    /// Get the largest amount of an asset the user can borrow, keeping the position below its max
+    /// LTV and within the available liquidity, borrow cap and uncollateralized loan limit.
+    /// Zero if borrowing is paused or disabled, or if the amount would leave the user's debt below
+    /// the market's min debt.
+    #[returns(Uint128)]
+    MaxBorrow {
+        user: String,
+        /// Sub-account of the user, the main account (0) if None is specified
+        account_id: Option<u32>,
+        denom: String,
+    },
+
+    /// Get the largest amount of an asset the user can withdraw, keeping the position above its
+    /// liquidation threshold and within the available liquidity
+    #[returns(Uint128)]
+    MaxWithdraw {
+        user: String,
+        /// Sub-account of the user, the main account (0) if None is specified
+        account_id: Option<u32>,
+        denom: String,
     },
 
     /// Get liquidity scaled amount for a given underlying asset amount.
@@ -1,7 +1,11 @@
+use std::fmt;
+
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{Decimal, Uint128};
 use mars_utils::{error::ValidationError, helpers::decimal_param_le_one};
 
+use crate::red_bank::{CreateOrUpdateConfig, InitOrUpdateAssetParams, Market};
+
 /// Global configuration
 #[cw_serde]
 pub struct Config<T> {
@@ -9,6 +13,9 @@ pub struct Config<T> {
     pub address_provider: T,
     /// Maximum percentage of outstanding debt that can be covered by a liquidator
     pub close_factor: Decimal,
+    /// Delay (in seconds) between queuing a change to the config or to the params of a market and
+    /// being able to apply it, or zero if changes are applied immediately
+    pub timelock: u64,
 }
 
 impl<T> Config<T> {
@@ -43,6 +50,209 @@ pub struct Debt {
     pub uncollateralized: bool,
 }
 
+/// Efficiency mode (e-mode) category of correlated assets, e.g. stATOM/ATOM or stablecoins.
+///
+/// Users opted into a category use its risk parameters instead of the market ones for the assets
+/// in the category, and can only borrow assets in the category.
+#[cw_serde]
+pub struct EModeCategory {
+    /// Category id
+    pub id: u8,
+    /// Denoms of the assets in the category
+    pub denoms: Vec<String>,
+    /// Max base asset that can be borrowed per "base asset" collateral of the category
+    pub max_loan_to_value: Decimal,
+    /// Base asset amount in debt position per "base asset" of collateral of the category that if
+    /// surpassed makes the user's position liquidatable
+    pub liquidation_threshold: Decimal,
+    /// Bonus amount of collateral of the category liquidator get when repaying user's debt
+    pub liquidation_bonus: Decimal,
+}
+
+impl EModeCategory {
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        decimal_param_le_one(self.max_loan_to_value, "max_loan_to_value")?;
+        decimal_param_le_one(self.liquidation_threshold, "liquidation_threshold")?;
+        decimal_param_le_one(self.liquidation_bonus, "liquidation_bonus")?;
+
+        // liquidation_threshold should be greater than max_loan_to_value
+        if self.liquidation_threshold <= self.max_loan_to_value {
+            return Err(ValidationError::InvalidParam {
+                param_name: "liquidation_threshold".to_string(),
+                invalid_value: self.liquidation_threshold.to_string(),
+                predicate: format!("> {} (max LTV)", self.max_loan_to_value),
+            });
+        }
+
+        // seizing the debt plus the bonus shouldn't take more collateral than the position holds at
+        // the liquidation threshold
+        if self.liquidation_threshold * (Decimal::one() + self.liquidation_bonus) > Decimal::one() {
+            return Err(ValidationError::InvalidParam {
+                param_name: "liquidation_threshold".to_string(),
+                invalid_value: self.liquidation_threshold.to_string(),
+                predicate: format!("* (1 + {} (LB)) <= 1", self.liquidation_bonus),
+            });
+        }
+
+        if self.denoms.is_empty() {
+            return Err(ValidationError::InvalidParam {
+                param_name: "denoms".to_string(),
+                invalid_value: "[]".to_string(),
+                predicate: "not empty".to_string(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// Flash loan taken out in the current transaction
+#[cw_serde]
+pub struct FlashLoan {
+    /// Asset denom
+    pub denom: String,
+    /// Amount sent to the borrower
+    pub amount: Uint128,
+    /// Fee owed on top of the loaned amount
+    pub fee: Uint128,
+    /// Amount returned so far
+    pub repaid: Uint128,
+}
+
+/// Action an operator can take on a user's position
+#[cw_serde]
+#[derive(Copy, Eq, Hash, PartialOrd, Ord)]
+pub enum OperatorPermission {
+    /// Withdraw the user's collateral
+    Withdraw,
+    /// Repay the user's debts, including uncollateralized ones
+    Repay,
+    /// Enable or disable the user's assets as collateral
+    UpdateAssetCollateralStatus,
+    /// Repay the user's debts with their collateral in the same asset
+    Deleverage,
+}
+
+impl fmt::Display for OperatorPermission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            OperatorPermission::Withdraw => "withdraw",
+            OperatorPermission::Repay => "repay",
+            OperatorPermission::UpdateAssetCollateralStatus => "update_asset_collateral_status",
+            OperatorPermission::Deleverage => "deleverage",
+        };
+        write!(f, "{s}")
+    }
+}
+
+/// Address approved by a user to act on their position
+#[cw_serde]
+pub struct Operator {
+    /// Actions the operator can take
+    pub permissions: Vec<OperatorPermission>,
+    /// Timestamp (in seconds) from which the operator can no longer act, or `None` if the approval
+    /// doesn't expire
+    pub expires: Option<u64>,
+}
+
+impl Operator {
+    /// Return `true` if the operator can take the action at the given block time
+    pub fn is_allowed(&self, permission: OperatorPermission, block_time: u64) -> bool {
+        let expired = self.expires.map(|expires| block_time >= expires).unwrap_or(false);
+        !expired && self.permissions.contains(&permission)
+    }
+}
+
+/// Action which can be paused, in a single market or in all of them
+#[cw_serde]
+#[derive(Copy, Eq, Hash, PartialOrd, Ord)]
+pub enum PausableAction {
+    Deposit,
+    Withdraw,
+    Borrow,
+    Repay,
+    Liquidate,
+    UpdateAssetCollateralStatus,
+}
+
+impl fmt::Display for PausableAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            PausableAction::Deposit => "deposit",
+            PausableAction::Withdraw => "withdraw",
+            PausableAction::Borrow => "borrow",
+            PausableAction::Repay => "repay",
+            PausableAction::Liquidate => "liquidate",
+            PausableAction::UpdateAssetCollateralStatus => "update_asset_collateral_status",
+        };
+        write!(f, "{s}")
+    }
+}
+
+/// Actions paused in a single market, or in all of them
+#[cw_serde]
+#[derive(Copy, Eq, Default)]
+pub struct PauseState {
+    pub deposit: bool,
+    pub withdraw: bool,
+    pub borrow: bool,
+    pub repay: bool,
+    pub liquidate: bool,
+    pub update_asset_collateral_status: bool,
+}
+
+impl PauseState {
+    pub fn is_paused(&self, action: PausableAction) -> bool {
+        match action {
+            PausableAction::Deposit => self.deposit,
+            PausableAction::Withdraw => self.withdraw,
+            PausableAction::Borrow => self.borrow,
+            PausableAction::Repay => self.repay,
+            PausableAction::Liquidate => self.liquidate,
+            PausableAction::UpdateAssetCollateralStatus => self.update_asset_collateral_status,
+        }
+    }
+
+    pub fn set_paused(&mut self, action: PausableAction, paused: bool) {
+        let flag = match action {
+            PausableAction::Deposit => &mut self.deposit,
+            PausableAction::Withdraw => &mut self.withdraw,
+            PausableAction::Borrow => &mut self.borrow,
+            PausableAction::Repay => &mut self.repay,
+            PausableAction::Liquidate => &mut self.liquidate,
+            PausableAction::UpdateAssetCollateralStatus => &mut self.update_asset_collateral_status,
+        };
+        *flag = paused;
+    }
+}
+
+/// Change to the config, to the params of a market or to an e-mode category, waiting for the
+/// timelock to pass
+#[cw_serde]
+#[allow(clippy::large_enum_variant)]
+pub enum ParamsChange {
+    UpdateConfig {
+        config: CreateOrUpdateConfig,
+    },
+    UpdateAsset {
+        denom: String,
+        params: InitOrUpdateAssetParams,
+    },
+    SetEModeCategory {
+        category: EModeCategory,
+    },
+}
+
+#[cw_serde]
+pub struct PendingChange {
+    pub id: u64,
+    pub change: ParamsChange,
+    /// Timestamp (in seconds) from which anyone can apply the change
+    pub executable_at: u64,
+    /// Timestamp (in seconds) from which the change can't be applied anymore, only cancelled
+    pub expires_at: u64,
+}
+
 #[cw_serde]
 pub enum UserHealthStatus {
     NotBorrowing,
@@ -78,6 +288,8 @@ pub struct ConfigResponse {
     pub address_provider: String,
     /// Maximum percentage of outstanding debt that can be covered by a liquidator
     pub close_factor: Decimal,
+    /// Delay (in seconds) before queued changes can be applied
+    pub timelock: u64,
 }
 
 #[cw_serde]
@@ -88,6 +300,54 @@ pub struct UncollateralizedLoanLimitResponse {
     pub limit: Uint128,
 }
 
+#[cw_serde]
+pub struct MarketLiveResponse {
+    /// The market, with its indexes updated to the current block
+    pub market: Market,
+    /// Total amount deposited, including the interest accrued
+    pub total_collateral: Uint128,
+    /// Total amount borrowed, including the interest accrued
+    pub total_debt: Uint128,
+    /// Amount held by the contract, available to withdraw or borrow
+    pub available_liquidity: Uint128,
+    /// Total debt over the sum of the available liquidity and total debt
+    pub utilization_rate: Decimal,
+}
+
+#[cw_serde]
+pub struct OperatorResponse {
+    /// Address of the operator
+    pub operator: String,
+    /// Actions the operator can take
+    pub permissions: Vec<OperatorPermission>,
+    /// Timestamp (in seconds) from which the operator can no longer act, or `None` if the approval
+    /// doesn't expire
+    pub expires: Option<u64>,
+}
+
+#[cw_serde]
+pub struct DeficitResponse {
+    /// Asset denom
+    pub denom: String,
+    /// Debt written off as bad debt and not covered yet
+    pub amount: Uint128,
+}
+
+#[cw_serde]
+pub struct LiquidationPreviewResponse {
+    /// Amount of debt repaid by the liquidator
+    pub debt_amount: Uint128,
+    /// Underlying amount of collateral seized from the user, including the liquidation bonus
+    pub collateral_amount: Uint128,
+    /// Scaled amount of collateral seized from the user
+    pub collateral_amount_scaled: Uint128,
+    /// Amount of the coins sent refunded to the liquidator
+    pub refund_amount: Uint128,
+    /// Liquidation health factor of the user's position after the liquidation, or `None` if no
+    /// debt is left
+    pub liquidation_health_factor: Option<Decimal>,
+}
+
 #[cw_serde]
 pub struct UserDebtResponse {
     /// Asset denom
@@ -125,3 +385,23 @@ pub struct UserPositionResponse {
     pub weighted_liquidation_threshold_collateral: Uint128,
     pub health_status: UserHealthStatus,
 }
+
+#[cw_serde]
+pub struct BorrowerResponse {
+    /// Address of the borrower
+    pub user: String,
+    /// Sub-account of the borrower holding the debt
+    pub account_id: u32,
+    /// Value of the enabled collateral weighted by liquidation threshold, over the value of the
+    /// collateralized debt. The position is liquidatable below 1.
+    pub liquidation_health_factor: Option<Decimal>,
+}
+
+#[cw_serde]
+pub struct BorrowersResponse {
+    /// Borrowers of the page which match the filter
+    pub borrowers: Vec<BorrowerResponse>,
+    /// Address and account id of the last borrower scanned, to be passed as `start_after` to query
+    /// the next page. None once all the borrowers have been scanned.
+    pub last_scanned: Option<(String, u32)>,
+}
@@ -36,6 +36,11 @@ pub fn divide_decimal_by_decimal(a: Decimal, b: Decimal) -> StdResult<Decimal> {
     })
 }
 
+/// Add 'a' and 'b', returning an error instead of panicking on overflow.
+pub fn decimal_checked_add(a: Decimal, b: Decimal) -> StdResult<Decimal> {
+    Ok(Decimal::new(a.atomics().checked_add(b.atomics())?))
+}
+
 /// Divide Uint128 by Decimal.
 /// (Uint128 / numerator / denominator) is equal to (Uint128 * denominator / numerator).
 pub fn divide_uint128_by_decimal(a: Uint128, b: Decimal) -> StdResult<Uint128> {
@@ -155,6 +160,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn checked_decimal_addition() {
+        let a = Decimal::from_ratio(15u128, 100u128);
+        let b = Decimal::from_ratio(5u128, 100u128);
+        assert_eq!(decimal_checked_add(a, b).unwrap(), Decimal::from_ratio(2u128, 10u128));
+
+        let res_error = decimal_checked_add(Decimal::MAX, Decimal::one()).unwrap_err();
+        assert_eq!(
+            res_error,
+            OverflowError::new(OverflowOperation::Add, Uint128::MAX, DECIMAL_FRACTIONAL).into()
+        );
+    }
+
     #[test]
     fn test_divide_uint128_by_decimal() {
         let a = Uint128::new(120u128);
@@ -167,7 +167,11 @@ export const osmoAsset: AssetConfig = {
   max_loan_to_value: '0.59',
   reserve_factor: '0.2',
   liquidation_threshold: '0.61',
-  liquidation_bonus: '0.15',
+  liquidation_bonus: {
+    min_lb: '0.15',
+    max_lb: '0.15',
+    slope: '0',
+  },
   interest_rate_model: {
     optimal_utilization_rate: '0.6',
     base: '0',
@@ -185,7 +189,11 @@ export const atomAsset: AssetConfig = {
   max_loan_to_value: '0.68',
   reserve_factor: '0.2',
   liquidation_threshold: '0.7',
-  liquidation_bonus: '0.15',
+  liquidation_bonus: {
+    min_lb: '0.15',
+    max_lb: '0.15',
+    slope: '0',
+  },
   interest_rate_model: {
     optimal_utilization_rate: '0.6',
     base: '0',
@@ -203,7 +211,11 @@ export const atomAssetTest: AssetConfig = {
   max_loan_to_value: '0.68',
   reserve_factor: '0.2',
   liquidation_threshold: '0.7',
-  liquidation_bonus: '0.15',
+  liquidation_bonus: {
+    min_lb: '0.15',
+    max_lb: '0.15',
+    slope: '0',
+  },
   interest_rate_model: {
     optimal_utilization_rate: '0.6',
     base: '0',
@@ -221,7 +233,11 @@ export const axlUSDCAsset: AssetConfig = {
   max_loan_to_value: '0.74',
   reserve_factor: '0.2',
   liquidation_threshold: '0.75',
-  liquidation_bonus: '0.1',
+  liquidation_bonus: {
+    min_lb: '0.1',
+    max_lb: '0.1',
+    slope: '0',
+  },
   interest_rate_model: {
     optimal_utilization_rate: '0.8',
     base: '0',
@@ -239,7 +255,11 @@ export const axlUSDCAssetTest: AssetConfig = {
   max_loan_to_value: '0.74',
   reserve_factor: '0.2',
   liquidation_threshold: '0.75',
-  liquidation_bonus: '0.1',
+  liquidation_bonus: {
+    min_lb: '0.1',
+    max_lb: '0.1',
+    slope: '0',
+  },
   interest_rate_model: {
     optimal_utilization_rate: '0.8',
     base: '0',
@@ -257,7 +277,11 @@ export const marsAssetTest: AssetConfig = {
   max_loan_to_value: '0.74',
   reserve_factor: '0.2',
   liquidation_threshold: '0.75',
-  liquidation_bonus: '0.1',
+  liquidation_bonus: {
+    min_lb: '0.1',
+    max_lb: '0.1',
+    slope: '0',
+  },
   interest_rate_model: {
     optimal_utilization_rate: '0.8',
     base: '0',
@@ -38,7 +38,11 @@ export interface AssetConfig {
   max_loan_to_value: string
   reserve_factor: string
   liquidation_threshold: string
-  liquidation_bonus: string
+  liquidation_bonus: {
+    min_lb: string
+    max_lb: string
+    slope: string
+  }
   interest_rate_model: {
     optimal_utilization_rate: string
     base: string
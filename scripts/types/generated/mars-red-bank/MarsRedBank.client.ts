@@ -6,7 +6,7 @@
  */
 
 import { CosmWasmClient, SigningCosmWasmClient, ExecuteResult } from '@cosmjs/cosmwasm-stargate'
-import { Coin, StdFee } from '@cosmjs/amino'
+import { StdFee } from '@cosmjs/amino'
 import {
   Decimal,
   InstantiateMsg,
@@ -14,24 +14,61 @@ import {
   ExecuteMsg,
   OwnerUpdate,
   Uint128,
-  InitOrUpdateAssetParams,
+  InterestAccrual,
   InterestRateModel,
+  OperatorPermission,
+  Binary,
+  PausableAction,
+  Action,
+  CallbackMsg,
+  InitOrUpdateAssetParams,
+  InterestRateKink,
+  LiquidationBonus,
+  Liquidation,
+  Coin,
+  EModeCategory,
   QueryMsg,
+  BorrowersResponse,
+  BorrowerResponse,
   ConfigResponse,
+  DeficitResponse,
+  ArrayOfDeficitResponse,
+  ArrayOfEModeCategory,
+  LiquidationPreviewResponse,
+  MarketStatus,
   Market,
+  RiskParamsRamp,
+  MarketLiveResponse,
   ArrayOfMarket,
+  ArrayOfMarketLiveResponse,
+  OperatorResponse,
+  ArrayOfOperatorResponse,
+  PauseState,
+  ParamsChange,
+  PendingChange,
+  ArrayOfPendingChange,
   UncollateralizedLoanLimitResponse,
   ArrayOfUncollateralizedLoanLimitResponse,
   UserCollateralResponse,
   ArrayOfUserCollateralResponse,
   UserDebtResponse,
   ArrayOfUserDebtResponse,
+  NullableEModeCategory,
   UserHealthStatus,
   UserPositionResponse,
 } from './MarsRedBank.types'
 export interface MarsRedBankReadOnlyInterface {
   contractAddress: string
   config: () => Promise<ConfigResponse>
+  pauseState: ({ denom }: { denom?: string }) => Promise<PauseState>
+  pendingChange: ({ id }: { id: number }) => Promise<PendingChange>
+  pendingChanges: ({
+    limit,
+    startAfter,
+  }: {
+    limit?: number
+    startAfter?: number
+  }) => Promise<ArrayOfPendingChange>
   market: ({ denom }: { denom: string }) => Promise<Market>
   markets: ({
     limit,
@@ -40,6 +77,88 @@ export interface MarsRedBankReadOnlyInterface {
     limit?: number
     startAfter?: string
   }) => Promise<ArrayOfMarket>
+  marketLive: ({ denom }: { denom: string }) => Promise<MarketLiveResponse>
+  marketsLive: ({
+    limit,
+    startAfter,
+  }: {
+    limit?: number
+    startAfter?: string
+  }) => Promise<ArrayOfMarketLiveResponse>
+  liquidationBonus: ({
+    denom,
+    liquidationHealthFactor,
+  }: {
+    denom: string
+    liquidationHealthFactor: Decimal
+  }) => Promise<Decimal>
+  liquidationPreview: ({
+    accountId,
+    amount,
+    collateralDenom,
+    debtDenom,
+    user,
+  }: {
+    accountId?: number
+    amount: Uint128
+    collateralDenom: string
+    debtDenom: string
+    user: string
+  }) => Promise<LiquidationPreviewResponse>
+  eModeCategory: ({ id }: { id: number }) => Promise<EModeCategory>
+  eModeCategories: ({
+    limit,
+    startAfter,
+  }: {
+    limit?: number
+    startAfter?: number
+  }) => Promise<ArrayOfEModeCategory>
+  userEMode: ({
+    accountId,
+    user,
+  }: {
+    accountId?: number
+    user: string
+  }) => Promise<NullableEModeCategory>
+  deficit: ({ denom }: { denom: string }) => Promise<DeficitResponse>
+  deficits: ({
+    limit,
+    startAfter,
+  }: {
+    limit?: number
+    startAfter?: string
+  }) => Promise<ArrayOfDeficitResponse>
+  borrowAllowance: ({
+    accountId,
+    delegatee,
+    delegator,
+    denom,
+  }: {
+    accountId?: number
+    delegatee: string
+    delegator: string
+    denom: string
+  }) => Promise<Uint128>
+  operator: ({
+    accountId,
+    operator,
+    user,
+  }: {
+    accountId?: number
+    operator: string
+    user: string
+  }) => Promise<OperatorResponse>
+  operators: ({
+    accountId,
+    limit,
+    startAfter,
+    user,
+  }: {
+    accountId?: number
+    limit?: number
+    startAfter?: string
+    user: string
+  }) => Promise<ArrayOfOperatorResponse>
   uncollateralizedLoanLimit: ({
     denom,
     user,
@@ -56,33 +175,81 @@ export interface MarsRedBankReadOnlyInterface {
     startAfter?: string
     user: string
   }) => Promise<ArrayOfUncollateralizedLoanLimitResponse>
-  userDebt: ({ denom, user }: { denom: string; user: string }) => Promise<UserDebtResponse>
+  userDebt: ({
+    accountId,
+    denom,
+    user,
+  }: {
+    accountId?: number
+    denom: string
+    user: string
+  }) => Promise<UserDebtResponse>
   userDebts: ({
+    accountId,
     limit,
     startAfter,
     user,
   }: {
+    accountId?: number
     limit?: number
     startAfter?: string
     user: string
   }) => Promise<ArrayOfUserDebtResponse>
   userCollateral: ({
+    accountId,
     denom,
     user,
   }: {
+    accountId?: number
     denom: string
     user: string
   }) => Promise<UserCollateralResponse>
+  userTotalCollateralScaled: ({ denom, user }: { denom: string; user: string }) => Promise<Uint128>
   userCollaterals: ({
+    accountId,
     limit,
     startAfter,
     user,
   }: {
+    accountId?: number
     limit?: number
     startAfter?: string
     user: string
   }) => Promise<ArrayOfUserCollateralResponse>
-  userPosition: ({ user }: { user: string }) => Promise<UserPositionResponse>
+  userPosition: ({
+    accountId,
+    user,
+  }: {
+    accountId?: number
+    user: string
+  }) => Promise<UserPositionResponse>
+  borrowers: ({
+    limit,
+    maxHealthFactor,
+    startAfter,
+  }: {
+    limit?: number
+    maxHealthFactor?: Decimal
+    startAfter?: string[][]
+  }) => Promise<BorrowersResponse>
+  maxBorrow: ({
+    accountId,
+    denom,
+    user,
+  }: {
+    accountId?: number
+    denom: string
+    user: string
+  }) => Promise<Uint128>
+  maxWithdraw: ({
+    accountId,
+    denom,
+    user,
+  }: {
+    accountId?: number
+    denom: string
+    user: string
+  }) => Promise<Uint128>
   scaledLiquidityAmount: ({ amount, denom }: { amount: Uint128; denom: string }) => Promise<Uint128>
   scaledDebtAmount: ({ amount, denom }: { amount: Uint128; denom: string }) => Promise<Uint128>
   underlyingLiquidityAmount: ({
@@ -108,15 +275,34 @@ export class MarsRedBankQueryClient implements MarsRedBankReadOnlyInterface {
     this.client = client
     this.contractAddress = contractAddress
     this.config = this.config.bind(this)
+    this.pauseState = this.pauseState.bind(this)
+    this.pendingChange = this.pendingChange.bind(this)
+    this.pendingChanges = this.pendingChanges.bind(this)
     this.market = this.market.bind(this)
     this.markets = this.markets.bind(this)
+    this.marketLive = this.marketLive.bind(this)
+    this.marketsLive = this.marketsLive.bind(this)
+    this.liquidationBonus = this.liquidationBonus.bind(this)
+    this.liquidationPreview = this.liquidationPreview.bind(this)
+    this.eModeCategory = this.eModeCategory.bind(this)
+    this.eModeCategories = this.eModeCategories.bind(this)
+    this.userEMode = this.userEMode.bind(this)
+    this.deficit = this.deficit.bind(this)
+    this.deficits = this.deficits.bind(this)
+    this.borrowAllowance = this.borrowAllowance.bind(this)
+    this.operator = this.operator.bind(this)
+    this.operators = this.operators.bind(this)
     this.uncollateralizedLoanLimit = this.uncollateralizedLoanLimit.bind(this)
     this.uncollateralizedLoanLimits = this.uncollateralizedLoanLimits.bind(this)
     this.userDebt = this.userDebt.bind(this)
     this.userDebts = this.userDebts.bind(this)
     this.userCollateral = this.userCollateral.bind(this)
+    this.userTotalCollateralScaled = this.userTotalCollateralScaled.bind(this)
     this.userCollaterals = this.userCollaterals.bind(this)
     this.userPosition = this.userPosition.bind(this)
+    this.borrowers = this.borrowers.bind(this)
+    this.maxBorrow = this.maxBorrow.bind(this)
+    this.maxWithdraw = this.maxWithdraw.bind(this)
     this.scaledLiquidityAmount = this.scaledLiquidityAmount.bind(this)
     this.scaledDebtAmount = this.scaledDebtAmount.bind(this)
     this.underlyingLiquidityAmount = this.underlyingLiquidityAmount.bind(this)
@@ -128,6 +314,34 @@ export class MarsRedBankQueryClient implements MarsRedBankReadOnlyInterface {
       config: {},
     })
   }
+  pauseState = async ({ denom }: { denom?: string }): Promise<PauseState> => {
+    return this.client.queryContractSmart(this.contractAddress, {
+      pause_state: {
+        denom,
+      },
+    })
+  }
+  pendingChange = async ({ id }: { id: number }): Promise<PendingChange> => {
+    return this.client.queryContractSmart(this.contractAddress, {
+      pending_change: {
+        id,
+      },
+    })
+  }
+  pendingChanges = async ({
+    limit,
+    startAfter,
+  }: {
+    limit?: number
+    startAfter?: number
+  }): Promise<ArrayOfPendingChange> => {
+    return this.client.queryContractSmart(this.contractAddress, {
+      pending_changes: {
+        limit,
+        start_after: startAfter,
+      },
+    })
+  }
   market = async ({ denom }: { denom: string }): Promise<Market> => {
     return this.client.queryContractSmart(this.contractAddress, {
       market: {
@@ -149,6 +363,177 @@ export class MarsRedBankQueryClient implements MarsRedBankReadOnlyInterface {
       },
     })
   }
+  marketLive = async ({ denom }: { denom: string }): Promise<MarketLiveResponse> => {
+    return this.client.queryContractSmart(this.contractAddress, {
+      market_live: {
+        denom,
+      },
+    })
+  }
+  marketsLive = async ({
+    limit,
+    startAfter,
+  }: {
+    limit?: number
+    startAfter?: string
+  }): Promise<ArrayOfMarketLiveResponse> => {
+    return this.client.queryContractSmart(this.contractAddress, {
+      markets_live: {
+        limit,
+        start_after: startAfter,
+      },
+    })
+  }
+  liquidationBonus = async ({
+    denom,
+    liquidationHealthFactor,
+  }: {
+    denom: string
+    liquidationHealthFactor: Decimal
+  }): Promise<Decimal> => {
+    return this.client.queryContractSmart(this.contractAddress, {
+      liquidation_bonus: {
+        denom,
+        liquidation_health_factor: liquidationHealthFactor,
+      },
+    })
+  }
+  liquidationPreview = async ({
+    accountId,
+    amount,
+    collateralDenom,
+    debtDenom,
+    user,
+  }: {
+    accountId?: number
+    amount: Uint128
+    collateralDenom: string
+    debtDenom: string
+    user: string
+  }): Promise<LiquidationPreviewResponse> => {
+    return this.client.queryContractSmart(this.contractAddress, {
+      liquidation_preview: {
+        account_id: accountId,
+        amount,
+        collateral_denom: collateralDenom,
+        debt_denom: debtDenom,
+        user,
+      },
+    })
+  }
+  eModeCategory = async ({ id }: { id: number }): Promise<EModeCategory> => {
+    return this.client.queryContractSmart(this.contractAddress, {
+      e_mode_category: {
+        id,
+      },
+    })
+  }
+  eModeCategories = async ({
+    limit,
+    startAfter,
+  }: {
+    limit?: number
+    startAfter?: number
+  }): Promise<ArrayOfEModeCategory> => {
+    return this.client.queryContractSmart(this.contractAddress, {
+      e_mode_categories: {
+        limit,
+        start_after: startAfter,
+      },
+    })
+  }
+  userEMode = async ({
+    accountId,
+    user,
+  }: {
+    accountId?: number
+    user: string
+  }): Promise<NullableEModeCategory> => {
+    return this.client.queryContractSmart(this.contractAddress, {
+      user_e_mode: {
+        account_id: accountId,
+        user,
+      },
+    })
+  }
+  deficit = async ({ denom }: { denom: string }): Promise<DeficitResponse> => {
+    return this.client.queryContractSmart(this.contractAddress, {
+      deficit: {
+        denom,
+      },
+    })
+  }
+  deficits = async ({
+    limit,
+    startAfter,
+  }: {
+    limit?: number
+    startAfter?: string
+  }): Promise<ArrayOfDeficitResponse> => {
+    return this.client.queryContractSmart(this.contractAddress, {
+      deficits: {
+        limit,
+        start_after: startAfter,
+      },
+    })
+  }
+  borrowAllowance = async ({
+    accountId,
+    delegatee,
+    delegator,
+    denom,
+  }: {
+    accountId?: number
+    delegatee: string
+    delegator: string
+    denom: string
+  }): Promise<Uint128> => {
+    return this.client.queryContractSmart(this.contractAddress, {
+      borrow_allowance: {
+        account_id: accountId,
+        delegatee,
+        delegator,
+        denom,
+      },
+    })
+  }
+  operator = async ({
+    accountId,
+    operator,
+    user,
+  }: {
+    accountId?: number
+    operator: string
+    user: string
+  }): Promise<OperatorResponse> => {
+    return this.client.queryContractSmart(this.contractAddress, {
+      operator: {
+        account_id: accountId,
+        operator,
+        user,
+      },
+    })
+  }
+  operators = async ({
+    accountId,
+    limit,
+    startAfter,
+    user,
+  }: {
+    accountId?: number
+    limit?: number
+    startAfter?: string
+    user: string
+  }): Promise<ArrayOfOperatorResponse> => {
+    return this.client.queryContractSmart(this.contractAddress, {
+      operators: {
+        account_id: accountId,
+        limit,
+        start_after: startAfter,
+        user,
+      },
+    })
+  }
   uncollateralizedLoanLimit = async ({
     denom,
     user,
@@ -181,30 +566,36 @@ export class MarsRedBankQueryClient implements MarsRedBankReadOnlyInterface {
     })
   }
   userDebt = async ({
+    accountId,
     denom,
     user,
   }: {
+    accountId?: number
     denom: string
     user: string
   }): Promise<UserDebtResponse> => {
     return this.client.queryContractSmart(this.contractAddress, {
       user_debt: {
+        account_id: accountId,
         denom,
         user,
       },
     })
   }
   userDebts = async ({
+    accountId,
     limit,
     startAfter,
     user,
   }: {
+    accountId?: number
     limit?: number
     startAfter?: string
     user: string
   }): Promise<ArrayOfUserDebtResponse> => {
     return this.client.queryContractSmart(this.contractAddress, {
       user_debts: {
+        account_id: accountId,
         limit,
         start_after: startAfter,
         user,
@@ -212,39 +603,117 @@ export class MarsRedBankQueryClient implements MarsRedBankReadOnlyInterface {
     })
   }
   userCollateral = async ({
+    accountId,
     denom,
     user,
   }: {
+    accountId?: number
     denom: string
     user: string
   }): Promise<UserCollateralResponse> => {
     return this.client.queryContractSmart(this.contractAddress, {
       user_collateral: {
+        account_id: accountId,
+        denom,
+        user,
+      },
+    })
+  }
+  userTotalCollateralScaled = async ({
+    denom,
+    user,
+  }: {
+    denom: string
+    user: string
+  }): Promise<Uint128> => {
+    return this.client.queryContractSmart(this.contractAddress, {
+      user_total_collateral_scaled: {
         denom,
         user,
       },
     })
   }
   userCollaterals = async ({
+    accountId,
     limit,
     startAfter,
     user,
   }: {
+    accountId?: number
     limit?: number
     startAfter?: string
     user: string
   }): Promise<ArrayOfUserCollateralResponse> => {
     return this.client.queryContractSmart(this.contractAddress, {
       user_collaterals: {
+        account_id: accountId,
         limit,
         start_after: startAfter,
         user,
       },
     })
   }
-  userPosition = async ({ user }: { user: string }): Promise<UserPositionResponse> => {
+  userPosition = async ({
+    accountId,
+    user,
+  }: {
+    accountId?: number
+    user: string
+  }): Promise<UserPositionResponse> => {
     return this.client.queryContractSmart(this.contractAddress, {
       user_position: {
+        account_id: accountId,
+        user,
+      },
+    })
+  }
+  borrowers = async ({
+    limit,
+    maxHealthFactor,
+    startAfter,
+  }: {
+    limit?: number
+    maxHealthFactor?: Decimal
+    startAfter?: string[][]
+  }): Promise<BorrowersResponse> => {
+    return this.client.queryContractSmart(this.contractAddress, {
+      borrowers: {
+        limit,
+        max_health_factor: maxHealthFactor,
+        start_after: startAfter,
+      },
+    })
+  }
+  maxBorrow = async ({
+    accountId,
+    denom,
+    user,
+  }: {
+    accountId?: number
+    denom: string
+    user: string
+  }): Promise<Uint128> => {
+    return this.client.queryContractSmart(this.contractAddress, {
+      max_borrow: {
+        account_id: accountId,
+        denom,
+        user,
+      },
+    })
+  }
+  maxWithdraw = async ({
+    accountId,
+    denom,
+    user,
+  }: {
+    accountId?: number
+    denom: string
+    user: string
+  }): Promise<Uint128> => {
+    return this.client.queryContractSmart(this.contractAddress, {
+      max_withdraw: {
+        account_id: accountId,
+        denom,
         user,
       },
     })
@@ -353,39 +822,97 @@ export interface MarsRedBankInterface extends MarsRedBankReadOnlyInterface {
     memo?: string,
     funds?: Coin[],
   ) => Promise<ExecuteResult>
-  updateUncollateralizedLoanLimit: (
+  executePendingChange: (
     {
-      denom,
-      newLimit,
-      user,
+      id,
     }: {
-      denom: string
-      newLimit: Uint128
-      user: string
+      id: number
     },
     fee?: number | StdFee | 'auto',
     memo?: string,
     funds?: Coin[],
   ) => Promise<ExecuteResult>
-  deposit: (
+  cancelPendingChange: (
     {
-      onBehalfOf,
+      id,
     }: {
-      onBehalfOf?: string
+      id: number
     },
     fee?: number | StdFee | 'auto',
     memo?: string,
     funds?: Coin[],
   ) => Promise<ExecuteResult>
-  withdraw: (
+  delistAsset: (
     {
-      amount,
       denom,
-      recipient,
+      rampDuration,
     }: {
-      amount?: Uint128
       denom: string
-      recipient?: string
+      rampDuration: number
+    },
+    fee?: number | StdFee | 'auto',
+    memo?: string,
+    funds?: Coin[],
+  ) => Promise<ExecuteResult>
+  sunsetAsset: (
+    {
+      denom,
+    }: {
+      denom: string
+    },
+    fee?: number | StdFee | 'auto',
+    memo?: string,
+    funds?: Coin[],
+  ) => Promise<ExecuteResult>
+  removeAsset: (
+    {
+      denom,
+    }: {
+      denom: string
+    },
+    fee?: number | StdFee | 'auto',
+    memo?: string,
+    funds?: Coin[],
+  ) => Promise<ExecuteResult>
+  updateUncollateralizedLoanLimit: (
+    {
+      denom,
+      newLimit,
+      user,
+    }: {
+      denom: string
+      newLimit: Uint128
+      user: string
+    },
+    fee?: number | StdFee | 'auto',
+    memo?: string,
+    funds?: Coin[],
+  ) => Promise<ExecuteResult>
+  deposit: (
+    {
+      accountId,
+      onBehalfOf,
+    }: {
+      accountId?: number
+      onBehalfOf?: string
+    },
+    fee?: number | StdFee | 'auto',
+    memo?: string,
+    funds?: Coin[],
+  ) => Promise<ExecuteResult>
+  withdraw: (
+    {
+      accountId,
+      amount,
+      denom,
+      onBehalfOf,
+      recipient,
+    }: {
+      accountId?: number
+      amount?: Uint128
+      denom: string
+      onBehalfOf?: string
+      recipient?: string
     },
     fee?: number | StdFee | 'auto',
     memo?: string,
@@ -393,22 +920,60 @@ export interface MarsRedBankInterface extends MarsRedBankReadOnlyInterface {
   ) => Promise<ExecuteResult>
   borrow: (
     {
+      accountId,
       amount,
       denom,
+      onBehalfOf,
       recipient,
     }: {
+      accountId?: number
       amount: Uint128
       denom: string
+      onBehalfOf?: string
       recipient?: string
     },
     fee?: number | StdFee | 'auto',
     memo?: string,
     funds?: Coin[],
   ) => Promise<ExecuteResult>
+  approveDelegation: (
+    {
+      accountId,
+      amount,
+      delegatee,
+      denom,
+    }: {
+      accountId?: number
+      amount: Uint128
+      delegatee: string
+      denom: string
+    },
+    fee?: number | StdFee | 'auto',
+    memo?: string,
+    funds?: Coin[],
+  ) => Promise<ExecuteResult>
+  setOperator: (
+    {
+      accountId,
+      expires,
+      operator,
+      permissions,
+    }: {
+      accountId?: number
+      expires?: number
+      operator: string
+      permissions: OperatorPermission[]
+    },
+    fee?: number | StdFee | 'auto',
+    memo?: string,
+    funds?: Coin[],
+  ) => Promise<ExecuteResult>
   repay: (
     {
+      accountId,
       onBehalfOf,
     }: {
+      accountId?: number
       onBehalfOf?: string
     },
     fee?: number | StdFee | 'auto',
@@ -417,10 +982,12 @@ export interface MarsRedBankInterface extends MarsRedBankReadOnlyInterface {
   ) => Promise<ExecuteResult>
   liquidate: (
     {
+      accountId,
       collateralDenom,
       recipient,
       user,
     }: {
+      accountId?: number
       collateralDenom: string
       recipient?: string
       user: string
@@ -429,18 +996,140 @@ export interface MarsRedBankInterface extends MarsRedBankReadOnlyInterface {
     memo?: string,
     funds?: Coin[],
   ) => Promise<ExecuteResult>
+  liquidateMany: (
+    {
+      liquidations,
+      recipient,
+    }: {
+      liquidations: Liquidation[]
+      recipient?: string
+    },
+    fee?: number | StdFee | 'auto',
+    memo?: string,
+    funds?: Coin[],
+  ) => Promise<ExecuteResult>
   updateAssetCollateralStatus: (
     {
+      accountId,
       denom,
       enable,
+      onBehalfOf,
     }: {
+      accountId?: number
       denom: string
       enable: boolean
+      onBehalfOf?: string
+    },
+    fee?: number | StdFee | 'auto',
+    memo?: string,
+    funds?: Coin[],
+  ) => Promise<ExecuteResult>
+  deleverage: (
+    {
+      accountId,
+      amount,
+      denom,
+      onBehalfOf,
+    }: {
+      accountId?: number
+      amount?: Uint128
+      denom: string
+      onBehalfOf?: string
+    },
+    fee?: number | StdFee | 'auto',
+    memo?: string,
+    funds?: Coin[],
+  ) => Promise<ExecuteResult>
+  flashLoan: (
+    {
+      callback,
+      coins,
+    }: {
+      callback: Binary
+      coins: Coin[]
+    },
+    fee?: number | StdFee | 'auto',
+    memo?: string,
+    funds?: Coin[],
+  ) => Promise<ExecuteResult>
+  repayFlashLoan: (
+    fee?: number | StdFee | 'auto',
+    memo?: string,
+    funds?: Coin[],
+  ) => Promise<ExecuteResult>
+  setEModeCategory: (
+    {
+      category,
+    }: {
+      category: EModeCategory
+    },
+    fee?: number | StdFee | 'auto',
+    memo?: string,
+    funds?: Coin[],
+  ) => Promise<ExecuteResult>
+  setUserEMode: (
+    {
+      accountId,
+      categoryId,
+    }: {
+      accountId?: number
+      categoryId?: number
+    },
+    fee?: number | StdFee | 'auto',
+    memo?: string,
+    funds?: Coin[],
+  ) => Promise<ExecuteResult>
+  setPauseState: (
+    {
+      actions,
+      denom,
+      paused,
+    }: {
+      actions: PausableAction[]
+      denom?: string
+      paused: boolean
+    },
+    fee?: number | StdFee | 'auto',
+    memo?: string,
+    funds?: Coin[],
+  ) => Promise<ExecuteResult>
+  coverDeficit: (
+    {
+      denom,
+    }: {
+      denom: string
     },
     fee?: number | StdFee | 'auto',
     memo?: string,
     funds?: Coin[],
   ) => Promise<ExecuteResult>
+  batch: (
+    {
+      accountId,
+      actions,
+    }: {
+      accountId?: number
+      actions: Action[]
+    },
+    fee?: number | StdFee | 'auto',
+    memo?: string,
+    funds?: Coin[],
+  ) => Promise<ExecuteResult>
+  migrateV1Positions: (
+    {
+      limit,
+    }: {
+      limit: number
+    },
+    fee?: number | StdFee | 'auto',
+    memo?: string,
+    funds?: Coin[],
+  ) => Promise<ExecuteResult>
+  callback: (
+    fee?: number | StdFee | 'auto',
+    memo?: string,
+    funds?: Coin[],
+  ) => Promise<ExecuteResult>
 }
 export class MarsRedBankClient extends MarsRedBankQueryClient implements MarsRedBankInterface {
   client: SigningCosmWasmClient
@@ -457,13 +1146,31 @@ export class MarsRedBankClient extends MarsRedBankQueryClient implements MarsRed
     this.updateConfig = this.updateConfig.bind(this)
     this.initAsset = this.initAsset.bind(this)
     this.updateAsset = this.updateAsset.bind(this)
+    this.executePendingChange = this.executePendingChange.bind(this)
+    this.cancelPendingChange = this.cancelPendingChange.bind(this)
+    this.delistAsset = this.delistAsset.bind(this)
+    this.sunsetAsset = this.sunsetAsset.bind(this)
+    this.removeAsset = this.removeAsset.bind(this)
     this.updateUncollateralizedLoanLimit = this.updateUncollateralizedLoanLimit.bind(this)
     this.deposit = this.deposit.bind(this)
     this.withdraw = this.withdraw.bind(this)
     this.borrow = this.borrow.bind(this)
+    this.approveDelegation = this.approveDelegation.bind(this)
+    this.setOperator = this.setOperator.bind(this)
     this.repay = this.repay.bind(this)
     this.liquidate = this.liquidate.bind(this)
+    this.liquidateMany = this.liquidateMany.bind(this)
     this.updateAssetCollateralStatus = this.updateAssetCollateralStatus.bind(this)
+    this.deleverage = this.deleverage.bind(this)
+    this.flashLoan = this.flashLoan.bind(this)
+    this.repayFlashLoan = this.repayFlashLoan.bind(this)
+    this.setEModeCategory = this.setEModeCategory.bind(this)
+    this.setUserEMode = this.setUserEMode.bind(this)
+    this.setPauseState = this.setPauseState.bind(this)
+    this.coverDeficit = this.coverDeficit.bind(this)
+    this.batch = this.batch.bind(this)
+    this.migrateV1Positions = this.migrateV1Positions.bind(this)
+    this.callback = this.callback.bind(this)
   }
 
   updateOwner = async (
@@ -573,15 +1280,11 @@ export class MarsRedBankClient extends MarsRedBankQueryClient implements MarsRed
       funds,
     )
   }
-  updateUncollateralizedLoanLimit = async (
+  executePendingChange = async (
     {
-      denom,
-      newLimit,
-      user,
+      id,
     }: {
-      denom: string
-      newLimit: Uint128
-      user: string
+      id: number
     },
     fee: number | StdFee | 'auto' = 'auto',
     memo?: string,
@@ -591,10 +1294,8 @@ export class MarsRedBankClient extends MarsRedBankQueryClient implements MarsRed
       this.sender,
       this.contractAddress,
       {
-        update_uncollateralized_loan_limit: {
-          denom,
-          new_limit: newLimit,
-          user,
+        execute_pending_change: {
+          id,
         },
       },
       fee,
@@ -602,11 +1303,11 @@ export class MarsRedBankClient extends MarsRedBankQueryClient implements MarsRed
       funds,
     )
   }
-  deposit = async (
+  cancelPendingChange = async (
     {
-      onBehalfOf,
+      id,
     }: {
-      onBehalfOf?: string
+      id: number
     },
     fee: number | StdFee | 'auto' = 'auto',
     memo?: string,
@@ -616,8 +1317,8 @@ export class MarsRedBankClient extends MarsRedBankQueryClient implements MarsRed
       this.sender,
       this.contractAddress,
       {
-        deposit: {
-          on_behalf_of: onBehalfOf,
+        cancel_pending_change: {
+          id,
         },
       },
       fee,
@@ -625,15 +1326,13 @@ export class MarsRedBankClient extends MarsRedBankQueryClient implements MarsRed
       funds,
     )
   }
-  withdraw = async (
+  delistAsset = async (
     {
-      amount,
       denom,
-      recipient,
+      rampDuration,
     }: {
-      amount?: Uint128
       denom: string
-      recipient?: string
+      rampDuration: number
     },
     fee: number | StdFee | 'auto' = 'auto',
     memo?: string,
@@ -643,10 +1342,9 @@ export class MarsRedBankClient extends MarsRedBankQueryClient implements MarsRed
       this.sender,
       this.contractAddress,
       {
-        withdraw: {
-          amount,
+        delist_asset: {
           denom,
-          recipient,
+          ramp_duration: rampDuration,
         },
       },
       fee,
@@ -654,15 +1352,11 @@ export class MarsRedBankClient extends MarsRedBankQueryClient implements MarsRed
       funds,
     )
   }
-  borrow = async (
+  sunsetAsset = async (
     {
-      amount,
       denom,
-      recipient,
     }: {
-      amount: Uint128
       denom: string
-      recipient?: string
     },
     fee: number | StdFee | 'auto' = 'auto',
     memo?: string,
@@ -672,10 +1366,8 @@ export class MarsRedBankClient extends MarsRedBankQueryClient implements MarsRed
       this.sender,
       this.contractAddress,
       {
-        borrow: {
-          amount,
+        sunset_asset: {
           denom,
-          recipient,
         },
       },
       fee,
@@ -683,11 +1375,11 @@ export class MarsRedBankClient extends MarsRedBankQueryClient implements MarsRed
       funds,
     )
   }
-  repay = async (
+  removeAsset = async (
     {
-      onBehalfOf,
+      denom,
     }: {
-      onBehalfOf?: string
+      denom: string
     },
     fee: number | StdFee | 'auto' = 'auto',
     memo?: string,
@@ -697,8 +1389,8 @@ export class MarsRedBankClient extends MarsRedBankQueryClient implements MarsRed
       this.sender,
       this.contractAddress,
       {
-        repay: {
-          on_behalf_of: onBehalfOf,
+        remove_asset: {
+          denom,
         },
       },
       fee,
@@ -706,14 +1398,14 @@ export class MarsRedBankClient extends MarsRedBankQueryClient implements MarsRed
       funds,
     )
   }
-  liquidate = async (
+  updateUncollateralizedLoanLimit = async (
     {
-      collateralDenom,
-      recipient,
+      denom,
+      newLimit,
       user,
     }: {
-      collateralDenom: string
-      recipient?: string
+      denom: string
+      newLimit: Uint128
       user: string
     },
     fee: number | StdFee | 'auto' = 'auto',
@@ -724,9 +1416,9 @@ export class MarsRedBankClient extends MarsRedBankQueryClient implements MarsRed
       this.sender,
       this.contractAddress,
       {
-        liquidate: {
-          collateral_denom: collateralDenom,
-          recipient,
+        update_uncollateralized_loan_limit: {
+          denom,
+          new_limit: newLimit,
           user,
         },
       },
@@ -735,13 +1427,45 @@ export class MarsRedBankClient extends MarsRedBankQueryClient implements MarsRed
       funds,
     )
   }
-  updateAssetCollateralStatus = async (
+  deposit = async (
+    {
+      accountId,
+      onBehalfOf,
+    }: {
+      accountId?: number
+      onBehalfOf?: string
+    },
+    fee: number | StdFee | 'auto' = 'auto',
+    memo?: string,
+    funds?: Coin[],
+  ): Promise<ExecuteResult> => {
+    return await this.client.execute(
+      this.sender,
+      this.contractAddress,
+      {
+        deposit: {
+          account_id: accountId,
+          on_behalf_of: onBehalfOf,
+        },
+      },
+      fee,
+      memo,
+      funds,
+    )
+  }
+  withdraw = async (
     {
+      accountId,
+      amount,
       denom,
-      enable,
+      onBehalfOf,
+      recipient,
     }: {
+      accountId?: number
+      amount?: Uint128
       denom: string
-      enable: boolean
+      onBehalfOf?: string
+      recipient?: string
     },
     fee: number | StdFee | 'auto' = 'auto',
     memo?: string,
@@ -751,10 +1475,468 @@ export class MarsRedBankClient extends MarsRedBankQueryClient implements MarsRed
       this.sender,
       this.contractAddress,
       {
-        update_asset_collateral_status: {
+        withdraw: {
+          account_id: accountId,
+          amount,
           denom,
-          enable,
-        },
+          on_behalf_of: onBehalfOf,
+          recipient,
+        },
+      },
+      fee,
+      memo,
+      funds,
+    )
+  }
+  borrow = async (
+    {
+      accountId,
+      amount,
+      denom,
+      onBehalfOf,
+      recipient,
+    }: {
+      accountId?: number
+      amount: Uint128
+      denom: string
+      onBehalfOf?: string
+      recipient?: string
+    },
+    fee: number | StdFee | 'auto' = 'auto',
+    memo?: string,
+    funds?: Coin[],
+  ): Promise<ExecuteResult> => {
+    return await this.client.execute(
+      this.sender,
+      this.contractAddress,
+      {
+        borrow: {
+          account_id: accountId,
+          amount,
+          denom,
+          on_behalf_of: onBehalfOf,
+          recipient,
+        },
+      },
+      fee,
+      memo,
+      funds,
+    )
+  }
+  approveDelegation = async (
+    {
+      accountId,
+      amount,
+      delegatee,
+      denom,
+    }: {
+      accountId?: number
+      amount: Uint128
+      delegatee: string
+      denom: string
+    },
+    fee: number | StdFee | 'auto' = 'auto',
+    memo?: string,
+    funds?: Coin[],
+  ): Promise<ExecuteResult> => {
+    return await this.client.execute(
+      this.sender,
+      this.contractAddress,
+      {
+        approve_delegation: {
+          account_id: accountId,
+          amount,
+          delegatee,
+          denom,
+        },
+      },
+      fee,
+      memo,
+      funds,
+    )
+  }
+  setOperator = async (
+    {
+      accountId,
+      expires,
+      operator,
+      permissions,
+    }: {
+      accountId?: number
+      expires?: number
+      operator: string
+      permissions: OperatorPermission[]
+    },
+    fee: number | StdFee | 'auto' = 'auto',
+    memo?: string,
+    funds?: Coin[],
+  ): Promise<ExecuteResult> => {
+    return await this.client.execute(
+      this.sender,
+      this.contractAddress,
+      {
+        set_operator: {
+          account_id: accountId,
+          expires,
+          operator,
+          permissions,
+        },
+      },
+      fee,
+      memo,
+      funds,
+    )
+  }
+  repay = async (
+    {
+      accountId,
+      onBehalfOf,
+    }: {
+      accountId?: number
+      onBehalfOf?: string
+    },
+    fee: number | StdFee | 'auto' = 'auto',
+    memo?: string,
+    funds?: Coin[],
+  ): Promise<ExecuteResult> => {
+    return await this.client.execute(
+      this.sender,
+      this.contractAddress,
+      {
+        repay: {
+          account_id: accountId,
+          on_behalf_of: onBehalfOf,
+        },
+      },
+      fee,
+      memo,
+      funds,
+    )
+  }
+  liquidate = async (
+    {
+      accountId,
+      collateralDenom,
+      recipient,
+      user,
+    }: {
+      accountId?: number
+      collateralDenom: string
+      recipient?: string
+      user: string
+    },
+    fee: number | StdFee | 'auto' = 'auto',
+    memo?: string,
+    funds?: Coin[],
+  ): Promise<ExecuteResult> => {
+    return await this.client.execute(
+      this.sender,
+      this.contractAddress,
+      {
+        liquidate: {
+          account_id: accountId,
+          collateral_denom: collateralDenom,
+          recipient,
+          user,
+        },
+      },
+      fee,
+      memo,
+      funds,
+    )
+  }
+  liquidateMany = async (
+    {
+      liquidations,
+      recipient,
+    }: {
+      liquidations: Liquidation[]
+      recipient?: string
+    },
+    fee: number | StdFee | 'auto' = 'auto',
+    memo?: string,
+    funds?: Coin[],
+  ): Promise<ExecuteResult> => {
+    return await this.client.execute(
+      this.sender,
+      this.contractAddress,
+      {
+        liquidate_many: {
+          liquidations,
+          recipient,
+        },
+      },
+      fee,
+      memo,
+      funds,
+    )
+  }
+  updateAssetCollateralStatus = async (
+    {
+      accountId,
+      denom,
+      enable,
+      onBehalfOf,
+    }: {
+      accountId?: number
+      denom: string
+      enable: boolean
+      onBehalfOf?: string
+    },
+    fee: number | StdFee | 'auto' = 'auto',
+    memo?: string,
+    funds?: Coin[],
+  ): Promise<ExecuteResult> => {
+    return await this.client.execute(
+      this.sender,
+      this.contractAddress,
+      {
+        update_asset_collateral_status: {
+          account_id: accountId,
+          denom,
+          enable,
+          on_behalf_of: onBehalfOf,
+        },
+      },
+      fee,
+      memo,
+      funds,
+    )
+  }
+  deleverage = async (
+    {
+      accountId,
+      amount,
+      denom,
+      onBehalfOf,
+    }: {
+      accountId?: number
+      amount?: Uint128
+      denom: string
+      onBehalfOf?: string
+    },
+    fee: number | StdFee | 'auto' = 'auto',
+    memo?: string,
+    funds?: Coin[],
+  ): Promise<ExecuteResult> => {
+    return await this.client.execute(
+      this.sender,
+      this.contractAddress,
+      {
+        deleverage: {
+          account_id: accountId,
+          amount,
+          denom,
+          on_behalf_of: onBehalfOf,
+        },
+      },
+      fee,
+      memo,
+      funds,
+    )
+  }
+  flashLoan = async (
+    {
+      callback,
+      coins,
+    }: {
+      callback: Binary
+      coins: Coin[]
+    },
+    fee: number | StdFee | 'auto' = 'auto',
+    memo?: string,
+    funds?: Coin[],
+  ): Promise<ExecuteResult> => {
+    return await this.client.execute(
+      this.sender,
+      this.contractAddress,
+      {
+        flash_loan: {
+          callback,
+          coins,
+        },
+      },
+      fee,
+      memo,
+      funds,
+    )
+  }
+  repayFlashLoan = async (
+    fee: number | StdFee | 'auto' = 'auto',
+    memo?: string,
+    funds?: Coin[],
+  ): Promise<ExecuteResult> => {
+    return await this.client.execute(
+      this.sender,
+      this.contractAddress,
+      {
+        repay_flash_loan: {},
+      },
+      fee,
+      memo,
+      funds,
+    )
+  }
+  setEModeCategory = async (
+    {
+      category,
+    }: {
+      category: EModeCategory
+    },
+    fee: number | StdFee | 'auto' = 'auto',
+    memo?: string,
+    funds?: Coin[],
+  ): Promise<ExecuteResult> => {
+    return await this.client.execute(
+      this.sender,
+      this.contractAddress,
+      {
+        set_e_mode_category: {
+          category,
+        },
+      },
+      fee,
+      memo,
+      funds,
+    )
+  }
+  setUserEMode = async (
+    {
+      accountId,
+      categoryId,
+    }: {
+      accountId?: number
+      categoryId?: number
+    },
+    fee: number | StdFee | 'auto' = 'auto',
+    memo?: string,
+    funds?: Coin[],
+  ): Promise<ExecuteResult> => {
+    return await this.client.execute(
+      this.sender,
+      this.contractAddress,
+      {
+        set_user_e_mode: {
+          account_id: accountId,
+          category_id: categoryId,
+        },
+      },
+      fee,
+      memo,
+      funds,
+    )
+  }
+  setPauseState = async (
+    {
+      actions,
+      denom,
+      paused,
+    }: {
+      actions: PausableAction[]
+      denom?: string
+      paused: boolean
+    },
+    fee: number | StdFee | 'auto' = 'auto',
+    memo?: string,
+    funds?: Coin[],
+  ): Promise<ExecuteResult> => {
+    return await this.client.execute(
+      this.sender,
+      this.contractAddress,
+      {
+        set_pause_state: {
+          actions,
+          denom,
+          paused,
+        },
+      },
+      fee,
+      memo,
+      funds,
+    )
+  }
+  coverDeficit = async (
+    {
+      denom,
+    }: {
+      denom: string
+    },
+    fee: number | StdFee | 'auto' = 'auto',
+    memo?: string,
+    funds?: Coin[],
+  ): Promise<ExecuteResult> => {
+    return await this.client.execute(
+      this.sender,
+      this.contractAddress,
+      {
+        cover_deficit: {
+          denom,
+        },
+      },
+      fee,
+      memo,
+      funds,
+    )
+  }
+  batch = async (
+    {
+      accountId,
+      actions,
+    }: {
+      accountId?: number
+      actions: Action[]
+    },
+    fee: number | StdFee | 'auto' = 'auto',
+    memo?: string,
+    funds?: Coin[],
+  ): Promise<ExecuteResult> => {
+    return await this.client.execute(
+      this.sender,
+      this.contractAddress,
+      {
+        batch: {
+          account_id: accountId,
+          actions,
+        },
+      },
+      fee,
+      memo,
+      funds,
+    )
+  }
+  migrateV1Positions = async (
+    {
+      limit,
+    }: {
+      limit: number
+    },
+    fee: number | StdFee | 'auto' = 'auto',
+    memo?: string,
+    funds?: Coin[],
+  ): Promise<ExecuteResult> => {
+    return await this.client.execute(
+      this.sender,
+      this.contractAddress,
+      {
+        migrate_v1_positions: {
+          limit,
+        },
+      },
+      fee,
+      memo,
+      funds,
+    )
+  }
+  callback = async (
+    fee: number | StdFee | 'auto' = 'auto',
+    memo?: string,
+    funds?: Coin[],
+  ): Promise<ExecuteResult> => {
+    return await this.client.execute(
+      this.sender,
+      this.contractAddress,
+      {
+        callback: {},
       },
       fee,
       memo,
@@ -7,7 +7,7 @@
 
 import { UseQueryOptions, useQuery, useMutation, UseMutationOptions } from '@tanstack/react-query'
 import { ExecuteResult } from '@cosmjs/cosmwasm-stargate'
-import { StdFee, Coin } from '@cosmjs/amino'
+import { StdFee } from '@cosmjs/amino'
 import {
   Decimal,
   InstantiateMsg,
@@ -15,18 +15,46 @@ import {
   ExecuteMsg,
   OwnerUpdate,
   Uint128,
-  InitOrUpdateAssetParams,
+  InterestAccrual,
   InterestRateModel,
+  OperatorPermission,
+  Binary,
+  PausableAction,
+  Action,
+  CallbackMsg,
+  InitOrUpdateAssetParams,
+  InterestRateKink,
+  LiquidationBonus,
+  Liquidation,
+  Coin,
+  EModeCategory,
   QueryMsg,
+  BorrowersResponse,
+  BorrowerResponse,
   ConfigResponse,
+  DeficitResponse,
+  ArrayOfDeficitResponse,
+  ArrayOfEModeCategory,
+  LiquidationPreviewResponse,
+  MarketStatus,
   Market,
+  RiskParamsRamp,
+  MarketLiveResponse,
   ArrayOfMarket,
+  ArrayOfMarketLiveResponse,
+  OperatorResponse,
+  ArrayOfOperatorResponse,
+  PauseState,
+  ParamsChange,
+  PendingChange,
+  ArrayOfPendingChange,
   UncollateralizedLoanLimitResponse,
   ArrayOfUncollateralizedLoanLimitResponse,
   UserCollateralResponse,
   ArrayOfUserCollateralResponse,
   UserDebtResponse,
   ArrayOfUserDebtResponse,
+  NullableEModeCategory,
   UserHealthStatus,
   UserPositionResponse,
 } from './MarsRedBank.types'
@@ -41,10 +69,56 @@ export const marsRedBankQueryKeys = {
     [{ ...marsRedBankQueryKeys.contract[0], address: contractAddress }] as const,
   config: (contractAddress: string | undefined, args?: Record<string, unknown>) =>
     [{ ...marsRedBankQueryKeys.address(contractAddress)[0], method: 'config', args }] as const,
+  pauseState: (contractAddress: string | undefined, args?: Record<string, unknown>) =>
+    [{ ...marsRedBankQueryKeys.address(contractAddress)[0], method: 'pause_state', args }] as const,
+  pendingChange: (contractAddress: string | undefined, args?: Record<string, unknown>) =>
+    [
+      { ...marsRedBankQueryKeys.address(contractAddress)[0], method: 'pending_change', args },
+    ] as const,
+  pendingChanges: (contractAddress: string | undefined, args?: Record<string, unknown>) =>
+    [
+      { ...marsRedBankQueryKeys.address(contractAddress)[0], method: 'pending_changes', args },
+    ] as const,
   market: (contractAddress: string | undefined, args?: Record<string, unknown>) =>
     [{ ...marsRedBankQueryKeys.address(contractAddress)[0], method: 'market', args }] as const,
   markets: (contractAddress: string | undefined, args?: Record<string, unknown>) =>
     [{ ...marsRedBankQueryKeys.address(contractAddress)[0], method: 'markets', args }] as const,
+  marketLive: (contractAddress: string | undefined, args?: Record<string, unknown>) =>
+    [{ ...marsRedBankQueryKeys.address(contractAddress)[0], method: 'market_live', args }] as const,
+  marketsLive: (contractAddress: string | undefined, args?: Record<string, unknown>) =>
+    [
+      { ...marsRedBankQueryKeys.address(contractAddress)[0], method: 'markets_live', args },
+    ] as const,
+  liquidationBonus: (contractAddress: string | undefined, args?: Record<string, unknown>) =>
+    [
+      { ...marsRedBankQueryKeys.address(contractAddress)[0], method: 'liquidation_bonus', args },
+    ] as const,
+  liquidationPreview: (contractAddress: string | undefined, args?: Record<string, unknown>) =>
+    [
+      { ...marsRedBankQueryKeys.address(contractAddress)[0], method: 'liquidation_preview', args },
+    ] as const,
+  eModeCategory: (contractAddress: string | undefined, args?: Record<string, unknown>) =>
+    [
+      { ...marsRedBankQueryKeys.address(contractAddress)[0], method: 'e_mode_category', args },
+    ] as const,
+  eModeCategories: (contractAddress: string | undefined, args?: Record<string, unknown>) =>
+    [
+      { ...marsRedBankQueryKeys.address(contractAddress)[0], method: 'e_mode_categories', args },
+    ] as const,
+  userEMode: (contractAddress: string | undefined, args?: Record<string, unknown>) =>
+    [{ ...marsRedBankQueryKeys.address(contractAddress)[0], method: 'user_e_mode', args }] as const,
+  deficit: (contractAddress: string | undefined, args?: Record<string, unknown>) =>
+    [{ ...marsRedBankQueryKeys.address(contractAddress)[0], method: 'deficit', args }] as const,
+  deficits: (contractAddress: string | undefined, args?: Record<string, unknown>) =>
+    [{ ...marsRedBankQueryKeys.address(contractAddress)[0], method: 'deficits', args }] as const,
+  borrowAllowance: (contractAddress: string | undefined, args?: Record<string, unknown>) =>
+    [
+      { ...marsRedBankQueryKeys.address(contractAddress)[0], method: 'borrow_allowance', args },
+    ] as const,
+  operator: (contractAddress: string | undefined, args?: Record<string, unknown>) =>
+    [{ ...marsRedBankQueryKeys.address(contractAddress)[0], method: 'operator', args }] as const,
+  operators: (contractAddress: string | undefined, args?: Record<string, unknown>) =>
+    [{ ...marsRedBankQueryKeys.address(contractAddress)[0], method: 'operators', args }] as const,
   uncollateralizedLoanLimit: (
     contractAddress: string | undefined,
     args?: Record<string, unknown>,
@@ -75,6 +149,17 @@ export const marsRedBankQueryKeys = {
     [
       { ...marsRedBankQueryKeys.address(contractAddress)[0], method: 'user_collateral', args },
     ] as const,
+  userTotalCollateralScaled: (
+    contractAddress: string | undefined,
+    args?: Record<string, unknown>,
+  ) =>
+    [
+      {
+        ...marsRedBankQueryKeys.address(contractAddress)[0],
+        method: 'user_total_collateral_scaled',
+        args,
+      },
+    ] as const,
   userCollaterals: (contractAddress: string | undefined, args?: Record<string, unknown>) =>
     [
       { ...marsRedBankQueryKeys.address(contractAddress)[0], method: 'user_collaterals', args },
@@ -83,6 +168,14 @@ export const marsRedBankQueryKeys = {
     [
       { ...marsRedBankQueryKeys.address(contractAddress)[0], method: 'user_position', args },
     ] as const,
+  borrowers: (contractAddress: string | undefined, args?: Record<string, unknown>) =>
+    [{ ...marsRedBankQueryKeys.address(contractAddress)[0], method: 'borrowers', args }] as const,
+  maxBorrow: (contractAddress: string | undefined, args?: Record<string, unknown>) =>
+    [{ ...marsRedBankQueryKeys.address(contractAddress)[0], method: 'max_borrow', args }] as const,
+  maxWithdraw: (contractAddress: string | undefined, args?: Record<string, unknown>) =>
+    [
+      { ...marsRedBankQueryKeys.address(contractAddress)[0], method: 'max_withdraw', args },
+    ] as const,
   scaledLiquidityAmount: (contractAddress: string | undefined, args?: Record<string, unknown>) =>
     [
       {
@@ -220,9 +313,86 @@ export function useMarsRedBankScaledLiquidityAmountQuery<TData = Uint128>({
     { ...options, enabled: !!client && (options?.enabled != undefined ? options.enabled : true) },
   )
 }
+export interface MarsRedBankMaxWithdrawQuery<TData> extends MarsRedBankReactQuery<Uint128, TData> {
+  args: {
+    accountId?: number
+    denom: string
+    user: string
+  }
+}
+export function useMarsRedBankMaxWithdrawQuery<TData = Uint128>({
+  client,
+  args,
+  options,
+}: MarsRedBankMaxWithdrawQuery<TData>) {
+  return useQuery<Uint128, Error, TData>(
+    marsRedBankQueryKeys.maxWithdraw(client?.contractAddress, args),
+    () =>
+      client
+        ? client.maxWithdraw({
+            accountId: args.accountId,
+            denom: args.denom,
+            user: args.user,
+          })
+        : Promise.reject(new Error('Invalid client')),
+    { ...options, enabled: !!client && (options?.enabled != undefined ? options.enabled : true) },
+  )
+}
+export interface MarsRedBankMaxBorrowQuery<TData> extends MarsRedBankReactQuery<Uint128, TData> {
+  args: {
+    accountId?: number
+    denom: string
+    user: string
+  }
+}
+export function useMarsRedBankMaxBorrowQuery<TData = Uint128>({
+  client,
+  args,
+  options,
+}: MarsRedBankMaxBorrowQuery<TData>) {
+  return useQuery<Uint128, Error, TData>(
+    marsRedBankQueryKeys.maxBorrow(client?.contractAddress, args),
+    () =>
+      client
+        ? client.maxBorrow({
+            accountId: args.accountId,
+            denom: args.denom,
+            user: args.user,
+          })
+        : Promise.reject(new Error('Invalid client')),
+    { ...options, enabled: !!client && (options?.enabled != undefined ? options.enabled : true) },
+  )
+}
+export interface MarsRedBankBorrowersQuery<TData>
+  extends MarsRedBankReactQuery<BorrowersResponse, TData> {
+  args: {
+    limit?: number
+    maxHealthFactor?: Decimal
+    startAfter?: string[][]
+  }
+}
+export function useMarsRedBankBorrowersQuery<TData = BorrowersResponse>({
+  client,
+  args,
+  options,
+}: MarsRedBankBorrowersQuery<TData>) {
+  return useQuery<BorrowersResponse, Error, TData>(
+    marsRedBankQueryKeys.borrowers(client?.contractAddress, args),
+    () =>
+      client
+        ? client.borrowers({
+            limit: args.limit,
+            maxHealthFactor: args.maxHealthFactor,
+            startAfter: args.startAfter,
+          })
+        : Promise.reject(new Error('Invalid client')),
+    { ...options, enabled: !!client && (options?.enabled != undefined ? options.enabled : true) },
+  )
+}
 export interface MarsRedBankUserPositionQuery<TData>
   extends MarsRedBankReactQuery<UserPositionResponse, TData> {
   args: {
+    accountId?: number
     user: string
   }
 }
@@ -236,6 +406,7 @@ export function useMarsRedBankUserPositionQuery<TData = UserPositionResponse>({
     () =>
       client
         ? client.userPosition({
+            accountId: args.accountId,
             user: args.user,
           })
         : Promise.reject(new Error('Invalid client')),
@@ -245,6 +416,7 @@ export function useMarsRedBankUserPositionQuery<TData = UserPositionResponse>({
 export interface MarsRedBankUserCollateralsQuery<TData>
   extends MarsRedBankReactQuery<ArrayOfUserCollateralResponse, TData> {
   args: {
+    accountId?: number
     limit?: number
     startAfter?: string
     user: string
@@ -260,6 +432,7 @@ export function useMarsRedBankUserCollateralsQuery<TData = ArrayOfUserCollateral
     () =>
       client
         ? client.userCollaterals({
+            accountId: args.accountId,
             limit: args.limit,
             startAfter: args.startAfter,
             user: args.user,
@@ -268,9 +441,34 @@ export function useMarsRedBankUserCollateralsQuery<TData = ArrayOfUserCollateral
     { ...options, enabled: !!client && (options?.enabled != undefined ? options.enabled : true) },
   )
 }
+export interface MarsRedBankUserTotalCollateralScaledQuery<TData>
+  extends MarsRedBankReactQuery<Uint128, TData> {
+  args: {
+    denom: string
+    user: string
+  }
+}
+export function useMarsRedBankUserTotalCollateralScaledQuery<TData = Uint128>({
+  client,
+  args,
+  options,
+}: MarsRedBankUserTotalCollateralScaledQuery<TData>) {
+  return useQuery<Uint128, Error, TData>(
+    marsRedBankQueryKeys.userTotalCollateralScaled(client?.contractAddress, args),
+    () =>
+      client
+        ? client.userTotalCollateralScaled({
+            denom: args.denom,
+            user: args.user,
+          })
+        : Promise.reject(new Error('Invalid client')),
+    { ...options, enabled: !!client && (options?.enabled != undefined ? options.enabled : true) },
+  )
+}
 export interface MarsRedBankUserCollateralQuery<TData>
   extends MarsRedBankReactQuery<UserCollateralResponse, TData> {
   args: {
+    accountId?: number
     denom: string
     user: string
   }
@@ -285,6 +483,7 @@ export function useMarsRedBankUserCollateralQuery<TData = UserCollateralResponse
     () =>
       client
         ? client.userCollateral({
+            accountId: args.accountId,
             denom: args.denom,
             user: args.user,
           })
@@ -295,6 +494,7 @@ export function useMarsRedBankUserCollateralQuery<TData = UserCollateralResponse
 export interface MarsRedBankUserDebtsQuery<TData>
   extends MarsRedBankReactQuery<ArrayOfUserDebtResponse, TData> {
   args: {
+    accountId?: number
     limit?: number
     startAfter?: string
     user: string
@@ -310,6 +510,7 @@ export function useMarsRedBankUserDebtsQuery<TData = ArrayOfUserDebtResponse>({
     () =>
       client
         ? client.userDebts({
+            accountId: args.accountId,
             limit: args.limit,
             startAfter: args.startAfter,
             user: args.user,
@@ -321,6 +522,7 @@ export function useMarsRedBankUserDebtsQuery<TData = ArrayOfUserDebtResponse>({
 export interface MarsRedBankUserDebtQuery<TData>
   extends MarsRedBankReactQuery<UserDebtResponse, TData> {
   args: {
+    accountId?: number
     denom: string
     user: string
   }
@@ -335,6 +537,7 @@ export function useMarsRedBankUserDebtQuery<TData = UserDebtResponse>({
     () =>
       client
         ? client.userDebt({
+            accountId: args.accountId,
             denom: args.denom,
             user: args.user,
           })
@@ -388,138 +591,809 @@ export function useMarsRedBankUncollateralizedLoanLimitQuery<
     { ...options, enabled: !!client && (options?.enabled != undefined ? options.enabled : true) },
   )
 }
-export interface MarsRedBankMarketsQuery<TData>
-  extends MarsRedBankReactQuery<ArrayOfMarket, TData> {
+export interface MarsRedBankOperatorsQuery<TData>
+  extends MarsRedBankReactQuery<ArrayOfOperatorResponse, TData> {
   args: {
+    accountId?: number
     limit?: number
     startAfter?: string
+    user: string
   }
 }
-export function useMarsRedBankMarketsQuery<TData = ArrayOfMarket>({
+export function useMarsRedBankOperatorsQuery<TData = ArrayOfOperatorResponse>({
   client,
   args,
   options,
-}: MarsRedBankMarketsQuery<TData>) {
-  return useQuery<ArrayOfMarket, Error, TData>(
-    marsRedBankQueryKeys.markets(client?.contractAddress, args),
+}: MarsRedBankOperatorsQuery<TData>) {
+  return useQuery<ArrayOfOperatorResponse, Error, TData>(
+    marsRedBankQueryKeys.operators(client?.contractAddress, args),
     () =>
       client
-        ? client.markets({
+        ? client.operators({
+            accountId: args.accountId,
             limit: args.limit,
             startAfter: args.startAfter,
+            user: args.user,
           })
         : Promise.reject(new Error('Invalid client')),
     { ...options, enabled: !!client && (options?.enabled != undefined ? options.enabled : true) },
   )
 }
-export interface MarsRedBankMarketQuery<TData> extends MarsRedBankReactQuery<Market, TData> {
+export interface MarsRedBankOperatorQuery<TData>
+  extends MarsRedBankReactQuery<OperatorResponse, TData> {
+  args: {
+    accountId?: number
+    operator: string
+    user: string
+  }
+}
+export function useMarsRedBankOperatorQuery<TData = OperatorResponse>({
+  client,
+  args,
+  options,
+}: MarsRedBankOperatorQuery<TData>) {
+  return useQuery<OperatorResponse, Error, TData>(
+    marsRedBankQueryKeys.operator(client?.contractAddress, args),
+    () =>
+      client
+        ? client.operator({
+            accountId: args.accountId,
+            operator: args.operator,
+            user: args.user,
+          })
+        : Promise.reject(new Error('Invalid client')),
+    { ...options, enabled: !!client && (options?.enabled != undefined ? options.enabled : true) },
+  )
+}
+export interface MarsRedBankBorrowAllowanceQuery<TData>
+  extends MarsRedBankReactQuery<Uint128, TData> {
   args: {
+    accountId?: number
+    delegatee: string
+    delegator: string
     denom: string
   }
 }
-export function useMarsRedBankMarketQuery<TData = Market>({
+export function useMarsRedBankBorrowAllowanceQuery<TData = Uint128>({
   client,
   args,
   options,
-}: MarsRedBankMarketQuery<TData>) {
-  return useQuery<Market, Error, TData>(
-    marsRedBankQueryKeys.market(client?.contractAddress, args),
+}: MarsRedBankBorrowAllowanceQuery<TData>) {
+  return useQuery<Uint128, Error, TData>(
+    marsRedBankQueryKeys.borrowAllowance(client?.contractAddress, args),
     () =>
       client
-        ? client.market({
+        ? client.borrowAllowance({
+            accountId: args.accountId,
+            delegatee: args.delegatee,
+            delegator: args.delegator,
             denom: args.denom,
           })
         : Promise.reject(new Error('Invalid client')),
     { ...options, enabled: !!client && (options?.enabled != undefined ? options.enabled : true) },
   )
 }
-export interface MarsRedBankConfigQuery<TData>
-  extends MarsRedBankReactQuery<ConfigResponse, TData> {}
-export function useMarsRedBankConfigQuery<TData = ConfigResponse>({
+export interface MarsRedBankDeficitsQuery<TData>
+  extends MarsRedBankReactQuery<ArrayOfDeficitResponse, TData> {
+  args: {
+    limit?: number
+    startAfter?: string
+  }
+}
+export function useMarsRedBankDeficitsQuery<TData = ArrayOfDeficitResponse>({
   client,
+  args,
   options,
-}: MarsRedBankConfigQuery<TData>) {
-  return useQuery<ConfigResponse, Error, TData>(
-    marsRedBankQueryKeys.config(client?.contractAddress),
-    () => (client ? client.config() : Promise.reject(new Error('Invalid client'))),
+}: MarsRedBankDeficitsQuery<TData>) {
+  return useQuery<ArrayOfDeficitResponse, Error, TData>(
+    marsRedBankQueryKeys.deficits(client?.contractAddress, args),
+    () =>
+      client
+        ? client.deficits({
+            limit: args.limit,
+            startAfter: args.startAfter,
+          })
+        : Promise.reject(new Error('Invalid client')),
     { ...options, enabled: !!client && (options?.enabled != undefined ? options.enabled : true) },
   )
 }
-export interface MarsRedBankUpdateAssetCollateralStatusMutation {
-  client: MarsRedBankClient
-  msg: {
+export interface MarsRedBankDeficitQuery<TData>
+  extends MarsRedBankReactQuery<DeficitResponse, TData> {
+  args: {
     denom: string
-    enable: boolean
-  }
-  args?: {
-    fee?: number | StdFee | 'auto'
-    memo?: string
-    funds?: Coin[]
   }
 }
-export function useMarsRedBankUpdateAssetCollateralStatusMutation(
-  options?: Omit<
-    UseMutationOptions<ExecuteResult, Error, MarsRedBankUpdateAssetCollateralStatusMutation>,
-    'mutationFn'
-  >,
-) {
-  return useMutation<ExecuteResult, Error, MarsRedBankUpdateAssetCollateralStatusMutation>(
-    ({ client, msg, args: { fee, memo, funds } = {} }) =>
-      client.updateAssetCollateralStatus(msg, fee, memo, funds),
-    options,
+export function useMarsRedBankDeficitQuery<TData = DeficitResponse>({
+  client,
+  args,
+  options,
+}: MarsRedBankDeficitQuery<TData>) {
+  return useQuery<DeficitResponse, Error, TData>(
+    marsRedBankQueryKeys.deficit(client?.contractAddress, args),
+    () =>
+      client
+        ? client.deficit({
+            denom: args.denom,
+          })
+        : Promise.reject(new Error('Invalid client')),
+    { ...options, enabled: !!client && (options?.enabled != undefined ? options.enabled : true) },
   )
 }
-export interface MarsRedBankLiquidateMutation {
-  client: MarsRedBankClient
-  msg: {
-    collateralDenom: string
-    recipient?: string
+export interface MarsRedBankUserEModeQuery<TData>
+  extends MarsRedBankReactQuery<NullableEModeCategory, TData> {
+  args: {
+    accountId?: number
     user: string
   }
-  args?: {
-    fee?: number | StdFee | 'auto'
-    memo?: string
-    funds?: Coin[]
-  }
 }
-export function useMarsRedBankLiquidateMutation(
-  options?: Omit<
-    UseMutationOptions<ExecuteResult, Error, MarsRedBankLiquidateMutation>,
-    'mutationFn'
-  >,
-) {
-  return useMutation<ExecuteResult, Error, MarsRedBankLiquidateMutation>(
-    ({ client, msg, args: { fee, memo, funds } = {} }) => client.liquidate(msg, fee, memo, funds),
-    options,
+export function useMarsRedBankUserEModeQuery<TData = NullableEModeCategory>({
+  client,
+  args,
+  options,
+}: MarsRedBankUserEModeQuery<TData>) {
+  return useQuery<NullableEModeCategory, Error, TData>(
+    marsRedBankQueryKeys.userEMode(client?.contractAddress, args),
+    () =>
+      client
+        ? client.userEMode({
+            accountId: args.accountId,
+            user: args.user,
+          })
+        : Promise.reject(new Error('Invalid client')),
+    { ...options, enabled: !!client && (options?.enabled != undefined ? options.enabled : true) },
   )
 }
-export interface MarsRedBankRepayMutation {
-  client: MarsRedBankClient
-  msg: {
-    onBehalfOf?: string
+export interface MarsRedBankEModeCategoriesQuery<TData>
+  extends MarsRedBankReactQuery<ArrayOfEModeCategory, TData> {
+  args: {
+    limit?: number
+    startAfter?: number
   }
-  args?: {
-    fee?: number | StdFee | 'auto'
-    memo?: string
-    funds?: Coin[]
+}
+export function useMarsRedBankEModeCategoriesQuery<TData = ArrayOfEModeCategory>({
+  client,
+  args,
+  options,
+}: MarsRedBankEModeCategoriesQuery<TData>) {
+  return useQuery<ArrayOfEModeCategory, Error, TData>(
+    marsRedBankQueryKeys.eModeCategories(client?.contractAddress, args),
+    () =>
+      client
+        ? client.eModeCategories({
+            limit: args.limit,
+            startAfter: args.startAfter,
+          })
+        : Promise.reject(new Error('Invalid client')),
+    { ...options, enabled: !!client && (options?.enabled != undefined ? options.enabled : true) },
+  )
+}
+export interface MarsRedBankEModeCategoryQuery<TData>
+  extends MarsRedBankReactQuery<EModeCategory, TData> {
+  args: {
+    id: number
   }
 }
-export function useMarsRedBankRepayMutation(
-  options?: Omit<UseMutationOptions<ExecuteResult, Error, MarsRedBankRepayMutation>, 'mutationFn'>,
-) {
-  return useMutation<ExecuteResult, Error, MarsRedBankRepayMutation>(
-    ({ client, msg, args: { fee, memo, funds } = {} }) => client.repay(msg, fee, memo, funds),
-    options,
+export function useMarsRedBankEModeCategoryQuery<TData = EModeCategory>({
+  client,
+  args,
+  options,
+}: MarsRedBankEModeCategoryQuery<TData>) {
+  return useQuery<EModeCategory, Error, TData>(
+    marsRedBankQueryKeys.eModeCategory(client?.contractAddress, args),
+    () =>
+      client
+        ? client.eModeCategory({
+            id: args.id,
+          })
+        : Promise.reject(new Error('Invalid client')),
+    { ...options, enabled: !!client && (options?.enabled != undefined ? options.enabled : true) },
   )
 }
-export interface MarsRedBankBorrowMutation {
-  client: MarsRedBankClient
-  msg: {
+export interface MarsRedBankLiquidationPreviewQuery<TData>
+  extends MarsRedBankReactQuery<LiquidationPreviewResponse, TData> {
+  args: {
+    accountId?: number
     amount: Uint128
-    denom: string
-    recipient?: string
+    collateralDenom: string
+    debtDenom: string
+    user: string
   }
-  args?: {
+}
+export function useMarsRedBankLiquidationPreviewQuery<TData = LiquidationPreviewResponse>({
+  client,
+  args,
+  options,
+}: MarsRedBankLiquidationPreviewQuery<TData>) {
+  return useQuery<LiquidationPreviewResponse, Error, TData>(
+    marsRedBankQueryKeys.liquidationPreview(client?.contractAddress, args),
+    () =>
+      client
+        ? client.liquidationPreview({
+            accountId: args.accountId,
+            amount: args.amount,
+            collateralDenom: args.collateralDenom,
+            debtDenom: args.debtDenom,
+            user: args.user,
+          })
+        : Promise.reject(new Error('Invalid client')),
+    { ...options, enabled: !!client && (options?.enabled != undefined ? options.enabled : true) },
+  )
+}
+export interface MarsRedBankLiquidationBonusQuery<TData>
+  extends MarsRedBankReactQuery<Decimal, TData> {
+  args: {
+    denom: string
+    liquidationHealthFactor: Decimal
+  }
+}
+export function useMarsRedBankLiquidationBonusQuery<TData = Decimal>({
+  client,
+  args,
+  options,
+}: MarsRedBankLiquidationBonusQuery<TData>) {
+  return useQuery<Decimal, Error, TData>(
+    marsRedBankQueryKeys.liquidationBonus(client?.contractAddress, args),
+    () =>
+      client
+        ? client.liquidationBonus({
+            denom: args.denom,
+            liquidationHealthFactor: args.liquidationHealthFactor,
+          })
+        : Promise.reject(new Error('Invalid client')),
+    { ...options, enabled: !!client && (options?.enabled != undefined ? options.enabled : true) },
+  )
+}
+export interface MarsRedBankMarketsLiveQuery<TData>
+  extends MarsRedBankReactQuery<ArrayOfMarketLiveResponse, TData> {
+  args: {
+    limit?: number
+    startAfter?: string
+  }
+}
+export function useMarsRedBankMarketsLiveQuery<TData = ArrayOfMarketLiveResponse>({
+  client,
+  args,
+  options,
+}: MarsRedBankMarketsLiveQuery<TData>) {
+  return useQuery<ArrayOfMarketLiveResponse, Error, TData>(
+    marsRedBankQueryKeys.marketsLive(client?.contractAddress, args),
+    () =>
+      client
+        ? client.marketsLive({
+            limit: args.limit,
+            startAfter: args.startAfter,
+          })
+        : Promise.reject(new Error('Invalid client')),
+    { ...options, enabled: !!client && (options?.enabled != undefined ? options.enabled : true) },
+  )
+}
+export interface MarsRedBankMarketLiveQuery<TData>
+  extends MarsRedBankReactQuery<MarketLiveResponse, TData> {
+  args: {
+    denom: string
+  }
+}
+export function useMarsRedBankMarketLiveQuery<TData = MarketLiveResponse>({
+  client,
+  args,
+  options,
+}: MarsRedBankMarketLiveQuery<TData>) {
+  return useQuery<MarketLiveResponse, Error, TData>(
+    marsRedBankQueryKeys.marketLive(client?.contractAddress, args),
+    () =>
+      client
+        ? client.marketLive({
+            denom: args.denom,
+          })
+        : Promise.reject(new Error('Invalid client')),
+    { ...options, enabled: !!client && (options?.enabled != undefined ? options.enabled : true) },
+  )
+}
+export interface MarsRedBankMarketsQuery<TData>
+  extends MarsRedBankReactQuery<ArrayOfMarket, TData> {
+  args: {
+    limit?: number
+    startAfter?: string
+  }
+}
+export function useMarsRedBankMarketsQuery<TData = ArrayOfMarket>({
+  client,
+  args,
+  options,
+}: MarsRedBankMarketsQuery<TData>) {
+  return useQuery<ArrayOfMarket, Error, TData>(
+    marsRedBankQueryKeys.markets(client?.contractAddress, args),
+    () =>
+      client
+        ? client.markets({
+            limit: args.limit,
+            startAfter: args.startAfter,
+          })
+        : Promise.reject(new Error('Invalid client')),
+    { ...options, enabled: !!client && (options?.enabled != undefined ? options.enabled : true) },
+  )
+}
+export interface MarsRedBankMarketQuery<TData> extends MarsRedBankReactQuery<Market, TData> {
+  args: {
+    denom: string
+  }
+}
+export function useMarsRedBankMarketQuery<TData = Market>({
+  client,
+  args,
+  options,
+}: MarsRedBankMarketQuery<TData>) {
+  return useQuery<Market, Error, TData>(
+    marsRedBankQueryKeys.market(client?.contractAddress, args),
+    () =>
+      client
+        ? client.market({
+            denom: args.denom,
+          })
+        : Promise.reject(new Error('Invalid client')),
+    { ...options, enabled: !!client && (options?.enabled != undefined ? options.enabled : true) },
+  )
+}
+export interface MarsRedBankPendingChangesQuery<TData>
+  extends MarsRedBankReactQuery<ArrayOfPendingChange, TData> {
+  args: {
+    limit?: number
+    startAfter?: number
+  }
+}
+export function useMarsRedBankPendingChangesQuery<TData = ArrayOfPendingChange>({
+  client,
+  args,
+  options,
+}: MarsRedBankPendingChangesQuery<TData>) {
+  return useQuery<ArrayOfPendingChange, Error, TData>(
+    marsRedBankQueryKeys.pendingChanges(client?.contractAddress, args),
+    () =>
+      client
+        ? client.pendingChanges({
+            limit: args.limit,
+            startAfter: args.startAfter,
+          })
+        : Promise.reject(new Error('Invalid client')),
+    { ...options, enabled: !!client && (options?.enabled != undefined ? options.enabled : true) },
+  )
+}
+export interface MarsRedBankPendingChangeQuery<TData>
+  extends MarsRedBankReactQuery<PendingChange, TData> {
+  args: {
+    id: number
+  }
+}
+export function useMarsRedBankPendingChangeQuery<TData = PendingChange>({
+  client,
+  args,
+  options,
+}: MarsRedBankPendingChangeQuery<TData>) {
+  return useQuery<PendingChange, Error, TData>(
+    marsRedBankQueryKeys.pendingChange(client?.contractAddress, args),
+    () =>
+      client
+        ? client.pendingChange({
+            id: args.id,
+          })
+        : Promise.reject(new Error('Invalid client')),
+    { ...options, enabled: !!client && (options?.enabled != undefined ? options.enabled : true) },
+  )
+}
+export interface MarsRedBankPauseStateQuery<TData>
+  extends MarsRedBankReactQuery<PauseState, TData> {
+  args: {
+    denom?: string
+  }
+}
+export function useMarsRedBankPauseStateQuery<TData = PauseState>({
+  client,
+  args,
+  options,
+}: MarsRedBankPauseStateQuery<TData>) {
+  return useQuery<PauseState, Error, TData>(
+    marsRedBankQueryKeys.pauseState(client?.contractAddress, args),
+    () =>
+      client
+        ? client.pauseState({
+            denom: args.denom,
+          })
+        : Promise.reject(new Error('Invalid client')),
+    { ...options, enabled: !!client && (options?.enabled != undefined ? options.enabled : true) },
+  )
+}
+export interface MarsRedBankConfigQuery<TData>
+  extends MarsRedBankReactQuery<ConfigResponse, TData> {}
+export function useMarsRedBankConfigQuery<TData = ConfigResponse>({
+  client,
+  options,
+}: MarsRedBankConfigQuery<TData>) {
+  return useQuery<ConfigResponse, Error, TData>(
+    marsRedBankQueryKeys.config(client?.contractAddress),
+    () => (client ? client.config() : Promise.reject(new Error('Invalid client'))),
+    { ...options, enabled: !!client && (options?.enabled != undefined ? options.enabled : true) },
+  )
+}
+export interface MarsRedBankCallbackMutation {
+  client: MarsRedBankClient
+  args?: {
+    fee?: number | StdFee | 'auto'
+    memo?: string
+    funds?: Coin[]
+  }
+}
+export function useMarsRedBankCallbackMutation(
+  options?: Omit<
+    UseMutationOptions<ExecuteResult, Error, MarsRedBankCallbackMutation>,
+    'mutationFn'
+  >,
+) {
+  return useMutation<ExecuteResult, Error, MarsRedBankCallbackMutation>(
+    ({ client, msg, args: { fee, memo, funds } = {} }) => client.callback(msg, fee, memo, funds),
+    options,
+  )
+}
+export interface MarsRedBankMigrateV1PositionsMutation {
+  client: MarsRedBankClient
+  msg: {
+    limit: number
+  }
+  args?: {
+    fee?: number | StdFee | 'auto'
+    memo?: string
+    funds?: Coin[]
+  }
+}
+export function useMarsRedBankMigrateV1PositionsMutation(
+  options?: Omit<
+    UseMutationOptions<ExecuteResult, Error, MarsRedBankMigrateV1PositionsMutation>,
+    'mutationFn'
+  >,
+) {
+  return useMutation<ExecuteResult, Error, MarsRedBankMigrateV1PositionsMutation>(
+    ({ client, msg, args: { fee, memo, funds } = {} }) =>
+      client.migrateV1Positions(msg, fee, memo, funds),
+    options,
+  )
+}
+export interface MarsRedBankBatchMutation {
+  client: MarsRedBankClient
+  msg: {
+    accountId?: number
+    actions: Action[]
+  }
+  args?: {
+    fee?: number | StdFee | 'auto'
+    memo?: string
+    funds?: Coin[]
+  }
+}
+export function useMarsRedBankBatchMutation(
+  options?: Omit<UseMutationOptions<ExecuteResult, Error, MarsRedBankBatchMutation>, 'mutationFn'>,
+) {
+  return useMutation<ExecuteResult, Error, MarsRedBankBatchMutation>(
+    ({ client, msg, args: { fee, memo, funds } = {} }) => client.batch(msg, fee, memo, funds),
+    options,
+  )
+}
+export interface MarsRedBankCoverDeficitMutation {
+  client: MarsRedBankClient
+  msg: {
+    denom: string
+  }
+  args?: {
+    fee?: number | StdFee | 'auto'
+    memo?: string
+    funds?: Coin[]
+  }
+}
+export function useMarsRedBankCoverDeficitMutation(
+  options?: Omit<
+    UseMutationOptions<ExecuteResult, Error, MarsRedBankCoverDeficitMutation>,
+    'mutationFn'
+  >,
+) {
+  return useMutation<ExecuteResult, Error, MarsRedBankCoverDeficitMutation>(
+    ({ client, msg, args: { fee, memo, funds } = {} }) =>
+      client.coverDeficit(msg, fee, memo, funds),
+    options,
+  )
+}
+export interface MarsRedBankSetPauseStateMutation {
+  client: MarsRedBankClient
+  msg: {
+    actions: PausableAction[]
+    denom?: string
+    paused: boolean
+  }
+  args?: {
+    fee?: number | StdFee | 'auto'
+    memo?: string
+    funds?: Coin[]
+  }
+}
+export function useMarsRedBankSetPauseStateMutation(
+  options?: Omit<
+    UseMutationOptions<ExecuteResult, Error, MarsRedBankSetPauseStateMutation>,
+    'mutationFn'
+  >,
+) {
+  return useMutation<ExecuteResult, Error, MarsRedBankSetPauseStateMutation>(
+    ({ client, msg, args: { fee, memo, funds } = {} }) =>
+      client.setPauseState(msg, fee, memo, funds),
+    options,
+  )
+}
+export interface MarsRedBankSetUserEModeMutation {
+  client: MarsRedBankClient
+  msg: {
+    accountId?: number
+    categoryId?: number
+  }
+  args?: {
+    fee?: number | StdFee | 'auto'
+    memo?: string
+    funds?: Coin[]
+  }
+}
+export function useMarsRedBankSetUserEModeMutation(
+  options?: Omit<
+    UseMutationOptions<ExecuteResult, Error, MarsRedBankSetUserEModeMutation>,
+    'mutationFn'
+  >,
+) {
+  return useMutation<ExecuteResult, Error, MarsRedBankSetUserEModeMutation>(
+    ({ client, msg, args: { fee, memo, funds } = {} }) =>
+      client.setUserEMode(msg, fee, memo, funds),
+    options,
+  )
+}
+export interface MarsRedBankSetEModeCategoryMutation {
+  client: MarsRedBankClient
+  msg: {
+    category: EModeCategory
+  }
+  args?: {
+    fee?: number | StdFee | 'auto'
+    memo?: string
+    funds?: Coin[]
+  }
+}
+export function useMarsRedBankSetEModeCategoryMutation(
+  options?: Omit<
+    UseMutationOptions<ExecuteResult, Error, MarsRedBankSetEModeCategoryMutation>,
+    'mutationFn'
+  >,
+) {
+  return useMutation<ExecuteResult, Error, MarsRedBankSetEModeCategoryMutation>(
+    ({ client, msg, args: { fee, memo, funds } = {} }) =>
+      client.setEModeCategory(msg, fee, memo, funds),
+    options,
+  )
+}
+export interface MarsRedBankRepayFlashLoanMutation {
+  client: MarsRedBankClient
+  args?: {
+    fee?: number | StdFee | 'auto'
+    memo?: string
+    funds?: Coin[]
+  }
+}
+export function useMarsRedBankRepayFlashLoanMutation(
+  options?: Omit<
+    UseMutationOptions<ExecuteResult, Error, MarsRedBankRepayFlashLoanMutation>,
+    'mutationFn'
+  >,
+) {
+  return useMutation<ExecuteResult, Error, MarsRedBankRepayFlashLoanMutation>(
+    ({ client, args: { fee, memo, funds } = {} }) => client.repayFlashLoan(fee, memo, funds),
+    options,
+  )
+}
+export interface MarsRedBankFlashLoanMutation {
+  client: MarsRedBankClient
+  msg: {
+    callback: Binary
+    coins: Coin[]
+  }
+  args?: {
+    fee?: number | StdFee | 'auto'
+    memo?: string
+    funds?: Coin[]
+  }
+}
+export function useMarsRedBankFlashLoanMutation(
+  options?: Omit<
+    UseMutationOptions<ExecuteResult, Error, MarsRedBankFlashLoanMutation>,
+    'mutationFn'
+  >,
+) {
+  return useMutation<ExecuteResult, Error, MarsRedBankFlashLoanMutation>(
+    ({ client, msg, args: { fee, memo, funds } = {} }) => client.flashLoan(msg, fee, memo, funds),
+    options,
+  )
+}
+export interface MarsRedBankDeleverageMutation {
+  client: MarsRedBankClient
+  msg: {
+    accountId?: number
+    amount?: Uint128
+    denom: string
+    onBehalfOf?: string
+  }
+  args?: {
+    fee?: number | StdFee | 'auto'
+    memo?: string
+    funds?: Coin[]
+  }
+}
+export function useMarsRedBankDeleverageMutation(
+  options?: Omit<
+    UseMutationOptions<ExecuteResult, Error, MarsRedBankDeleverageMutation>,
+    'mutationFn'
+  >,
+) {
+  return useMutation<ExecuteResult, Error, MarsRedBankDeleverageMutation>(
+    ({ client, msg, args: { fee, memo, funds } = {} }) => client.deleverage(msg, fee, memo, funds),
+    options,
+  )
+}
+export interface MarsRedBankUpdateAssetCollateralStatusMutation {
+  client: MarsRedBankClient
+  msg: {
+    accountId?: number
+    denom: string
+    enable: boolean
+    onBehalfOf?: string
+  }
+  args?: {
+    fee?: number | StdFee | 'auto'
+    memo?: string
+    funds?: Coin[]
+  }
+}
+export function useMarsRedBankUpdateAssetCollateralStatusMutation(
+  options?: Omit<
+    UseMutationOptions<ExecuteResult, Error, MarsRedBankUpdateAssetCollateralStatusMutation>,
+    'mutationFn'
+  >,
+) {
+  return useMutation<ExecuteResult, Error, MarsRedBankUpdateAssetCollateralStatusMutation>(
+    ({ client, msg, args: { fee, memo, funds } = {} }) =>
+      client.updateAssetCollateralStatus(msg, fee, memo, funds),
+    options,
+  )
+}
+export interface MarsRedBankLiquidateManyMutation {
+  client: MarsRedBankClient
+  msg: {
+    liquidations: Liquidation[]
+    recipient?: string
+  }
+  args?: {
+    fee?: number | StdFee | 'auto'
+    memo?: string
+    funds?: Coin[]
+  }
+}
+export function useMarsRedBankLiquidateManyMutation(
+  options?: Omit<
+    UseMutationOptions<ExecuteResult, Error, MarsRedBankLiquidateManyMutation>,
+    'mutationFn'
+  >,
+) {
+  return useMutation<ExecuteResult, Error, MarsRedBankLiquidateManyMutation>(
+    ({ client, msg, args: { fee, memo, funds } = {} }) =>
+      client.liquidateMany(msg, fee, memo, funds),
+    options,
+  )
+}
+export interface MarsRedBankLiquidateMutation {
+  client: MarsRedBankClient
+  msg: {
+    accountId?: number
+    collateralDenom: string
+    recipient?: string
+    user: string
+  }
+  args?: {
+    fee?: number | StdFee | 'auto'
+    memo?: string
+    funds?: Coin[]
+  }
+}
+export function useMarsRedBankLiquidateMutation(
+  options?: Omit<
+    UseMutationOptions<ExecuteResult, Error, MarsRedBankLiquidateMutation>,
+    'mutationFn'
+  >,
+) {
+  return useMutation<ExecuteResult, Error, MarsRedBankLiquidateMutation>(
+    ({ client, msg, args: { fee, memo, funds } = {} }) => client.liquidate(msg, fee, memo, funds),
+    options,
+  )
+}
+export interface MarsRedBankRepayMutation {
+  client: MarsRedBankClient
+  msg: {
+    accountId?: number
+    onBehalfOf?: string
+  }
+  args?: {
+    fee?: number | StdFee | 'auto'
+    memo?: string
+    funds?: Coin[]
+  }
+}
+export function useMarsRedBankRepayMutation(
+  options?: Omit<UseMutationOptions<ExecuteResult, Error, MarsRedBankRepayMutation>, 'mutationFn'>,
+) {
+  return useMutation<ExecuteResult, Error, MarsRedBankRepayMutation>(
+    ({ client, msg, args: { fee, memo, funds } = {} }) => client.repay(msg, fee, memo, funds),
+    options,
+  )
+}
+export interface MarsRedBankSetOperatorMutation {
+  client: MarsRedBankClient
+  msg: {
+    accountId?: number
+    expires?: number
+    operator: string
+    permissions: OperatorPermission[]
+  }
+  args?: {
+    fee?: number | StdFee | 'auto'
+    memo?: string
+    funds?: Coin[]
+  }
+}
+export function useMarsRedBankSetOperatorMutation(
+  options?: Omit<
+    UseMutationOptions<ExecuteResult, Error, MarsRedBankSetOperatorMutation>,
+    'mutationFn'
+  >,
+) {
+  return useMutation<ExecuteResult, Error, MarsRedBankSetOperatorMutation>(
+    ({ client, msg, args: { fee, memo, funds } = {} }) => client.setOperator(msg, fee, memo, funds),
+    options,
+  )
+}
+export interface MarsRedBankApproveDelegationMutation {
+  client: MarsRedBankClient
+  msg: {
+    accountId?: number
+    amount: Uint128
+    delegatee: string
+    denom: string
+  }
+  args?: {
+    fee?: number | StdFee | 'auto'
+    memo?: string
+    funds?: Coin[]
+  }
+}
+export function useMarsRedBankApproveDelegationMutation(
+  options?: Omit<
+    UseMutationOptions<ExecuteResult, Error, MarsRedBankApproveDelegationMutation>,
+    'mutationFn'
+  >,
+) {
+  return useMutation<ExecuteResult, Error, MarsRedBankApproveDelegationMutation>(
+    ({ client, msg, args: { fee, memo, funds } = {} }) =>
+      client.approveDelegation(msg, fee, memo, funds),
+    options,
+  )
+}
+export interface MarsRedBankBorrowMutation {
+  client: MarsRedBankClient
+  msg: {
+    accountId?: number
+    amount: Uint128
+    denom: string
+    onBehalfOf?: string
+    recipient?: string
+  }
+  args?: {
     fee?: number | StdFee | 'auto'
     memo?: string
     funds?: Coin[]
@@ -536,8 +1410,10 @@ export function useMarsRedBankBorrowMutation(
 export interface MarsRedBankWithdrawMutation {
   client: MarsRedBankClient
   msg: {
+    accountId?: number
     amount?: Uint128
     denom: string
+    onBehalfOf?: string
     recipient?: string
   }
   args?: {
@@ -560,6 +1436,7 @@ export function useMarsRedBankWithdrawMutation(
 export interface MarsRedBankDepositMutation {
   client: MarsRedBankClient
   msg: {
+    accountId?: number
     onBehalfOf?: string
   }
   args?: {
@@ -604,6 +1481,119 @@ export function useMarsRedBankUpdateUncollateralizedLoanLimitMutation(
     options,
   )
 }
+export interface MarsRedBankRemoveAssetMutation {
+  client: MarsRedBankClient
+  msg: {
+    denom: string
+  }
+  args?: {
+    fee?: number | StdFee | 'auto'
+    memo?: string
+    funds?: Coin[]
+  }
+}
+export function useMarsRedBankRemoveAssetMutation(
+  options?: Omit<
+    UseMutationOptions<ExecuteResult, Error, MarsRedBankRemoveAssetMutation>,
+    'mutationFn'
+  >,
+) {
+  return useMutation<ExecuteResult, Error, MarsRedBankRemoveAssetMutation>(
+    ({ client, msg, args: { fee, memo, funds } = {} }) => client.removeAsset(msg, fee, memo, funds),
+    options,
+  )
+}
+export interface MarsRedBankSunsetAssetMutation {
+  client: MarsRedBankClient
+  msg: {
+    denom: string
+  }
+  args?: {
+    fee?: number | StdFee | 'auto'
+    memo?: string
+    funds?: Coin[]
+  }
+}
+export function useMarsRedBankSunsetAssetMutation(
+  options?: Omit<
+    UseMutationOptions<ExecuteResult, Error, MarsRedBankSunsetAssetMutation>,
+    'mutationFn'
+  >,
+) {
+  return useMutation<ExecuteResult, Error, MarsRedBankSunsetAssetMutation>(
+    ({ client, msg, args: { fee, memo, funds } = {} }) => client.sunsetAsset(msg, fee, memo, funds),
+    options,
+  )
+}
+export interface MarsRedBankDelistAssetMutation {
+  client: MarsRedBankClient
+  msg: {
+    denom: string
+    rampDuration: number
+  }
+  args?: {
+    fee?: number | StdFee | 'auto'
+    memo?: string
+    funds?: Coin[]
+  }
+}
+export function useMarsRedBankDelistAssetMutation(
+  options?: Omit<
+    UseMutationOptions<ExecuteResult, Error, MarsRedBankDelistAssetMutation>,
+    'mutationFn'
+  >,
+) {
+  return useMutation<ExecuteResult, Error, MarsRedBankDelistAssetMutation>(
+    ({ client, msg, args: { fee, memo, funds } = {} }) => client.delistAsset(msg, fee, memo, funds),
+    options,
+  )
+}
+export interface MarsRedBankCancelPendingChangeMutation {
+  client: MarsRedBankClient
+  msg: {
+    id: number
+  }
+  args?: {
+    fee?: number | StdFee | 'auto'
+    memo?: string
+    funds?: Coin[]
+  }
+}
+export function useMarsRedBankCancelPendingChangeMutation(
+  options?: Omit<
+    UseMutationOptions<ExecuteResult, Error, MarsRedBankCancelPendingChangeMutation>,
+    'mutationFn'
+  >,
+) {
+  return useMutation<ExecuteResult, Error, MarsRedBankCancelPendingChangeMutation>(
+    ({ client, msg, args: { fee, memo, funds } = {} }) =>
+      client.cancelPendingChange(msg, fee, memo, funds),
+    options,
+  )
+}
+export interface MarsRedBankExecutePendingChangeMutation {
+  client: MarsRedBankClient
+  msg: {
+    id: number
+  }
+  args?: {
+    fee?: number | StdFee | 'auto'
+    memo?: string
+    funds?: Coin[]
+  }
+}
+export function useMarsRedBankExecutePendingChangeMutation(
+  options?: Omit<
+    UseMutationOptions<ExecuteResult, Error, MarsRedBankExecutePendingChangeMutation>,
+    'mutationFn'
+  >,
+) {
+  return useMutation<ExecuteResult, Error, MarsRedBankExecutePendingChangeMutation>(
+    ({ client, msg, args: { fee, memo, funds } = {} }) =>
+      client.executePendingChange(msg, fee, memo, funds),
+    options,
+  )
+}
 export interface MarsRedBankUpdateAssetMutation {
   client: MarsRedBankClient
   msg: {
@@ -14,6 +14,7 @@ export interface InstantiateMsg {
 export interface CreateOrUpdateConfig {
   address_provider?: string | null
   close_factor?: Decimal | null
+  timelock?: number | null
 }
 export type ExecuteMsg =
   | {
@@ -39,6 +40,32 @@ export type ExecuteMsg =
         params: InitOrUpdateAssetParams
       }
     }
+  | {
+      execute_pending_change: {
+        id: number
+      }
+    }
+  | {
+      cancel_pending_change: {
+        id: number
+      }
+    }
+  | {
+      delist_asset: {
+        denom: string
+        ramp_duration: number
+      }
+    }
+  | {
+      sunset_asset: {
+        denom: string
+      }
+    }
+  | {
+      remove_asset: {
+        denom: string
+      }
+    }
   | {
       update_uncollateralized_loan_limit: {
         denom: string
@@ -48,41 +75,126 @@ export type ExecuteMsg =
     }
   | {
       deposit: {
+        account_id?: number | null
         on_behalf_of?: string | null
       }
     }
   | {
       withdraw: {
+        account_id?: number | null
         amount?: Uint128 | null
         denom: string
+        on_behalf_of?: string | null
         recipient?: string | null
       }
     }
   | {
       borrow: {
+        account_id?: number | null
         amount: Uint128
         denom: string
+        on_behalf_of?: string | null
         recipient?: string | null
       }
     }
+  | {
+      approve_delegation: {
+        account_id?: number | null
+        amount: Uint128
+        delegatee: string
+        denom: string
+      }
+    }
+  | {
+      set_operator: {
+        account_id?: number | null
+        expires?: number | null
+        operator: string
+        permissions: OperatorPermission[]
+      }
+    }
   | {
       repay: {
+        account_id?: number | null
         on_behalf_of?: string | null
       }
     }
   | {
       liquidate: {
+        account_id?: number | null
         collateral_denom: string
         recipient?: string | null
         user: string
       }
     }
+  | {
+      liquidate_many: {
+        liquidations: Liquidation[]
+        recipient?: string | null
+      }
+    }
   | {
       update_asset_collateral_status: {
+        account_id?: number | null
         denom: string
         enable: boolean
+        on_behalf_of?: string | null
+      }
+    }
+  | {
+      deleverage: {
+        account_id?: number | null
+        amount?: Uint128 | null
+        denom: string
+        on_behalf_of?: string | null
+      }
+    }
+  | {
+      flash_loan: {
+        callback: Binary
+        coins: Coin[]
+      }
+    }
+  | {
+      repay_flash_loan: {}
+    }
+  | {
+      set_e_mode_category: {
+        category: EModeCategory
+      }
+    }
+  | {
+      set_user_e_mode: {
+        account_id?: number | null
+        category_id?: number | null
+      }
+    }
+  | {
+      set_pause_state: {
+        actions: PausableAction[]
+        denom?: string | null
+        paused: boolean
+      }
+    }
+  | {
+      cover_deficit: {
+        denom: string
       }
     }
+  | {
+      batch: {
+        account_id?: number | null
+        actions: Action[]
+      }
+    }
+  | {
+      migrate_v1_positions: {
+        limit: number
+      }
+    }
+  | {
+      callback: CallbackMsg
+    }
 export type OwnerUpdate =
   | {
       propose_new_owner: {
@@ -93,26 +205,146 @@ export type OwnerUpdate =
   | 'accept_proposed'
   | 'abolish_owner_role'
 export type Uint128 = string
+export type InterestAccrual = 'linear' | 'compounding'
+export type InterestRateModel =
+  | {
+      base: Decimal
+      optimal_utilization_rate: Decimal
+      slope_1: Decimal
+      slope_2: Decimal
+    }
+  | {
+      base: Decimal
+      kinks: InterestRateKink[]
+    }
+  | {
+      borrow_rate: Decimal
+    }
+  | {
+      integral_gain: Decimal
+      kink_rate: Decimal
+      max_kink_rate: Decimal
+      min_kink_rate: Decimal
+      proportional_gain: Decimal
+      target_utilization_rate: Decimal
+      utilization_rate?: Decimal
+    }
+export type OperatorPermission =
+  | 'withdraw'
+  | 'repay'
+  | 'update_asset_collateral_status'
+  | 'deleverage'
+export type Binary = string
+export type PausableAction =
+  | 'deposit'
+  | 'withdraw'
+  | 'borrow'
+  | 'repay'
+  | 'liquidate'
+  | 'update_asset_collateral_status'
+export type Action =
+  | {
+      deposit: {
+        amount: Uint128
+        denom: string
+      }
+    }
+  | {
+      withdraw: {
+        amount?: Uint128 | null
+        denom: string
+        recipient?: string | null
+      }
+    }
+  | {
+      borrow: {
+        amount: Uint128
+        denom: string
+        recipient?: string | null
+      }
+    }
+  | {
+      repay: {
+        amount: Uint128
+        denom: string
+      }
+    }
+  | {
+      update_asset_collateral_status: {
+        denom: string
+        enable: boolean
+      }
+    }
+export type CallbackMsg = {
+  finalize_flash_loan: {}
+}
 export interface InitOrUpdateAssetParams {
+  borrow_cap?: Uint128 | null
   borrow_enabled?: boolean | null
+  borrowable_in_isolation?: boolean | null
+  debt_ceiling?: Uint128 | null
   deposit_cap?: Uint128 | null
   deposit_enabled?: boolean | null
+  flash_loan_fee?: Decimal | null
+  interest_accrual?: InterestAccrual | null
   interest_rate_model?: InterestRateModel | null
-  liquidation_bonus?: Decimal | null
+  isolated?: boolean | null
+  liquidation_bonus?: LiquidationBonus | null
   liquidation_threshold?: Decimal | null
   max_loan_to_value?: Decimal | null
+  min_debt?: Uint128 | null
+  protocol_liquidation_fee?: Decimal | null
+  ramp_duration?: number | null
   reserve_factor?: Decimal | null
 }
-export interface InterestRateModel {
-  base: Decimal
-  optimal_utilization_rate: Decimal
-  slope_1: Decimal
-  slope_2: Decimal
+export interface InterestRateKink {
+  borrow_rate: Decimal
+  utilization_rate: Decimal
+}
+export interface LiquidationBonus {
+  max_lb: Decimal
+  min_lb: Decimal
+  slope: Decimal
+}
+export interface Liquidation {
+  account_id?: number | null
+  amount: Uint128
+  collateral_denom: string
+  debt_denom: string
+  user: string
+}
+export interface Coin {
+  amount: Uint128
+  denom: string
+  [k: string]: unknown
+}
+export interface EModeCategory {
+  denoms: string[]
+  id: number
+  liquidation_bonus: Decimal
+  liquidation_threshold: Decimal
+  max_loan_to_value: Decimal
 }
 export type QueryMsg =
   | {
       config: {}
     }
+  | {
+      pause_state: {
+        denom?: string | null
+      }
+    }
+  | {
+      pending_change: {
+        id: number
+      }
+    }
+  | {
+      pending_changes: {
+        limit?: number | null
+        start_after?: number | null
+      }
+    }
   | {
       market: {
         denom: string
@@ -124,6 +356,83 @@ export type QueryMsg =
         start_after?: string | null
       }
     }
+  | {
+      market_live: {
+        denom: string
+      }
+    }
+  | {
+      markets_live: {
+        limit?: number | null
+        start_after?: string | null
+      }
+    }
+  | {
+      liquidation_bonus: {
+        denom: string
+        liquidation_health_factor: Decimal
+      }
+    }
+  | {
+      liquidation_preview: {
+        account_id?: number | null
+        amount: Uint128
+        collateral_denom: string
+        debt_denom: string
+        user: string
+      }
+    }
+  | {
+      e_mode_category: {
+        id: number
+      }
+    }
+  | {
+      e_mode_categories: {
+        limit?: number | null
+        start_after?: number | null
+      }
+    }
+  | {
+      user_e_mode: {
+        account_id?: number | null
+        user: string
+      }
+    }
+  | {
+      deficit: {
+        denom: string
+      }
+    }
+  | {
+      deficits: {
+        limit?: number | null
+        start_after?: string | null
+      }
+    }
+  | {
+      borrow_allowance: {
+        account_id?: number | null
+        delegatee: string
+        delegator: string
+        denom: string
+      }
+    }
+  | {
+      operator: {
+        account_id?: number | null
+        operator: string
+        user: string
+      }
+    }
+  | {
+      operators: {
+        account_id?: number | null
+        limit?: number | null
+        start_after?: string | null
+        user: string
+      }
+    }
   | {
       uncollateralized_loan_limit: {
         denom: string
@@ -139,12 +448,14 @@ export type QueryMsg =
     }
   | {
       user_debt: {
+        account_id?: number | null
         denom: string
         user: string
       }
     }
   | {
       user_debts: {
+        account_id?: number | null
         limit?: number | null
         start_after?: string | null
         user: string
@@ -152,12 +463,20 @@ export type QueryMsg =
     }
   | {
       user_collateral: {
+        account_id?: number | null
+        denom: string
+        user: string
+      }
+    }
+  | {
+      user_total_collateral_scaled: {
         denom: string
         user: string
       }
     }
   | {
       user_collaterals: {
+        account_id?: number | null
         limit?: number | null
         start_after?: string | null
         user: string
@@ -165,6 +484,28 @@ export type QueryMsg =
     }
   | {
       user_position: {
+        account_id?: number | null
+        user: string
+      }
+    }
+  | {
+      borrowers: {
+        limit?: number | null
+        max_health_factor?: Decimal | null
+        start_after?: [string, number] | null
+      }
+    }
+  | {
+      max_borrow: {
+        account_id?: number | null
+        denom: string
+        user: string
+      }
+    }
+  | {
+      max_withdraw: {
+        account_id?: number | null
+        denom: string
         user: string
       }
     }
@@ -192,6 +533,15 @@ export type QueryMsg =
         denom: string
       }
     }
+export interface BorrowersResponse {
+  borrowers: BorrowerResponse[]
+  last_scanned?: [string, number] | null
+}
+export interface BorrowerResponse {
+  account_id: number
+  liquidation_health_factor?: Decimal | null
+  user: string
+}
 export interface ConfigResponse {
   address_provider: string
   close_factor: Decimal
@@ -199,26 +549,103 @@ export interface ConfigResponse {
   owner?: string | null
   proposed_new_emergency_owner?: string | null
   proposed_new_owner?: string | null
+  timelock: number
+}
+export interface DeficitResponse {
+  amount: Uint128
+  denom: string
 }
+export type ArrayOfDeficitResponse = DeficitResponse[]
+export type ArrayOfEModeCategory = EModeCategory[]
+export interface LiquidationPreviewResponse {
+  collateral_amount: Uint128
+  collateral_amount_scaled: Uint128
+  debt_amount: Uint128
+  liquidation_health_factor?: Decimal | null
+  refund_amount: Uint128
+}
+export type MarketStatus = 'listed' | 'delisted' | 'sunset'
 export interface Market {
+  borrow_cap: Uint128
   borrow_enabled: boolean
   borrow_index: Decimal
   borrow_rate: Decimal
+  borrowable_in_isolation: boolean
   collateral_total_scaled: Uint128
+  debt_ceiling: Uint128
   debt_total_scaled: Uint128
   denom: string
   deposit_cap: Uint128
   deposit_enabled: boolean
+  flash_loan_fee: Decimal
   indexes_last_updated: number
+  interest_accrual: InterestAccrual
   interest_rate_model: InterestRateModel
-  liquidation_bonus: Decimal
+  isolated: boolean
+  liquidation_bonus: LiquidationBonus
   liquidation_threshold: Decimal
   liquidity_index: Decimal
   liquidity_rate: Decimal
   max_loan_to_value: Decimal
+  min_debt: Uint128
+  protocol_liquidation_fee: Decimal
   reserve_factor: Decimal
+  risk_params_ramp?: RiskParamsRamp | null
+  status: MarketStatus
+}
+export interface RiskParamsRamp {
+  end_time: number
+  initial_liquidation_threshold: Decimal
+  initial_max_loan_to_value: Decimal
+  start_time: number
+}
+export interface MarketLiveResponse {
+  available_liquidity: Uint128
+  market: Market
+  total_collateral: Uint128
+  total_debt: Uint128
+  utilization_rate: Decimal
 }
 export type ArrayOfMarket = Market[]
+export type ArrayOfMarketLiveResponse = MarketLiveResponse[]
+export interface OperatorResponse {
+  expires?: number | null
+  operator: string
+  permissions: OperatorPermission[]
+}
+export type ArrayOfOperatorResponse = OperatorResponse[]
+export interface PauseState {
+  borrow: boolean
+  deposit: boolean
+  liquidate: boolean
+  repay: boolean
+  update_asset_collateral_status: boolean
+  withdraw: boolean
+}
+export type ParamsChange =
+  | {
+      update_config: {
+        config: CreateOrUpdateConfig
+      }
+    }
+  | {
+      update_asset: {
+        denom: string
+        params: InitOrUpdateAssetParams
+      }
+    }
+  | {
+      set_e_mode_category: {
+        category: EModeCategory
+      }
+    }
+export interface PendingChange {
+  change: ParamsChange
+  executable_at: number
+  expires_at: number
+  id: number
+}
+export type ArrayOfPendingChange = PendingChange[]
 export interface UncollateralizedLoanLimitResponse {
   denom: string
   limit: Uint128
@@ -238,6 +665,7 @@ export interface UserDebtResponse {
   uncollateralized: boolean
 }
 export type ArrayOfUserDebtResponse = UserDebtResponse[]
+export type NullableEModeCategory = EModeCategory | null
 export type UserHealthStatus =
   | 'not_borrowing'
   | {